@@ -1,6 +1,7 @@
 use super::*;
 use crate::{
     component::Handled,
+    dispatch::{network_status::NetworkStatusPort, reachability::ReachabilityPort},
     messaging::{DispatchEnvelope, MsgEnvelope, NetMessage, UnpackError},
 };
 use std::{
@@ -149,6 +150,20 @@ pub trait Actor {
 pub trait Dispatcher: ActorRaw<Message = DispatchEnvelope> {
     /// Returns the system path for this dispatcher
     fn system_path(&mut self) -> SystemPath;
+
+    /// Returns the port on which this dispatcher reports the reachability of remote systems
+    ///
+    /// The default implementation returns `None`, for dispatchers which do not track reachability.
+    fn reachability_port(&mut self) -> Option<&mut ProvidedPort<ReachabilityPort>> {
+        None
+    }
+
+    /// Returns the port on which this dispatcher reports the status of its connections
+    ///
+    /// The default implementation returns `None`, for dispatchers without connections.
+    fn network_status_port(&mut self) -> Option<&mut ProvidedPort<NetworkStatusPort>> {
+        None
+    }
 }
 
 impl<A, M: MessageBounds> ActorRaw for A
@@ -160,7 +175,7 @@ where
     fn receive(&mut self, env: MsgEnvelope<M>) -> Handled {
         match env {
             MsgEnvelope::Typed(m) => self.receive_local(m),
-            MsgEnvelope::Net(nm) => self.receive_network(nm),
+            MsgEnvelope::Net(nm) => self.receive_network(*nm),
         }
     }
 }
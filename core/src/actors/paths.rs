@@ -9,7 +9,9 @@ use std::{
     fmt::{self, Debug},
     net::{AddrParseError, IpAddr, SocketAddr},
     ops::Div,
+    path::PathBuf,
     str::FromStr,
+    time::Duration,
 };
 use uuid::Uuid;
 
@@ -29,6 +31,8 @@ pub enum Transport {
     TCP = 0b01,
     /// Send messages as UDP datagrams
     UDP = 0b10,
+    /// Send messages over Unix domain sockets to systems on the same host
+    UDS = 0b11,
 }
 
 impl Transport {
@@ -41,6 +45,14 @@ impl Transport {
     pub fn is_remote(&self) -> bool {
         !self.is_local()
     }
+
+    /// Returns `true` if this protocol addresses systems by IP address and port
+    ///
+    /// [set_protocol](ActorPath::set_protocol) only switches paths between such protocols,
+    /// as their addresses do not fit [UDS](Transport::UDS).
+    pub fn is_ip(&self) -> bool {
+        !matches!(*self, Transport::UDS)
+    }
 }
 
 impl fmt::Display for Transport {
@@ -49,6 +61,7 @@ impl fmt::Display for Transport {
             &Transport::LOCAL => write!(fmt, "local"),
             &Transport::TCP => write!(fmt, "tcp"),
             &Transport::UDP => write!(fmt, "udp"),
+            &Transport::UDS => write!(fmt, "uds"),
         }
     }
 }
@@ -61,6 +74,7 @@ impl FromStr for Transport {
             "local" => Ok(Transport::LOCAL),
             "tcp" => Ok(Transport::TCP),
             "udp" => Ok(Transport::UDP),
+            "uds" => Ok(Transport::UDS),
             _ => Err(TransportParseError),
         }
     }
@@ -78,7 +92,7 @@ impl fmt::Display for TransportParseError {
 
 impl Error for TransportParseError {
     fn description(&self) -> &str {
-        "Transport must be one of [local,tcp,udp]"
+        "Transport must be one of [local,tcp,udp,uds]"
     }
 }
 
@@ -93,6 +107,8 @@ pub enum PathParseError {
     Addr(AddrParseError),
     /// The path contains a disallowed character
     IllegalCharacter(char),
+    /// The hostname is not a valid DNS hostname
+    Hostname(String),
 }
 
 impl fmt::Display for PathParseError {
@@ -104,6 +120,7 @@ impl fmt::Display for PathParseError {
             PathParseError::IllegalCharacter(c) => {
                 write!(fmt, "The path contains an illegal character: {}", c)
             }
+            PathParseError::Hostname(host) => write!(fmt, "Invalid hostname: {}", host),
         }
     }
 }
@@ -119,6 +136,7 @@ impl Error for PathParseError {
             &PathParseError::Transport(ref e) => Some(e),
             &PathParseError::Addr(ref e) => Some(e),
             &PathParseError::IllegalCharacter(_) => None,
+            &PathParseError::Hostname(_) => None,
         }
     }
 }
@@ -135,33 +153,119 @@ impl From<AddrParseError> for PathParseError {
     }
 }
 
+/// The address of a [KompactSystem](KompactSystem) in a [SystemPath](SystemPath)
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Address {
+    /// An IPv4 or IPv6 address, completed by the port of the system path
+    Ip(IpAddr),
+    /// The file path of a Unix domain socket, used with the [UDS](Transport::UDS) protocol
+    Uds(PathBuf),
+    /// A DNS hostname, completed by the port of the system path
+    ///
+    /// Hostnames are resolved by the dispatcher before connecting.
+    Hostname(String),
+}
+
+impl Address {
+    /// Returns the IP address, if this is one
+    pub fn ip(&self) -> Option<&IpAddr> {
+        match self {
+            Address::Ip(ip) => Some(ip),
+            _ => None,
+        }
+    }
+
+    /// Returns the socket file path, if this is a Unix domain socket address
+    pub fn uds_path(&self) -> Option<&PathBuf> {
+        match self {
+            Address::Uds(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns the hostname, if this is one
+    pub fn hostname(&self) -> Option<&str> {
+        match self {
+            Address::Hostname(host) => Some(host),
+            _ => None,
+        }
+    }
+}
+
+impl From<IpAddr> for Address {
+    fn from(ip: IpAddr) -> Self {
+        Address::Ip(ip)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Ip(ip) => write!(fmt, "{}", ip),
+            Address::Uds(path) => write!(fmt, "[{}]", path.display()),
+            Address::Hostname(host) => write!(fmt, "{}", host),
+        }
+    }
+}
+
 /// The part of an [ActorPath](ActorPath) that refers to the [KompactSystem](KompactSystem)
 ///
 /// As a URI, a `SystemPath` looks like `"tcp://127.0.0.1:8080"`, for example.
+/// Instead of an IP address, it may also carry a hostname, as in `"tcp://myhost:8080"`.
+/// Systems reachable via [UDS](Transport::UDS) are addressed by their socket file instead,
+/// as in `"uds://[/tmp/kompact.sock]"`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct SystemPath {
     protocol: Transport,
-    // TODO address could be IPv4, IPv6, or a domain name (not supported yet)
-    address: IpAddr,
+    address: Address,
     port: u16,
 }
 
 impl SystemPath {
     /// Construct a new system path from individual parts
     pub fn new(protocol: Transport, address: IpAddr, port: u16) -> SystemPath {
+        assert!(protocol.is_ip(), "{} paths need a socket file", protocol);
         SystemPath {
             protocol,
-            address,
+            address: Address::Ip(address),
             port,
         }
     }
 
     /// Construct a new system path from individual parts using a [SocketAddr](std::net::SocketAddr)
     pub fn with_socket(protocol: Transport, socket: SocketAddr) -> SystemPath {
-        SystemPath {
+        SystemPath::new(protocol, socket.ip(), socket.port())
+    }
+
+    /// Construct a new system path addressing the system by `hostname` instead of IP address
+    ///
+    /// Hostnames are case-insensitive and stored in lower case.
+    ///
+    /// Fails with [PathParseError::Hostname](PathParseError::Hostname) if `hostname` is not
+    /// a valid DNS hostname, for example because it is longer than 253 bytes.
+    pub fn with_hostname<S: AsRef<str>>(
+        protocol: Transport,
+        hostname: S,
+        port: u16,
+    ) -> Result<SystemPath, PathParseError> {
+        assert!(protocol.is_ip(), "{} paths need a socket file", protocol);
+        let hostname = hostname.as_ref();
+        if !is_hostname(hostname) {
+            return Err(PathParseError::Hostname(hostname.to_string()));
+        }
+        Ok(SystemPath {
             protocol,
-            address: socket.ip(),
-            port: socket.port(),
+            address: Address::Hostname(hostname.to_ascii_lowercase()),
+            port,
+        })
+    }
+
+    /// Construct a new [UDS](Transport::UDS) system path for the Unix domain socket at `path`
+    pub fn with_uds_path<P: Into<PathBuf>>(path: P) -> SystemPath {
+        SystemPath {
+            protocol: Transport::UDS,
+            address: Address::Uds(path.into()),
+            port: 0,
         }
     }
 
@@ -170,16 +274,25 @@ impl SystemPath {
         self.protocol
     }
 
-    /// Returns a reference to the IP address associated with with this system path
-    pub fn address(&self) -> &IpAddr {
+    /// Returns a reference to the address associated with with this system path
+    pub fn address(&self) -> &Address {
         &self.address
     }
 
     /// Returns the port associated with with this system path
+    ///
+    /// The port is always `0` for [UDS](Transport::UDS) paths.
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    /// Returns the socket address of this system path, if it is addressed by IP
+    ///
+    /// Paths addressed by hostname must be resolved first.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        self.address.ip().map(|ip| SocketAddr::new(*ip, self.port))
+    }
+
     /// Create a named path starting with this system path and ending with the given string
     ///
     /// Paths created with this function will be validated to be a valid lookup path,
@@ -205,9 +318,28 @@ impl SystemPath {
     }
 }
 
+impl FromStr for SystemPath {
+    type Err = PathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = NamedPath::from_str(s)?;
+        if path.path_ref().is_empty() {
+            Ok(path.system().clone())
+        } else {
+            Err(PathParseError::Form(s.to_string()))
+        }
+    }
+}
+
 impl fmt::Display for SystemPath {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{}://{}:{}", self.protocol, self.address, self.port)
+        match self.address {
+            Address::Ip(ref ip) => write!(fmt, "{}://{}:{}", self.protocol, ip, self.port),
+            Address::Hostname(ref host) => {
+                write!(fmt, "{}://{}:{}", self.protocol, host, self.port)
+            }
+            Address::Uds(_) => write!(fmt, "{}://{}", self.protocol, self.address),
+        }
     }
 }
 
@@ -222,7 +354,7 @@ pub trait SystemField {
     }
 
     /// Returns the address used in the system path
-    fn address(&self) -> &IpAddr {
+    fn address(&self) -> &Address {
         &self.system().address()
     }
 
@@ -326,8 +458,7 @@ impl ActorPath {
         S: ActorPathFactory + Dispatching,
         B: Into<Box<dyn Serialisable>>,
     {
-        let mut src = from.actor_path();
-        src.set_protocol(self.protocol());
+        let src = self.sender_path(from);
         self.tell_with_sender(m, from, src)
     }
 
@@ -368,8 +499,7 @@ impl ActorPath {
         CD: ComponentTraits + ComponentLifecycle,
         B: Serialisable + 'static,
     {
-        let mut src = from.actor_path();
-        src.set_protocol(self.protocol());
+        let src = self.sender_path(from);
         self.tell_serialised_with_sender(m, from, src)
     }
 
@@ -418,8 +548,7 @@ impl ActorPath {
     where
         CD: ComponentTraits + ComponentLifecycle,
     {
-        let mut src = from.actor_path();
-        src.set_protocol(self.protocol());
+        let src = self.sender_path(from);
         self.tell_preserialised_with_sender(content, from, src)
     }
 
@@ -450,6 +579,135 @@ impl ActorPath {
         })
     }
 
+    /// Send message `m` to the actor designated by this path and wait for its reply
+    ///
+    /// The dispatcher sends `m` from a temporary path, which only exists until the first message
+    /// arrives at it or the `timeout` expires. So the recipient simply replies to the sender of `m`.
+    /// The returned future is fulfilled with that reply,
+    /// or with [AskError::Timeout](crate::prelude::AskError::Timeout) if there is none in time.
+    ///
+    /// Like with [tell](ActorPath::tell), `m` is only serialised if it actually goes over the network.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kompact::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(ComponentDefinition)]
+    /// struct Echo {
+    ///     ctx: ComponentContext<Self>,
+    /// }
+    /// ignore_lifecycle!(Echo);
+    /// impl Actor for Echo {
+    ///     type Message = Never;
+    ///
+    ///     fn receive_local(&mut self, _msg: Self::Message) -> Handled {
+    ///         unreachable!("Can't instantiate Never type!");
+    ///     }
+    ///
+    ///     fn receive_network(&mut self, msg: NetMessage) -> Handled {
+    ///         let sender = msg.sender.clone();
+    ///         match_deser!(msg; {
+    ///             text: String [String] => sender.tell(text, self),
+    ///             !Err(e) => error!(self.log(), "Could not deserialise message: {:?}", e),
+    ///         });
+    ///         Handled::Ok
+    ///     }
+    /// }
+    ///
+    /// let mut cfg = KompactConfig::new();
+    /// cfg.system_components(DeadletterBox::new, NetworkConfig::default().build());
+    /// let system = cfg.build().expect("KompactSystem");
+    /// let (echo, registration) = system.create_and_register(|| Echo {
+    ///     ctx: ComponentContext::uninitialised(),
+    /// });
+    /// let echo_path = registration.wait_expect(Duration::from_millis(1000), "registration");
+    /// system.start(&echo);
+    ///
+    /// let reply = echo_path
+    ///     .ask_serialised("hello".to_string(), Duration::from_millis(1000), &system)
+    ///     .wait()
+    ///     .expect("reply");
+    /// let text: String = reply.try_deserialise::<String, String>().expect("String");
+    /// assert_eq!("hello", text);
+    /// # system.shutdown().expect("shutdown");
+    /// ```
+    pub fn ask_serialised<B, D>(
+        &self,
+        m: B,
+        timeout: Duration,
+        dispatch: &D,
+    ) -> KFuture<Result<NetMessage, AskError>>
+    where
+        B: Into<Box<dyn Serialisable>>,
+        D: Dispatching,
+    {
+        let (promise, future) = promise();
+        let env = DispatchEnvelope::Ask {
+            dst: self.clone(),
+            msg: m.into(),
+            timeout,
+            promise,
+        };
+        dispatch.dispatcher_ref().enqueue(MsgEnvelope::Typed(env));
+        future
+    }
+
+    /// Send message `m` to the actor designated by this path, redelivering it until it arrives
+    ///
+    /// All messages sent reliably from one actor to another form a channel, in which the
+    /// messages are numbered. The receiving system acknowledges the messages it delivered,
+    /// and the sending dispatcher sends all unacknowledged messages again whenever the connection
+    /// to the receiving system is re-established, or the
+    /// [redelivery interval](crate::prelude::NetworkConfig::set_redelivery_interval) elapses.
+    /// The receiving system drops duplicates and only delivers the messages of a channel in order,
+    /// so every message is delivered *at least once* as long as both systems are running,
+    /// and actually *exactly once* unless the receiving system restarts.
+    ///
+    /// Unlike with [tell](ActorPath::tell), `m` is serialised eagerly if this path is remote,
+    /// since the dispatcher has to keep it around until it is acknowledged.
+    /// Messages to systems that are [blocked](crate::prelude::NetworkStatusRequest::BlockSystem),
+    /// or that the dispatcher gave up reconnecting to, are dropped along with their channels.
+    pub fn tell_reliably<S, B>(&self, m: B, from: &S) -> ()
+    where
+        S: ActorPathFactory + Dispatching,
+        B: Into<Box<dyn Serialisable>>,
+    {
+        let env = DispatchEnvelope::Reliable {
+            src: self.sender_path(from),
+            dst: self.clone(),
+            msg: m.into(),
+        };
+        from.dispatcher_ref().enqueue(MsgEnvelope::Typed(env))
+    }
+
+    /// Send message `m` to the actor designated by this path, ahead of bulk data
+    ///
+    /// Behaves like [tell](ActorPath::tell), except that over TCP the message is sent in a separate
+    /// priority lane of the channel, which overtakes messages already queued to the same system.
+    /// This is meant for small, latency sensitive messages like heartbeats or votes,
+    /// which should not wait for large transfers to finish.
+    /// Prioritised messages are still delivered in order with respect to each other,
+    /// but not with respect to messages sent with [tell](ActorPath::tell).
+    ///
+    /// Prioritised messages are never [streamed](crate::prelude::NetworkConfig::set_stream_threshold),
+    /// and are sent like any other message over other transports.
+    pub fn tell_prioritised<S, B>(&self, m: B, from: &S) -> ()
+    where
+        S: ActorPathFactory + Dispatching,
+        B: Into<Box<dyn Serialisable>>,
+    {
+        let src = self.sender_path(from);
+        let dst = self.clone();
+        let env = DispatchEnvelope::Prioritised {
+            src: src.clone(),
+            dst: dst.clone(),
+            msg: DispatchData::Lazy(m.into(), src, dst),
+        };
+        from.dispatcher_ref().enqueue(MsgEnvelope::Typed(env))
+    }
+
     /// Forwards the still serialised message to this path without changing the sender
     ///
     /// This can be used for routing protocls where the final recipient is supposed to reply
@@ -512,9 +770,23 @@ impl ActorPath {
         }
     }
 
+    /// The path of `from`, switched to the protocol of this path if both are addressed by IP
+    fn sender_path<S: ActorPathFactory>(&self, from: &S) -> ActorPath {
+        let mut src = from.actor_path();
+        src.set_protocol(self.protocol());
+        src
+    }
+
     /// Change the transport protocol for this actor path
-    pub fn set_protocol(&mut self, proto: Transport) {
+    ///
+    /// Returns `false` and leaves the path unchanged when switching between a protocol
+    /// addressed by IP and [UDS](Transport::UDS), as the address of the path would not fit the new protocol.
+    pub fn set_protocol(&mut self, proto: Transport) -> bool {
+        if self.protocol().is_ip() != proto.is_ip() {
+            return false;
+        }
         self.system_mut().protocol = proto;
+        true
     }
 
     /// Sets the transport protocol for this actor path to UDP
@@ -729,16 +1001,26 @@ impl FromStr for UniquePath {
             return Err(PathParseError::Form(s.to_string()));
         }
         let proto: Transport = parts[0].parse()?;
+        if proto == Transport::UDS {
+            // parts: [/path/to/socket]#[UUID]
+            let (system, id) =
+                split_uds_system(parts[1]).ok_or_else(|| PathParseError::Form(s.to_string()))?;
+            let uuid = id
+                .strip_prefix(UNIQUE_PATH_SEP)
+                .and_then(|id| Uuid::from_str(id).ok())
+                .ok_or_else(|| PathParseError::Form(s.to_string()))?;
+            return Ok(UniquePath::with_system(system, uuid));
+        }
         let parts: Vec<&str> = parts[1].split(UNIQUE_PATH_SEP).collect();
         // parts: [IP:port]#[UUID]
         if parts.len() != 2 {
             return Err(PathParseError::Form(s.to_string()));
         }
-        let socket = SocketAddr::from_str(parts[0])?;
+        let system = parse_ip_system(proto, parts[0])?;
         let uuid =
             Uuid::from_str(parts[1]).map_err(|_parse_err| PathParseError::Form(s.to_string()))?;
 
-        Ok(UniquePath::with_socket(proto, socket, uuid))
+        Ok(UniquePath::with_system(system, uuid))
     }
 }
 
@@ -874,19 +1156,76 @@ impl FromStr for NamedPath {
             return Err(PathParseError::Form(s.to_string()));
         }
         let proto: Transport = s1[0].parse()?;
+        if proto == Transport::UDS {
+            let (system, rest) =
+                split_uds_system(s1[1]).ok_or_else(|| PathParseError::Form(s.to_string()))?;
+            if !(rest.is_empty() || rest.starts_with(PATH_SEP)) {
+                return Err(PathParseError::Form(s.to_string()));
+            }
+            let path: Vec<String> = rest
+                .split(PATH_SEP)
+                .skip(1)
+                .map(|v| v.to_string())
+                .collect();
+            validate_lookup_path(&path)?;
+            return Ok(NamedPath::with_system(system, path));
+        }
         let mut s2: Vec<&str> = s1[1].split(PATH_SEP).collect();
         if s2.is_empty() {
             return Err(PathParseError::Form(s.to_string()));
         }
-        let socket = SocketAddr::from_str(s2[0])?;
+        let system = parse_ip_system(proto, s2[0])?;
         let path: Vec<String> = if s2.len() > 1 {
             s2.split_off(1).into_iter().map(|v| v.to_string()).collect()
         } else {
             Vec::default()
         };
         validate_lookup_path(&path)?;
-        Ok(NamedPath::with_socket(proto, socket, path))
+        Ok(NamedPath::with_system(system, path))
+    }
+}
+
+/// Parses the `IP:port` or `hostname:port` system part of a path
+fn parse_ip_system(proto: Transport, s: &str) -> Result<SystemPath, PathParseError> {
+    match SocketAddr::from_str(s) {
+        Ok(socket) => Ok(SystemPath::with_socket(proto, socket)),
+        Err(e) => {
+            let (host, port) = s.rsplit_once(':').ok_or_else(|| e.clone())?;
+            let port: u16 = port.parse().map_err(|_| e.clone())?;
+            if is_hostname(host) {
+                SystemPath::with_hostname(proto, host, port)
+            } else {
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// Checks that `host` is a valid DNS hostname
+///
+/// Names whose last label is numeric are rejected, so malformed IPv4 addresses
+/// are not mistaken for hostnames.
+pub(crate) fn is_hostname(host: &str) -> bool {
+    let labels: Vec<&str> = host.split('.').collect();
+    host.len() <= 253
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && !labels[labels.len() - 1].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Splits the `[/path/to/socket]` system part off the front of a [UDS](Transport::UDS) path
+fn split_uds_system(s: &str) -> Option<(SystemPath, &str)> {
+    let rest = s.strip_prefix('[')?;
+    let end = rest.find(']')?;
+    if end == 0 {
+        return None;
     }
+    Some((SystemPath::with_uds_path(&rest[..end]), &rest[end + 1..]))
 }
 
 /// Some syntactic sugar for [append](NamedPath::append)
@@ -1039,4 +1378,97 @@ mod tests {
         assert_eq!(ref1, ref1_deser);
         assert_eq!(ref1, ref1_deser2);
     }
+
+    #[test]
+    fn actor_path_uds_strings() {
+        let system = SystemPath::with_uds_path("/tmp/kompact/test.sock");
+        let unique: ActorPath = system.clone().into_unique(Uuid::new_v4()).into();
+        let named: ActorPath = system
+            .into_named_with_string("test/path")
+            .expect("a named path")
+            .into();
+        for path in [unique, named].iter() {
+            let path_string = path.to_string();
+            assert!(path_string.starts_with("uds://[/tmp/kompact/test.sock]"));
+            let deser = ActorPath::from_str(&path_string).expect("a proper path");
+            assert_eq!(path, &deser);
+            assert_eq!(
+                deser.address().uds_path(),
+                Some(&PathBuf::from("/tmp/kompact/test.sock"))
+            );
+        }
+        assert!(ActorPath::from_str("uds:///tmp/kompact/test.sock/test").is_err());
+        assert!(ActorPath::from_str("uds://[]/test").is_err());
+    }
+
+    #[test]
+    fn set_protocol_keeps_uds_paths() {
+        let mut uds_path: ActorPath = SystemPath::with_uds_path("/tmp/kompact/test.sock")
+            .into_unique(Uuid::new_v4())
+            .into();
+        assert!(!uds_path.set_protocol(Transport::TCP));
+        assert_eq!(uds_path.protocol(), Transport::UDS);
+
+        let mut tcp_path = ActorPath::from_str("tcp://127.0.0.1:8080/test").expect("a proper path");
+        assert!(!tcp_path.set_protocol(Transport::UDS));
+        assert!(tcp_path.set_protocol(Transport::UDP));
+        assert_eq!(tcp_path.protocol(), Transport::UDP);
+    }
+
+    #[test]
+    fn system_path_strings() {
+        let systems = [
+            SystemPath::new(
+                Transport::TCP,
+                "127.0.0.1".parse().expect("hardcoded IP"),
+                8080,
+            ),
+            SystemPath::with_uds_path("/tmp/kompact/test.sock"),
+            SystemPath::with_hostname(Transport::TCP, "my-host.example.com", 8080)
+                .expect("a valid hostname"),
+        ];
+        for system in systems.iter() {
+            let deser = SystemPath::from_str(&system.to_string()).expect("a proper path");
+            assert_eq!(system, &deser);
+        }
+        assert!(SystemPath::from_str(PATH).is_err());
+    }
+
+    #[test]
+    fn actor_path_hostname_strings() {
+        let named = ActorPath::from_str("tcp://MyHost:1234/foo").expect("a proper path");
+        assert_eq!(named.address().hostname(), Some("myhost"));
+        assert_eq!(named.system().port(), 1234);
+        assert_eq!(named.system().socket_addr(), None);
+        assert_eq!(named.to_string(), "tcp://myhost:1234/foo");
+
+        let unique: ActorPath = SystemPath::with_hostname(Transport::UDP, "myhost", 1234)
+            .expect("a valid hostname")
+            .into_unique(Uuid::new_v4())
+            .into();
+        let deser = ActorPath::from_str(&unique.to_string()).expect("a proper path");
+        assert_eq!(unique, deser);
+
+        assert!(matches!(
+            ActorPath::from_str("tcp://300.1.1.1:1234/foo"),
+            Err(PathParseError::Addr(_))
+        ));
+        assert!(ActorPath::from_str("tcp://my_host:1234/foo").is_err());
+        assert!(ActorPath::from_str("tcp://-myhost:1234/foo").is_err());
+        assert!(ActorPath::from_str("tcp://myhost/foo").is_err());
+        assert!(ActorPath::from_str("tcp://myhost:123456/foo").is_err());
+
+        let long = [
+            "a".repeat(63),
+            "b".repeat(63),
+            "c".repeat(63),
+            "d".repeat(63),
+        ]
+        .join(".");
+        assert!(matches!(
+            SystemPath::with_hostname(Transport::TCP, &long, 1234),
+            Err(PathParseError::Hostname(_))
+        ));
+        assert!(SystemPath::with_hostname(Transport::TCP, "my host", 1234).is_err());
+    }
 }
@@ -1,5 +1,6 @@
 use super::*;
 
+use crate::supervision::LifecycleWatchable;
 use std::{fmt, ops::Deref};
 use uuid::Uuid;
 
@@ -42,7 +43,7 @@ impl<M: MessageBounds> TypedMsgQueue<M> {
 impl<M: MessageBounds> DynMsgQueue for TypedMsgQueue<M> {
     #[inline(always)]
     fn push_net(&self, value: NetMessage) {
-        self.push(MsgEnvelope::Net(value));
+        self.push(MsgEnvelope::Net(Box::new(value)));
     }
 }
 
@@ -163,6 +164,19 @@ impl DynActorRef {
         self.component.upgrade().is_some()
     }
 
+    /// Returns `true` if the target component is still allocated and neither faulty nor destroyed
+    pub(crate) fn is_alive(&self) -> bool {
+        match self.component.upgrade() {
+            Some(c) => c.core().is_alive(),
+            None => false,
+        }
+    }
+
+    /// Returns the unique id of the target component, if it is still allocated
+    pub(crate) fn component_id(&self) -> Option<Uuid> {
+        self.component.upgrade().map(|c| c.id())
+    }
+
     /// Send a network message to the target actor
     pub fn tell<I>(&self, v: I) -> ()
     where
@@ -192,6 +206,12 @@ impl PartialEq for DynActorRef {
     }
 }
 
+impl LifecycleWatchable for DynActorRef {
+    fn watched_component(&self) -> Option<Arc<dyn CoreContainer>> {
+        self.component.upgrade()
+    }
+}
+
 /// A version of [ActorRef](ActorRef) that prevents the target from being deallocated
 ///
 /// Holding this kind of reference increases performance slightly,
@@ -217,6 +237,12 @@ impl<M: MessageBounds> Clone for ActorRefStrong<M> {
     }
 }
 
+impl<M: MessageBounds> LifecycleWatchable for ActorRefStrong<M> {
+    fn watched_component(&self) -> Option<Arc<dyn CoreContainer>> {
+        self.component.clone().downgrade_dyn().upgrade()
+    }
+}
+
 impl<M: MessageBounds> ActorRefStrong<M> {
     pub(crate) fn enqueue(&self, env: MsgEnvelope<M>) -> () {
         let c = &self.component;
@@ -440,6 +466,14 @@ impl<M: MessageBounds> Clone for ActorRef<M> {
     }
 }
 
+impl<M: MessageBounds> LifecycleWatchable for ActorRef<M> {
+    fn watched_component(&self) -> Option<Arc<dyn CoreContainer>> {
+        self.component
+            .upgrade()
+            .and_then(|component| component.downgrade_dyn().upgrade())
+    }
+}
+
 impl<M: MessageBounds> ActorRef<M> {
     pub(crate) fn new(component: Weak<dyn MsgQueueContainer<Message = M>>) -> ActorRef<M> {
         ActorRef { component }
@@ -0,0 +1,1038 @@
+//! Cluster membership for groups of Kompact systems
+//!
+//! Every system that wants to be part of a cluster runs a [ClusterMembership] component.
+//! On start, it registers itself under the [MEMBERSHIP_ALIAS] and repeatedly sends its view of the cluster
+//! to the configured seed nodes, until one of them answers. From then on, the members periodically
+//! gossip their views with a random other member and merge the views they receive.
+//!
+//! Members move through the [MemberState]s in order and never back.
+//! The *leader*, which is simply the smallest [SystemPath] among the members that are up
+//! (or joining, while no member is up yet), moves joining members up and leaving members down,
+//! and removes members that are down. It only does so once gossip has *converged*, i.e. once every
+//! member that is not down is reachable and has seen the current view.
+//!
+//! Any member marks another one down once it [stops existing](crate::prelude::Terminated).
+//! A member that merely becomes unreachable is only reported as such, and keeps the leader from
+//! acting until it is reachable again or explicitly [downed](ClusterRequest::Down).
+//! A system restarted at the address of a former member has a new [incarnation](Member::incarnation)
+//! and joins as a new member, which replaces the old one.
+//! Removed incarnations are gossiped along with the members, so outdated gossip can not bring them back,
+//! until they expire after the [tombstone TTL](ClusterConfig::set_tombstone_ttl).
+//!
+//! Changes in membership are reported on the [ClusterPort].
+//!
+//! # Example
+//!
+//! ```
+//! use kompact::{cluster::*, prelude::*};
+//! use std::time::Duration;
+//!
+//! let mut cfg = KompactConfig::new();
+//! cfg.system_components(DeadletterBox::new, NetworkConfig::default().build());
+//! let system = cfg.build().expect("KompactSystem");
+//!
+//! // Systems joining later would set `system.system_path()` as their seed node
+//! let membership = system.create(|| ClusterMembership::new(ClusterConfig::new()));
+//! system
+//!     .start_notify(&membership)
+//!     .wait_timeout(Duration::from_millis(1000))
+//!     .expect("membership started");
+//! # system.shutdown().expect("shutdown");
+//! ```
+//!
+//! The configuration can also be given in HOCON:
+//!
+//! ```text
+//! cluster {
+//!     seed_nodes = ["tcp://127.0.0.1:12345"]
+//!     gossip_interval = 500 ms
+//!     tombstone_ttl = 1 h
+//! }
+//! ```
+//!
+//! On top of membership, the [pubsub] module offers publish/subscribe across all members.
+use crate::{
+    actors::NamedPath,
+    prelude::*,
+    serialisation::{serialisation_ids, SerError, SerId},
+    utils::random_fraction,
+};
+use hocon::Hocon;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+pub mod pubsub;
+
+/// The alias under which [ClusterMembership] components register themselves
+pub const MEMBERSHIP_ALIAS: &str = "kompact_cluster_membership";
+
+const GOSSIP_INTERVAL: Duration = Duration::from_millis(1000);
+const TOMBSTONE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Returns the path of the [ClusterMembership] component of the system at `system`
+pub fn membership_path(system: SystemPath) -> ActorPath {
+    ActorPath::Named(NamedPath::with_system(
+        system,
+        vec![MEMBERSHIP_ALIAS.to_string()],
+    ))
+}
+
+/// The states a cluster member moves through, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MemberState {
+    /// The system asked to join the cluster, but the leader has not let it in, yet
+    Joining,
+    /// The system is a full member of the cluster
+    Up,
+    /// The system asked to leave the cluster
+    Leaving,
+    /// The system left the cluster, stopped existing, or was downed explicitly
+    Down,
+}
+
+impl TryFrom<u8> for MemberState {
+    type Error = SerError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MemberState::Joining),
+            1 => Ok(MemberState::Up),
+            2 => Ok(MemberState::Leaving),
+            3 => Ok(MemberState::Down),
+            _ => Err(SerError::InvalidData(format!(
+                "Unknown member state {}",
+                value
+            ))),
+        }
+    }
+}
+
+impl MemberState {
+    fn as_u8(self) -> u8 {
+        match self {
+            MemberState::Joining => 0,
+            MemberState::Up => 1,
+            MemberState::Leaving => 2,
+            MemberState::Down => 3,
+        }
+    }
+}
+
+/// A system in the cluster, along with its state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    /// The member system
+    pub system: SystemPath,
+    /// The [incarnation](crate::prelude::KompactSystem::incarnation) of the member system
+    ///
+    /// A system restarted at the same address is a new member, with a new incarnation.
+    pub incarnation: Uuid,
+    /// The state the member is in
+    pub state: MemberState,
+}
+
+/// A port on which the [ClusterMembership] component reports changes in membership
+pub struct ClusterPort;
+
+impl Port for ClusterPort {
+    type Indication = ClusterEvent;
+    type Request = ClusterRequest;
+}
+
+/// Changes in cluster membership, and answers to [ClusterRequest]s
+///
+/// A member which skips a state, e.g. because it left before the changes were gossiped,
+/// is only reported in the state it ended up in.
+#[derive(Clone, Debug)]
+pub enum ClusterEvent {
+    /// The system was seen joining the cluster
+    MemberJoining(SystemPath),
+    /// The system became a full member of the cluster
+    MemberUp(SystemPath),
+    /// The system started leaving the cluster
+    MemberLeaving(SystemPath),
+    /// The system is no longer part of the cluster
+    MemberDown(SystemPath),
+    /// The system, which was down, has been removed from the cluster
+    MemberRemoved(SystemPath),
+    /// The system can not be reached, but is still part of the cluster
+    ///
+    /// Until it is reachable again or [downed](ClusterRequest::Down), the leader makes no changes.
+    MemberUnreachable(SystemPath),
+    /// The system can be reached again, after it was unreachable
+    MemberReachable(SystemPath),
+    /// All known members, as requested via [ClusterRequest::GetMembers]
+    Members(Vec<Member>),
+}
+
+impl ClusterEvent {
+    fn for_state(system: SystemPath, state: MemberState) -> Self {
+        match state {
+            MemberState::Joining => ClusterEvent::MemberJoining(system),
+            MemberState::Up => ClusterEvent::MemberUp(system),
+            MemberState::Leaving => ClusterEvent::MemberLeaving(system),
+            MemberState::Down => ClusterEvent::MemberDown(system),
+        }
+    }
+}
+
+/// Requests to the [ClusterMembership] component
+#[derive(Clone, Debug)]
+pub enum ClusterRequest {
+    /// Asks for all known members, answered with [ClusterEvent::Members]
+    GetMembers,
+    /// Leaves the cluster gracefully
+    Leave,
+    /// Marks the system as down, e.g. because it is known to have crashed
+    ///
+    /// This is the way to give up on a member that stays unreachable.
+    Down(SystemPath),
+}
+
+/// Configuration for a [ClusterMembership] component
+///
+/// Settings which are not configured in code are read from the `cluster` section of the system's HOCON config.
+#[derive(Clone, Debug, Default)]
+pub struct ClusterConfig {
+    seed_nodes: Vec<SystemPath>,
+    gossip_interval: Option<Duration>,
+    tombstone_ttl: Option<Duration>,
+}
+
+impl ClusterConfig {
+    /// Creates an empty configuration
+    pub fn new() -> Self {
+        ClusterConfig::default()
+    }
+
+    /// Reads the `cluster` section of the given `config`
+    ///
+    /// # Panics
+    ///
+    /// Panics if a seed node is not a valid [SystemPath] or the gossip interval or tombstone TTL is not a duration.
+    pub fn from_config(config: &Hocon) -> Self {
+        let section = &config["cluster"];
+        let seed_nodes = match section["seed_nodes"].as_string() {
+            // a single seed node without brackets
+            Some(seed) => vec![seed],
+            None => match &section["seed_nodes"] {
+                Hocon::Array(seeds) => seeds.iter().filter_map(Hocon::as_string).collect(),
+                _ => Vec::new(),
+            },
+        };
+        let seed_nodes = seed_nodes
+            .iter()
+            .map(|seed| {
+                SystemPath::from_str(seed).unwrap_or_else(|e| {
+                    panic!(
+                        "cluster.seed_nodes contains an invalid path {}: {}",
+                        seed, e
+                    )
+                })
+            })
+            .collect();
+        let duration = |key: &str| match &section[key] {
+            Hocon::BadValue(_) => None,
+            value => Some(
+                value
+                    .as_milliseconds()
+                    .map(|millis| Duration::from_micros((millis * 1000.0) as u64))
+                    .unwrap_or_else(|| panic!("cluster.{} must be a duration", key)),
+            ),
+        };
+        ClusterConfig {
+            seed_nodes,
+            gossip_interval: duration("gossip_interval"),
+            tombstone_ttl: duration("tombstone_ttl"),
+        }
+    }
+
+    /// Sets the systems to ask for admission to the cluster
+    ///
+    /// A system without any seed nodes, or with only itself as a seed node, starts a new cluster.
+    pub fn set_seed_nodes(&mut self, seed_nodes: Vec<SystemPath>) -> () {
+        self.seed_nodes = seed_nodes;
+    }
+
+    /// The systems to ask for admission to the cluster
+    pub fn get_seed_nodes(&self) -> &[SystemPath] {
+        &self.seed_nodes
+    }
+
+    /// Sets how often a member gossips its view of the cluster
+    ///
+    /// Default value is 1 second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn set_gossip_interval(&mut self, interval: Duration) -> () {
+        assert!(
+            interval > Duration::from_millis(0),
+            "The gossip interval must be positive"
+        );
+        self.gossip_interval = Some(interval);
+    }
+
+    /// How often a member gossips its view of the cluster
+    pub fn get_gossip_interval(&self) -> Duration {
+        self.gossip_interval.unwrap_or(GOSSIP_INTERVAL)
+    }
+
+    /// Sets how long the members remember that an incarnation was removed from the cluster
+    ///
+    /// Gossip still listing a removed incarnation after that brings it back as a member which is down,
+    /// until the leader removes it again.
+    /// The members compare the removal times with their own clocks, so the TTL should be much longer
+    /// than the clocks may be apart.
+    ///
+    /// Default value is 24 hours.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ttl` is zero.
+    pub fn set_tombstone_ttl(&mut self, ttl: Duration) -> () {
+        assert!(
+            ttl > Duration::from_millis(0),
+            "The tombstone TTL must be positive"
+        );
+        self.tombstone_ttl = Some(ttl);
+    }
+
+    /// How long the members remember that an incarnation was removed from the cluster
+    pub fn get_tombstone_ttl(&self) -> Duration {
+        self.tombstone_ttl.unwrap_or(TOMBSTONE_TTL)
+    }
+
+    fn or_else(mut self, other: ClusterConfig) -> Self {
+        if self.seed_nodes.is_empty() {
+            self.seed_nodes = other.seed_nodes;
+        }
+        if self.gossip_interval.is_none() {
+            self.gossip_interval = other.gossip_interval;
+        }
+        if self.tombstone_ttl.is_none() {
+            self.tombstone_ttl = other.tombstone_ttl;
+        }
+        self
+    }
+}
+
+/// A view of the cluster, as exchanged between [ClusterMembership] components
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Gossip {
+    members: Vec<Member>,
+    /// The members which have seen exactly this view
+    seen: Vec<SystemPath>,
+    /// The incarnations which were removed from the cluster, along with when they were removed
+    removed: Vec<(SystemPath, Uuid, Tombstone)>,
+}
+
+/// When an incarnation was removed from the cluster, in milliseconds since the UNIX epoch
+type Tombstone = u64;
+
+fn now_millis() -> Tombstone {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis() as Tombstone)
+        .unwrap_or(0)
+}
+
+impl Serialisable for Gossip {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::CLUSTER_GOSSIP
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        let members = self.members.iter().try_fold(4, |size, member| {
+            member.system.size_hint().map(|system| size + system + 17)
+        })?;
+        let seen = self.seen.iter().try_fold(4, |size, system| {
+            system.size_hint().map(|system| size + system)
+        })?;
+        let removed = self.removed.iter().try_fold(4, |size, (system, _, _)| {
+            system.size_hint().map(|system| size + system + 24)
+        })?;
+        Some(members + seen + removed)
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        buf.put_u32(self.members.len() as u32);
+        for member in self.members.iter() {
+            member.system.serialise(buf)?;
+            buf.put_slice(member.incarnation.as_bytes());
+            buf.put_u8(member.state.as_u8());
+        }
+        buf.put_u32(self.seen.len() as u32);
+        for system in self.seen.iter() {
+            system.serialise(buf)?;
+        }
+        buf.put_u32(self.removed.len() as u32);
+        for (system, incarnation, removed_at) in self.removed.iter() {
+            system.serialise(buf)?;
+            buf.put_slice(incarnation.as_bytes());
+            buf.put_u64(*removed_at);
+        }
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+fn get_count(buf: &mut dyn Buf, what: &str) -> Result<usize, SerError> {
+    if buf.remaining() < 4 {
+        return Err(SerError::InvalidData(format!(
+            "Could not get {} count",
+            what
+        )));
+    }
+    Ok(buf.get_u32() as usize)
+}
+
+fn get_incarnation(buf: &mut dyn Buf) -> Result<Uuid, SerError> {
+    if buf.remaining() < 16 {
+        return Err(SerError::InvalidData("Could not get incarnation".into()));
+    }
+    let mut bytes = [0u8; 16];
+    buf.copy_to_slice(&mut bytes);
+    Ok(Uuid::from_bytes(bytes))
+}
+
+impl Deserialiser<Gossip> for Gossip {
+    const SER_ID: SerId = serialisation_ids::CLUSTER_GOSSIP;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<Gossip, SerError> {
+        let count = get_count(buf, "member")?;
+        let mut members = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let system = SystemPath::deserialise(buf)?;
+            let incarnation = get_incarnation(buf)?;
+            if buf.remaining() < 1 {
+                return Err(SerError::InvalidData("Could not get member state".into()));
+            }
+            let state = MemberState::try_from(buf.get_u8())?;
+            members.push(Member {
+                system,
+                incarnation,
+                state,
+            });
+        }
+        let count = get_count(buf, "seen")?;
+        let mut seen = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            seen.push(SystemPath::deserialise(buf)?);
+        }
+        let count = get_count(buf, "removed")?;
+        let mut removed = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let system = SystemPath::deserialise(buf)?;
+            let incarnation = get_incarnation(buf)?;
+            if buf.remaining() < 8 {
+                return Err(SerError::InvalidData("Could not get removal time".into()));
+            }
+            removed.push((system, incarnation, buf.get_u64()));
+        }
+        Ok(Gossip {
+            members,
+            seen,
+            removed,
+        })
+    }
+}
+
+/// A component which makes its system a member of a cluster
+///
+/// See the [module documentation](crate::cluster) for how membership works.
+/// The system must use a [NetworkDispatcher](crate::prelude::NetworkDispatcher).
+#[derive(ComponentDefinition)]
+pub struct ClusterMembership {
+    ctx: ComponentContext<Self>,
+    /// Reports changes in membership and accepts [ClusterRequest]s
+    pub cluster: ProvidedPort<ClusterPort>,
+    config: ClusterConfig,
+    /// Our own system and its incarnation, once started
+    system: Option<(SystemPath, Uuid)>,
+    members: BTreeMap<SystemPath, Member>,
+    /// The members which have seen our current view, including ourselves
+    seen: BTreeSet<SystemPath>,
+    /// The members we lost touch with, which are not down for that alone
+    unreachable: BTreeSet<SystemPath>,
+    /// The incarnations which were removed from the cluster, and when
+    ///
+    /// They are remembered for the [tombstone TTL](ClusterConfig::set_tombstone_ttl),
+    /// so outdated gossip can not bring them back.
+    removed: BTreeMap<(SystemPath, Uuid), Tombstone>,
+    /// Whether any other member has gossiped with us, yet
+    joined: bool,
+    gossip_timer: Option<ScheduledTimer>,
+}
+
+impl ClusterMembership {
+    /// Creates a new membership component with the given `config`
+    pub fn new(config: ClusterConfig) -> Self {
+        ClusterMembership {
+            ctx: ComponentContext::uninitialised(),
+            cluster: ProvidedPort::uninitialised(),
+            config,
+            system: None,
+            members: BTreeMap::new(),
+            seen: BTreeSet::new(),
+            unreachable: BTreeSet::new(),
+            removed: BTreeMap::new(),
+            joined: false,
+            gossip_timer: None,
+        }
+    }
+
+    /// Returns all known members, ordered by their system paths
+    pub fn members(&self) -> Vec<Member> {
+        self.members.values().cloned().collect()
+    }
+
+    /// Returns the members which are currently unreachable
+    pub fn unreachable_members(&self) -> Vec<SystemPath> {
+        self.unreachable.iter().cloned().collect()
+    }
+
+    fn own_system(&self) -> &SystemPath {
+        &self
+            .system
+            .as_ref()
+            .expect("ClusterMembership has not been started")
+            .0
+    }
+
+    fn own_incarnation(&self) -> Uuid {
+        self.system
+            .as_ref()
+            .expect("ClusterMembership has not been started")
+            .1
+    }
+
+    fn own_state(&self) -> MemberState {
+        self.members[self.own_system()].state
+    }
+
+    fn seeds(&self) -> Vec<SystemPath> {
+        let own = self.own_system();
+        self.config
+            .get_seed_nodes()
+            .iter()
+            .filter(|seed| *seed != own)
+            .cloned()
+            .collect()
+    }
+
+    fn start_gossiping(&mut self) -> () {
+        let own = self.own_system().clone();
+        if !self.members.contains_key(&own) {
+            let incarnation = self.own_incarnation();
+            self.update(Member {
+                system: own,
+                incarnation,
+                state: MemberState::Joining,
+            });
+        }
+        let interval = self.config.get_gossip_interval();
+        let timer = self.schedule_periodic(Duration::from_millis(0), interval, |target, _id| {
+            target.gossip_round();
+            Handled::Ok
+        });
+        self.gossip_timer = Some(timer);
+    }
+
+    fn stop_gossiping(&mut self) -> () {
+        if let Some(timer) = self.gossip_timer.take() {
+            self.cancel_timer(timer);
+        }
+    }
+
+    fn gossip_round(&mut self) -> () {
+        if self.own_state() == MemberState::Down {
+            self.stop_gossiping();
+            return;
+        }
+        self.expire_tombstones(now_millis());
+        self.lead();
+        let targets = if self.joined || self.seeds().is_empty() {
+            self.random_peer().into_iter().collect()
+        } else {
+            self.seeds()
+        };
+        for target in targets {
+            self.gossip_to(target);
+        }
+    }
+
+    /// Performs the leader's duties, if we are the leader
+    fn lead(&mut self) -> () {
+        let own = self.own_system().clone();
+        // Don't start a cluster of our own while we are still asking to join one
+        if !(self.joined || self.seeds().is_empty()) {
+            return;
+        }
+        let is_leader = match self.leader() {
+            Some(leader) => *leader == own,
+            None => false,
+        };
+        if is_leader {
+            // Only act on a view that all reachable members agree on
+            if !self.converged() {
+                return;
+            }
+            let down: Vec<Member> = self
+                .members
+                .values()
+                .filter(|member| member.state == MemberState::Down && member.system != own)
+                .cloned()
+                .collect();
+            for member in down {
+                self.remove(member);
+            }
+            let moving: Vec<(SystemPath, MemberState)> = self
+                .members
+                .values()
+                .filter_map(|member| match member.state {
+                    MemberState::Joining => Some((member.system.clone(), MemberState::Up)),
+                    MemberState::Leaving => Some((member.system.clone(), MemberState::Down)),
+                    _ => None,
+                })
+                .collect();
+            for (system, state) in moving {
+                self.advance(&system, state);
+            }
+        } else if self.own_state() == MemberState::Leaving
+            && self
+                .members
+                .values()
+                .all(|member| member.system == own || member.state == MemberState::Down)
+        {
+            // The last one to leave turns off the lights
+            self.advance(&own, MemberState::Down);
+        }
+    }
+
+    fn leader(&self) -> Option<&SystemPath> {
+        let with_state = |wanted: MemberState| {
+            self.members
+                .values()
+                .find(|member| member.state == wanted)
+                .map(|member| &member.system)
+        };
+        with_state(MemberState::Up).or_else(|| with_state(MemberState::Joining))
+    }
+
+    /// Whether every member that is not down is reachable and has seen our current view
+    ///
+    /// Joining members don't have to have seen it, as they may not have heard from anyone, yet.
+    fn converged(&self) -> bool {
+        self.members.values().all(|member| match member.state {
+            MemberState::Down => true,
+            MemberState::Joining => !self.unreachable.contains(&member.system),
+            MemberState::Up | MemberState::Leaving => {
+                !self.unreachable.contains(&member.system) && self.seen.contains(&member.system)
+            }
+        })
+    }
+
+    /// Picks a random member to gossip with
+    ///
+    /// Unreachable members are included, as gossip is how we find out that they are back.
+    fn random_peer(&self) -> Option<SystemPath> {
+        let own = self.own_system();
+        let peers: Vec<&SystemPath> = self
+            .members
+            .values()
+            .filter(|member| member.system != *own && member.state != MemberState::Down)
+            .map(|member| &member.system)
+            .collect();
+        if peers.is_empty() {
+            None
+        } else {
+            let index = (random_fraction() * peers.len() as f64) as usize;
+            Some(peers[index.min(peers.len() - 1)].clone())
+        }
+    }
+
+    fn gossip(&self) -> Gossip {
+        Gossip {
+            members: self.members(),
+            seen: self.seen.iter().cloned().collect(),
+            removed: self
+                .removed
+                .iter()
+                .map(|((system, incarnation), removed_at)| {
+                    (system.clone(), *incarnation, *removed_at)
+                })
+                .collect(),
+        }
+    }
+
+    fn gossip_to(&mut self, target: SystemPath) -> () {
+        let gossip = self.gossip();
+        let dst = membership_path(target);
+        trace!(self.ctx.log(), "Gossiping {:?} to {}", gossip, dst);
+        dst.tell(gossip, self);
+    }
+
+    fn on_gossip(&mut self, sender: ActorPath, gossip: Gossip) -> () {
+        if self.own_state() == MemberState::Down {
+            return;
+        }
+        self.joined = true;
+        let from = sender.system().clone();
+        if self.unreachable.remove(&from) {
+            info!(self.ctx.log(), "Member {} is reachable again", from);
+            self.ctx.watch(&membership_path(from.clone()));
+            self.cluster
+                .trigger(ClusterEvent::MemberReachable(from.clone()));
+        }
+        let expired = now_millis().saturating_sub(self.tombstone_ttl());
+        for (system, incarnation, removed_at) in gossip.removed.iter() {
+            if *removed_at > expired {
+                self.forget(system, *incarnation, *removed_at);
+            }
+        }
+        for member in gossip.members.iter() {
+            let about_sender = member.system == from;
+            self.merge(member.clone(), about_sender);
+        }
+        let view = self.gossip();
+        if view.members == gossip.members && view.removed == gossip.removed {
+            self.seen.extend(gossip.seen.iter().cloned());
+        }
+        if self.gossip() != gossip {
+            // Let the sender catch up with what we know
+            self.gossip_to(from);
+        }
+    }
+
+    /// Merges what another member knows about `member` into our view
+    ///
+    /// A new incarnation at the address of a known member replaces the known one,
+    /// if that one is down or if the new one told us about itself (`about_sender`).
+    fn merge(&mut self, member: Member, about_sender: bool) -> () {
+        if self
+            .removed
+            .contains_key(&(member.system.clone(), member.incarnation))
+        {
+            return;
+        }
+        let known = match self.members.get(&member.system) {
+            Some(known) => known.clone(),
+            None => return self.update(member),
+        };
+        if known.incarnation == member.incarnation {
+            if known.state < member.state {
+                self.update(member);
+            }
+        } else if member.system == *self.own_system() {
+            // A previous incarnation of our own system
+            self.bury(member.system, member.incarnation, now_millis());
+        } else if about_sender || known.state == MemberState::Down {
+            info!(
+                self.ctx.log(),
+                "Member {} was replaced by a new incarnation", member.system
+            );
+            self.remove(known);
+            self.update(member);
+        }
+    }
+
+    /// Removes the `incarnation` of `system`, which another member removed from the cluster at `removed_at`
+    fn forget(&mut self, system: &SystemPath, incarnation: Uuid, removed_at: Tombstone) -> () {
+        if *system == *self.own_system() && incarnation == self.own_incarnation() {
+            // We have been removed, so we are down for sure
+            let own = system.clone();
+            self.advance(&own, MemberState::Down);
+            return;
+        }
+        match self.members.get(system) {
+            Some(known) if known.incarnation == incarnation => {
+                let known = known.clone();
+                self.remove(known);
+            }
+            _ => (),
+        }
+        self.bury(system.clone(), incarnation, removed_at);
+    }
+
+    /// Moves the known member `system` to `state`, unless it is already in the same or a later state
+    fn advance(&mut self, system: &SystemPath, state: MemberState) -> () {
+        match self.members.get(system) {
+            Some(known) if known.state < state => {
+                let member = Member {
+                    state,
+                    ..known.clone()
+                };
+                self.update(member);
+            }
+            _ => (),
+        }
+    }
+
+    /// Removes `member` from the cluster for good, after marking it down if it was not already
+    fn remove(&mut self, member: Member) -> () {
+        self.advance(&member.system, MemberState::Down);
+        debug!(self.ctx.log(), "Removing member {}", member.system);
+        self.members.remove(&member.system);
+        self.unreachable.remove(&member.system);
+        self.bury(member.system.clone(), member.incarnation, now_millis());
+        self.cluster
+            .trigger(ClusterEvent::MemberRemoved(member.system));
+    }
+
+    /// Remembers that the `incarnation` of `system` was removed from the cluster at `removed_at`
+    ///
+    /// Members which removed the same incarnation at different times agree on the earliest time.
+    fn bury(&mut self, system: SystemPath, incarnation: Uuid, removed_at: Tombstone) -> () {
+        let known = self
+            .removed
+            .entry((system, incarnation))
+            .or_insert(Tombstone::MAX);
+        if removed_at < *known {
+            *known = removed_at;
+            self.changed();
+        }
+    }
+
+    /// Forgets the removed incarnations whose tombstone TTL ran out by `now`
+    fn expire_tombstones(&mut self, now: Tombstone) -> () {
+        let expired = now.saturating_sub(self.tombstone_ttl());
+        let before = self.removed.len();
+        self.removed.retain(|_, removed_at| *removed_at > expired);
+        if self.removed.len() < before {
+            self.changed();
+        }
+    }
+
+    fn tombstone_ttl(&self) -> Tombstone {
+        self.config.get_tombstone_ttl().as_millis() as Tombstone
+    }
+
+    fn update(&mut self, member: Member) -> () {
+        debug!(
+            self.ctx.log(),
+            "Member {} is {:?}", member.system, member.state
+        );
+        let system = member.system.clone();
+        let state = member.state;
+        let previous = self.members.insert(system.clone(), member);
+        self.changed();
+        if system != *self.own_system() {
+            let path = membership_path(system.clone());
+            if previous.is_none() && state != MemberState::Down {
+                self.ctx.watch(&path);
+            } else if previous.is_some() && state == MemberState::Down {
+                self.unreachable.remove(&system);
+                self.ctx.unwatch(&path);
+            }
+        }
+        self.cluster.trigger(ClusterEvent::for_state(system, state));
+    }
+
+    /// Notes that our view changed, which nobody else has seen, yet
+    fn changed(&mut self) -> () {
+        self.seen.clear();
+        let own = self.own_system().clone();
+        self.seen.insert(own);
+    }
+
+    fn on_terminated(&mut self, terminated: Terminated) -> () {
+        let system = terminated.path.system().clone();
+        match self.members.get(&system) {
+            Some(member) if member.state != MemberState::Down => (),
+            _ => return,
+        }
+        if terminated.reason == TerminationReason::Unreachable {
+            if self.unreachable.insert(system.clone()) {
+                info!(self.ctx.log(), "Member {} is unreachable", system);
+                self.cluster
+                    .trigger(ClusterEvent::MemberUnreachable(system));
+            }
+        } else {
+            info!(
+                self.ctx.log(),
+                "Lost member {}: {:?}", system, terminated.reason
+            );
+            self.advance(&system, MemberState::Down);
+        }
+    }
+}
+
+impl ComponentLifecycle for ClusterMembership {
+    fn on_start(&mut self) -> Handled {
+        let configured = ClusterConfig::from_config(self.ctx.config());
+        self.config = std::mem::take(&mut self.config).or_else(configured);
+        self.system = Some((
+            self.ctx.system().system_path(),
+            self.ctx.system().incarnation(),
+        ));
+        if self.gossip_timer.is_some() {
+            return Handled::Ok;
+        }
+        let registration = self
+            .ctx
+            .system()
+            .register_by_alias(&self.ctx.typed_component(), MEMBERSHIP_ALIAS);
+        Handled::block_on(self, move |mut async_self| async move {
+            match registration.await {
+                Ok(Ok(_)) => async_self.start_gossiping(),
+                Ok(Err(e)) => error!(
+                    async_self.ctx.log(),
+                    "Could not register cluster membership: {:?}", e
+                ),
+                Err(e) => error!(
+                    async_self.ctx.log(),
+                    "Could not register cluster membership: {:?}", e
+                ),
+            }
+        })
+    }
+
+    fn on_stop(&mut self) -> Handled {
+        self.stop_gossiping();
+        Handled::Ok
+    }
+
+    fn on_kill(&mut self) -> Handled {
+        self.stop_gossiping();
+        Handled::Ok
+    }
+}
+
+impl Provide<ClusterPort> for ClusterMembership {
+    fn handle(&mut self, request: ClusterRequest) -> Handled {
+        if self.system.is_none() {
+            warn!(
+                self.ctx.log(),
+                "Ignoring {:?} before the component started", request
+            );
+            return Handled::Ok;
+        }
+        match request {
+            ClusterRequest::GetMembers => {
+                let members = self.members();
+                self.cluster.trigger(ClusterEvent::Members(members));
+            }
+            ClusterRequest::Leave => {
+                let own = self.own_system().clone();
+                self.advance(&own, MemberState::Leaving);
+                if let Some(peer) = self.random_peer() {
+                    self.gossip_to(peer);
+                }
+            }
+            ClusterRequest::Down(system) => self.advance(&system, MemberState::Down),
+        }
+        Handled::Ok
+    }
+}
+
+impl Actor for ClusterMembership {
+    type Message = Never;
+
+    fn receive_local(&mut self, _msg: Self::Message) -> Handled {
+        unreachable!("Can't instantiate Never type!");
+    }
+
+    fn receive_network(&mut self, msg: NetMessage) -> Handled {
+        if self.system.is_none() {
+            return Handled::Ok;
+        }
+        let sender = msg.sender.clone();
+        match_deser! {msg; {
+            gossip: Gossip [Gossip] => self.on_gossip(sender, gossip),
+            terminated: Terminated [Terminated] => self.on_terminated(terminated),
+            !Err(e) => error!(self.ctx.log(), "Could not deserialise cluster message: {:?}", e),
+            _ => warn!(self.ctx.log(), "Received unexpected message from {}", sender),
+        }}
+        Handled::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use hocon::HoconLoader;
+
+    fn system(port: u16) -> SystemPath {
+        SystemPath::new(
+            Transport::TCP,
+            "127.0.0.1".parse().expect("hardcoded IP"),
+            port,
+        )
+    }
+
+    #[test]
+    fn gossip_roundtrip() {
+        let gossip = Gossip {
+            members: vec![
+                Member {
+                    system: system(1),
+                    incarnation: Uuid::new_v4(),
+                    state: MemberState::Up,
+                },
+                Member {
+                    system: system(2),
+                    incarnation: Uuid::new_v4(),
+                    state: MemberState::Down,
+                },
+            ],
+            seen: vec![system(1)],
+            removed: vec![(system(3), Uuid::new_v4(), now_millis())],
+        };
+        let mut buf = BytesMut::with_capacity(gossip.size_hint().expect("size hint"));
+        gossip.serialise(&mut buf).expect("serialise");
+        let deserialised = Gossip::deserialise(&mut buf.freeze()).expect("deserialise");
+        assert_eq!(gossip, deserialised);
+    }
+
+    #[test]
+    fn tombstones_keep_the_earliest_removal_and_expire() {
+        let mut config = ClusterConfig::new();
+        config.set_tombstone_ttl(Duration::from_secs(1));
+        let mut membership = ClusterMembership::new(config);
+        membership.system = Some((system(1), Uuid::new_v4()));
+        let incarnation = Uuid::new_v4();
+        membership.bury(system(2), incarnation, 5000);
+        membership.bury(system(2), incarnation, 3000);
+        membership.bury(system(2), incarnation, 4000);
+        assert_eq!(
+            membership.gossip().removed,
+            vec![(system(2), incarnation, 3000)]
+        );
+
+        membership.expire_tombstones(3999);
+        assert_eq!(membership.removed.len(), 1);
+        membership.expire_tombstones(4000);
+        assert!(membership.removed.is_empty());
+    }
+
+    #[test]
+    fn config_is_read_from_hocon() {
+        let config = HoconLoader::new()
+            .load_str(
+                r#"cluster { seed_nodes = ["tcp://127.0.0.1:1", "tcp://127.0.0.1:2"], gossip_interval = 2 s, tombstone_ttl = 1 h }"#,
+            )
+            .expect("config")
+            .hocon()
+            .expect("hocon");
+        let config = ClusterConfig::from_config(&config);
+        assert_eq!(config.get_seed_nodes(), &[system(1), system(2)]);
+        assert_eq!(config.get_gossip_interval(), Duration::from_secs(2));
+        assert_eq!(config.get_tombstone_ttl(), Duration::from_secs(60 * 60));
+
+        let empty = ClusterConfig::new();
+        assert!(empty.get_seed_nodes().is_empty());
+        assert_eq!(empty.get_gossip_interval(), GOSSIP_INTERVAL);
+        assert_eq!(empty.get_tombstone_ttl(), TOMBSTONE_TTL);
+    }
+}
@@ -0,0 +1,658 @@
+//! Distributed publish/subscribe across the members of a cluster
+//!
+//! Every member system that takes part runs a [PubSubMediator], connected to the
+//! [ClusterPort](super::ClusterPort) of its [ClusterMembership](super::ClusterMembership) component.
+//! Actors subscribe to topics at the mediator of their own system,
+//! and publish to a topic by sending a [Publish] request to that same mediator.
+//!
+//! Each mediator only keeps track of its local subscribers. It replicates the set of topics
+//! it has subscribers for to the mediators of all other members that are up,
+//! whenever that set changes and periodically at the cluster's gossip interval.
+//! A published message is delivered to the local subscribers first,
+//! and then sent once to each other member whose mediator reported subscribers for the topic,
+//! which in turn delivers it to its own local subscribers.
+//!
+//! Subscribers receive published messages as [network messages](crate::messaging::NetMessage),
+//! with the publisher as the sender.
+//! Subscribers must be registered, so their paths can be resolved,
+//! and are unsubscribed automatically once they stop existing.
+//!
+//! # Example
+//!
+//! ```
+//! use kompact::{
+//!     cluster::{pubsub::*, *},
+//!     prelude::*,
+//! };
+//! use std::time::Duration;
+//!
+//! let mut cfg = KompactConfig::new();
+//! cfg.system_components(DeadletterBox::new, NetworkConfig::default().build());
+//! let system = cfg.build().expect("KompactSystem");
+//!
+//! let membership = system.create(|| ClusterMembership::new(ClusterConfig::new()));
+//! let mediator = system.create(PubSubMediator::new);
+//! biconnect_components::<ClusterPort, _, _>(&membership, &mediator).expect("connection");
+//! system.start_notify(&membership).wait_timeout(Duration::from_millis(1000)).expect("membership");
+//! system.start_notify(&mediator).wait_timeout(Duration::from_millis(1000)).expect("mediator");
+//!
+//! // Subscribers have to be registered in the same system as the mediator
+//! let (subscriber, registration) = system.create_and_register(Subscriber::new);
+//! let subscriber_path = registration.wait_expect(Duration::from_millis(1000), "registration");
+//! system.start(&subscriber);
+//! let mediator_ref = mediator.actor_ref();
+//! mediator_ref.tell(PubSubMessage::subscribe("news", subscriber_path.clone()));
+//!
+//! // Publishers name the sender the subscribers see, here the subscriber itself
+//! let news = "Kompact forms clusters now".to_string();
+//! let publish = Publish::new("news", &news, subscriber_path).expect("serialisable");
+//! mediator_ref.tell(publish);
+//! # system.shutdown().expect("shutdown");
+//!
+//! #[derive(ComponentDefinition, Actor)]
+//! struct Subscriber {
+//!     ctx: ComponentContext<Self>,
+//! }
+//! impl Subscriber {
+//!     fn new() -> Self {
+//!         Subscriber { ctx: ComponentContext::uninitialised() }
+//!     }
+//! }
+//! ignore_lifecycle!(Subscriber);
+//! ```
+use super::{ClusterConfig, ClusterEvent, ClusterPort, ClusterRequest, MemberState};
+use crate::{
+    actors::NamedPath,
+    prelude::*,
+    serialisation::{serialisation_ids, SerError, SerId},
+};
+use bytes::Bytes;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+/// The alias under which [PubSubMediator] components register themselves
+pub const MEDIATOR_ALIAS: &str = "kompact_pubsub_mediator";
+
+/// Returns the path of the [PubSubMediator] component of the system at `system`
+pub fn mediator_path(system: SystemPath) -> ActorPath {
+    ActorPath::Named(NamedPath::with_system(
+        system,
+        vec![MEDIATOR_ALIAS.to_string()],
+    ))
+}
+
+/// A request to deliver a message to all subscribers of a topic
+///
+/// The message is serialised once when the request is created,
+/// and the serialised bytes are shared between all deliveries.
+#[derive(Clone, Debug)]
+pub struct Publish {
+    topic: String,
+    sender: ActorPath,
+    ser_id: SerId,
+    data: Bytes,
+}
+
+impl Publish {
+    /// Creates a request to publish `msg` to `topic`, on behalf of `sender`
+    ///
+    /// Subscribers will see `sender` as the sender of the message.
+    pub fn new<T, S>(topic: T, msg: &S, sender: ActorPath) -> Result<Self, SerError>
+    where
+        T: Into<String>,
+        S: Serialisable + ?Sized,
+    {
+        let serialised = crate::serialisation::ser_helpers::serialise_to_serialised(msg)?;
+        Ok(Publish {
+            topic: topic.into(),
+            sender,
+            ser_id: serialised.ser_id,
+            data: serialised.data,
+        })
+    }
+
+    /// The topic to publish to
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// The sender subscribers will see
+    pub fn sender(&self) -> &ActorPath {
+        &self.sender
+    }
+
+    fn message_for(&self, subscriber: ActorPath) -> NetMessage {
+        NetMessage::with_bytes(
+            self.ser_id,
+            self.sender.clone(),
+            subscriber,
+            self.data.clone(),
+        )
+    }
+}
+
+impl Serialisable for Publish {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::PUBSUB_PUBLISH
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.sender
+            .size_hint()
+            .map(|sender| self.topic.len() + 8 + sender + 8 + 4 + self.data.len())
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        self.topic.serialise(buf)?;
+        self.sender.serialise(buf)?;
+        buf.put_u64(self.ser_id);
+        buf.put_u32(self.data.len() as u32);
+        buf.put_slice(&self.data);
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<Publish> for Publish {
+    const SER_ID: SerId = serialisation_ids::PUBSUB_PUBLISH;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<Publish, SerError> {
+        let topic = String::deserialise(buf)?;
+        let sender = ActorPath::deserialise(buf)?;
+        if buf.remaining() < 12 {
+            return Err(SerError::InvalidData(
+                "Could not get published message header".into(),
+            ));
+        }
+        let ser_id = buf.get_u64();
+        let len = buf.get_u32() as usize;
+        if buf.remaining() < len {
+            return Err(SerError::InvalidData(format!(
+                "Published message is truncated: expected {} bytes, got {}",
+                len,
+                buf.remaining()
+            )));
+        }
+        let data = buf.copy_to_bytes(len);
+        Ok(Publish {
+            topic,
+            sender,
+            ser_id,
+            data,
+        })
+    }
+}
+
+/// The messages a [PubSubMediator] accepts from local actors
+#[derive(Debug)]
+pub enum PubSubMessage {
+    /// Subscribes the actor at `subscriber`, which must be in the same system, to `topic`
+    Subscribe {
+        /// The topic to subscribe to
+        topic: String,
+        /// The subscribing actor
+        subscriber: ActorPath,
+    },
+    /// Cancels an earlier `Subscribe`
+    Unsubscribe {
+        /// The topic to unsubscribe from
+        topic: String,
+        /// The unsubscribing actor
+        subscriber: ActorPath,
+    },
+    /// Publishes a message to all subscribers of its topic in the cluster
+    Publish(Publish),
+}
+
+impl PubSubMessage {
+    /// Creates a request to subscribe `subscriber` to `topic`
+    pub fn subscribe<T: Into<String>>(topic: T, subscriber: ActorPath) -> Self {
+        PubSubMessage::Subscribe {
+            topic: topic.into(),
+            subscriber,
+        }
+    }
+
+    /// Creates a request to unsubscribe `subscriber` from `topic`
+    pub fn unsubscribe<T: Into<String>>(topic: T, subscriber: ActorPath) -> Self {
+        PubSubMessage::Unsubscribe {
+            topic: topic.into(),
+            subscriber,
+        }
+    }
+}
+
+impl From<Publish> for PubSubMessage {
+    fn from(publish: Publish) -> Self {
+        PubSubMessage::Publish(publish)
+    }
+}
+
+/// The topics a mediator has local subscribers for, as replicated to the other mediators
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct TopicRegistry {
+    /// Incremented whenever the topics change, so outdated registries can be ignored
+    version: u64,
+    topics: BTreeSet<String>,
+}
+
+impl Serialisable for TopicRegistry {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::PUBSUB_REGISTRY
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(
+            self.topics
+                .iter()
+                .fold(8 + 4, |size, topic| size + topic.len() + 8),
+        )
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        buf.put_u64(self.version);
+        buf.put_u32(self.topics.len() as u32);
+        for topic in self.topics.iter() {
+            topic.serialise(buf)?;
+        }
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<TopicRegistry> for TopicRegistry {
+    const SER_ID: SerId = serialisation_ids::PUBSUB_REGISTRY;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<TopicRegistry, SerError> {
+        if buf.remaining() < 12 {
+            return Err(SerError::InvalidData(
+                "Could not get topic registry header".into(),
+            ));
+        }
+        let version = buf.get_u64();
+        let count = buf.get_u32();
+        let topics = (0..count)
+            .map(|_| String::deserialise(buf))
+            .collect::<Result<BTreeSet<String>, SerError>>()?;
+        Ok(TopicRegistry { version, topics })
+    }
+}
+
+/// A component which delivers published messages to the subscribers of their topics throughout the cluster
+///
+/// See the [module documentation](crate::cluster::pubsub) for how delivery works.
+/// The `cluster` port must be connected to a [ClusterMembership](super::ClusterMembership) component.
+#[derive(ComponentDefinition)]
+pub struct PubSubMediator {
+    ctx: ComponentContext<Self>,
+    /// Tells the mediator which other members to replicate topics to
+    pub cluster: RequiredPort<ClusterPort>,
+    gossip_interval: Option<Duration>,
+    /// Our own system, once started
+    system: Option<SystemPath>,
+    subscribers: BTreeMap<String, BTreeSet<ActorPath>>,
+    registry: TopicRegistry,
+    /// The registries of the other members that are up
+    peers: BTreeMap<SystemPath, TopicRegistry>,
+    gossip_timer: Option<ScheduledTimer>,
+}
+
+impl PubSubMediator {
+    /// Creates a new mediator
+    ///
+    /// It replicates its topics at the gossip interval in the `cluster` section of the system's HOCON config.
+    pub fn new() -> Self {
+        PubSubMediator {
+            ctx: ComponentContext::uninitialised(),
+            cluster: RequiredPort::uninitialised(),
+            gossip_interval: None,
+            system: None,
+            subscribers: BTreeMap::new(),
+            registry: TopicRegistry::default(),
+            peers: BTreeMap::new(),
+            gossip_timer: None,
+        }
+    }
+
+    /// Creates a new mediator, which replicates its topics every `gossip_interval`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gossip_interval` is zero.
+    pub fn with_gossip_interval(gossip_interval: Duration) -> Self {
+        assert!(
+            gossip_interval > Duration::from_millis(0),
+            "The gossip interval must be positive"
+        );
+        PubSubMediator {
+            gossip_interval: Some(gossip_interval),
+            ..PubSubMediator::new()
+        }
+    }
+
+    /// Returns the topics with local subscribers
+    pub fn local_topics(&self) -> Vec<String> {
+        self.subscribers.keys().cloned().collect()
+    }
+
+    /// Returns the topics the mediator of `system` has reported subscribers for
+    pub fn remote_topics(&self, system: &SystemPath) -> Vec<String> {
+        match self.peers.get(system) {
+            Some(registry) => registry.topics.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn own_system(&self) -> &SystemPath {
+        self.system
+            .as_ref()
+            .expect("PubSubMediator has not been started")
+    }
+
+    fn start_gossiping(&mut self) -> () {
+        self.cluster.trigger(ClusterRequest::GetMembers);
+        let interval = match self.gossip_interval {
+            Some(interval) => interval,
+            None => ClusterConfig::from_config(self.ctx.config()).get_gossip_interval(),
+        };
+        let timer = self.schedule_periodic(interval, interval, |target, _id| {
+            target.replicate_to_all();
+            Handled::Ok
+        });
+        self.gossip_timer = Some(timer);
+    }
+
+    fn stop_gossiping(&mut self) -> () {
+        if let Some(timer) = self.gossip_timer.take() {
+            self.cancel_timer(timer);
+        }
+    }
+
+    fn subscribe(&mut self, topic: String, subscriber: ActorPath) -> () {
+        if subscriber.system() != self.own_system() {
+            warn!(
+                self.ctx.log(),
+                "Ignoring subscription of {} to {}, as it is not in this system", subscriber, topic
+            );
+            return;
+        }
+        if !self.is_subscribed(&subscriber) {
+            self.ctx.watch(&subscriber);
+        }
+        let subscribers = self.subscribers.entry(topic).or_default();
+        subscribers.insert(subscriber);
+        if subscribers.len() == 1 {
+            self.topics_changed();
+        }
+    }
+
+    fn unsubscribe(&mut self, topic: &str, subscriber: &ActorPath) -> () {
+        let removed = match self.subscribers.get_mut(topic) {
+            Some(subscribers) => subscribers.remove(subscriber),
+            None => false,
+        };
+        if removed {
+            if !self.is_subscribed(subscriber) {
+                self.ctx.unwatch(subscriber);
+            }
+            self.remove_empty_topics();
+        }
+    }
+
+    fn unsubscribe_all(&mut self, subscriber: &ActorPath) -> () {
+        for subscribers in self.subscribers.values_mut() {
+            subscribers.remove(subscriber);
+        }
+        self.remove_empty_topics();
+    }
+
+    fn is_subscribed(&self, subscriber: &ActorPath) -> bool {
+        self.subscribers
+            .values()
+            .any(|subscribers| subscribers.contains(subscriber))
+    }
+
+    fn remove_empty_topics(&mut self) -> () {
+        let before = self.subscribers.len();
+        self.subscribers
+            .retain(|_, subscribers| !subscribers.is_empty());
+        if self.subscribers.len() != before {
+            self.topics_changed();
+        }
+    }
+
+    fn topics_changed(&mut self) -> () {
+        self.registry.version += 1;
+        self.registry.topics = self.subscribers.keys().cloned().collect();
+        debug!(
+            self.ctx.log(),
+            "Local topics are now {:?}", self.registry.topics
+        );
+        self.replicate_to_all();
+    }
+
+    fn replicate_to_all(&mut self) -> () {
+        let peers: Vec<SystemPath> = self.peers.keys().cloned().collect();
+        for peer in peers {
+            self.replicate_to(peer);
+        }
+    }
+
+    fn replicate_to(&mut self, peer: SystemPath) -> () {
+        let own = mediator_path(self.own_system().clone());
+        mediator_path(peer).tell_with_sender(self.registry.clone(), self, own);
+    }
+
+    fn on_registry(&mut self, sender: &ActorPath, registry: TopicRegistry) -> () {
+        if let Some(known) = self.peers.get_mut(sender.system()) {
+            if known.version < registry.version {
+                trace!(
+                    self.ctx.log(),
+                    "{} has subscribers for {:?}",
+                    sender.system(),
+                    registry.topics
+                );
+                *known = registry;
+            }
+        }
+    }
+
+    fn add_peer(&mut self, system: SystemPath) -> () {
+        if system != *self.own_system() && !self.peers.contains_key(&system) {
+            self.peers.insert(system.clone(), TopicRegistry::default());
+            self.replicate_to(system);
+        }
+    }
+
+    fn remove_peer(&mut self, system: &SystemPath) -> () {
+        self.peers.remove(system);
+    }
+
+    /// Delivers `publish` to the local subscribers and, unless it was `forwarded` by another mediator,
+    /// to all other members with subscribers for its topic
+    fn publish(&mut self, publish: Publish, forwarded: bool) -> () {
+        if let Some(subscribers) = self.subscribers.get(publish.topic()) {
+            for subscriber in subscribers.iter() {
+                subscriber
+                    .forward_with_original_sender(publish.message_for(subscriber.clone()), self);
+            }
+        }
+        if forwarded {
+            return;
+        }
+        let own = mediator_path(self.own_system().clone());
+        for (system, registry) in self.peers.iter() {
+            if registry.topics.contains(publish.topic()) {
+                mediator_path(system.clone()).tell_with_sender(publish.clone(), self, own.clone());
+            }
+        }
+    }
+}
+
+impl Default for PubSubMediator {
+    fn default() -> Self {
+        PubSubMediator::new()
+    }
+}
+
+impl ComponentLifecycle for PubSubMediator {
+    fn on_start(&mut self) -> Handled {
+        self.system = Some(self.ctx.system().system_path());
+        if self.gossip_timer.is_some() {
+            return Handled::Ok;
+        }
+        let registration = self
+            .ctx
+            .system()
+            .register_by_alias(&self.ctx.typed_component(), MEDIATOR_ALIAS);
+        Handled::block_on(self, move |mut async_self| async move {
+            match registration.await {
+                Ok(Ok(_)) => async_self.start_gossiping(),
+                Ok(Err(e)) => error!(
+                    async_self.ctx.log(),
+                    "Could not register pub/sub mediator: {:?}", e
+                ),
+                Err(e) => error!(
+                    async_self.ctx.log(),
+                    "Could not register pub/sub mediator: {:?}", e
+                ),
+            }
+        })
+    }
+
+    fn on_stop(&mut self) -> Handled {
+        self.stop_gossiping();
+        Handled::Ok
+    }
+
+    fn on_kill(&mut self) -> Handled {
+        self.stop_gossiping();
+        Handled::Ok
+    }
+}
+
+impl Require<ClusterPort> for PubSubMediator {
+    fn handle(&mut self, event: ClusterEvent) -> Handled {
+        if self.system.is_none() {
+            return Handled::Ok;
+        }
+        match event {
+            ClusterEvent::MemberUp(system) => self.add_peer(system),
+            ClusterEvent::MemberDown(system) => self.remove_peer(&system),
+            ClusterEvent::Members(members) => {
+                for member in members {
+                    match member.state {
+                        MemberState::Up | MemberState::Leaving => self.add_peer(member.system),
+                        MemberState::Down => self.remove_peer(&member.system),
+                        MemberState::Joining => (),
+                    }
+                }
+            }
+            ClusterEvent::MemberJoining(_)
+            | ClusterEvent::MemberLeaving(_)
+            | ClusterEvent::MemberRemoved(_)
+            | ClusterEvent::MemberUnreachable(_)
+            | ClusterEvent::MemberReachable(_) => (),
+        }
+        Handled::Ok
+    }
+}
+
+impl Actor for PubSubMediator {
+    type Message = PubSubMessage;
+
+    fn receive_local(&mut self, msg: Self::Message) -> Handled {
+        if self.system.is_none() {
+            warn!(
+                self.ctx.log(),
+                "Ignoring {:?} before the component started", msg
+            );
+            return Handled::Ok;
+        }
+        match msg {
+            PubSubMessage::Subscribe { topic, subscriber } => self.subscribe(topic, subscriber),
+            PubSubMessage::Unsubscribe { topic, subscriber } => {
+                self.unsubscribe(&topic, &subscriber)
+            }
+            PubSubMessage::Publish(publish) => self.publish(publish, false),
+        }
+        Handled::Ok
+    }
+
+    fn receive_network(&mut self, msg: NetMessage) -> Handled {
+        if self.system.is_none() {
+            return Handled::Ok;
+        }
+        let sender = msg.sender.clone();
+        let from_mediator = sender == mediator_path(sender.system().clone())
+            && sender.system() != self.own_system();
+        match_deser! {msg; {
+            publish: Publish [Publish] => self.publish(publish, from_mediator),
+            registry: TopicRegistry [TopicRegistry] => self.on_registry(&sender, registry),
+            terminated: Terminated [Terminated] => {
+                debug!(self.ctx.log(), "Subscriber {} is gone", terminated.path);
+                self.unsubscribe_all(&terminated.path);
+            },
+            !Err(e) => error!(self.ctx.log(), "Could not deserialise pub/sub message: {:?}", e),
+            _ => warn!(self.ctx.log(), "Received unexpected message from {}", sender),
+        }}
+        Handled::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use uuid::Uuid;
+
+    fn system() -> SystemPath {
+        SystemPath::new(
+            Transport::TCP,
+            "127.0.0.1".parse().expect("hardcoded IP"),
+            8080,
+        )
+    }
+
+    #[test]
+    fn publish_roundtrip() {
+        let sender = ActorPath::from((system(), Uuid::new_v4()));
+        let publish =
+            Publish::new("news", &"hello".to_string(), sender.clone()).expect("serialise message");
+        let mut buf = BytesMut::with_capacity(publish.size_hint().expect("size hint"));
+        publish.serialise(&mut buf).expect("serialise");
+        let deserialised = Publish::deserialise(&mut buf.freeze()).expect("deserialise");
+        assert_eq!(deserialised.topic(), "news");
+        assert_eq!(deserialised.sender(), &sender);
+
+        let receiver = mediator_path(system());
+        let msg = deserialised.message_for(receiver.clone());
+        assert_eq!(msg.receiver, receiver);
+        let content: String = msg
+            .try_deserialise::<String, String>()
+            .expect("published message");
+        assert_eq!(content, "hello");
+    }
+
+    #[test]
+    fn registry_roundtrip() {
+        let registry = TopicRegistry {
+            version: 42,
+            topics: vec!["news".to_string(), "sports".to_string()]
+                .into_iter()
+                .collect(),
+        };
+        let mut buf = BytesMut::with_capacity(registry.size_hint().expect("size hint"));
+        registry.serialise(&mut buf).expect("serialise");
+        let deserialised = TopicRegistry::deserialise(&mut buf.freeze()).expect("deserialise");
+        assert_eq!(registry, deserialised);
+    }
+}
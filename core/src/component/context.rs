@@ -1,6 +1,9 @@
 use super::*;
 
-use crate::net::buffers::{BufferConfig, ChunkAllocator, ChunkRef};
+use crate::{
+    messaging::DispatchEnvelope,
+    net::buffers::{BufferConfig, ChunkAllocator, ChunkRef},
+};
 use std::task::Poll;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -321,6 +324,31 @@ where
         &self.inner_ref().id
     }
 
+    /// Asks to be notified once the actor at `path` stops existing
+    ///
+    /// The notification arrives as a [Terminated](crate::prelude::Terminated) network message,
+    /// at most once per call. It is also sent if there is no actor at `path` to begin with,
+    /// or if the connection to its system is lost.
+    ///
+    /// Watching requires a [NetworkDispatcher](crate::prelude::NetworkDispatcher).
+    pub fn watch(&self, path: &ActorPath) -> () {
+        self.dispatcher_ref().tell(DispatchEnvelope::Watch {
+            watcher: self.actor_ref().dyn_ref(),
+            watched: path.clone(),
+        });
+    }
+
+    /// Cancels an earlier [watch](ComponentContext::watch) of `path`
+    ///
+    /// A [Terminated](crate::prelude::Terminated) notification may still arrive,
+    /// if it was already on its way.
+    pub fn unwatch(&self, path: &ActorPath) -> () {
+        self.dispatcher_ref().tell(DispatchEnvelope::Unwatch {
+            watcher: self.actor_ref().dyn_ref(),
+            watched: path.clone(),
+        });
+    }
+
     /// Destroys this component lazily
     ///
     /// This simply sends a `Kill` event to itself,
@@ -56,6 +56,19 @@ impl ComponentCore {
         }
     }
 
+    /// Returns `true` if the component is neither faulty nor destroyed
+    pub(crate) fn is_alive(&self) -> bool {
+        !(self.is_faulty() || self.is_destroyed())
+    }
+
+    pub(crate) fn is_faulty(&self) -> bool {
+        lifecycle::is_faulty(&self.state)
+    }
+
+    pub(crate) fn is_destroyed(&self) -> bool {
+        lifecycle::is_destroyed(&self.state)
+    }
+
     pub(crate) fn increment_work(&self) -> SchedulingDecision {
         LifecycleState::increment_work(&self.state)
     }
@@ -1,5 +1,11 @@
 use super::*;
 
+use futures::{
+    channel::mpsc,
+    future::{AbortHandle, Abortable},
+    StreamExt,
+};
+
 /// Object-safe part of [`ComponentDefinition`].
 ///
 /// This trait aggregates all the object-safe super-traits of [`ComponentDefinition`] to make
@@ -116,6 +122,95 @@ where
         self.ctx_mut().non_blocking_futures.insert(tag, future);
     }
 
+    /// Call `handler` with every [LifecycleEvent] of the watched `component` from now on
+    ///
+    /// The events are delivered until the watched component is destroyed or faulty,
+    /// or this component is destroyed, whichever happens first.
+    /// If the watched component is already destroyed or faulty, only that event is delivered.
+    /// Watching a reference to a component which has already been deallocated delivers
+    /// [Destroyed](LifecycleEvent::Destroyed) with a [nil](uuid::Uuid::nil) id right away.
+    ///
+    /// Returns a handle which can be passed to [unwatch_lifecycle](ComponentDefinition::unwatch_lifecycle)
+    /// to stop watching earlier.
+    ///
+    /// If `handler` returns anything but [Handled::Ok](Handled::Ok), this component stops
+    /// watching and handles the result as if a message handler had returned it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use kompact::prelude::*;
+    ///
+    /// #[derive(ComponentDefinition, Actor)]
+    /// struct Watcher {
+    ///    ctx: ComponentContext<Self>,
+    ///    watched: ActorRef<Never>,
+    ///    alive: bool,
+    /// }
+    /// impl ComponentLifecycle for Watcher {
+    ///     fn on_start(&mut self) -> Handled {
+    ///         let watched = self.watched.clone();
+    ///         self.watch_lifecycle(&watched, |this, event| {
+    ///             if let LifecycleEvent::Destroyed(_) = event {
+    ///                 this.alive = false;
+    ///             }
+    ///             Handled::Ok
+    ///         });
+    ///         Handled::Ok
+    ///     }
+    /// }
+    /// ```
+    fn watch_lifecycle<W, F>(&mut self, component: &W, mut handler: F) -> LifecycleWatch
+    where
+        W: LifecycleWatchable + ?Sized,
+        F: FnMut(&mut Self, LifecycleEvent) -> Handled + Send + 'static,
+    {
+        let (subscriber, mut events) = mpsc::unbounded();
+        match component.watched_component() {
+            Some(component) => {
+                self.ctx()
+                    .component()
+                    .system()
+                    .supervision_port()
+                    .enqueue(SupervisorMsg::Subscribe(component, subscriber));
+            }
+            None => {
+                debug!(
+                    self.log(),
+                    "Watching the lifecycle of an already deallocated component"
+                );
+                let _ = subscriber.unbounded_send(LifecycleEvent::Destroyed(Uuid::nil()));
+            }
+        }
+        let (abort_handle, registration) = AbortHandle::new_pair();
+        self.spawn_local(move |mut async_self| {
+            let watch = async move {
+                while let Some(event) = events.next().await {
+                    let handled = handler(&mut async_self, event);
+                    if !handled.is_ok() {
+                        return handled;
+                    }
+                }
+                Handled::Ok
+            };
+            async move {
+                Abortable::new(watch, registration)
+                    .await
+                    .unwrap_or(Handled::Ok)
+            }
+        });
+        LifecycleWatch::new(abort_handle)
+    }
+
+    /// Stop calling the handler of the lifecycle `watch`
+    ///
+    /// No more events are delivered for the `watch` after this call,
+    /// even if they already happened.
+    /// Unwatching a watch that already ended has no effect.
+    fn unwatch_lifecycle(&mut self, watch: LifecycleWatch) -> () {
+        watch.cancel();
+    }
+
     /// Run a Future on this system's executor pool and return a handle to the result
     ///
     /// Handles can be awaited like any other future.
@@ -442,9 +442,16 @@ impl SchedulingDecision {
 
 #[cfg(test)]
 mod tests {
-    use crate::{component::AbstractComponent, prelude::*};
+    use crate::{
+        component::{AbstractComponent, MsgQueueContainer},
+        prelude::*,
+    };
     use futures::channel::oneshot;
-    use std::{sync::Arc, thread, time::Duration};
+    use std::{
+        sync::{Arc, Weak},
+        thread,
+        time::Duration,
+    };
 
     use std::ops::Deref;
 
@@ -1281,4 +1288,163 @@ mod tests {
 
         system.shutdown().expect("shutdown");
     }
+
+    /// Fulfils `done` with the first `expected` lifecycle events of `watched`
+    #[derive(ComponentDefinition)]
+    struct LifecycleWatcher {
+        ctx: ComponentContext<Self>,
+        watched: ActorRef<bool>,
+        expected: usize,
+        events: Vec<LifecycleEvent>,
+        done: Option<KPromise<Vec<LifecycleEvent>>>,
+    }
+
+    impl LifecycleWatcher {
+        fn new(
+            watched: ActorRef<bool>,
+            expected: usize,
+            done: KPromise<Vec<LifecycleEvent>>,
+        ) -> Self {
+            LifecycleWatcher {
+                ctx: ComponentContext::uninitialised(),
+                watched,
+                expected,
+                events: Vec::new(),
+                done: Some(done),
+            }
+        }
+    }
+
+    impl ComponentLifecycle for LifecycleWatcher {
+        fn on_start(&mut self) -> Handled {
+            let watched = self.watched.clone();
+            // Any event this watch gets would show up twice in `events`
+            let unwatched = self.watch_lifecycle(&watched, |this, event| {
+                this.events.push(event);
+                Handled::Ok
+            });
+            self.unwatch_lifecycle(unwatched);
+            self.watch_lifecycle(&watched, |this, event| {
+                this.events.push(event);
+                if this.events.len() == this.expected {
+                    if let Some(done) = this.done.take() {
+                        done.fulfil(this.events.clone()).expect("fulfilled");
+                    }
+                }
+                Handled::Ok
+            });
+            Handled::Ok
+        }
+    }
+
+    impl Actor for LifecycleWatcher {
+        type Message = Never;
+
+        fn receive_local(&mut self, _msg: Self::Message) -> Handled {
+            unreachable!("Can't instantiate Never type!");
+        }
+
+        fn receive_network(&mut self, _msg: NetMessage) -> Handled {
+            unimplemented!("No networking here!");
+        }
+    }
+
+    /// Panics when told `true`
+    #[derive(ComponentDefinition)]
+    struct Panicker {
+        ctx: ComponentContext<Self>,
+    }
+
+    ignore_lifecycle!(Panicker);
+
+    impl Actor for Panicker {
+        type Message = bool;
+
+        fn receive_local(&mut self, panic: Self::Message) -> Handled {
+            if panic {
+                panic!("Told to panic");
+            }
+            Handled::Ok
+        }
+
+        fn receive_network(&mut self, _msg: NetMessage) -> Handled {
+            unimplemented!("No networking here!");
+        }
+    }
+
+    fn watch_events(
+        system: &KompactSystem,
+        watched: ActorRef<bool>,
+        expected: usize,
+        act: impl FnOnce(),
+    ) -> Vec<LifecycleEvent> {
+        let (p, f) = promise::<Vec<LifecycleEvent>>();
+        let watcher = system.create(move || LifecycleWatcher::new(watched, expected, p));
+        system
+            .start_notify(&watcher)
+            .wait_timeout(TIMEOUT)
+            .expect("Watcher didn't start");
+        act();
+        f.wait_timeout(TIMEOUT)
+            .expect("Watcher didn't see all events")
+    }
+
+    #[test]
+    fn test_lifecycle_watch() {
+        let system = KompactConfig::default().build().expect("System");
+        let panicker = system.create(|| Panicker {
+            ctx: ComponentContext::uninitialised(),
+        });
+        let id = panicker.id();
+        let events = watch_events(&system, panicker.actor_ref(), 4, || {
+            system
+                .start_notify(&panicker)
+                .wait_timeout(TIMEOUT)
+                .expect("Panicker didn't start");
+            system
+                .stop_notify(&panicker)
+                .wait_timeout(TIMEOUT)
+                .expect("Panicker didn't stop");
+            system
+                .start_notify(&panicker)
+                .wait_timeout(TIMEOUT)
+                .expect("Panicker didn't start");
+            system
+                .kill_notify(panicker.clone())
+                .wait_timeout(TIMEOUT)
+                .expect("Panicker didn't die");
+        });
+        assert_eq!(
+            events,
+            vec![
+                LifecycleEvent::Started(id),
+                LifecycleEvent::Stopped(id),
+                LifecycleEvent::Started(id),
+                LifecycleEvent::Destroyed(id),
+            ]
+        );
+        // Watching a dead component tells the watcher right away
+        let events = watch_events(&system, panicker.actor_ref(), 1, || ());
+        assert_eq!(events, vec![LifecycleEvent::Destroyed(id)]);
+        // Even if its reference has already been deallocated
+        let deallocated: Weak<dyn MsgQueueContainer<Message = bool>> =
+            Weak::<Component<Panicker>>::new();
+        let events = watch_events(&system, ActorRef::new(deallocated), 1, || ());
+        assert_eq!(events, vec![LifecycleEvent::Destroyed(uuid::Uuid::nil())]);
+
+        let panicker = system.create(|| Panicker {
+            ctx: ComponentContext::uninitialised(),
+        });
+        let id = panicker.id();
+        system
+            .start_notify(&panicker)
+            .wait_timeout(TIMEOUT)
+            .expect("Panicker didn't start");
+        let events = watch_events(&system, panicker.actor_ref(), 1, || {
+            panicker.actor_ref().tell(true);
+        });
+        assert_eq!(events, vec![LifecycleEvent::Faulted(id)]);
+
+        system.shutdown().expect("shutdown");
+    }
 }
@@ -122,6 +122,10 @@ impl SystemHandle for ContextSystemHandle {
         self.component.system().system_path()
     }
 
+    fn incarnation(&self) -> Uuid {
+        self.component.system().incarnation()
+    }
+
     fn deadletter_ref(&self) -> ActorRef<Never> {
         self.component.system().actor_ref()
     }
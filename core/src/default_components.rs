@@ -1,5 +1,6 @@
 use super::*;
 use crate::{
+    dispatch::{network_status::NetworkStatusPort, reachability::ReachabilityPort},
     messaging::{DispatchEnvelope, NetMessage},
     timer::timer_manager::TimerRefFactory,
 };
@@ -117,6 +118,28 @@ where
         self.dispatcher.on_definition(|cd| cd.system_path())
     }
 
+    fn connect_reachability_port(&self, port: &mut RequiredPort<ReachabilityPort>) -> bool {
+        self.dispatcher
+            .on_definition(|cd| match cd.reachability_port() {
+                Some(provided) => {
+                    biconnect_ports(provided, port);
+                    true
+                }
+                None => false,
+            })
+    }
+
+    fn connect_network_status_port(&self, port: &mut RequiredPort<NetworkStatusPort>) -> bool {
+        self.dispatcher
+            .on_definition(|cd| match cd.network_status_port() {
+                Some(provided) => {
+                    biconnect_ports(provided, port);
+                    true
+                }
+                None => false,
+            })
+    }
+
     fn start(&self, system: &KompactSystem) -> () {
         system.start(&self.deadletter_box);
         system.start(&self.dispatcher);
@@ -214,15 +237,23 @@ impl Actor for LocalDispatcher {
             "LocalDispatcher received {:?}, but doesn't know what to do with it (hint: implement dispatching ;)",
             msg,
         );
-        if let DispatchEnvelope::Registration(RegistrationEnvelope { promise, .. }) = msg {
-            if let RegistrationPromise::Fulfil(p) = promise {
-                p.fulfil(Err(RegistrationError::Unsupported))
+        match msg {
+            DispatchEnvelope::Registration(RegistrationEnvelope { promise, .. }) => {
+                if let RegistrationPromise::Fulfil(p) = promise {
+                    p.fulfil(Err(RegistrationError::Unsupported))
+                        .unwrap_or_else(|e| {
+                            error!(self.ctx.log(), "Could not notify listeners: {:?}", e)
+                        });
+                }
+            }
+            DispatchEnvelope::Ask { promise, .. } => {
+                promise
+                    .fulfil(Err(AskError::Unsupported))
                     .unwrap_or_else(|e| {
                         error!(self.ctx.log(), "Could not notify listeners: {:?}", e)
                     });
             }
-        } else {
-            error!(self.ctx.log(), "Ignoring message {:?}.", msg);
+            msg => error!(self.ctx.log(), "Ignoring message {:?}.", msg),
         }
         Handled::Ok
     }
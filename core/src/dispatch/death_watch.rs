@@ -0,0 +1,246 @@
+use crate::{
+    actors::{ActorPath, SystemPath},
+    serialisation::{serialisation_ids, Deserialiser, SerError, SerId, Serialisable},
+};
+use bytes::{Buf, BufMut};
+use std::any::Any;
+use uuid::Uuid;
+
+/// The notification a component receives when an actor it [watches](crate::component::ComponentContext::watch)
+/// stops existing
+///
+/// It arrives as a [network message](crate::messaging::NetMessage) sent by the watched path.
+///
+/// # Example
+///
+/// ```
+/// use kompact::prelude::*;
+///
+/// #[derive(ComponentDefinition)]
+/// struct Watcher {
+///     ctx: ComponentContext<Self>,
+///     watched: ActorPath,
+/// }
+/// impl ComponentLifecycle for Watcher {
+///     fn on_start(&mut self) -> Handled {
+///         self.ctx.watch(&self.watched);
+///         Handled::Ok
+///     }
+/// }
+/// impl Actor for Watcher {
+///     type Message = Never;
+///
+///     fn receive_local(&mut self, _msg: Self::Message) -> Handled {
+///         unreachable!("Can't instantiate Never type!");
+///     }
+///
+///     fn receive_network(&mut self, msg: NetMessage) -> Handled {
+///         match_deser!(msg; {
+///             terminated: Terminated [Terminated] => {
+///                 info!(self.log(), "{} is gone: {:?}", terminated.path, terminated.reason);
+///             },
+///             !Err(e) => error!(self.log(), "Could not deserialise message: {:?}", e),
+///         });
+///         Handled::Ok
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terminated {
+    /// The watched path
+    pub path: ActorPath,
+    /// Why the path stopped existing
+    pub reason: TerminationReason,
+}
+
+/// The reasons for a [Terminated] notification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminationReason {
+    /// The actor was killed, faulted, or another actor was registered at its path
+    Stopped,
+    /// There was no actor at the path when the watch request arrived
+    NotFound,
+    /// The connection to the actor's system was lost, or the system became unreachable
+    ///
+    /// The actor may well be alive, but there is no way of telling anymore.
+    Unreachable,
+}
+
+impl TerminationReason {
+    fn from_u8(value: u8) -> Result<Self, SerError> {
+        match value {
+            0 => Ok(TerminationReason::Stopped),
+            1 => Ok(TerminationReason::NotFound),
+            2 => Ok(TerminationReason::Unreachable),
+            _ => Err(SerError::InvalidData(format!(
+                "Unknown termination reason {}",
+                value
+            ))),
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            TerminationReason::Stopped => 0,
+            TerminationReason::NotFound => 1,
+            TerminationReason::Unreachable => 2,
+        }
+    }
+}
+
+impl Serialisable for Terminated {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::TERMINATED
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.path.size_hint().map(|size| size + 1)
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        self.path.serialise(buf)?;
+        buf.put_u8(self.reason.as_u8());
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<Terminated> for Terminated {
+    const SER_ID: SerId = serialisation_ids::TERMINATED;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<Terminated, SerError> {
+        let path = ActorPath::deserialise(buf)?;
+        if buf.remaining() < 1 {
+            return Err(SerError::InvalidData(
+                "Could not get termination reason".into(),
+            ));
+        }
+        let reason = TerminationReason::from_u8(buf.get_u8())?;
+        Ok(Terminated { path, reason })
+    }
+}
+
+/// The unique path of the [NetworkDispatcher](super::NetworkDispatcher) of the system at `system`
+///
+/// Dispatchers register themselves under the nil id, so they can tell each other about watched actors.
+pub(crate) fn dispatcher_path(system: SystemPath) -> ActorPath {
+    ActorPath::from((system, Uuid::nil()))
+}
+
+/// The messages dispatchers exchange about watched actors, besides [Terminated] notifications
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum WatchMessage {
+    /// Asks the receiving dispatcher to report when the actor at the path stops existing
+    Watch(ActorPath),
+    /// Cancels an earlier `Watch`
+    Unwatch(ActorPath),
+}
+
+impl Serialisable for WatchMessage {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::WATCH
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self {
+            WatchMessage::Watch(path) | WatchMessage::Unwatch(path) => {
+                path.size_hint().map(|size| size + 1)
+            }
+        }
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        match self {
+            WatchMessage::Watch(path) => {
+                buf.put_u8(0);
+                path.serialise(buf)
+            }
+            WatchMessage::Unwatch(path) => {
+                buf.put_u8(1);
+                path.serialise(buf)
+            }
+        }
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<WatchMessage> for WatchMessage {
+    const SER_ID: SerId = serialisation_ids::WATCH;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<WatchMessage, SerError> {
+        if buf.remaining() < 1 {
+            return Err(SerError::InvalidData(
+                "Could not get watch message tag".into(),
+            ));
+        }
+        match buf.get_u8() {
+            0 => ActorPath::deserialise(buf).map(WatchMessage::Watch),
+            1 => ActorPath::deserialise(buf).map(WatchMessage::Unwatch),
+            tag => Err(SerError::InvalidData(format!(
+                "Unknown watch message tag {}",
+                tag
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::{NamedPath, Transport};
+    use bytes::BytesMut;
+
+    fn system() -> SystemPath {
+        SystemPath::new(Transport::TCP, "127.0.0.1".parse().unwrap(), 8080)
+    }
+
+    #[test]
+    fn terminated_roundtrip() {
+        let paths = vec![
+            ActorPath::from((system(), Uuid::new_v4())),
+            ActorPath::Named(NamedPath::with_system(
+                system(),
+                vec!["a".into(), "b".into()],
+            )),
+        ];
+        let reasons = [
+            TerminationReason::Stopped,
+            TerminationReason::NotFound,
+            TerminationReason::Unreachable,
+        ];
+        for path in paths {
+            for reason in reasons.iter() {
+                let terminated = Terminated {
+                    path: path.clone(),
+                    reason: *reason,
+                };
+                let mut buf = BytesMut::with_capacity(terminated.size_hint().unwrap());
+                terminated.serialise(&mut buf).expect("serialise");
+                let deserialised = Terminated::deserialise(&mut buf.freeze()).expect("deserialise");
+                assert_eq!(terminated, deserialised);
+            }
+        }
+    }
+
+    #[test]
+    fn watch_message_roundtrip() {
+        let path = ActorPath::from((system(), Uuid::new_v4()));
+        for msg in [
+            WatchMessage::Watch(path.clone()),
+            WatchMessage::Unwatch(path),
+        ]
+        .iter()
+        {
+            let mut buf = BytesMut::with_capacity(msg.size_hint().unwrap());
+            msg.serialise(&mut buf).expect("serialise");
+            let deserialised = WatchMessage::deserialise(&mut buf.freeze()).expect("deserialise");
+            assert_eq!(msg, &deserialised);
+        }
+    }
+}
@@ -1,10 +1,17 @@
 use super::*;
 
 use crate::{
-    actors::{Actor, ActorPath, Dispatcher, DynActorRef, SystemPath, Transport},
+    actors::{
+        is_hostname, Actor, ActorPath, Address, Dispatcher, DynActorRef, SystemPath, Transport,
+    },
     component::{Component, ComponentContext, ExecuteResult},
 };
-use std::{net::SocketAddr, pin::Pin, sync::Arc};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
 
 use crate::{
     actors::NamedPath,
@@ -22,25 +29,69 @@ use crate::{
         RegistrationEvent,
         RegistrationPromise,
     },
-    net::{buffers::*, events::NetworkEvent, ConnectionState, NetworkBridgeErr},
-    timer::timer_manager::Timer,
+    net::{
+        auth::Authenticator,
+        buffers::*,
+        compression::Compression,
+        events::NetworkEvent,
+        resolver::{DnsResolver, Resolver, ResolverCache},
+        retry::{RetryPolicy, RetryStrategy},
+        BridgeConfig,
+        ConnectionState,
+        NetworkBridgeErr,
+    },
+    supervision::{LifecycleEvent, LifecycleWatch},
+    timer::timer_manager::{ScheduledTimer, Timer},
 };
 use arc_swap::ArcSwap;
+use death_watch::{dispatcher_path, Terminated, TerminationReason, WatchMessage};
 use futures::{
     self,
     task::{Context, Poll},
 };
 use lookup::{ActorLookup, ActorStore, InsertResult, LookupResult};
-use queue_manager::QueueManager;
-use rustc_hash::FxHashMap;
-use std::{collections::VecDeque, io::ErrorKind, time::Duration};
+use network_status::{NetworkStatus, NetworkStatusPort, NetworkStatusRequest};
+use queue_manager::{OverflowPolicy, QueueManager, Undeliverable};
+use reachability::{ReachabilityIndication, ReachabilityPort, ReachabilityRequest};
+use reliable::{ReliableAck, ReliableMessage, ReliableReceiver, ReliableRelease, ReliableSender};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use std::{
+    collections::{hash_map::Entry, VecDeque},
+    hash::{Hash, Hasher},
+    io::{self, ErrorKind},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
 
+pub mod death_watch;
 pub mod lookup;
+pub mod network_status;
 pub mod queue_manager;
+pub mod reachability;
+pub mod reliable;
 
 // Default values for network config.
 const RETRY_CONNECTIONS_INTERVAL: u64 = 5000;
 const MAX_RETRY_ATTEMPTS: u8 = 10;
+const FLOW_CONTROL_WINDOW: u32 = 1024;
+const UDP_RETRANSMIT_INTERVAL: u64 = 200;
+const MAX_UDP_RETRANSMITS: u8 = 10;
+const UDP_SEND_WINDOW: usize = 1024;
+const UDP_REASSEMBLY_TIMEOUT: u64 = 5000;
+const MAX_UDP_PARTIAL_MESSAGES: usize = 64;
+const MAX_UDP_PARTIAL_BYTES: usize = 16 * 1024 * 1024;
+const HEARTBEAT_INTERVAL: u64 = 1000;
+const ACCEPTABLE_HEARTBEAT_PAUSE: u64 = 3000;
+const PHI_THRESHOLD: f64 = 8.0;
+const REDELIVERY_INTERVAL: u64 = 1000;
+const MAX_REDELIVERED_MESSAGES: usize = 1024;
+const MAX_UNACKED_RELIABLE_MESSAGES: usize = 1024;
+const DNS_CACHE_TTL: u64 = 30000;
+const NETWORK_THREADS: usize = 1;
+const COMPRESSION_THRESHOLD: usize = 1024;
+const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+const MAX_STREAMED_SIZE: usize = 256 * 1024 * 1024;
+const MAX_PARTIAL_STREAMS: usize = 16;
 
 type NetHashMap<K, V> = FxHashMap<K, V>;
 
@@ -61,12 +112,48 @@ type NetHashMap<K, V> = FxHashMap<K, V>;
 #[derive(Clone, Debug)]
 pub struct NetworkConfig {
     addr: SocketAddr,
+    advertised_addr: Option<SocketAddr>,
+    advertised_hostname: Option<String>,
     transport: Transport,
     buffer_config: BufferConfig,
     custom_allocator: Option<Arc<dyn ChunkAllocator>>,
     tcp_nodelay: bool,
     max_connection_retry_attempts: u8,
     connection_retry_interval: u64,
+    flow_control_window: u32,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    #[cfg(feature = "tls")]
+    tls_config: Option<crate::net::tls::TlsConfig>,
+    uds_path: Option<PathBuf>,
+    reliable_udp: bool,
+    udp_retransmit_interval: u64,
+    max_udp_retransmits: u8,
+    udp_send_window: usize,
+    udp_reassembly_timeout: u64,
+    max_udp_partial_messages: usize,
+    max_udp_partial_bytes: usize,
+    heartbeat_interval: u64,
+    acceptable_heartbeat_pause: u64,
+    phi_threshold: f64,
+    bridge_config: BridgeConfig,
+    redelivery_interval: u64,
+    max_redelivered_messages: usize,
+    max_unacked_reliable_messages: usize,
+    resolver: Arc<dyn Resolver>,
+    dns_cache_ttl: u64,
+    network_threads: usize,
+    compression: Option<Compression>,
+    compression_threshold: usize,
+    max_decompressed_size: usize,
+    stream_threshold: Option<usize>,
+    max_streamed_size: usize,
+    max_partial_streams: usize,
+    max_queued_messages: Option<usize>,
+    max_queued_bytes: Option<usize>,
+    queue_overflow_policy: OverflowPolicy,
+    idle_timeout: Option<u64>,
+    max_inbound_connections: Option<usize>,
+    max_outbound_connections: Option<usize>,
 }
 
 impl NetworkConfig {
@@ -75,12 +162,48 @@ impl NetworkConfig {
     pub fn new(addr: SocketAddr) -> Self {
         NetworkConfig {
             addr,
+            advertised_addr: None,
+            advertised_hostname: None,
             transport: Transport::TCP,
             buffer_config: BufferConfig::default(),
             custom_allocator: None,
             tcp_nodelay: true,
             max_connection_retry_attempts: MAX_RETRY_ATTEMPTS,
             connection_retry_interval: RETRY_CONNECTIONS_INTERVAL,
+            flow_control_window: FLOW_CONTROL_WINDOW,
+            authenticator: None,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            uds_path: None,
+            reliable_udp: false,
+            udp_retransmit_interval: UDP_RETRANSMIT_INTERVAL,
+            max_udp_retransmits: MAX_UDP_RETRANSMITS,
+            udp_send_window: UDP_SEND_WINDOW,
+            udp_reassembly_timeout: UDP_REASSEMBLY_TIMEOUT,
+            max_udp_partial_messages: MAX_UDP_PARTIAL_MESSAGES,
+            max_udp_partial_bytes: MAX_UDP_PARTIAL_BYTES,
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            acceptable_heartbeat_pause: ACCEPTABLE_HEARTBEAT_PAUSE,
+            phi_threshold: PHI_THRESHOLD,
+            bridge_config: BridgeConfig::default(),
+            redelivery_interval: REDELIVERY_INTERVAL,
+            max_redelivered_messages: MAX_REDELIVERED_MESSAGES,
+            max_unacked_reliable_messages: MAX_UNACKED_RELIABLE_MESSAGES,
+            resolver: Arc::new(DnsResolver),
+            dns_cache_ttl: DNS_CACHE_TTL,
+            network_threads: NETWORK_THREADS,
+            compression: None,
+            compression_threshold: COMPRESSION_THRESHOLD,
+            max_decompressed_size: MAX_DECOMPRESSED_SIZE,
+            stream_threshold: None,
+            max_streamed_size: MAX_STREAMED_SIZE,
+            max_partial_streams: MAX_PARTIAL_STREAMS,
+            max_queued_messages: None,
+            max_queued_bytes: None,
+            queue_overflow_policy: OverflowPolicy::DeadLetter,
+            idle_timeout: None,
+            max_inbound_connections: None,
+            max_outbound_connections: None,
         }
     }
 
@@ -90,12 +213,48 @@ impl NetworkConfig {
         buffer_config.validate();
         NetworkConfig {
             addr,
+            advertised_addr: None,
+            advertised_hostname: None,
             transport: Transport::TCP,
             buffer_config,
             custom_allocator: None,
             tcp_nodelay: true,
             max_connection_retry_attempts: MAX_RETRY_ATTEMPTS,
             connection_retry_interval: RETRY_CONNECTIONS_INTERVAL,
+            flow_control_window: FLOW_CONTROL_WINDOW,
+            authenticator: None,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            uds_path: None,
+            reliable_udp: false,
+            udp_retransmit_interval: UDP_RETRANSMIT_INTERVAL,
+            max_udp_retransmits: MAX_UDP_RETRANSMITS,
+            udp_send_window: UDP_SEND_WINDOW,
+            udp_reassembly_timeout: UDP_REASSEMBLY_TIMEOUT,
+            max_udp_partial_messages: MAX_UDP_PARTIAL_MESSAGES,
+            max_udp_partial_bytes: MAX_UDP_PARTIAL_BYTES,
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            acceptable_heartbeat_pause: ACCEPTABLE_HEARTBEAT_PAUSE,
+            phi_threshold: PHI_THRESHOLD,
+            bridge_config: BridgeConfig::default(),
+            redelivery_interval: REDELIVERY_INTERVAL,
+            max_redelivered_messages: MAX_REDELIVERED_MESSAGES,
+            max_unacked_reliable_messages: MAX_UNACKED_RELIABLE_MESSAGES,
+            resolver: Arc::new(DnsResolver),
+            dns_cache_ttl: DNS_CACHE_TTL,
+            network_threads: NETWORK_THREADS,
+            compression: None,
+            compression_threshold: COMPRESSION_THRESHOLD,
+            max_decompressed_size: MAX_DECOMPRESSED_SIZE,
+            stream_threshold: None,
+            max_streamed_size: MAX_STREAMED_SIZE,
+            max_partial_streams: MAX_PARTIAL_STREAMS,
+            max_queued_messages: None,
+            max_queued_bytes: None,
+            queue_overflow_policy: OverflowPolicy::DeadLetter,
+            idle_timeout: None,
+            max_inbound_connections: None,
+            max_outbound_connections: None,
         }
     }
 
@@ -109,12 +268,48 @@ impl NetworkConfig {
         buffer_config.validate();
         NetworkConfig {
             addr,
+            advertised_addr: None,
+            advertised_hostname: None,
             transport: Transport::TCP,
             buffer_config,
             custom_allocator: Some(custom_allocator),
             tcp_nodelay: true,
             max_connection_retry_attempts: MAX_RETRY_ATTEMPTS,
             connection_retry_interval: RETRY_CONNECTIONS_INTERVAL,
+            flow_control_window: FLOW_CONTROL_WINDOW,
+            authenticator: None,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            uds_path: None,
+            reliable_udp: false,
+            udp_retransmit_interval: UDP_RETRANSMIT_INTERVAL,
+            max_udp_retransmits: MAX_UDP_RETRANSMITS,
+            udp_send_window: UDP_SEND_WINDOW,
+            udp_reassembly_timeout: UDP_REASSEMBLY_TIMEOUT,
+            max_udp_partial_messages: MAX_UDP_PARTIAL_MESSAGES,
+            max_udp_partial_bytes: MAX_UDP_PARTIAL_BYTES,
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            acceptable_heartbeat_pause: ACCEPTABLE_HEARTBEAT_PAUSE,
+            phi_threshold: PHI_THRESHOLD,
+            bridge_config: BridgeConfig::default(),
+            redelivery_interval: REDELIVERY_INTERVAL,
+            max_redelivered_messages: MAX_REDELIVERED_MESSAGES,
+            max_unacked_reliable_messages: MAX_UNACKED_RELIABLE_MESSAGES,
+            resolver: Arc::new(DnsResolver),
+            dns_cache_ttl: DNS_CACHE_TTL,
+            network_threads: NETWORK_THREADS,
+            compression: None,
+            compression_threshold: COMPRESSION_THRESHOLD,
+            max_decompressed_size: MAX_DECOMPRESSED_SIZE,
+            stream_threshold: None,
+            max_streamed_size: MAX_STREAMED_SIZE,
+            max_partial_streams: MAX_PARTIAL_STREAMS,
+            max_queued_messages: None,
+            max_queued_bytes: None,
+            queue_overflow_policy: OverflowPolicy::DeadLetter,
+            idle_timeout: None,
+            max_inbound_connections: None,
+            max_outbound_connections: None,
         }
     }
 
@@ -124,6 +319,48 @@ impl NetworkConfig {
         self
     }
 
+    /// Advertises this system to remote systems as reachable at `addr`,
+    /// instead of at the socket address it is bound to.
+    ///
+    /// The advertised address is used in this system's [SystemPath](SystemPath),
+    /// and thus in all [ActorPaths](ActorPath) it hands out, as well as in its handshakes.
+    /// This is necessary when the bound address is not reachable from the outside,
+    /// for example when binding `0.0.0.0` in a container or running behind a NAT.
+    ///
+    /// If the port of `addr` is 0, the port the system actually bound is advertised.
+    pub fn set_advertised_addr(&mut self, addr: SocketAddr) {
+        self.advertised_addr = Some(addr);
+    }
+
+    /// The address this system is advertised at, if it differs from the one it is bound to.
+    pub fn get_advertised_addr(&self) -> Option<SocketAddr> {
+        self.advertised_addr
+    }
+
+    /// Addresses this system by `hostname` in its [SystemPath](SystemPath), instead of by IP address.
+    ///
+    /// The port of the path is still the advertised (or bound) port.
+    /// Remote systems must be able to [resolve](NetworkConfig::set_resolver) `hostname`
+    /// to the advertised address.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `hostname` is not a valid DNS hostname.
+    pub fn set_advertised_hostname<S: Into<String>>(&mut self, hostname: S) {
+        let hostname = hostname.into();
+        assert!(
+            is_hostname(&hostname),
+            "{} is not a valid hostname",
+            hostname
+        );
+        self.advertised_hostname = Some(hostname);
+    }
+
+    /// The hostname this system is addressed by, if any.
+    pub fn get_advertised_hostname(&self) -> Option<&str> {
+        self.advertised_hostname.as_deref()
+    }
+
     /// Complete the configuration and provide a function that produces a network dispatcher
     ///
     /// Returns the appropriate function type for use
@@ -164,6 +401,9 @@ impl NetworkConfig {
     /// Configures how many attempts at re-establishing a connection will be made before giving up
     /// and discarding the enqueued outgoing messages.
     ///
+    /// Only applies if no [retry strategy](BridgeConfig::set_retry_strategy) is configured.
+    /// The network thread also uses it to limit its attempts at getting a buffer for a channel.
+    ///
     /// Default value is 10 times.
     pub fn set_max_connection_retry_attempts(&mut self, count: u8) {
         self.max_connection_retry_attempts = count;
@@ -176,6 +416,9 @@ impl NetworkConfig {
 
     /// Configures how long to wait (in ms) between attempts at establishing a connection.
     ///
+    /// Only applies if no [retry strategy](BridgeConfig::set_retry_strategy) is configured.
+    /// The network thread also waits this long between attempts at getting a buffer while it is out of them.
+    ///
     /// Default value is 5000 ms.
     pub fn set_connection_retry_interval(&mut self, milliseconds: u64) {
         self.connection_retry_interval = milliseconds;
@@ -185,6 +428,628 @@ impl NetworkConfig {
     pub fn get_connection_retry_interval(&self) -> u64 {
         self.connection_retry_interval
     }
+
+    /// Configures how many data frames a remote host may send on a channel before it must
+    /// wait for this host to grant it more credits.
+    ///
+    /// The credit of a data frame is only granted again once the message it carried was handled
+    /// or dropped by the receiving actor, so actors which fall behind hold back the remote host.
+    /// Senders which run out of credits hold back further messages in the `NetworkDispatcher`
+    /// until the receiver has caught up. Setting the window to `0` disables flow control,
+    /// granting remote hosts unlimited credits.
+    ///
+    /// Default value is 1024 frames.
+    pub fn set_flow_control_window(&mut self, credits: u32) {
+        self.flow_control_window = credits;
+    }
+
+    /// Returns the number of credits granted to remote hosts for sending data frames.
+    pub fn get_flow_control_window(&self) -> u32 {
+        self.flow_control_window
+    }
+
+    /// Replaces the configuration of the network bridge, such as its [retry strategy](BridgeConfig::set_retry_strategy).
+    ///
+    /// Settings left unset fall back to the `bridge_config` section of the system's HOCON config.
+    pub fn set_bridge_config(&mut self, bridge_config: BridgeConfig) {
+        self.bridge_config = bridge_config;
+    }
+
+    /// Returns the configuration of the network bridge.
+    pub fn get_bridge_config(&self) -> &BridgeConfig {
+        &self.bridge_config
+    }
+
+    /// Requires all remote hosts to authenticate themselves with `authenticator` when connecting.
+    ///
+    /// Hosts failing to authenticate are disconnected before they can send any messages.
+    /// All hosts in the system must be configured with compatible authenticators.
+    ///
+    /// Default is no authentication.
+    pub fn set_authenticator(&mut self, authenticator: Arc<dyn Authenticator>) {
+        self.authenticator = Some(authenticator);
+    }
+
+    /// Returns the authenticator for remote hosts, if authentication is required.
+    pub fn get_authenticator(&self) -> &Option<Arc<dyn Authenticator>> {
+        &self.authenticator
+    }
+
+    /// Encrypts all TCP channels with TLS, using the certificates in `tls_config`.
+    ///
+    /// All hosts in the system must be configured with TLS, as plaintext and TLS channels can not talk to each other.
+    ///
+    /// Default is no encryption. Requires the `tls` feature.
+    #[cfg(feature = "tls")]
+    pub fn set_tls_config(&mut self, tls_config: crate::net::tls::TlsConfig) {
+        self.tls_config = Some(tls_config);
+    }
+
+    /// Returns the TLS configuration, if the TCP channels are encrypted.
+    #[cfg(feature = "tls")]
+    pub fn get_tls_config(&self) -> Option<&crate::net::tls::TlsConfig> {
+        self.tls_config.as_ref()
+    }
+
+    /// Listens on a Unix domain socket at `path` and addresses this system via [UDS](Transport::UDS).
+    ///
+    /// Actor paths of this system will look like `"uds://[/path/to/socket]/my-actor"`,
+    /// so that systems on the same host send messages to it without going through TCP loopback.
+    /// Systems on other hosts can not reach actors by such paths.
+    /// A socket file left behind by a system which is no longer running is replaced.
+    ///
+    /// Default is no Unix domain socket. Only supported on Unix platforms.
+    pub fn set_uds_path<P: Into<PathBuf>>(&mut self, path: P) {
+        self.uds_path = Some(path.into());
+    }
+
+    /// Returns the path of the Unix domain socket, if this system is addressed via [UDS](Transport::UDS).
+    pub fn get_uds_path(&self) -> Option<&Path> {
+        self.uds_path.as_deref()
+    }
+
+    /// Sends [UDP](Transport::UDP) messages reliably and in order.
+    ///
+    /// Every datagram is numbered and retransmitted until the receiving host acknowledges it.
+    /// The receiver drops duplicates and delivers the messages for each actor in the order they were sent,
+    /// while messages to different actors never wait for each other.
+    /// Receivers always understand reliable datagrams, so only the sending side needs to enable this.
+    ///
+    /// Default is `false`, i.e. UDP messages may be lost, duplicated or reordered.
+    pub fn set_reliable_udp(&mut self, reliable: bool) {
+        self.reliable_udp = reliable;
+    }
+
+    /// Returns `true` if UDP messages are sent reliably and in order.
+    pub fn get_reliable_udp(&self) -> bool {
+        self.reliable_udp
+    }
+
+    /// Configures how long to wait (in ms) for the acknowledgement of a reliable UDP message
+    /// before sending it again. The interval doubles with every retransmission of the same message.
+    ///
+    /// Default value is 200 ms.
+    pub fn set_udp_retransmit_interval(&mut self, milliseconds: u64) {
+        self.udp_retransmit_interval = milliseconds;
+    }
+
+    /// How long (in ms) the system waits for an acknowledgement before retransmitting a reliable UDP message.
+    pub fn get_udp_retransmit_interval(&self) -> u64 {
+        self.udp_retransmit_interval
+    }
+
+    /// Configures how many times an unacknowledged reliable UDP message is retransmitted
+    /// before the system gives up on all pending messages to that host.
+    ///
+    /// Default value is 10 times.
+    pub fn set_max_udp_retransmits(&mut self, count: u8) {
+        self.max_udp_retransmits = count;
+    }
+
+    /// Returns the number of times a reliable UDP message is retransmitted before giving up.
+    pub fn get_max_udp_retransmits(&self) -> u8 {
+        self.max_udp_retransmits
+    }
+
+    /// Configures how many reliable UDP messages to a single host may be unacknowledged at once.
+    ///
+    /// Messages beyond the window wait until acknowledgements make room for them,
+    /// and count towards the [max_queued_messages](NetworkConfig::set_max_queued_messages) while doing so.
+    /// Receivers only accept up to 4096 messages beyond the last one they received without gaps,
+    /// so larger windows are capped at that.
+    ///
+    /// Default value is 1024 messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `messages` is 0.
+    pub fn set_udp_send_window(&mut self, messages: usize) {
+        assert!(messages > 0, "The UDP send window must be positive");
+        self.udp_send_window = messages;
+    }
+
+    /// Returns how many reliable UDP messages to a single host may be unacknowledged at once.
+    pub fn get_udp_send_window(&self) -> usize {
+        self.udp_send_window
+    }
+
+    /// Configures how long (in ms) the fragments of a UDP message too large for a single datagram are kept
+    /// while waiting for the rest of its fragments to arrive.
+    ///
+    /// Default value is 5000 ms.
+    pub fn set_udp_reassembly_timeout(&mut self, milliseconds: u64) {
+        self.udp_reassembly_timeout = milliseconds;
+    }
+
+    /// How long (in ms) the system waits for the missing fragments of a UDP message before dropping it.
+    pub fn get_udp_reassembly_timeout(&self) -> u64 {
+        self.udp_reassembly_timeout
+    }
+
+    /// Configures how many UDP messages from a single host may be missing fragments at once.
+    ///
+    /// Once the limit is reached, the oldest of them is dropped to make room for the next one.
+    ///
+    /// Default value is 64 messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is 0.
+    pub fn set_max_udp_partial_messages(&mut self, count: usize) {
+        assert!(count > 0, "The partial UDP message limit must be positive");
+        self.max_udp_partial_messages = count;
+    }
+
+    /// Returns how many UDP messages from a single host may be missing fragments at once.
+    pub fn get_max_udp_partial_messages(&self) -> usize {
+        self.max_udp_partial_messages
+    }
+
+    /// Configures how many bytes of fragments are kept at most for the UDP messages of a single host
+    /// which are missing fragments.
+    ///
+    /// Once the limit is exceeded, the oldest of them are dropped until the fragments fit again.
+    ///
+    /// Default value is 16 MiB.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is 0.
+    pub fn set_max_udp_partial_bytes(&mut self, bytes: usize) {
+        assert!(bytes > 0, "The partial UDP byte limit must be positive");
+        self.max_udp_partial_bytes = bytes;
+    }
+
+    /// Returns how many bytes of fragments are kept at most for the partial UDP messages of a single host.
+    pub fn get_max_udp_partial_bytes(&self) -> usize {
+        self.max_udp_partial_bytes
+    }
+
+    /// Configures how often (in ms) heartbeats are sent on idle TCP channels,
+    /// and how often the failure detector is consulted about each connected system.
+    ///
+    /// Default value is 1000 ms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `milliseconds` is 0.
+    pub fn set_heartbeat_interval(&mut self, milliseconds: u64) {
+        assert!(milliseconds > 0, "The heartbeat interval must be positive");
+        self.heartbeat_interval = milliseconds;
+    }
+
+    /// How often (in ms) heartbeats are sent on idle TCP channels.
+    pub fn get_heartbeat_interval(&self) -> u64 {
+        self.heartbeat_interval
+    }
+
+    /// Configures how long (in ms) heartbeats may be delayed beyond their usual interval,
+    /// before the failure detector starts to suspect a remote system in earnest.
+    ///
+    /// Default value is 3000 ms.
+    pub fn set_acceptable_heartbeat_pause(&mut self, milliseconds: u64) {
+        self.acceptable_heartbeat_pause = milliseconds;
+    }
+
+    /// How long (in ms) heartbeats may be delayed before the failure detector suspects a remote system.
+    pub fn get_acceptable_heartbeat_pause(&self) -> u64 {
+        self.acceptable_heartbeat_pause
+    }
+
+    /// Configures the suspicion level (phi) of the failure detector above which a remote system is considered unreachable.
+    ///
+    /// A phi of 1 corresponds to a 10% chance of the failure detector being wrong, 2 to 1%, 3 to 0.1%, and so on.
+    ///
+    /// Default value is 8.0.
+    pub fn set_phi_threshold(&mut self, phi: f64) {
+        self.phi_threshold = phi;
+    }
+
+    /// The suspicion level (phi) above which a remote system is considered unreachable.
+    pub fn get_phi_threshold(&self) -> f64 {
+        self.phi_threshold
+    }
+
+    /// Configures how long (in ms) the dispatcher waits for the acknowledgement of a message sent
+    /// via [tell_reliably](ActorPath::tell_reliably), before it sends the message again.
+    ///
+    /// Unacknowledged messages are also sent again whenever a connection is re-established.
+    ///
+    /// Default value is 1000 ms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `milliseconds` is 0.
+    pub fn set_redelivery_interval(&mut self, milliseconds: u64) {
+        assert!(milliseconds > 0, "The redelivery interval must be positive");
+        self.redelivery_interval = milliseconds;
+    }
+
+    /// How long (in ms) the dispatcher waits for the acknowledgement of a reliable message before sending it again.
+    pub fn get_redelivery_interval(&self) -> u64 {
+        self.redelivery_interval
+    }
+
+    /// Configures how many unacknowledged reliable messages the dispatcher sends again at once,
+    /// per [redelivery interval](NetworkConfig::set_redelivery_interval) or re-established connection.
+    ///
+    /// Messages over the limit are sent again in a later interval.
+    /// Messages to systems which are not connected are only sent again once they are.
+    ///
+    /// Default value is 1024.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn set_max_redelivered_messages(&mut self, max: usize) {
+        assert!(max > 0, "At least one message must be redelivered");
+        self.max_redelivered_messages = max;
+    }
+
+    /// How many unacknowledged reliable messages the dispatcher sends again at once.
+    pub fn get_max_redelivered_messages(&self) -> usize {
+        self.max_redelivered_messages
+    }
+
+    /// Configures how many messages sent via [tell_reliably](ActorPath::tell_reliably)
+    /// from one actor to another may be unacknowledged at once.
+    ///
+    /// Further messages between the same actors are rejected until acknowledgements make room for them,
+    /// and handled according to the [overflow policy](NetworkConfig::set_queue_overflow_policy),
+    /// except that no unacknowledged message is dropped to make room for them.
+    ///
+    /// Default value is 1024.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn set_max_unacked_reliable_messages(&mut self, max: usize) {
+        assert!(
+            max > 0,
+            "At least one reliable message must be unacknowledged"
+        );
+        self.max_unacked_reliable_messages = max;
+    }
+
+    /// How many reliable messages from one actor to another may be unacknowledged at once.
+    pub fn get_max_unacked_reliable_messages(&self) -> usize {
+        self.max_unacked_reliable_messages
+    }
+
+    /// Resolves the hostnames in [system paths](SystemPath) with `resolver`.
+    ///
+    /// Default is the [DnsResolver](crate::net::resolver::DnsResolver) of the operating system.
+    pub fn set_resolver(&mut self, resolver: Arc<dyn Resolver>) {
+        self.resolver = resolver;
+    }
+
+    /// Returns the resolver for hostnames in system paths.
+    pub fn get_resolver(&self) -> &Arc<dyn Resolver> {
+        &self.resolver
+    }
+
+    /// Configures how long (in ms) resolved hostnames are cached.
+    ///
+    /// Hostnames are resolved again earlier, if connecting to their cached address fails.
+    ///
+    /// Default value is 30000 ms.
+    ///
+    /// # Panics
+    ///
+    /// If `milliseconds` is 0.
+    pub fn set_dns_cache_ttl(&mut self, milliseconds: u64) {
+        assert!(milliseconds > 0, "The DNS cache TTL must be positive");
+        self.dns_cache_ttl = milliseconds;
+    }
+
+    /// How long (in ms) resolved hostnames are cached.
+    pub fn get_dns_cache_ttl(&self) -> u64 {
+        self.dns_cache_ttl
+    }
+
+    /// Configures how many network threads drive the channels to remote systems.
+    ///
+    /// Channels are distributed over the threads by the address of the remote system,
+    /// so all messages to the same system keep going through the same thread in order.
+    /// Only the first thread listens for incoming connections, as well as UDP and Unix domain socket traffic.
+    ///
+    /// Default value is 1.
+    ///
+    /// # Panics
+    ///
+    /// If `threads` is 0.
+    pub fn set_network_threads(&mut self, threads: usize) {
+        assert!(threads > 0, "There must be at least one network thread");
+        self.network_threads = threads;
+    }
+
+    /// How many network threads drive the channels to remote systems.
+    pub fn get_network_threads(&self) -> usize {
+        self.network_threads
+    }
+
+    /// Compresses large data frames sent over TCP with `compression`.
+    ///
+    /// Frames are only compressed for remote hosts which can decompress them,
+    /// and only if they get smaller.
+    /// How well the frames compress is reported as [CompressionStats](NetworkStatus::CompressionStats)
+    /// on the [NetworkStatusPort].
+    ///
+    /// Default is `None`, i.e. no compression.
+    pub fn set_compression(&mut self, compression: Option<Compression>) {
+        self.compression = compression;
+    }
+
+    /// The codec large data frames are compressed with, if any.
+    pub fn get_compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    /// Configures the size (in bytes) above which data frames are compressed.
+    ///
+    /// Default value is 1024 bytes.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is 0.
+    pub fn set_compression_threshold(&mut self, bytes: usize) {
+        assert!(bytes > 0, "The compression threshold must be positive");
+        self.compression_threshold = bytes;
+    }
+
+    /// The size (in bytes) above which data frames are compressed.
+    pub fn get_compression_threshold(&self) -> usize {
+        self.compression_threshold
+    }
+
+    /// Configures the largest size (in bytes) a received frame may decompress to.
+    ///
+    /// Compressed frames claiming a larger original size are dropped before decompressing them,
+    /// so remote hosts can not make this host allocate arbitrary amounts of memory.
+    ///
+    /// Default value is 16 MiB.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is 0.
+    pub fn set_max_decompressed_size(&mut self, bytes: usize) {
+        assert!(bytes > 0, "The maximum decompressed size must be positive");
+        self.max_decompressed_size = bytes;
+    }
+
+    /// The largest size (in bytes) a received frame may decompress to.
+    pub fn get_max_decompressed_size(&self) -> usize {
+        self.max_decompressed_size
+    }
+
+    /// Configures the size (in bytes) above which lazily serialised messages are streamed.
+    ///
+    /// Streamed messages are serialised onto the heap instead of into the network buffers,
+    /// and sent as a series of fragments no larger than a
+    /// [chunk](crate::net::buffers::BufferConfig::chunk_size) each,
+    /// such that messages larger than the [BufferConfig](crate::net::buffers::BufferConfig)
+    /// allows can still be sent.
+    /// Messages are picked by their [size hint](crate::serialisation::Serialisable::size_hint).
+    /// Messages without a size hint are serialised onto the heap first to find out whether they must be streamed,
+    /// so large messages should provide one.
+    ///
+    /// Default value is the [chunk size](crate::net::buffers::BufferConfig::chunk_size)
+    /// of the [buffer config](NetworkConfig::get_buffer_config).
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is 0.
+    pub fn set_stream_threshold(&mut self, bytes: usize) {
+        assert!(bytes > 0, "The stream threshold must be positive");
+        self.stream_threshold = Some(bytes);
+    }
+
+    /// The size (in bytes) above which lazily serialised messages are streamed.
+    pub fn get_stream_threshold(&self) -> usize {
+        self.stream_threshold
+            .unwrap_or(self.buffer_config.chunk_size)
+    }
+
+    /// Configures the largest size (in bytes) of a streamed message.
+    ///
+    /// Larger messages are dropped by the sender.
+    /// Channels to remote hosts streaming larger messages are closed.
+    ///
+    /// Default value is 256 MiB.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is 0.
+    pub fn set_max_streamed_size(&mut self, bytes: usize) {
+        assert!(bytes > 0, "The maximum streamed size must be positive");
+        self.max_streamed_size = bytes;
+    }
+
+    /// The largest size (in bytes) of a streamed message.
+    pub fn get_max_streamed_size(&self) -> usize {
+        self.max_streamed_size
+    }
+
+    /// Configures how many messages a remote host may stream over a channel at the same time.
+    ///
+    /// Each incomplete message holds on to a buffer as large as the whole message.
+    /// Channels to remote hosts streaming more messages at once are closed.
+    ///
+    /// Default value is 16.
+    ///
+    /// # Panics
+    ///
+    /// If `count` is 0.
+    pub fn set_max_partial_streams(&mut self, count: usize) {
+        assert!(
+            count > 0,
+            "The maximum number of partial streams must be positive"
+        );
+        self.max_partial_streams = count;
+    }
+
+    /// How many messages a remote host may stream over a channel at the same time.
+    pub fn get_max_partial_streams(&self) -> usize {
+        self.max_partial_streams
+    }
+
+    /// Configures how many messages may be queued for a single remote host.
+    ///
+    /// Messages are queued while the connection to their destination is being established,
+    /// retried, or blocked by flow control.
+    /// What happens to messages beyond the limit is decided by the
+    /// [overflow policy](NetworkConfig::set_queue_overflow_policy).
+    ///
+    /// Default value is `None`, i.e. unlimited.
+    ///
+    /// # Panics
+    ///
+    /// If `messages` is `Some(0)`.
+    pub fn set_max_queued_messages(&mut self, messages: Option<usize>) {
+        assert!(
+            messages != Some(0),
+            "The maximum number of queued messages must be positive"
+        );
+        self.max_queued_messages = messages;
+    }
+
+    /// How many messages may be queued for a single remote host, if limited.
+    pub fn get_max_queued_messages(&self) -> Option<usize> {
+        self.max_queued_messages
+    }
+
+    /// Configures how many bytes of messages may be queued for a single remote host.
+    ///
+    /// Lazily serialised messages are counted according to their size hints.
+    /// Messages without a size hint count as 0 bytes, so only
+    /// [max_queued_messages](NetworkConfig::set_max_queued_messages) bounds them.
+    /// What happens to messages beyond the limit is decided by the
+    /// [overflow policy](NetworkConfig::set_queue_overflow_policy).
+    ///
+    /// Default value is `None`, i.e. unlimited.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is `Some(0)`.
+    pub fn set_max_queued_bytes(&mut self, bytes: Option<usize>) {
+        assert!(
+            bytes != Some(0),
+            "The maximum number of queued bytes must be positive"
+        );
+        self.max_queued_bytes = bytes;
+    }
+
+    /// How many bytes of messages may be queued for a single remote host, if limited.
+    pub fn get_max_queued_bytes(&self) -> Option<usize> {
+        self.max_queued_bytes
+    }
+
+    /// Configures what happens to messages sent to a remote host whose queue is full.
+    ///
+    /// Default value is [DeadLetter](OverflowPolicy::DeadLetter).
+    pub fn set_queue_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.queue_overflow_policy = policy;
+    }
+
+    /// What happens to messages sent to a remote host whose queue is full.
+    pub fn get_queue_overflow_policy(&self) -> OverflowPolicy {
+        self.queue_overflow_policy
+    }
+
+    /// Configures how long (in ms) a TCP channel may go without sending or receiving any messages
+    /// before it is closed.
+    ///
+    /// Idle channels are closed gracefully, and neither system tries to re-establish the connection
+    /// until it has messages to send again.
+    /// Channels are checked once every [heartbeat interval](NetworkConfig::set_heartbeat_interval),
+    /// so they may stay open for up to one interval longer.
+    ///
+    /// Default value is `None`, i.e. channels are never closed for being idle.
+    ///
+    /// # Panics
+    ///
+    /// If `milliseconds` is `Some(0)`.
+    pub fn set_idle_timeout(&mut self, milliseconds: Option<u64>) {
+        assert!(milliseconds != Some(0), "The idle timeout must be positive");
+        self.idle_timeout = milliseconds;
+    }
+
+    /// How long (in ms) a TCP channel may be idle before it is closed, if ever.
+    pub fn get_idle_timeout(&self) -> Option<u64> {
+        self.idle_timeout
+    }
+
+    /// Configures how many TCP connections accepted from remote hosts may be open at once,
+    /// across all [network threads](NetworkConfig::set_network_threads).
+    ///
+    /// Once the limit is reached, the least recently used channel without queued messages is closed
+    /// as if it was [idle](NetworkConfig::set_idle_timeout) to make room for a new connection.
+    /// As the remote host has to agree to close that channel first, the new connection is refused
+    /// meanwhile, just as when all channels have messages queued.
+    ///
+    /// Default value is `None`, i.e. unlimited.
+    ///
+    /// # Panics
+    ///
+    /// If `connections` is `Some(0)`.
+    pub fn set_max_inbound_connections(&mut self, connections: Option<usize>) {
+        assert!(
+            connections != Some(0),
+            "The maximum number of inbound connections must be positive"
+        );
+        self.max_inbound_connections = connections;
+    }
+
+    /// How many TCP connections accepted from remote hosts may be open at once across all network threads, if limited.
+    pub fn get_max_inbound_connections(&self) -> Option<usize> {
+        self.max_inbound_connections
+    }
+
+    /// Configures how many TCP connections to remote hosts may be requested at once,
+    /// across all [network threads](NetworkConfig::set_network_threads).
+    ///
+    /// Once the limit is reached, the least recently used channel without queued messages is closed
+    /// as if it was [idle](NetworkConfig::set_idle_timeout) to make room for a new connection.
+    /// As the remote host has to agree to close that channel first, the new connection is retried later
+    /// meanwhile, just as when all channels have messages queued.
+    ///
+    /// Default value is `None`, i.e. unlimited.
+    ///
+    /// # Panics
+    ///
+    /// If `connections` is `Some(0)`.
+    pub fn set_max_outbound_connections(&mut self, connections: Option<usize>) {
+        assert!(
+            connections != Some(0),
+            "The maximum number of outbound connections must be positive"
+        );
+        self.max_outbound_connections = connections;
+    }
+
+    /// How many TCP connections to remote hosts may be requested at once across all network threads, if limited.
+    pub fn get_max_outbound_connections(&self) -> Option<usize> {
+        self.max_outbound_connections
+    }
 }
 
 /// Socket defaults to `127.0.0.1:0` (i.e. a random local port) and protocol is [TCP](Transport::TCP)
@@ -192,12 +1057,48 @@ impl Default for NetworkConfig {
     fn default() -> Self {
         NetworkConfig {
             addr: "127.0.0.1:0".parse().unwrap(),
+            advertised_addr: None,
+            advertised_hostname: None,
             transport: Transport::TCP,
             buffer_config: BufferConfig::default(),
             custom_allocator: None,
             tcp_nodelay: true,
             max_connection_retry_attempts: MAX_RETRY_ATTEMPTS,
             connection_retry_interval: RETRY_CONNECTIONS_INTERVAL,
+            flow_control_window: FLOW_CONTROL_WINDOW,
+            authenticator: None,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            uds_path: None,
+            reliable_udp: false,
+            udp_retransmit_interval: UDP_RETRANSMIT_INTERVAL,
+            max_udp_retransmits: MAX_UDP_RETRANSMITS,
+            udp_send_window: UDP_SEND_WINDOW,
+            udp_reassembly_timeout: UDP_REASSEMBLY_TIMEOUT,
+            max_udp_partial_messages: MAX_UDP_PARTIAL_MESSAGES,
+            max_udp_partial_bytes: MAX_UDP_PARTIAL_BYTES,
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            acceptable_heartbeat_pause: ACCEPTABLE_HEARTBEAT_PAUSE,
+            phi_threshold: PHI_THRESHOLD,
+            bridge_config: BridgeConfig::default(),
+            redelivery_interval: REDELIVERY_INTERVAL,
+            max_redelivered_messages: MAX_REDELIVERED_MESSAGES,
+            max_unacked_reliable_messages: MAX_UNACKED_RELIABLE_MESSAGES,
+            resolver: Arc::new(DnsResolver),
+            dns_cache_ttl: DNS_CACHE_TTL,
+            network_threads: NETWORK_THREADS,
+            compression: None,
+            compression_threshold: COMPRESSION_THRESHOLD,
+            max_decompressed_size: MAX_DECOMPRESSED_SIZE,
+            stream_threshold: None,
+            max_streamed_size: MAX_STREAMED_SIZE,
+            max_partial_streams: MAX_PARTIAL_STREAMS,
+            max_queued_messages: None,
+            max_queued_bytes: None,
+            queue_overflow_policy: OverflowPolicy::DeadLetter,
+            idle_timeout: None,
+            max_inbound_connections: None,
+            max_outbound_connections: None,
         }
     }
 }
@@ -209,8 +1110,8 @@ impl Default for NetworkConfig {
 /// This dispatcher automatically creates channels to requested target
 /// systems on demand and maintains them while in use.
 ///
-/// The current implementation only supports [TCP](Transport::TCP) as
-/// a transport protocol.
+/// The current implementation supports [TCP](Transport::TCP), [UDP](Transport::UDP)
+/// and, on Unix platforms, [UDS](Transport::UDS) as transport protocols.
 ///
 /// If possible, this implementation will "reflect" messages
 /// to local actors directly back up, instead of serialising them first.
@@ -233,12 +1134,80 @@ pub struct NetworkDispatcher {
     /// Reaper which cleans up deregistered actor references in the actor lookup table
     reaper: lookup::gc::ActorRefReaper,
     notify_ready: Option<KPromise<()>>,
-    /// Stores the number of retry-attempts for connections. Checked and incremented periodically by the reaper.
-    retry_map: FxHashMap<SocketAddr, u8>,
+    /// Stores the number of retry-attempts for connections which are yet to be (re-)established
+    retry_map: FxHashMap<SocketAddr, RetryState>,
+    /// Tells stale retry timers from the current ones
+    next_retry_id: u64,
+    /// Resolved at [Start](ControlEvent::Start), since it may come from the system's config
+    retry_strategy: RetryStrategy,
     garbage_buffers: VecDeque<BufferChunk>,
+    /// The latest suspicion reported for each connected host which sends heartbeats
+    suspicions: FxHashMap<SocketAddr, Suspicion>,
+    /// The path each remote system was first addressed with, which reports about its host refer to
+    known_systems: FxHashMap<SocketAddr, SystemPath>,
+    reachability_port: ProvidedPort<ReachabilityPort>,
+    network_status_port: ProvidedPort<NetworkStatusPort>,
+    /// Hosts whose connection is being closed as requested, and should not be re-established
+    disconnecting: FxHashSet<SocketAddr>,
+    /// Hosts whose connection is being closed for being idle, and should only be re-established when needed
+    closing_idle: FxHashSet<SocketAddr>,
+    /// Hosts we refuse to communicate with
+    blocked: FxHashSet<SocketAddr>,
+    /// Local watchers by the path they watch
+    watchers: FxHashMap<ActorPath, Vec<DynActorRef>>,
+    /// Local actors by their watched path, along with the dispatchers watching them
+    watched: FxHashMap<ActorPath, WatchedActor>,
+    /// Asks waiting for a reply, by the id of their temporary path
+    pending_asks: FxHashMap<Uuid, PendingAsk>,
+    /// Messages sent via [tell_reliably](ActorPath::tell_reliably) that were not acknowledged yet
+    reliable_sender: ReliableSender,
+    /// What was delivered of the reliable messages other systems sent to us
+    reliable_receiver: ReliableReceiver,
+    /// The incarnations of the remote systems we connected to, by their address
+    incarnations: FxHashMap<SocketAddr, Uuid>,
+    /// Addresses of the hostnames in remote system paths
+    resolver_cache: ResolverCache,
+    /// Hostnames being resolved, with the work held back until they are
+    resolving: FxHashMap<(String, u16), PendingResolution>,
 }
 
-impl NetworkDispatcher {
+struct Suspicion {
+    phi: f64,
+    reachable: bool,
+}
+
+struct RetryState {
+    attempt: u32,
+    id: u64,
+}
+
+struct WatchedActor {
+    id: Uuid,
+    watchers: FxHashSet<ActorPath>,
+    lifecycle: LifecycleWatch,
+}
+
+struct PendingAsk {
+    promise: KPromise<Result<NetMessage, AskError>>,
+    timer: ScheduledTimer,
+}
+
+struct PendingResolution {
+    attempt: u32,
+    deferred: Vec<Deferred>,
+}
+
+/// Work for a system whose hostname is still being resolved
+enum Deferred {
+    Route {
+        dst: ActorPath,
+        msg: Box<DispatchData>,
+        prioritised: bool,
+    },
+    Status(NetworkStatusRequest),
+}
+
+impl NetworkDispatcher {
     /// Create a new dispatcher with the default configuration
     ///
     /// See also [NetworkConfig](NetworkConfig).
@@ -267,6 +1236,21 @@ impl NetworkDispatcher {
     pub fn with_config(cfg: NetworkConfig, notify_ready: KPromise<()>) -> Self {
         let lookup = Arc::new(ArcSwap::from_pointee(ActorStore::new()));
         let reaper = lookup::gc::ActorRefReaper::default();
+        let retry_strategy = RetryStrategy::Fixed {
+            interval: Duration::from_millis(cfg.connection_retry_interval),
+            max_attempts: Some(cfg.max_connection_retry_attempts as u32),
+        };
+        let resolver_cache = ResolverCache::new(
+            cfg.resolver.clone(),
+            Duration::from_millis(cfg.dns_cache_ttl),
+        );
+
+        let queue_manager = QueueManager::new(
+            cfg.max_queued_messages,
+            cfg.max_queued_bytes,
+            cfg.queue_overflow_policy,
+        );
+        let reliable_sender = ReliableSender::new(cfg.max_unacked_reliable_messages);
 
         NetworkDispatcher {
             ctx: ComponentContext::uninitialised(),
@@ -275,11 +1259,28 @@ impl NetworkDispatcher {
             lookup,
             net_bridge: None,
             system_path: None,
-            queue_manager: QueueManager::new(),
+            queue_manager,
             reaper,
             notify_ready: Some(notify_ready),
             garbage_buffers: VecDeque::new(),
             retry_map: Default::default(),
+            next_retry_id: 0,
+            retry_strategy,
+            suspicions: Default::default(),
+            known_systems: Default::default(),
+            reachability_port: ProvidedPort::uninitialised(),
+            network_status_port: ProvidedPort::uninitialised(),
+            disconnecting: Default::default(),
+            closing_idle: Default::default(),
+            blocked: Default::default(),
+            watchers: Default::default(),
+            watched: Default::default(),
+            pending_asks: Default::default(),
+            reliable_sender,
+            reliable_receiver: Default::default(),
+            incarnations: Default::default(),
+            resolver_cache,
+            resolving: Default::default(),
         }
     }
 
@@ -302,7 +1303,7 @@ impl NetworkDispatcher {
         }
     }
 
-    fn start(&mut self) -> () {
+    fn start(&mut self) -> Result<(), NetworkBridgeErr> {
         debug!(self.ctx.log(), "Starting self and network bridge");
         let dispatcher = self
             .actor_ref()
@@ -316,20 +1317,44 @@ impl NetworkDispatcher {
             bridge_logger,
             self.cfg.addr,
             dispatcher.clone(),
+            self.ctx.system().incarnation(),
             &self.cfg,
-        );
+        )?;
 
         let deadletter: DynActorRef = self.ctx.system().deadletter_ref().dyn_ref();
+        let this: DynActorRef = self.actor_ref().dyn_ref();
         self.lookup.rcu(|current| {
             let mut next = ActorStore::clone(&current);
             next.insert(PathResolvable::System, deadletter.clone())
                 .expect("Deadletter shouldn't error");
+            // Other dispatchers reach us here with their watch requests
+            next.insert(PathResolvable::ActorId(Uuid::nil()), this.clone())
+                .expect("Dispatcher shouldn't error");
             next
         });
 
         bridge.set_dispatcher(dispatcher);
-        self.schedule_retries();
+        let configured = BridgeConfig::from_config(self.ctx.config());
+        if let Some(strategy) = self
+            .cfg
+            .get_bridge_config()
+            .get_retry_strategy()
+            .or_else(|| configured.get_retry_strategy())
+        {
+            self.retry_strategy = strategy.clone();
+        }
+        debug!(
+            self.ctx.log(),
+            "Retrying connections with {:?}", self.retry_strategy
+        );
         self.net_bridge = Some(bridge);
+
+        let redelivery_interval = Duration::from_millis(self.cfg.redelivery_interval);
+        self.schedule_periodic(redelivery_interval, redelivery_interval, |target, _id| {
+            target.redeliver_due();
+            Handled::Ok
+        });
+        Ok(())
     }
 
     fn stop(&mut self) -> () {
@@ -377,128 +1402,907 @@ impl NetworkDispatcher {
                 retry_queue.push_back(trash);
             }
         }
-        // info!(self.ctx().log(), "tried to clean {} buffer(s)", retry_queue.len()); // manual verification in testing
-        self.garbage_buffers.append(&mut retry_queue);
-
-        self.schedule_once(Duration::from_millis(next_wakeup), move |target, _id| {
-            target.schedule_reaper();
-            Handled::Ok
-        });
+        // info!(self.ctx().log(), "tried to clean {} buffer(s)", retry_queue.len()); // manual verification in testing
+        self.garbage_buffers.append(&mut retry_queue);
+
+        self.schedule_once(Duration::from_millis(next_wakeup), move |target, _id| {
+            target.schedule_reaper();
+            Handled::Ok
+        });
+    }
+
+    /// Makes sure the connection to `addr` is re-requested, unless it is established in time
+    fn start_retrying(&mut self, addr: SocketAddr) -> () {
+        if !self.retry_map.contains_key(&addr) {
+            self.schedule_retry(addr, 0);
+        }
+    }
+
+    /// Checks on the connection to `addr` once the `attempt`-th attempt at establishing it had its time
+    fn schedule_retry(&mut self, addr: SocketAddr, attempt: u32) -> () {
+        let id = self.next_retry_id;
+        self.next_retry_id = self.next_retry_id.wrapping_add(1);
+        self.retry_map.insert(addr, RetryState { attempt, id });
+        let delay = self.retry_strategy.retry_delay(attempt);
+        self.schedule_once(delay, move |target, _id| {
+            target.retry(addr, id);
+            Handled::Ok
+        });
+    }
+
+    /// Re-establishes the idle connection to `addr` for the messages queued for it
+    fn reconnect(&mut self, addr: SocketAddr) -> Result<(), NetworkBridgeErr> {
+        if let Some(bridge) = &self.net_bridge {
+            debug!(self.ctx.log(), "Re-establishing connection to {:?}", addr);
+            bridge.connect(Transport::TCP, addr)?;
+            self.connections.insert(addr, ConnectionState::Initializing);
+            self.start_retrying(addr);
+        }
+        Ok(())
+    }
+
+    fn retry(&mut self, addr: SocketAddr, id: u64) -> () {
+        let attempt = match self.retry_map.get(&addr) {
+            Some(state) if state.id == id => state.attempt,
+            _ => return, // connected, given up on, or rescheduled in the meantime
+        };
+        let should_retry = match self.retry_strategy.max_attempts() {
+            Some(max_attempts) => attempt < max_attempts,
+            None => true,
+        };
+        if should_retry {
+            let attempt = attempt + 1;
+            // Make sure we will re-request connection later
+            self.schedule_retry(addr, attempt);
+            if let Some(bridge) = &self.net_bridge {
+                // Do connection attempt
+                debug!(
+                    self.ctx().log(),
+                    "Dispatcher retrying connection to host {}, attempt {}/{:?}",
+                    addr,
+                    attempt,
+                    self.retry_strategy.max_attempts()
+                );
+                if let Err(e) = bridge.connect(Transport::TCP, addr) {
+                    error!(
+                        self.ctx().log(),
+                        "Error while retrying connection to {}, \n{:?}", addr, e
+                    );
+                }
+            }
+        } else {
+            // Too many retries, give up on the connection.
+            info!(
+                self.ctx().log(),
+                "Dispatcher giving up on remote host {}, dropping queues", addr
+            );
+            self.retry_map.remove(&addr);
+            self.drop_queue(addr);
+            self.connections.remove(&addr);
+            self.terminate_watches(addr);
+            self.resolver_cache.invalidate(addr);
+            self.network_status_port
+                .trigger(NetworkStatus::ConnectionDropped(self.remote_path(addr)));
+        }
+    }
+
+    fn on_event(&mut self, ev: EventEnvelope) {
+        match ev {
+            EventEnvelope::Network(ev) => match ev {
+                NetworkEvent::Connection(addr, conn_state) => {
+                    if let Err(e) = self.on_conn_state(addr, conn_state) {
+                        error!(
+                            self.ctx().log(),
+                            "Error while connecting to {}, \n{:?}", addr, e
+                        )
+                    }
+                }
+                NetworkEvent::Data(_) => {
+                    // TODO shouldn't be receiving these here, as they should be routed directly to the ActorRef
+                    debug!(self.ctx().log(), "Received important data!");
+                }
+                NetworkEvent::RejectedData(addr, data) => {
+                    if self.blocked.contains(&addr) {
+                        self.network_status_port
+                            .trigger(NetworkStatus::DroppedMessages(self.remote_path(addr), 1));
+                    } else if !self.connections.contains_key(&addr) {
+                        // The channel was closed for being idle while the message was on its way
+                        self.queue_manager.enqueue_priority_data(data, addr);
+                        if let Err(e) = self.reconnect(addr) {
+                            error!(self.ctx().log(), "Failed to reconnect to {}: {:?}", addr, e);
+                        }
+                    } else {
+                        // These are messages which we routed to a network-thread before they lost the connection.
+                        self.queue_manager.enqueue_priority_data(data, addr);
+                    }
+                }
+                NetworkEvent::AuthenticationFailed(addr) => {
+                    // Don't keep retrying hosts we don't trust
+                    warn!(
+                        self.ctx().log(),
+                        "Remote host {} failed to authenticate, dropping queues", addr
+                    );
+                    self.retry_map.remove(&addr);
+                    self.drop_queue(addr);
+                    self.connections.remove(&addr);
+                    self.terminate_watches(addr);
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionDropped(self.remote_path(addr)));
+                }
+                NetworkEvent::Suspicion(addr, phi) => self.on_suspicion(addr, phi),
+                NetworkEvent::Compression(addr, stats) => {
+                    let system = self.remote_path(addr);
+                    self.network_status_port
+                        .trigger(NetworkStatus::CompressionStats(system, stats))
+                }
+                NetworkEvent::Idle(addr) => {
+                    self.closing_idle.insert(addr);
+                }
+                NetworkEvent::Incarnation(addr, incarnation) => {
+                    self.on_incarnation(addr, incarnation)
+                }
+                NetworkEvent::UdsConnected(path) => {
+                    let system = SystemPath::with_uds_path(path);
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionEstablished(system))
+                }
+                NetworkEvent::UdsLost(path) => {
+                    let system = SystemPath::with_uds_path(path);
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionLost(system))
+                }
+                NetworkEvent::UdsDropped(path) => {
+                    let system = SystemPath::with_uds_path(path);
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionDropped(system))
+                }
+                NetworkEvent::UdsDroppedMessages(path, dropped) => {
+                    let system = SystemPath::with_uds_path(path);
+                    self.network_status_port
+                        .trigger(NetworkStatus::DroppedMessages(system, dropped))
+                }
+                NetworkEvent::UdpDroppedMessages(addr, dropped) => {
+                    let system = SystemPath::with_socket(Transport::UDP, addr);
+                    self.network_status_port
+                        .trigger(NetworkStatus::DroppedMessages(system, dropped))
+                }
+            },
+        }
+    }
+
+    /// Records the `incarnation` of the system at `addr`, and cleans up after its previous incarnation
+    ///
+    /// The actors of a restarted system are gone, so messages queued for their unique paths are dropped.
+    /// Messages to named paths are kept, as the new incarnation may have registered the same names.
+    fn on_incarnation(&mut self, addr: SocketAddr, incarnation: Uuid) -> () {
+        match self.incarnations.insert(addr, incarnation) {
+            Some(previous) if previous != incarnation => {
+                info!(
+                    self.ctx.log(),
+                    "System at {} restarted, incarnation {} replaced {}",
+                    addr,
+                    incarnation,
+                    previous
+                );
+                let dropped = self
+                    .queue_manager
+                    .drop_matching(&addr, |receiver| matches!(receiver, ActorPath::Unique(_)))
+                    + self.reliable_sender.restarted(addr);
+                if dropped > 0 {
+                    let system = self.remote_path(addr);
+                    self.network_status_port
+                        .trigger(NetworkStatus::DroppedMessages(system, dropped));
+                }
+                self.reliable_receiver.restarted(addr);
+                self.terminate_watches(addr);
+                self.network_status_port
+                    .trigger(NetworkStatus::SystemRestarted(self.remote_path(addr)));
+            }
+            _ => (),
+        }
+    }
+
+    fn on_conn_state(
+        &mut self,
+        addr: SocketAddr,
+        mut state: ConnectionState,
+    ) -> Result<(), NetworkBridgeErr> {
+        use self::ConnectionState::*;
+        let was_connected = matches!(
+            self.connections.get(&addr),
+            Some(Connected(_)) | Some(Blocked)
+        );
+        match state {
+            Connected(ref mut _frame_sender) => {
+                if self.blocked.contains(&addr) {
+                    // We requested the connection before the host was blocked
+                    if let Some(bridge) = &self.net_bridge {
+                        bridge.disconnect(addr)?;
+                    }
+                } else if !was_connected {
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionEstablished(self.remote_path(addr)));
+                }
+                info!(
+                    self.ctx().log(),
+                    "registering newly connected conn at {:?}", addr
+                );
+                let _ = self.retry_map.remove(&addr);
+                if self.queue_manager.has_data(&addr) {
+                    // Drain as much as possible
+                    Self::drain_queue(&mut self.queue_manager, self.net_bridge.as_ref(), addr)?;
+                }
+                // Whatever was in flight on a lost connection may never have arrived
+                let limit = self.cfg.max_redelivered_messages;
+                let lost = self
+                    .reliable_sender
+                    .reconnected(addr, Instant::now(), limit);
+                self.send_reliable(lost);
+            }
+            Blocked => {
+                debug!(
+                    self.ctx().log(),
+                    "connection to {:?} ran out of credits, queueing messages", addr
+                );
+            }
+            Closed => {
+                let idle = self.closing_idle.remove(&addr);
+                if idle && !self.disconnecting.contains(&addr) && !self.blocked.contains(&addr) {
+                    // Nothing was lost, so the connection is only re-established once there are messages to send
+                    debug!(self.ctx().log(), "idle connection closed for {:?}", addr);
+                    self.suspicions.remove(&addr);
+                    self.retry_map.remove(&addr);
+                    self.connections.remove(&addr);
+                    // Reliable messages may still be waiting for their acknowledgement
+                    self.reliable_sender.connection_lost(addr);
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionIdle(self.remote_path(addr)));
+                    if let Some(bridge) = &self.net_bridge {
+                        bridge.ack_closed(addr)?;
+                    }
+                    if self.queue_manager.has_data(&addr) {
+                        // Messages sent while the channel was closing
+                        self.reconnect(addr)?;
+                    }
+                    return Ok(());
+                }
+                if let Some(suspicion) = self.suspicions.remove(&addr) {
+                    if suspicion.reachable {
+                        self.reachability_port
+                            .trigger(ReachabilityIndication::Unreachable(self.remote_path(addr)));
+                    }
+                }
+                if self.disconnecting.remove(&addr) || self.blocked.contains(&addr) {
+                    // Closed as requested, so the connection must not be re-established
+                    debug!(
+                        self.ctx().log(),
+                        "connection closed for {:?} as requested", addr
+                    );
+                    self.connections.remove(&addr);
+                    self.terminate_watches(addr);
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionClosed(self.remote_path(addr)));
+                    if let Some(bridge) = &self.net_bridge {
+                        bridge.ack_closed(addr)?;
+                    }
+                    return Ok(());
+                }
+                if was_connected {
+                    self.terminate_watches(addr);
+                    self.reliable_sender.connection_lost(addr);
+                    // The host may have moved, so look it up again before reconnecting
+                    self.resolver_cache.invalidate(addr);
+                    self.network_status_port
+                        .trigger(NetworkStatus::ConnectionLost(self.remote_path(addr)));
+                }
+                if !self.retry_map.contains_key(&addr) {
+                    warn!(self.ctx().log(), "connection closed for {:?}", addr);
+                    self.start_retrying(addr); // Make sure we try to re-establish the connection
+                }
+                // Ack the close message
+                if let Some(bridge) = &self.net_bridge {
+                    bridge.ack_closed(addr)?;
+                }
+            }
+            Error(ref err) => {
+                match err {
+                    x if x.kind() == ErrorKind::ConnectionRefused => {
+                        error!(self.ctx().log(), "connection refused for {:?}", addr);
+                        // TODO determine how we want to proceed
+                        // If TCP, the network bridge has already attempted retries with exponential
+                        // backoff according to its configuration.
+                    }
+                    why => {
+                        error!(
+                            self.ctx().log(),
+                            "connection error for {:?}: {:?}", addr, why
+                        );
+                    }
+                }
+            }
+            ref _other => (), // Don't care
+        }
+        self.connections.insert(addr, state);
+        Ok(())
+    }
+
+    /// The path of the remote system at `addr`, as it was first addressed
+    ///
+    /// Systems which connected to us without ever being addressed are reported with their socket address.
+    fn remote_path(&self, addr: SocketAddr) -> SystemPath {
+        match self.known_systems.get(&addr) {
+            Some(system) => system.clone(),
+            None => SystemPath::with_socket(Transport::TCP, addr),
+        }
+    }
+
+    /// Remembers the path `system` at `addr` was addressed with, unless it was addressed before
+    fn remember_system(&mut self, addr: SocketAddr, system: &SystemPath) -> () {
+        self.known_systems
+            .entry(addr)
+            .or_insert_with(|| system.clone());
+    }
+
+    /// Tracks the reachability of `addr`, reporting it on the [ReachabilityPort] when it changes
+    fn on_suspicion(&mut self, addr: SocketAddr, phi: f64) -> () {
+        let reachable = phi < self.cfg.phi_threshold;
+        let previous = self.suspicions.insert(addr, Suspicion { phi, reachable });
+        if previous.map(|suspicion| suspicion.reachable) == Some(reachable) {
+            return;
+        }
+        let system = self.remote_path(addr);
+        if reachable {
+            debug!(self.ctx.log(), "Remote host {} is reachable", addr);
+            self.reachability_port
+                .trigger(ReachabilityIndication::Reachable(system));
+        } else {
+            warn!(
+                self.ctx.log(),
+                "Remote host {} is unreachable, suspicion level {:.2}", addr, phi
+            );
+            self.terminate_watches(addr);
+            self.reachability_port
+                .trigger(ReachabilityIndication::Unreachable(system));
+        }
+    }
+
+    /// Registers `watcher` to be told once the actor at `watched` stops existing
+    ///
+    /// Only the first watcher of a path asks the dispatcher of the path's system to watch it.
+    fn watch(&mut self, watcher: DynActorRef, watched: ActorPath) -> () {
+        let unreachable = match self.resolve_system(watched.system()) {
+            Some(addr) => self.blocked.contains(&addr),
+            None => false,
+        };
+        let watchers = self.watchers.entry(watched.clone()).or_default();
+        if watchers.contains(&watcher) {
+            return;
+        }
+        watchers.push(watcher);
+        if unreachable {
+            self.notify_watchers(Terminated {
+                path: watched,
+                reason: TerminationReason::Unreachable,
+            });
+        } else if watchers.len() == 1 {
+            self.send_watch_message(WatchMessage::Watch(watched));
+        }
+    }
+
+    fn unwatch(&mut self, watcher: DynActorRef, watched: ActorPath) -> () {
+        if let Some(watchers) = self.watchers.get_mut(&watched) {
+            watchers.retain(|other| other != &watcher);
+            if watchers.is_empty() {
+                self.watchers.remove(&watched);
+                self.send_watch_message(WatchMessage::Unwatch(watched));
+            }
+        }
+    }
+
+    fn send_watch_message(&mut self, msg: WatchMessage) -> () {
+        let system = match &msg {
+            WatchMessage::Watch(path) | WatchMessage::Unwatch(path) => path.system().clone(),
+        };
+        let src = dispatcher_path(self.system_path());
+        let dst = dispatcher_path(system);
+        if let Err(e) = self.route(dst.clone(), DispatchData::Lazy(Box::new(msg), src, dst)) {
+            error!(self.ctx.log(), "Failed to route watch message: {:?}", e);
+        }
+    }
+
+    /// Handles a watch request from the dispatcher at `sender` about one of our actors
+    fn on_watch_message(&mut self, sender: ActorPath, msg: WatchMessage) -> () {
+        match msg {
+            WatchMessage::Watch(path) => {
+                let actor = match self.lookup.load().get_by_actor_path(&path) {
+                    LookupResult::Ref(actor) if actor.is_alive() => Some(actor.clone()),
+                    _ => None,
+                };
+                let actor = match actor.and_then(|actor| Some((actor.component_id()?, actor))) {
+                    Some(actor) => actor,
+                    None => return self.send_terminated(sender, path, TerminationReason::NotFound),
+                };
+                match self.watched.entry(path.clone()) {
+                    Entry::Occupied(mut watched) => {
+                        watched.get_mut().watchers.insert(sender);
+                    }
+                    Entry::Vacant(_) => {
+                        let (id, actor) = actor;
+                        let mut watchers = FxHashSet::default();
+                        watchers.insert(sender);
+                        let watched_path = path.clone();
+                        let lifecycle = self.watch_lifecycle(&actor, move |this, event| {
+                            // The event's id is nil if the actor was deallocated in the meantime
+                            if let LifecycleEvent::Faulted(_) | LifecycleEvent::Destroyed(_) = event
+                            {
+                                this.end_watch(&watched_path, Some(id));
+                            }
+                            Handled::Ok
+                        });
+                        let watched = WatchedActor {
+                            id,
+                            watchers,
+                            lifecycle,
+                        };
+                        self.watched.insert(path, watched);
+                    }
+                }
+            }
+            WatchMessage::Unwatch(path) => {
+                if let Some(watched) = self.watched.get_mut(&path) {
+                    watched.watchers.remove(&sender);
+                    if watched.watchers.is_empty() {
+                        if let Some(watched) = self.watched.remove(&path) {
+                            self.unwatch_lifecycle(watched.lifecycle);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn send_terminated(&mut self, watcher: ActorPath, path: ActorPath, reason: TerminationReason) {
+        let src = dispatcher_path(self.system_path());
+        let msg = Terminated { path, reason };
+        if let Err(e) = self.route(
+            watcher.clone(),
+            DispatchData::Lazy(Box::new(msg), src, watcher),
+        ) {
+            error!(
+                self.ctx.log(),
+                "Failed to route termination notice: {:?}", e
+            );
+        }
+    }
+
+    /// Tells the watching dispatchers that the actor at `path` is gone
+    ///
+    /// If `id` is given, the watch only ends if it is still about that very component,
+    /// and not about one that has since been registered at the same path.
+    fn end_watch(&mut self, path: &ActorPath, id: Option<Uuid>) -> () {
+        match self.watched.get(path) {
+            Some(watched) if id.is_none() || id == Some(watched.id) => (),
+            _ => return,
+        }
+        if let Some(watched) = self.watched.remove(path) {
+            self.unwatch_lifecycle(watched.lifecycle);
+            for watcher in watched.watchers {
+                self.send_terminated(watcher, path.clone(), TerminationReason::Stopped);
+            }
+        }
+    }
+
+    /// Whether `first` and `second` name the same system, possibly via different addresses
+    fn same_system(&mut self, first: &SystemPath, second: &SystemPath) -> bool {
+        first == second
+            || matches!(
+                (self.resolve_system(first), self.resolve_system(second)),
+                (Some(first), Some(second)) if first == second
+            )
+    }
+
+    /// Delivers `terminated` to all local watchers of its path, which are then forgotten
+    fn notify_watchers(&mut self, terminated: Terminated) -> () {
+        if let Some(watchers) = self.watchers.remove(&terminated.path) {
+            let system = self.system_path();
+            for watcher in watchers {
+                if let Some(id) = watcher.component_id() {
+                    watcher.tell(NetMessage::with_box(
+                        serialisation_ids::TERMINATED,
+                        terminated.path.clone(),
+                        ActorPath::from((system.clone(), id)),
+                        Box::new(terminated.clone()),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Ends all watches involving the system at `addr`, once we lost touch with it
+    ///
+    /// Local watchers of its actors are told they are unreachable,
+    /// and its watches of our actors are dropped.
+    fn terminate_watches(&mut self, addr: SocketAddr) -> () {
+        self.terminate_watches_where(|cache, system| cached_addr(cache, system) == Some(addr));
+    }
+
+    /// Ends all watches involving systems addressed by `host` and `port`, once resolving it failed for good
+    ///
+    /// Their addresses were never known, so [terminate_watches](NetworkDispatcher::terminate_watches)
+    /// can't match them.
+    fn terminate_unresolved_watches(&mut self, host: &str, port: u16) -> () {
+        self.terminate_watches_where(|_, system| {
+            system.address().hostname() == Some(host) && system.port() == port
+        });
+    }
+
+    fn terminate_watches_where<F>(&mut self, lost: F) -> ()
+    where
+        F: Fn(&ResolverCache, &SystemPath) -> bool,
+    {
+        let cache = &self.resolver_cache;
+        let unreachable: Vec<ActorPath> = self
+            .watchers
+            .keys()
+            .filter(|path| lost(cache, path.system()))
+            .cloned()
+            .collect();
+        for path in unreachable {
+            self.notify_watchers(Terminated {
+                path,
+                reason: TerminationReason::Unreachable,
+            });
+        }
+        let cache = &self.resolver_cache;
+        let forgotten: Vec<ActorPath> = self
+            .watched
+            .iter_mut()
+            .filter_map(|(path, watched)| {
+                watched
+                    .watchers
+                    .retain(|watcher| !lost(cache, watcher.system()));
+                Some(path.clone()).filter(|_| watched.watchers.is_empty())
+            })
+            .collect();
+        for path in forgotten {
+            if let Some(watched) = self.watched.remove(&path) {
+                self.unwatch_lifecycle(watched.lifecycle);
+            }
+        }
+    }
+
+    /// Sends `msg` to `dst` from a fresh temporary path, which fulfils `promise` with the first reply
+    ///
+    /// The temporary path is registered to the dispatcher itself, so replies arrive
+    /// in [receive_network](Actor::receive_network) no matter where they come from.
+    fn ask(
+        &mut self,
+        dst: ActorPath,
+        msg: Box<dyn Serialisable>,
+        timeout: Duration,
+        promise: KPromise<Result<NetMessage, AskError>>,
+    ) -> () {
+        let id = Uuid::new_v4();
+        let mut reply_path = ActorPath::from((self.system_path(), id));
+        reply_path.set_protocol(dst.protocol());
+        let this: DynActorRef = self.actor_ref().dyn_ref();
+        self.lookup.rcu(|current| {
+            let mut next = ActorStore::clone(current);
+            next.insert(PathResolvable::ActorId(id), this.clone())
+                .expect("Fresh ids shouldn't error");
+            next
+        });
+        let timer = self.schedule_once(timeout, move |target, _id| {
+            if let Some(promise) = target.finish_ask(id) {
+                promise.fulfil(Err(AskError::Timeout)).unwrap_or(());
+            }
+            Handled::Ok
+        });
+        self.pending_asks.insert(id, PendingAsk { promise, timer });
+        trace!(self.ctx.log(), "Asking {} from {}", dst, reply_path);
+        if let Err(e) = self.route(dst.clone(), DispatchData::Lazy(msg, reply_path, dst)) {
+            if let Some(promise) = self.finish_ask(id) {
+                promise
+                    .fulfil(Err(AskError::SendFailed(format!("{:?}", e))))
+                    .unwrap_or(());
+            }
+        }
+    }
+
+    /// Fulfils the pending ask whose temporary path `msg` was sent to, if there is one
+    ///
+    /// Returns the message again, if it was not a reply.
+    fn try_reply(&mut self, msg: NetMessage) -> Option<NetMessage> {
+        let id = match &msg.receiver {
+            ActorPath::Unique(path) if !path.id().is_nil() => path.id(),
+            _ => return Some(msg),
+        };
+        match self.finish_ask(id) {
+            Some(promise) => {
+                promise.fulfil(Ok(msg)).unwrap_or(());
+                None
+            }
+            None => Some(msg),
+        }
+    }
+
+    /// Removes the temporary path `id` and cancels its timeout, returning the promise to fulfil
+    fn finish_ask(&mut self, id: Uuid) -> Option<KPromise<Result<NetMessage, AskError>>> {
+        let pending = self.pending_asks.remove(&id)?;
+        self.cancel_timer(pending.timer);
+        self.lookup.rcu(|current| {
+            let mut next = ActorStore::clone(current);
+            next.remove_by_uuid(&id);
+            next
+        });
+        Some(pending.promise)
+    }
+
+    /// Sends `msg` from `src` to `dst` at least once
+    ///
+    /// Local messages are simply delivered, remote ones are serialised and
+    /// kept until the receiving dispatcher acknowledges them.
+    fn tell_reliably(&mut self, src: ActorPath, dst: ActorPath, msg: Box<dyn Serialisable>) -> () {
+        let addr = self.resolve_system(dst.system());
+        let blocked = match addr {
+            Some(addr) => self.blocked.contains(&addr),
+            None => false,
+        };
+        if self.system_path_ref() == dst.system()
+            || dst.system().protocol() == Transport::LOCAL
+            || blocked
+        {
+            // Nothing to redeliver, either because it can't get lost or because it is dropped anyway
+            if let Err(e) = self.route(dst.clone(), DispatchData::Lazy(msg, src, dst)) {
+                error!(self.ctx.log(), "Failed to route message: {:?}", e);
+            }
+            return;
+        }
+        match ser_helpers::serialise_to_serialised(msg.as_ref()) {
+            Ok(serialised) => {
+                match self.reliable_sender.send(
+                    src,
+                    dst,
+                    addr,
+                    serialised.ser_id,
+                    serialised.data,
+                    Instant::now(),
+                ) {
+                    Ok(msg) => self.send_reliable(vec![msg]),
+                    Err(rejected) => {
+                        let system = rejected.receiver.system().clone();
+                        self.handle_overflow(system, vec![DispatchData::NetMessage(*rejected)]);
+                    }
+                }
+            }
+            Err(e) => error!(
+                self.ctx.log(),
+                "Could not serialise msg: {:?}. Dropping...", e
+            ),
+        }
+    }
+
+    /// Sends the reliable messages again that were not acknowledged within the redelivery interval,
+    /// and releases the channels that were fully acknowledged for as long
+    fn redeliver_due(&mut self) -> () {
+        let now = Instant::now();
+        let interval = Duration::from_millis(self.cfg.redelivery_interval);
+        let limit = self.cfg.max_redelivered_messages;
+        let connections = &self.connections;
+        let due = self.reliable_sender.due(now, interval, limit, |addr| {
+            matches!(connections.get(&addr), Some(ConnectionState::Connected(_)))
+        });
+        self.send_reliable(due);
+        let src = dispatcher_path(self.system_path());
+        for (system, release) in self.reliable_sender.release_idle(now, interval) {
+            let dst = dispatcher_path(system);
+            if let Err(e) = self.route(
+                dst.clone(),
+                DispatchData::Lazy(Box::new(release), src.clone(), dst),
+            ) {
+                error!(self.ctx.log(), "Failed to route channel release: {:?}", e);
+            }
+        }
+    }
+
+    /// Sends reliable messages to the dispatchers of their destinations
+    fn send_reliable(&mut self, messages: Vec<ReliableMessage>) -> () {
+        let src = dispatcher_path(self.system_path());
+        for msg in messages {
+            let dst = dispatcher_path(msg.dst.system().clone());
+            trace!(
+                self.ctx.log(),
+                "Sending reliable message {} on channel {} to {}",
+                msg.seq,
+                msg.channel,
+                msg.dst
+            );
+            if let Err(e) = self.route(
+                dst.clone(),
+                DispatchData::Lazy(Box::new(msg), src.clone(), dst),
+            ) {
+                error!(self.ctx.log(), "Failed to route reliable message: {:?}", e);
+            }
+        }
+    }
+
+    /// Delivers a reliable message from the dispatcher at `sender`, unless it is a duplicate, and acknowledges it
+    fn on_reliable_message(&mut self, sender: ActorPath, msg: ReliableMessage) -> () {
+        let addr = self.resolve_system(sender.system());
+        let (deliver, ack) = self.reliable_receiver.receive(msg, addr);
+        if let Some(netmsg) = deliver {
+            self.route_local(netmsg.receiver.clone(), DispatchData::NetMessage(netmsg));
+        }
+        if let Some(ack) = ack {
+            let src = dispatcher_path(self.system_path());
+            if let Err(e) = self.route(
+                sender.clone(),
+                DispatchData::Lazy(Box::new(ack), src, sender),
+            ) {
+                error!(self.ctx.log(), "Failed to route acknowledgement: {:?}", e);
+            }
+        }
+    }
+
+    /// Drops the messages queued for `addr`, reporting them on the [NetworkStatusPort]
+    fn drop_queue(&mut self, addr: SocketAddr) -> () {
+        let dropped = self.queue_manager.drop_queue(&addr) + self.reliable_sender.drop_to(addr);
+        if dropped > 0 {
+            let system = self.remote_path(addr);
+            self.network_status_port
+                .trigger(NetworkStatus::DroppedMessages(system, dropped));
+        }
+    }
+
+    /// Drops `data` if `addr` is blocked, reporting it on the [NetworkStatusPort]
+    ///
+    /// Returns `None` if the data was dropped.
+    fn unless_blocked(&mut self, addr: SocketAddr, data: DispatchData) -> Option<DispatchData> {
+        if self.blocked.contains(&addr) {
+            debug!(self.ctx.log(), "Dropping message to blocked host {}", addr);
+            self.network_status_port
+                .trigger(NetworkStatus::DroppedMessages(self.remote_path(addr), 1));
+            None
+        } else {
+            Some(data)
+        }
+    }
+
+    /// Handles `request`, unless the hostname of its system has to be resolved first
+    fn on_status_request(&mut self, request: NetworkStatusRequest) -> () {
+        let system = match &request {
+            NetworkStatusRequest::ConnectSystem(system)
+            | NetworkStatusRequest::DisconnectSystem(system)
+            | NetworkStatusRequest::BlockSystem(system)
+            | NetworkStatusRequest::UnblockSystem(system) => system.clone(),
+        };
+        if self.awaiting_resolution(&system) {
+            self.defer(&system, Deferred::Status(request));
+            return;
+        }
+        let result = match request {
+            NetworkStatusRequest::ConnectSystem(system) => self.connect_system(system),
+            NetworkStatusRequest::DisconnectSystem(system) => self.disconnect_system(system),
+            NetworkStatusRequest::BlockSystem(system) => self.block_system(system),
+            NetworkStatusRequest::UnblockSystem(system) => self.unblock_system(system),
+        };
+        if let Err(e) = result {
+            error!(
+                self.ctx.log(),
+                "Failed to handle network status request: {:?}", e
+            );
+        }
+    }
+
+    fn connect_system(&mut self, system: SystemPath) -> Result<(), NetworkBridgeErr> {
+        let addr = match self.resolve_system(&system) {
+            Some(addr) if system.protocol() == Transport::TCP => addr,
+            _ => {
+                warn!(
+                    self.ctx.log(),
+                    "Can not connect to {}, only TCP systems have connections", system
+                );
+                return Ok(());
+            }
+        };
+        self.remember_system(addr, &system);
+        if self.blocked.contains(&addr) {
+            warn!(
+                self.ctx.log(),
+                "Not connecting to blocked system {}", system
+            );
+            return Ok(());
+        }
+        match self.connections.get(&addr) {
+            Some(ConnectionState::Connected(_))
+            | Some(ConnectionState::Blocked)
+            | Some(ConnectionState::Initializing) => {
+                debug!(
+                    self.ctx.log(),
+                    "Already connected or connecting to {}", system
+                );
+            }
+            _ => {
+                if let Some(bridge) = &self.net_bridge {
+                    debug!(self.ctx.log(), "Establishing new connection to {}", system);
+                    bridge.connect(Transport::TCP, addr)?;
+                    self.connections.insert(addr, ConnectionState::Initializing);
+                    self.start_retrying(addr); // Make sure we will re-request connection later
+                }
+            }
+        }
+        Ok(())
     }
 
-    fn schedule_retries(&mut self) {
-        // First check the retry_map if we should re-request connections
-        let drain = self.retry_map.clone();
-        self.retry_map.clear();
-        for (addr, retry) in drain {
-            if retry < self.cfg.max_connection_retry_attempts {
-                // Make sure we will re-request connection later
-                self.retry_map.insert(addr, retry + 1);
+    fn disconnect_system(&mut self, system: SystemPath) -> Result<(), NetworkBridgeErr> {
+        let addr = match self.resolve_system(&system) {
+            Some(addr) if system.protocol() == Transport::TCP => addr,
+            _ => {
+                warn!(
+                    self.ctx.log(),
+                    "Can not disconnect from {}, only TCP systems have connections", system
+                );
+                return Ok(());
+            }
+        };
+        match self.connections.get(&addr) {
+            Some(ConnectionState::Connected(_)) | Some(ConnectionState::Blocked) => {
                 if let Some(bridge) = &self.net_bridge {
-                    // Do connection attempt
-                    debug!(
-                        self.ctx().log(),
-                        "Dispatcher retrying connection to host {}, attempt {}/{}",
-                        addr,
-                        retry,
-                        self.cfg.max_connection_retry_attempts
-                    );
-                    bridge.connect(Transport::TCP, addr).unwrap();
+                    debug!(self.ctx.log(), "Disconnecting from {}", system);
+                    self.disconnecting.insert(addr);
+                    self.retry_map.remove(&addr);
+                    bridge.disconnect(addr)?;
                 }
-            } else {
-                // Too many retries, give up on the connection.
-                info!(
-                    self.ctx().log(),
-                    "Dispatcher giving up on remote host {}, dropping queues", addr
-                );
-                self.queue_manager.drop_queue(&addr);
-                self.connections.remove(&addr);
+            }
+            _ => {
+                debug!(self.ctx.log(), "Not connected to {}", system);
             }
         }
-        self.schedule_once(
-            Duration::from_millis(self.cfg.connection_retry_interval),
-            move |target, _id| {
-                target.schedule_retries();
-                Handled::Ok
-            },
-        );
+        Ok(())
     }
 
-    fn on_event(&mut self, ev: EventEnvelope) {
-        match ev {
-            EventEnvelope::Network(ev) => match ev {
-                NetworkEvent::Connection(addr, conn_state) => {
-                    if let Err(e) = self.on_conn_state(addr, conn_state) {
-                        error!(
-                            self.ctx().log(),
-                            "Error while connecting to {}, \n{:?}", addr, e
-                        )
-                    }
-                }
-                NetworkEvent::Data(_) => {
-                    // TODO shouldn't be receiving these here, as they should be routed directly to the ActorRef
-                    debug!(self.ctx().log(), "Received important data!");
-                }
-                NetworkEvent::RejectedData(addr, data) => {
-                    // These are messages which we routed to a network-thread before they lost the connection.
-                    self.queue_manager.enqueue_priority_data(data, addr);
-                }
-            },
+    fn block_system(&mut self, system: SystemPath) -> Result<(), NetworkBridgeErr> {
+        let addr = match self.resolve_system(&system) {
+            Some(addr) => addr,
+            None => {
+                warn!(
+                    self.ctx.log(),
+                    "Can not block {}, only systems with an IP address can be blocked", system
+                );
+                return Ok(());
+            }
+        };
+        info!(self.ctx.log(), "Blocking system {}", system);
+        self.blocked.insert(addr);
+        self.retry_map.remove(&addr);
+        self.drop_queue(addr);
+        if let Some(bridge) = &self.net_bridge {
+            bridge.block(addr)?;
         }
+        self.network_status_port
+            .trigger(NetworkStatus::BlockedSystem(system));
+        Ok(())
     }
 
-    fn on_conn_state(
-        &mut self,
-        addr: SocketAddr,
-        mut state: ConnectionState,
-    ) -> Result<(), NetworkBridgeErr> {
-        use self::ConnectionState::*;
-        match state {
-            Connected(ref mut _frame_sender) => {
-                info!(
-                    self.ctx().log(),
-                    "registering newly connected conn at {:?}", addr
+    fn unblock_system(&mut self, system: SystemPath) -> Result<(), NetworkBridgeErr> {
+        let addr = match self.resolve_system(&system) {
+            Some(addr) => addr,
+            None => {
+                warn!(
+                    self.ctx.log(),
+                    "Can not unblock {}, only systems with an IP address can be blocked", system
                 );
-                let _ = self.retry_map.remove(&addr);
-                if self.queue_manager.has_data(&addr) {
-                    // Drain as much as possible
-                    while let Some(frame) = self.queue_manager.pop_data(&addr) {
-                        if let Some(bridge) = &self.net_bridge {
-                            //println!("Sending queued frame to newly established connection");
-                            bridge.route(addr, frame, net::Protocol::TCP)?;
-                        }
-                    }
-                }
-            }
-            Closed => {
-                if self.retry_map.get(&addr).is_none() {
-                    warn!(self.ctx().log(), "connection closed for {:?}", addr);
-                    self.retry_map.insert(addr, 0); // Make sure we try to re-establish the connection
-                }
-                // Ack the close message
-                if let Some(bridge) = &self.net_bridge {
-                    bridge.ack_closed(addr)?;
-                }
+                return Ok(());
             }
-            Error(ref err) => {
-                match err {
-                    x if x.kind() == ErrorKind::ConnectionRefused => {
-                        error!(self.ctx().log(), "connection refused for {:?}", addr);
-                        // TODO determine how we want to proceed
-                        // If TCP, the network bridge has already attempted retries with exponential
-                        // backoff according to its configuration.
-                    }
-                    why => {
-                        error!(
-                            self.ctx().log(),
-                            "connection error for {:?}: {:?}", addr, why
-                        );
-                    }
-                }
+        };
+        if self.blocked.remove(&addr) {
+            info!(self.ctx.log(), "Unblocking system {}", system);
+            if let Some(bridge) = &self.net_bridge {
+                bridge.unblock(addr)?;
             }
-            ref _other => (), // Don't care
+            self.network_status_port
+                .trigger(NetworkStatus::UnblockedSystem(system));
         }
-        self.connections.insert(addr, state);
         Ok(())
     }
 
@@ -520,7 +2324,9 @@ impl NetworkDispatcher {
                         "No local actor found at {:?}. Forwarding to DeadletterBox",
                         netmsg.receiver,
                     );
-                    self.ctx.deadletter_ref().enqueue(MsgEnvelope::Net(netmsg));
+                    self.ctx
+                        .deadletter_ref()
+                        .enqueue(MsgEnvelope::Net(Box::new(netmsg)));
                 }
                 LookupResult::Err(e) => {
                     error!(
@@ -529,7 +2335,9 @@ impl NetworkDispatcher {
                         netmsg.receiver,
                         e
                     );
-                    self.ctx.deadletter_ref().enqueue(MsgEnvelope::Net(netmsg));
+                    self.ctx
+                        .deadletter_ref()
+                        .enqueue(MsgEnvelope::Net(Box::new(netmsg)));
                 }
             },
             Err(e) => {
@@ -541,10 +2349,15 @@ impl NetworkDispatcher {
     fn route_remote_udp(
         &mut self,
         addr: SocketAddr,
+        lane: u64,
         data: DispatchData,
     ) -> Result<(), NetworkBridgeErr> {
+        let data = match self.unless_blocked(addr, data) {
+            Some(data) => data,
+            None => return Ok(()),
+        };
         if let Some(bridge) = &self.net_bridge {
-            bridge.route(addr, data, net::Protocol::UDP)?;
+            bridge.route_udp(addr, lane, data)?;
         } else {
             warn!(
                 self.ctx.log(),
@@ -554,11 +2367,38 @@ impl NetworkDispatcher {
         Ok(())
     }
 
+    fn route_remote_uds(
+        &mut self,
+        path: PathBuf,
+        data: DispatchData,
+    ) -> Result<(), NetworkBridgeErr> {
+        if let Some(bridge) = &self.net_bridge {
+            bridge.route_uds(path, data)?;
+        } else {
+            warn!(
+                self.ctx.log(),
+                "Dropping UDS message to {}, as bridge is not connected.",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Routes `data` to the TCP channel to `addr`, in its priority lane if `prioritised`
+    ///
+    /// Prioritised data is queued apart from the other data while the channel is not connected,
+    /// drained into the priority lane once it is, and never overflows the queue.
     fn route_remote_tcp(
         &mut self,
         addr: SocketAddr,
         data: DispatchData,
+        prioritised: bool,
     ) -> Result<(), NetworkBridgeErr> {
+        let data = match self.unless_blocked(addr, data) {
+            Some(data) => data,
+            None => return Ok(()),
+        };
+        let mut overflow = Vec::new();
         let state: &mut ConnectionState =
             self.connections.entry(addr).or_insert(ConnectionState::New);
         let next: Option<ConnectionState> = match *state {
@@ -567,11 +2407,10 @@ impl NetworkDispatcher {
                     self.ctx.log(),
                     "No connection found; establishing and queuing frame"
                 );
-                self.queue_manager.enqueue_data(data, addr);
+                overflow = self.queue_manager.enqueue(data, addr, prioritised);
 
                 if let Some(ref mut bridge) = self.net_bridge {
                     debug!(self.ctx.log(), "Establishing new connection to {:?}", addr);
-                    self.retry_map.insert(addr, 0); // Make sure we will re-request connection later
                     bridge.connect(Transport::TCP, addr).unwrap();
                     Some(ConnectionState::Initializing)
                 } else {
@@ -579,15 +2418,16 @@ impl NetworkDispatcher {
                     Some(ConnectionState::Closed)
                 }
             }
+            ConnectionState::Connected(_) if prioritised => {
+                if let Some(bridge) = &self.net_bridge {
+                    bridge.route_prioritised(addr, data)?;
+                }
+                None
+            }
             ConnectionState::Connected(_) => {
                 if self.queue_manager.has_data(&addr) {
-                    self.queue_manager.enqueue_data(data, addr);
-
-                    if let Some(bridge) = &self.net_bridge {
-                        while let Some(queued_data) = self.queue_manager.pop_data(&addr) {
-                            bridge.route(addr, queued_data, net::Protocol::TCP)?;
-                        }
-                    }
+                    overflow = self.queue_manager.enqueue(data, addr, prioritised);
+                    Self::drain_queue(&mut self.queue_manager, self.net_bridge.as_ref(), addr)?;
                     None
                 } else {
                     // Send frame
@@ -599,23 +2439,101 @@ impl NetworkDispatcher {
             }
             ConnectionState::Initializing => {
                 //debug!(self.ctx.log(), "Connection is initializing; queuing frame");
-                self.queue_manager.enqueue_data(data, addr);
+                overflow = self.queue_manager.enqueue(data, addr, prioritised);
+                None
+            }
+            ConnectionState::Blocked => {
+                // The remote host is not accepting more data, hold on to the frame until it does
+                overflow = self.queue_manager.enqueue(data, addr, prioritised);
                 None
             }
             ConnectionState::Closed => {
                 // Enqueue the Frame. The connection will sort itself out or drop the queue eventually
-                self.queue_manager.enqueue_data(data, addr);
+                overflow = self.queue_manager.enqueue(data, addr, prioritised);
                 None
             }
             _ => None,
         };
 
         if let Some(next) = next {
+            let initializing = matches!(next, ConnectionState::Initializing);
             *state = next;
+            if initializing {
+                self.start_retrying(addr); // Make sure we will re-request connection later
+            }
+        }
+        if !overflow.is_empty() {
+            let system = self.remote_path(addr);
+            self.handle_overflow(system, overflow);
+        }
+        Ok(())
+    }
+
+    /// Routes everything queued for `addr` to its TCP channel, prioritised messages in its priority lane
+    fn drain_queue(
+        queue_manager: &mut QueueManager,
+        bridge: Option<&net::Bridge>,
+        addr: SocketAddr,
+    ) -> Result<(), NetworkBridgeErr> {
+        if let Some(bridge) = bridge {
+            while let Some(queued_data) = queue_manager.pop_prioritised_data(&addr) {
+                bridge.route_prioritised(addr, queued_data)?;
+            }
+            while let Some(queued_data) = queue_manager.pop_data(&addr) {
+                bridge.route(addr, queued_data, net::Protocol::TCP)?;
+            }
         }
         Ok(())
     }
 
+    /// Handles the data to `system` which did not fit into its queue according to the [OverflowPolicy]
+    fn handle_overflow(&mut self, system: SystemPath, overflow: Vec<DispatchData>) -> () {
+        let policy = self.queue_manager.overflow_policy();
+        let dropped = overflow.len();
+        debug!(
+            self.ctx.log(),
+            "Queue for {} is full, handling {} message(s) with {:?}", system, dropped, policy
+        );
+        self.network_status_port
+            .trigger(NetworkStatus::DroppedMessages(system.clone(), dropped));
+        for data in overflow {
+            match data.into_local() {
+                Ok(netmsg) if policy == OverflowPolicy::RejectToSender => {
+                    let sender = netmsg.sender.clone();
+                    match Undeliverable::from_message(netmsg) {
+                        Ok(undeliverable) => {
+                            let src = self.deadletter_path();
+                            let msg =
+                                DispatchData::Lazy(Box::new(undeliverable), src, sender.clone());
+                            self.route_local(sender, msg);
+                        }
+                        Err(e) => {
+                            warn!(
+                                self.ctx.log(),
+                                "Could not serialise message to {} which did not fit its queue: {}",
+                                system,
+                                e
+                            );
+                        }
+                    }
+                }
+                Ok(netmsg) => {
+                    self.ctx
+                        .deadletter_ref()
+                        .enqueue(MsgEnvelope::Net(Box::new(netmsg)));
+                }
+                Err(e) => {
+                    warn!(
+                        self.ctx.log(),
+                        "Could not deserialise message to {} which did not fit its queue: {}",
+                        system,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
     fn resolve_path(&mut self, resolvable: &PathResolvable) -> Result<ActorPath, PathParseError> {
         match resolvable {
             PathResolvable::Path(actor_path) => Ok(actor_path.clone()),
@@ -635,28 +2553,256 @@ impl NetworkDispatcher {
     /// Forwards `msg` to destination described by `dst`, routing it across the network
     /// if needed.
     fn route(&mut self, dst: ActorPath, msg: DispatchData) -> Result<(), NetworkBridgeErr> {
+        self.route_with_priority(dst, msg, false)
+    }
+
+    /// Like [route](NetworkDispatcher::route), but sends `msg` in the priority lane of TCP channels if `prioritised`
+    fn route_with_priority(
+        &mut self,
+        dst: ActorPath,
+        msg: DispatchData,
+        prioritised: bool,
+    ) -> Result<(), NetworkBridgeErr> {
         if self.system_path_ref() == dst.system() {
             self.route_local(dst, msg);
             Ok(())
         } else {
             let proto = dst.system().protocol();
+            if proto.is_ip() && self.awaiting_resolution(dst.system()) {
+                let system = dst.system().clone();
+                let deferred = Deferred::Route {
+                    dst,
+                    msg: Box::new(msg),
+                    prioritised,
+                };
+                self.defer(&system, deferred);
+                return Ok(());
+            }
             match proto {
                 Transport::LOCAL => {
                     self.route_local(dst, msg);
                     Ok(())
                 }
                 Transport::TCP => {
-                    let addr = SocketAddr::new(*dst.address(), dst.port());
-                    self.route_remote_tcp(addr, msg)
+                    let addr = self.resolve_remote(dst.system())?;
+                    if self.is_own_addr(dst.system(), addr) {
+                        self.route_local(dst, msg);
+                        return Ok(());
+                    }
+                    self.remember_system(addr, dst.system());
+                    self.route_remote_tcp(addr, msg, prioritised)
                 }
                 Transport::UDP => {
-                    let addr = SocketAddr::new(*dst.address(), dst.port());
-                    self.route_remote_udp(addr, msg)
+                    let addr = self.resolve_remote(dst.system())?;
+                    if self.is_own_addr(dst.system(), addr) {
+                        self.route_local(dst, msg);
+                        return Ok(());
+                    }
+                    // Reliable UDP orders messages per destination actor
+                    let mut hasher = FxHasher::default();
+                    dst.hash(&mut hasher);
+                    self.route_remote_udp(addr, hasher.finish(), msg)
+                }
+                Transport::UDS => match dst.address() {
+                    Address::Uds(path) => self.route_remote_uds(path.clone(), msg),
+                    _ => unreachable!("UDS paths have a socket file"),
+                },
+            }
+        }
+    }
+
+    /// Returns the socket address of `system`, if it is known without blocking
+    ///
+    /// Hostnames without a live cached address are resolved in the background.
+    /// Until that succeeds, their last cached address is returned, if any.
+    /// Returns `None` for [UDS](Transport::UDS) systems and hostnames that are not resolved yet.
+    fn resolve_system(&mut self, system: &SystemPath) -> Option<SocketAddr> {
+        match system.address() {
+            Address::Ip(ip) => Some(SocketAddr::new(*ip, system.port())),
+            Address::Hostname(host) => {
+                if self
+                    .resolver_cache
+                    .expired(host, system.port(), Instant::now())
+                {
+                    self.start_resolving(host.clone(), system.port());
+                }
+                self.resolver_cache.cached(host, system.port())
+            }
+            Address::Uds(_) => None,
+        }
+    }
+
+    /// Returns `true` if `system` is addressed by a hostname which has no address yet
+    fn awaiting_resolution(&mut self, system: &SystemPath) -> bool {
+        system.address().hostname().is_some() && self.resolve_system(system).is_none()
+    }
+
+    /// Holds `deferred` back until the hostname of `system` is resolved
+    ///
+    /// Must only be called if the dispatcher is [awaiting resolution](NetworkDispatcher::awaiting_resolution) of `system`.
+    /// At most [max_queued_messages](NetworkConfig::set_max_queued_messages) are held back per hostname.
+    fn defer(&mut self, system: &SystemPath, deferred: Deferred) -> () {
+        let key = (
+            system.address().hostname().unwrap_or_default().to_string(),
+            system.port(),
+        );
+        let full = match self.resolving.get_mut(&key) {
+            Some(pending) => {
+                let full = matches!(
+                    self.cfg.max_queued_messages,
+                    Some(max) if pending.deferred.len() >= max
+                );
+                if !full {
+                    pending.deferred.push(deferred);
+                    return;
+                }
+                full
+            }
+            None => false,
+        };
+        warn!(
+            self.ctx.log(),
+            "Dropping work for {}, which is still being resolved (queue full: {})", system, full
+        );
+        self.drop_deferred(deferred);
+    }
+
+    /// Resolves `host` on a separate thread, unless that is already happening
+    fn start_resolving(&mut self, host: String, port: u16) -> () {
+        if let Entry::Vacant(entry) = self.resolving.entry((host.clone(), port)) {
+            entry.insert(PendingResolution {
+                attempt: 0,
+                deferred: Vec::new(),
+            });
+            self.spawn_lookup(host, port);
+        }
+    }
+
+    fn spawn_lookup(&mut self, host: String, port: u16) -> () {
+        let lookup = self
+            .resolver_cache
+            .resolve_in_background(host.clone(), port);
+        self.spawn_local(move |mut async_self| async move {
+            let result = lookup.await.unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    ErrorKind::Interrupted,
+                    "The resolver thread did not complete",
+                ))
+            });
+            async_self.on_resolved(host, port, result);
+            Handled::Ok
+        });
+    }
+
+    /// Continues the work held back for `host`, or retries resolving it after a failure
+    ///
+    /// Failed lookups are retried like [connections](NetworkConfig::set_max_connection_retry_attempts),
+    /// after which the held back work is dropped.
+    fn on_resolved(&mut self, host: String, port: u16, result: io::Result<Vec<SocketAddr>>) -> () {
+        let result = result.and_then(|addrs| {
+            self.resolver_cache
+                .insert(host.clone(), port, addrs, Instant::now())
+        });
+        let key = (host, port);
+        match result {
+            Ok(addr) => {
+                debug!(self.ctx.log(), "Resolved {}:{} to {}", key.0, port, addr);
+                if let Some(bridge) = &self.net_bridge {
+                    if let Err(e) = bridge.resolved(key.0.clone(), addr) {
+                        warn!(
+                            self.ctx.log(),
+                            "Failed to pass on the address of {}: {:?}", key.0, e
+                        );
+                    }
+                }
+                if let Some(pending) = self.resolving.remove(&key) {
+                    for deferred in pending.deferred {
+                        self.run_deferred(deferred);
+                    }
+                }
+            }
+            Err(e) => {
+                let attempt = match self.resolving.get_mut(&key) {
+                    Some(pending) => {
+                        pending.attempt += 1;
+                        pending.attempt
+                    }
+                    None => return,
+                };
+                if attempt <= u32::from(self.cfg.max_connection_retry_attempts) {
+                    warn!(
+                        self.ctx.log(),
+                        "Could not resolve {}:{}, retrying (attempt {}): {}",
+                        key.0,
+                        port,
+                        attempt,
+                        e
+                    );
+                    let (host, port) = key;
+                    let interval = Duration::from_millis(self.cfg.connection_retry_interval);
+                    self.schedule_once(interval, move |target, _id| {
+                        target.spawn_lookup(host, port);
+                        Handled::Ok
+                    });
+                } else {
+                    error!(
+                        self.ctx.log(),
+                        "Could not resolve {}:{}, giving up: {}", key.0, port, e
+                    );
+                    if let Some(pending) = self.resolving.remove(&key) {
+                        for deferred in pending.deferred {
+                            self.drop_deferred(deferred);
+                        }
+                    }
+                    self.terminate_unresolved_watches(&key.0, port);
+                }
+            }
+        }
+    }
+
+    fn run_deferred(&mut self, deferred: Deferred) -> () {
+        match deferred {
+            Deferred::Route {
+                dst,
+                msg,
+                prioritised,
+            } => {
+                if let Err(e) = self.route_with_priority(dst, *msg, prioritised) {
+                    error!(self.ctx.log(), "Failed to route message: {:?}", e);
                 }
             }
+            Deferred::Status(request) => self.on_status_request(request),
         }
     }
 
+    fn drop_deferred(&mut self, deferred: Deferred) -> () {
+        match deferred {
+            Deferred::Route { dst, .. } => {
+                self.network_status_port
+                    .trigger(NetworkStatus::DroppedMessages(dst.system().clone(), 1));
+            }
+            Deferred::Status(request) => {
+                warn!(self.ctx.log(), "Dropping request {:?}", request);
+            }
+        }
+    }
+
+    /// Like [resolve_system](NetworkDispatcher::resolve_system), but fails if `system` can not be resolved
+    fn resolve_remote(&mut self, system: &SystemPath) -> Result<SocketAddr, NetworkBridgeErr> {
+        self.resolve_system(system)
+            .ok_or_else(|| NetworkBridgeErr::Resolution(system.to_string()))
+    }
+
+    /// Returns `true` if `system` is addressed by a hostname that resolved to our own `addr`
+    fn is_own_addr(&self, system: &SystemPath, addr: SocketAddr) -> bool {
+        system.address().hostname().is_some()
+            && self
+                .net_bridge
+                .as_ref()
+                .and_then(|bridge| *bridge.advertised_addr())
+                == Some(addr)
+    }
+
     fn deadletter_path(&mut self) -> ActorPath {
         ActorPath::Named(NamedPath::with_system(self.system_path(), Vec::new()))
     }
@@ -691,6 +2837,8 @@ impl NetworkDispatcher {
                     if let Ok(ref res) = result {
                         if !res.is_empty() {
                             info!(self.ctx.log(), "Replaced entry for path={:?}", path);
+                            // Whoever watched the old actor must not mistake the new one for it
+                            self.end_watch(&ap, None);
                         }
                     }
                     result.map(|_| ap)
@@ -746,6 +2894,8 @@ impl NetworkDispatcher {
                     if let Ok(ref res) = result {
                         if !res.is_empty() {
                             info!(self.ctx.log(), "Replaced entry for path={:?}", path);
+                            // Whoever watched the old actor must not mistake the new one for it
+                            self.end_watch(&ap, None);
                         }
                     }
                     result.map(|_| ap).map_err(RegistrationError::InvalidPath)
@@ -793,12 +2943,48 @@ impl Actor for NetworkDispatcher {
             }
             DispatchEnvelope::Event(ev) => self.on_event(ev),
             DispatchEnvelope::LockedChunk(trash) => self.garbage_buffers.push_back(trash),
+            DispatchEnvelope::Watch { watcher, watched } => self.watch(watcher, watched),
+            DispatchEnvelope::Unwatch { watcher, watched } => self.unwatch(watcher, watched),
+            DispatchEnvelope::Ask {
+                dst,
+                msg,
+                timeout,
+                promise,
+            } => self.ask(dst, msg, timeout, promise),
+            DispatchEnvelope::Reliable { src, dst, msg } => self.tell_reliably(src, dst, msg),
+            DispatchEnvelope::Prioritised { src: _, dst, msg } => {
+                if let Err(e) = self.route_with_priority(dst, msg, true) {
+                    error!(self.ctx.log(), "Failed to route message: {:?}", e);
+                };
+            }
         }
         Handled::Ok
     }
 
     fn receive_network(&mut self, msg: NetMessage) -> Handled {
-        warn!(self.ctx.log(), "Received network message: {:?}", msg,);
+        let msg = match self.try_reply(msg) {
+            Some(msg) => msg,
+            None => return Handled::Ok,
+        };
+        let sender = msg.sender.clone();
+        match_deser! {msg; {
+            watch: WatchMessage [WatchMessage] => self.on_watch_message(sender, watch),
+            terminated: Terminated [Terminated] => {
+                if self.same_system(sender.system(), terminated.path.system()) {
+                    self.notify_watchers(terminated);
+                } else {
+                    warn!(
+                        self.ctx.log(),
+                        "Ignoring termination of {} reported by {}", terminated.path, sender
+                    );
+                }
+            },
+            reliable: ReliableMessage [ReliableMessage] => self.on_reliable_message(sender, reliable),
+            ack: ReliableAck [ReliableAck] => self.reliable_sender.on_ack(ack),
+            release: ReliableRelease [ReliableRelease] => self.reliable_receiver.on_release(release),
+            !Err(e) => error!(self.ctx.log(), "Could not deserialise system message: {:?}", e),
+            _ => warn!(self.ctx.log(), "Received network message: {:?}", msg),
+        }}
         Handled::Ok
     }
 }
@@ -812,21 +2998,74 @@ impl Dispatcher for NetworkDispatcher {
             Some(ref path) => path.clone(),
             None => {
                 let bound_addr = match self.net_bridge {
-                    Some(ref net_bridge) => net_bridge.local_addr().clone().expect("If net bridge is ready, port should be as well!"),
+                    Some(ref net_bridge) => net_bridge.advertised_addr().clone().expect("If net bridge is ready, port should be as well!"),
                     None => panic!("You must wait until the socket is bound before attempting to create a system path!"),
                 };
-                let sp = SystemPath::new(self.cfg.transport, bound_addr.ip(), bound_addr.port());
+                let sp = match (&self.cfg.uds_path, &self.cfg.advertised_hostname) {
+                    (Some(path), _) => SystemPath::with_uds_path(path.clone()),
+                    (None, Some(host)) => {
+                        SystemPath::with_hostname(self.cfg.transport, host, bound_addr.port())
+                            .expect("validated by set_advertised_hostname")
+                    }
+                    (None, None) => {
+                        SystemPath::new(self.cfg.transport, bound_addr.ip(), bound_addr.port())
+                    }
+                };
                 self.system_path = Some(sp.clone());
                 sp
             }
         }
     }
+
+    fn reachability_port(&mut self) -> Option<&mut ProvidedPort<ReachabilityPort>> {
+        Some(&mut self.reachability_port)
+    }
+
+    fn network_status_port(&mut self) -> Option<&mut ProvidedPort<NetworkStatusPort>> {
+        Some(&mut self.network_status_port)
+    }
+}
+
+/// The socket address of `system`, if it has an IP address or a hostname resolved in `cache`
+fn cached_addr(cache: &ResolverCache, system: &SystemPath) -> Option<SocketAddr> {
+    match system.address() {
+        Address::Hostname(host) => cache.cached(host, system.port()),
+        _ => system.socket_addr(),
+    }
+}
+
+impl Provide<ReachabilityPort> for NetworkDispatcher {
+    fn handle(&mut self, request: ReachabilityRequest) -> Handled {
+        match request {
+            ReachabilityRequest::Suspicion(system) => {
+                // Only TCP systems have connections which carry heartbeats
+                let phi = Some(&system)
+                    .filter(|system| system.protocol() == Transport::TCP)
+                    .and_then(|system| self.resolve_system(system))
+                    .and_then(|addr| self.suspicions.get(&addr))
+                    .map(|suspicion| suspicion.phi);
+                self.reachability_port
+                    .trigger(ReachabilityIndication::Suspicion(system, phi));
+            }
+        }
+        Handled::Ok
+    }
+}
+
+impl Provide<NetworkStatusPort> for NetworkDispatcher {
+    fn handle(&mut self, request: NetworkStatusRequest) -> Handled {
+        self.on_status_request(request);
+        Handled::Ok
+    }
 }
 
 impl ComponentLifecycle for NetworkDispatcher {
     fn on_start(&mut self) -> Handled {
         info!(self.ctx.log(), "Starting network...");
-        self.start();
+        if let Err(e) = self.start() {
+            // Poisons the system, which makes building it fail
+            panic!("NetworkDispatcher failed to start the network: {:?}", e);
+        }
         info!(self.ctx.log(), "Started network just fine.");
         if let Some(promise) = self.notify_ready.take() {
             promise
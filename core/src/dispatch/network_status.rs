@@ -0,0 +1,69 @@
+use crate::{actors::SystemPath, net::compression::CompressionStats, ports::Port};
+
+/// A port on which the [NetworkDispatcher](super::NetworkDispatcher) reports the lifecycle of its connections
+///
+/// It also accepts requests to connect to, disconnect from, and block remote systems.
+/// Only systems reached via [TCP](crate::prelude::Transport::TCP) have connections,
+/// but blocking a system also drops its [UDP](crate::prelude::Transport::UDP) messages in either direction.
+///
+/// Connect to it via [connect_network_status_port](crate::prelude::KompactSystem::connect_network_status_port).
+pub struct NetworkStatusPort;
+
+impl Port for NetworkStatusPort {
+    type Indication = NetworkStatus;
+    type Request = NetworkStatusRequest;
+}
+
+/// Changes in the status of connections to remote systems
+#[derive(Clone, Debug)]
+pub enum NetworkStatus {
+    /// A connection to the system was established
+    ConnectionEstablished(SystemPath),
+    /// The connection to the system was lost, and the dispatcher is trying to re-establish it
+    ConnectionLost(SystemPath),
+    /// The dispatcher gave up on connecting to the system,
+    /// either after running out of retries or because the system failed to authenticate
+    ConnectionDropped(SystemPath),
+    /// The connection to the system was closed as requested
+    ConnectionClosed(SystemPath),
+    /// The connection to the system was closed by either system because it was
+    /// [idle](crate::prelude::NetworkConfig::set_idle_timeout), or to make room for another connection
+    ///
+    /// Messages sent to the system afterwards establish a new connection.
+    ConnectionIdle(SystemPath),
+    /// The given number of messages to the system were dropped without being sent,
+    /// or without being acknowledged if they were sent via reliable UDP
+    DroppedMessages(SystemPath, usize),
+    /// The system was blocked as requested
+    BlockedSystem(SystemPath),
+    /// The system was unblocked as requested
+    UnblockedSystem(SystemPath),
+    /// A new incarnation of the system appeared at the address of a previous one
+    ///
+    /// The actors of the previous incarnation are gone, so messages still queued for them were dropped.
+    SystemRestarted(SystemPath),
+    /// How well the data frames sent to the system compressed since the last report
+    ///
+    /// Reported once every heartbeat interval while [compression](crate::prelude::NetworkConfig::set_compression)
+    /// is enabled and large frames are being sent to the system.
+    CompressionStats(SystemPath, CompressionStats),
+}
+
+/// Requests to change the connections to remote systems
+#[derive(Clone, Debug)]
+pub enum NetworkStatusRequest {
+    /// Establishes a connection to the system, unless there already is one
+    ConnectSystem(SystemPath),
+    /// Closes the connection to the system
+    ///
+    /// Messages sent to the system afterwards establish a new connection,
+    /// just like the remote system may do for its own messages.
+    /// Use [BlockSystem](NetworkStatusRequest::BlockSystem) to prevent that.
+    DisconnectSystem(SystemPath),
+    /// Closes the connection to the system and refuses all communication with it until it is unblocked
+    ///
+    /// Messages queued for or sent to the blocked system are dropped.
+    BlockSystem(SystemPath),
+    /// Allows communication with a blocked system again
+    UnblockSystem(SystemPath),
+}
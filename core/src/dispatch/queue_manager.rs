@@ -1,48 +1,308 @@
-use crate::messaging::dispatch::DispatchData;
+use crate::{
+    actors::ActorPath,
+    messaging::{dispatch::DispatchData, HeapOrSer, NetMessage, SerialisedFrame},
+    net::frames::FRAME_HEAD_LEN,
+    serialisation::{
+        ser_helpers::framed_size_hint, serialisation_ids, Deserialiser, SerError, SerId, SerIdBuf,
+        SerIdBufMut, SerIdSize, Serialisable,
+    },
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use std::{
+    any::Any,
     collections::{HashMap, VecDeque},
     net::SocketAddr,
 };
 
+/// What happens to data sent to a remote host whose queue is full
+///
+/// Data is queued while the connection to its destination is being established,
+/// retried, or blocked by flow control.
+/// The queues are bounded by the [queue limits](crate::dispatch::NetworkConfig::set_max_queued_messages)
+/// of the [NetworkConfig](crate::dispatch::NetworkConfig).
+/// The number of messages dropped due to full queues is reported as
+/// [DroppedMessages](crate::dispatch::network_status::NetworkStatus::DroppedMessages), regardless of the policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued data to make room for the new data, and deliver it to the system's deadletter box
+    DropOldest,
+    /// Drop the new data, and deliver it to the system's deadletter box
+    DropNewest,
+    /// Return the new data to the actor which sent it as an [Undeliverable] message
+    RejectToSender,
+    /// Deliver the new data to the system's deadletter box
+    DeadLetter,
+}
+
+/// A message which could not be delivered, returned to the actor which sent it
+///
+/// With [RejectToSender](OverflowPolicy::RejectToSender), the dispatcher returns messages
+/// which did not fit their queue this way.
+/// The original message is kept in serialised form and can be recovered with
+/// [try_deserialise](Undeliverable::try_deserialise).
+///
+/// # Example
+///
+/// ```
+/// use kompact::prelude::*;
+///
+/// #[derive(ComponentDefinition)]
+/// struct Sender {
+///     ctx: ComponentContext<Self>,
+///     rejected: Vec<String>,
+/// }
+/// ignore_lifecycle!(Sender);
+/// impl Actor for Sender {
+///     type Message = Never;
+///
+///     fn receive_local(&mut self, _msg: Self::Message) -> Handled {
+///         unreachable!();
+///     }
+///
+///     fn receive_network(&mut self, msg: NetMessage) -> Handled {
+///         if let Ok(undeliverable) = msg.try_deserialise::<Undeliverable, Undeliverable>() {
+///             if let Ok(text) = undeliverable.try_deserialise::<String, String>() {
+///                 self.rejected.push(text);
+///             }
+///         }
+///         Handled::Ok
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Undeliverable {
+    /// The actor the message was sent to
+    pub receiver: ActorPath,
+    /// The serialisation id of the message
+    pub ser_id: SerId,
+    /// The serialised message
+    pub data: Bytes,
+}
+
+impl Undeliverable {
+    /// Serialises the data of `msg`, unless it is serialised already
+    pub(crate) fn from_message(msg: NetMessage) -> Result<Undeliverable, SerError> {
+        let NetMessage { receiver, data, .. } = msg;
+        let ser_id = data.ser_id;
+        let data = match data.data {
+            HeapOrSer::Boxed(ser) => {
+                let mut buf = BytesMut::with_capacity(ser.size_hint().unwrap_or(0));
+                ser.serialise(&mut buf)?;
+                buf.freeze()
+            }
+            HeapOrSer::Serialised(bytes) => bytes,
+            HeapOrSer::ChunkLease(mut chunk_lease) => {
+                chunk_lease.copy_to_bytes(chunk_lease.remaining())
+            }
+            HeapOrSer::ChunkRef(mut chunk_ref) => chunk_ref.copy_to_bytes(chunk_ref.remaining()),
+        };
+        Ok(Undeliverable {
+            receiver,
+            ser_id,
+            data,
+        })
+    }
+
+    /// Deserialises the original message with the [deserialiser](Deserialiser) `D`
+    ///
+    /// Fails if `D` is not meant for this serialisation id.
+    pub fn try_deserialise<T, D: Deserialiser<T>>(&self) -> Result<T, SerError> {
+        if self.ser_id == D::SER_ID {
+            D::deserialise(&mut self.data.clone())
+        } else {
+            Err(SerError::InvalidType(format!(
+                "Undeliverable message has serialisation id {}, not {}",
+                self.ser_id,
+                D::SER_ID
+            )))
+        }
+    }
+}
+
+impl Serialisable for Undeliverable {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::UNDELIVERABLE
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.receiver
+            .size_hint()
+            .map(|size| size + self.ser_id.size() + 4 + self.data.len())
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        self.receiver.serialise(buf)?;
+        (&mut *buf).put_ser_id(self.ser_id);
+        buf.put_u32(self.data.len() as u32);
+        buf.put_slice(&self.data);
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<Undeliverable> for Undeliverable {
+    const SER_ID: SerId = serialisation_ids::UNDELIVERABLE;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<Undeliverable, SerError> {
+        let receiver = ActorPath::deserialise(buf)?;
+        let ser_id = (&mut *buf).get_ser_id();
+        let len = buf.get_u32() as usize;
+        if buf.remaining() < len {
+            return Err(SerError::InvalidData(
+                "Undeliverable message is shorter than announced".into(),
+            ));
+        }
+        Ok(Undeliverable {
+            receiver,
+            ser_id,
+            data: buf.copy_to_bytes(len),
+        })
+    }
+}
+
+/// The number and size of the messages queued for a single remote host
+#[derive(Clone, Copy, Debug, Default)]
+struct QueueSize {
+    messages: usize,
+    bytes: usize,
+}
+
 /// Wrapper around a hashmap of frame queues.
 ///
 /// Used when waiting for connections to establish and drained when possible.
 /// `priority_queue` allows the NetworkDispatcher to maintain FIFO Order in the event of shaky connections
+/// `prioritised` holds [prioritised](crate::prelude::ActorPath::tell_prioritised) messages,
+/// which are sent in the priority lane of the channel once it is connected.
 pub struct QueueManager {
     inner: HashMap<SocketAddr, VecDeque<DispatchData>>,
     priority_queue: HashMap<SocketAddr, VecDeque<DispatchData>>,
+    prioritised: HashMap<SocketAddr, VecDeque<DispatchData>>,
+    sizes: HashMap<SocketAddr, QueueSize>,
+    max_messages: Option<usize>,
+    max_bytes: Option<usize>,
+    overflow_policy: OverflowPolicy,
 }
 
 impl QueueManager {
-    pub fn new() -> Self {
+    pub fn new(
+        max_messages: Option<usize>,
+        max_bytes: Option<usize>,
+        overflow_policy: OverflowPolicy,
+    ) -> Self {
         QueueManager {
             inner: HashMap::new(),
             priority_queue: HashMap::new(),
+            prioritised: HashMap::new(),
+            sizes: HashMap::new(),
+            max_messages,
+            max_bytes,
+            overflow_policy,
         }
     }
 
+    /// The policy for data sent to a remote host whose queue is full
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
     /*
     The queuemanager is really just a struct, no need to stop it?
     pub fn stop(self) -> () {
         drop(self); // doesn't need any cleanup, yet
     }
     */
-    /// Appends the given frame onto the SocketAddr's queue
-    pub fn enqueue_data(&mut self, data: DispatchData, dst: SocketAddr) {
+    /// Appends the given frame onto the SocketAddr's queue, unless the queue is full
+    ///
+    /// Returns the data which did not fit into the queue according to the [overflow policy](OverflowPolicy),
+    /// which is either the new data itself or the oldest queued data.
+    /// Data larger than the byte limit on its own never fits.
+    pub fn enqueue_data(&mut self, data: DispatchData, dst: SocketAddr) -> Vec<DispatchData> {
+        let size = data_size(&data);
+        if matches!(self.max_bytes, Some(max_bytes) if size > max_bytes) {
+            return vec![data];
+        }
+        let mut overflow = Vec::new();
+        while !self.fits(&dst, size) {
+            if self.overflow_policy != OverflowPolicy::DropOldest {
+                return vec![data];
+            }
+            match self.pop_data(&dst) {
+                Some(oldest) => overflow.push(oldest),
+                None => break,
+            }
+        }
+        self.add_size(dst, size);
         self.inner
             .entry(dst)
             .or_insert_with(VecDeque::new)
             .push_front(data);
+        overflow
     }
 
-    /// Appends the given frame onto the SocketAddr's queue
+    /// Appends the given frame onto the SocketAddr's priority queue, which is sent before the regular queue
+    ///
+    /// This holds data which was already accepted into a queue before.
+    /// It is queued regardless of the limits, but counts towards them.
     pub fn enqueue_priority_data(&mut self, data: DispatchData, dst: SocketAddr) {
+        self.add_size(dst, data_size(&data));
         self.priority_queue
             .entry(dst)
             .or_insert_with(VecDeque::new)
             .push_front(data);
     }
 
+    /// Appends the given [prioritised](crate::prelude::ActorPath::tell_prioritised) message
+    /// onto the SocketAddr's queue of prioritised messages
+    ///
+    /// It is queued regardless of the limits, but counts towards them.
+    pub fn enqueue_prioritised_data(&mut self, data: DispatchData, dst: SocketAddr) {
+        self.add_size(dst, data_size(&data));
+        self.prioritised.entry(dst).or_default().push_front(data);
+    }
+
+    /// Queues `data` with [enqueue_prioritised_data](QueueManager::enqueue_prioritised_data) if `prioritised`,
+    /// or with [enqueue_data](QueueManager::enqueue_data) otherwise
+    ///
+    /// Returns the data which did not fit into the queue.
+    pub fn enqueue(
+        &mut self,
+        data: DispatchData,
+        dst: SocketAddr,
+        prioritised: bool,
+    ) -> Vec<DispatchData> {
+        if prioritised {
+            self.enqueue_prioritised_data(data, dst);
+            Vec::new()
+        } else {
+            self.enqueue_data(data, dst)
+        }
+    }
+
+    fn fits(&self, dst: &SocketAddr, size: usize) -> bool {
+        let queued = self.sizes.get(dst).copied().unwrap_or_default();
+        !matches!(self.max_messages, Some(max_messages) if queued.messages >= max_messages)
+            && !matches!(self.max_bytes, Some(max_bytes) if queued.bytes + size > max_bytes)
+    }
+
+    fn add_size(&mut self, dst: SocketAddr, size: usize) {
+        let queued = self.sizes.entry(dst).or_default();
+        queued.messages += 1;
+        queued.bytes += size;
+    }
+
+    fn remove_size(&mut self, dst: &SocketAddr, size: usize) {
+        if let Some(queued) = self.sizes.get_mut(dst) {
+            queued.messages -= 1;
+            queued.bytes -= size;
+            if queued.messages == 0 {
+                self.sizes.remove(dst);
+            }
+        }
+    }
+
     /// Extracts the next queue-up frame for the SocketAddr, if one exists
     ///
     /// If the SocketAddr exists but its queue is empty, the entry is removed.
@@ -57,12 +317,60 @@ impl QueueManager {
                 self.inner.remove(dst);
             }
         }
+        if let Some(data) = &res {
+            self.remove_size(dst, data_size(data));
+        }
         res
     }
 
-    pub fn drop_queue(&mut self, addr: &SocketAddr) {
-        self.priority_queue.remove(addr);
-        self.inner.remove(addr);
+    /// Extracts the next queued-up prioritised message for the SocketAddr, if one exists
+    ///
+    /// Prioritised messages are not returned by [pop_data](QueueManager::pop_data).
+    pub fn pop_prioritised_data(&mut self, dst: &SocketAddr) -> Option<DispatchData> {
+        let res = self.prioritised.get_mut(dst).and_then(|q| q.pop_back());
+        if self.prioritised.contains_key(dst) && res.is_none() {
+            self.prioritised.remove(dst);
+        }
+        if let Some(data) = &res {
+            self.remove_size(dst, data_size(data));
+        }
+        res
+    }
+
+    /// Drops all frames queued for the SocketAddr and returns how many there were
+    pub fn drop_queue(&mut self, addr: &SocketAddr) -> usize {
+        self.sizes.remove(addr);
+        let prioritised = self.prioritised.remove(addr).map_or(0, |q| q.len());
+        let priority = self.priority_queue.remove(addr).map_or(0, |q| q.len());
+        prioritised + priority + self.inner.remove(addr).map_or(0, |q| q.len())
+    }
+
+    /// Drops the data queued for the SocketAddr whose receiver matches `predicate` and returns how much there was
+    ///
+    /// Data whose receiver can not be read is kept.
+    pub fn drop_matching<F>(&mut self, addr: &SocketAddr, predicate: F) -> usize
+    where
+        F: Fn(&ActorPath) -> bool,
+    {
+        let mut dropped = Vec::new();
+        let mut queues = [
+            self.prioritised.get_mut(addr),
+            self.priority_queue.get_mut(addr),
+            self.inner.get_mut(addr),
+        ];
+        for queue in queues.iter_mut().flatten() {
+            queue.retain(|data| match receiver(data) {
+                Some(receiver) if predicate(&receiver) => {
+                    dropped.push(data_size(data));
+                    false
+                }
+                _ => true,
+            });
+        }
+        for size in dropped.iter() {
+            self.remove_size(addr, *size);
+        }
+        dropped.len()
     }
 
     /*
@@ -95,6 +403,9 @@ impl QueueManager {
     // }
 
     pub fn has_data(&self, dst: &SocketAddr) -> bool {
+        if matches!(self.prioritised.get(dst), Some(q) if !q.is_empty()) {
+            return true;
+        }
         if self
             .priority_queue
             .get(dst)
@@ -105,3 +416,166 @@ impl QueueManager {
         self.inner.get(dst).map_or(false, |q| !q.is_empty())
     }
 }
+
+/// The number of bytes `data` takes up on the wire, as far as it is known without serialising it
+///
+/// Messages without a size hint count as 0 bytes.
+fn data_size(data: &DispatchData) -> usize {
+    match data {
+        DispatchData::Lazy(ser, src, dst) => framed_size_hint(src, dst, ser.as_ref()).unwrap_or(0),
+        DispatchData::Serialised(frame) => frame.len(),
+        DispatchData::NetMessage(msg) => match &msg.data.data {
+            HeapOrSer::Boxed(ser) => ser.size_hint().unwrap_or(0),
+            HeapOrSer::Serialised(bytes) => bytes.len(),
+            HeapOrSer::ChunkLease(chunk_lease) => chunk_lease.capacity(),
+            HeapOrSer::ChunkRef(chunk_ref) => chunk_ref.capacity(),
+        },
+    }
+}
+
+/// The receiver of `data`, read from the frame if it is serialised already
+fn receiver(data: &DispatchData) -> Option<ActorPath> {
+    match data {
+        DispatchData::Lazy(_, _, dst) => Some(dst.clone()),
+        DispatchData::NetMessage(msg) => Some(msg.receiver.clone()),
+        DispatchData::Serialised(SerialisedFrame::Bytes(bytes)) => frame_receiver(bytes.clone()),
+        DispatchData::Serialised(SerialisedFrame::ChunkLease(chunk_lease)) => {
+            frame_receiver(chunk_lease.create_byte_clone())
+        }
+        DispatchData::Serialised(SerialisedFrame::ChunkRef(chunk_ref)) => {
+            frame_receiver(chunk_ref.clone())
+        }
+    }
+}
+
+/// Reads the receiver from a framed message, as written by [serialise_msg](crate::serialisation::ser_helpers::serialise_msg)
+fn frame_receiver<B: Buf>(mut frame: B) -> Option<ActorPath> {
+    if frame.remaining() < FRAME_HEAD_LEN as usize {
+        return None;
+    }
+    frame.advance(FRAME_HEAD_LEN as usize);
+    ActorPath::deserialise(&mut frame).ok()?;
+    ActorPath::deserialise(&mut frame).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::SerialisedFrame;
+    use bytes::Bytes;
+
+    fn data(len: usize) -> DispatchData {
+        DispatchData::Serialised(SerialisedFrame::Bytes(Bytes::from(vec![0u8; len])))
+    }
+
+    fn len(data: DispatchData) -> usize {
+        data_size(&data)
+    }
+
+    #[test]
+    fn full_queues_drop_the_newest_data() {
+        let dst = "127.0.0.1:1234".parse().unwrap();
+        let mut queue_manager = QueueManager::new(Some(2), None, OverflowPolicy::DropNewest);
+        assert!(queue_manager.enqueue_data(data(1), dst).is_empty());
+        assert!(queue_manager.enqueue_data(data(2), dst).is_empty());
+        let overflow = queue_manager.enqueue_data(data(3), dst);
+        assert_eq!(vec![3], overflow.into_iter().map(len).collect::<Vec<_>>());
+        assert_eq!(Some(1), queue_manager.pop_data(&dst).map(len));
+        assert!(queue_manager.enqueue_data(data(4), dst).is_empty());
+        assert_eq!(2, queue_manager.drop_queue(&dst));
+        assert!(queue_manager.enqueue_data(data(5), dst).is_empty());
+    }
+
+    #[test]
+    fn full_queues_drop_the_oldest_data() {
+        let dst = "127.0.0.1:1234".parse().unwrap();
+        let mut queue_manager = QueueManager::new(None, Some(10), OverflowPolicy::DropOldest);
+        queue_manager.enqueue_priority_data(data(3), dst);
+        assert!(queue_manager.enqueue_data(data(4), dst).is_empty());
+        let overflow = queue_manager.enqueue_data(data(6), dst);
+        assert_eq!(vec![3], overflow.into_iter().map(len).collect::<Vec<_>>());
+        // Data larger than the limit never fits
+        let overflow = queue_manager.enqueue_data(data(11), dst);
+        assert_eq!(vec![11], overflow.into_iter().map(len).collect::<Vec<_>>());
+        assert_eq!(Some(4), queue_manager.pop_data(&dst).map(len));
+        assert_eq!(Some(6), queue_manager.pop_data(&dst).map(len));
+        assert!(!queue_manager.has_data(&dst));
+    }
+
+    #[test]
+    fn prioritised_data_is_kept_apart() {
+        let dst = "127.0.0.1:1234".parse().unwrap();
+        let mut queue_manager = QueueManager::new(Some(2), None, OverflowPolicy::DropOldest);
+        assert!(queue_manager.enqueue(data(1), dst, false).is_empty());
+        assert!(queue_manager.enqueue(data(2), dst, true).is_empty());
+        assert!(queue_manager.enqueue(data(3), dst, true).is_empty());
+        // Prioritised data counts towards the limits, but is never dropped
+        let overflow = queue_manager.enqueue(data(4), dst, false);
+        assert_eq!(vec![1], overflow.into_iter().map(len).collect::<Vec<_>>());
+        assert_eq!(Some(2), queue_manager.pop_prioritised_data(&dst).map(len));
+        assert_eq!(Some(3), queue_manager.pop_prioritised_data(&dst).map(len));
+        assert!(queue_manager.pop_prioritised_data(&dst).is_none());
+        assert!(queue_manager.has_data(&dst));
+        assert_eq!(Some(4), queue_manager.pop_data(&dst).map(len));
+        assert!(!queue_manager.has_data(&dst));
+        assert!(queue_manager.sizes.is_empty());
+    }
+
+    #[test]
+    fn only_matching_receivers_are_dropped() {
+        use crate::{
+            actors::{NamedPath, SystemPath, Transport},
+            serialisation::ser_helpers::serialise_msg_to_bytes,
+        };
+        use uuid::Uuid;
+
+        let dst = "127.0.0.1:1234".parse().unwrap();
+        let system = SystemPath::new(Transport::TCP, "127.0.0.1".parse().unwrap(), 1234);
+        let unique = ActorPath::from((system.clone(), Uuid::new_v4()));
+        let named = ActorPath::Named(NamedPath::with_system(system, vec!["named".to_string()]));
+        let lazy = |receiver: &ActorPath| {
+            DispatchData::Lazy(Box::new(7u64), unique.clone(), receiver.clone())
+        };
+        let framed = |receiver: &ActorPath| {
+            let bytes = serialise_msg_to_bytes(&unique, receiver, &7u64).expect("serialise");
+            DispatchData::Serialised(SerialisedFrame::Bytes(bytes))
+        };
+        let mut queue_manager = QueueManager::new(None, None, OverflowPolicy::DropNewest);
+        queue_manager.enqueue_priority_data(lazy(&unique), dst);
+        queue_manager.enqueue_data(framed(&unique), dst);
+        queue_manager.enqueue_data(lazy(&named), dst);
+        queue_manager.enqueue_data(framed(&named), dst);
+        queue_manager.enqueue_data(data(3), dst);
+
+        let dropped =
+            queue_manager.drop_matching(&dst, |receiver| matches!(receiver, ActorPath::Unique(_)));
+        assert_eq!(dropped, 2);
+        let mut kept = Vec::new();
+        while let Some(data) = queue_manager.pop_data(&dst) {
+            kept.push(receiver(&data));
+        }
+        assert_eq!(kept, vec![Some(named.clone()), Some(named), None]);
+        assert!(queue_manager.sizes.is_empty());
+    }
+    #[test]
+    fn undeliverable_messages_keep_the_original_message() {
+        use crate::actors::{SystemPath, Transport};
+        use uuid::Uuid;
+
+        let system = SystemPath::new(Transport::TCP, "127.0.0.1".parse().unwrap(), 1234);
+        let sender = ActorPath::from((system.clone(), Uuid::new_v4()));
+        let receiver = ActorPath::from((system, Uuid::new_v4()));
+        let msg = NetMessage::with_box(7u64.ser_id(), sender, receiver.clone(), Box::new(7u64));
+        let undeliverable = Undeliverable::from_message(msg).expect("serialise");
+        assert_eq!(receiver, undeliverable.receiver);
+        let original = undeliverable.try_deserialise::<u64, u64>();
+        assert_eq!(7u64, original.expect("deserialise"));
+        assert!(undeliverable.try_deserialise::<String, String>().is_err());
+
+        let mut buf = BytesMut::new();
+        undeliverable.serialise(&mut buf).expect("serialise");
+        assert_eq!(undeliverable.size_hint(), Some(buf.len()));
+        let deserialised = Undeliverable::deserialise(&mut buf.freeze()).expect("deserialise");
+        assert_eq!(undeliverable, deserialised);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{actors::SystemPath, ports::Port};
+
+/// A port on which the [NetworkDispatcher](super::NetworkDispatcher) reports the reachability of remote systems
+///
+/// The dispatcher consults a phi-accrual failure detector about every connected system once per
+/// [heartbeat interval](super::NetworkConfig::set_heartbeat_interval).
+/// A system becomes unreachable when its suspicion level exceeds the
+/// [phi threshold](super::NetworkConfig::set_phi_threshold) or its connection is lost,
+/// and reachable again once it is connected and its suspicion level drops below the threshold.
+/// Systems are reported with the path they were first addressed with,
+/// or with their socket address if they connected without ever being addressed.
+///
+/// Connect to it via [connect_reachability_port](crate::prelude::KompactSystem::connect_reachability_port).
+pub struct ReachabilityPort;
+
+impl Port for ReachabilityPort {
+    type Indication = ReachabilityIndication;
+    type Request = ReachabilityRequest;
+}
+
+/// Changes in the reachability of remote systems, and answers to [ReachabilityRequest]s
+#[derive(Clone, Debug)]
+pub enum ReachabilityIndication {
+    /// The system is connected and its suspicion level is below the threshold
+    Reachable(SystemPath),
+    /// The system's suspicion level exceeded the threshold, or its connection was lost
+    Unreachable(SystemPath),
+    /// The current suspicion level (phi) of the system, or `None` if it is not connected
+    Suspicion(SystemPath, Option<f64>),
+}
+
+/// Queries about the reachability of remote systems
+#[derive(Clone, Debug)]
+pub enum ReachabilityRequest {
+    /// Asks for the current suspicion level of the system, answered with [ReachabilityIndication::Suspicion]
+    Suspicion(SystemPath),
+}
@@ -0,0 +1,749 @@
+//! At-least-once delivery of messages sent via [tell_reliably](crate::prelude::ActorPath::tell_reliably)
+//!
+//! Every pair of sending and receiving actor forms a *channel*, which the sending dispatcher
+//! identifies with a fresh id and numbers the messages of consecutively.
+//! Messages are sent to the receiving system's dispatcher, which delivers them to the receiver
+//! in order, drops duplicates, and acknowledges the highest sequence number it delivered so far.
+//! The sending dispatcher keeps the unacknowledged messages, and sends them all again,
+//! once the connection to the receiving system was re-established
+//! or the oldest of them has not been acknowledged for the
+//! [redelivery interval](super::NetworkConfig::set_redelivery_interval),
+//! up to [a limit](super::NetworkConfig::set_max_redelivered_messages) at a time.
+//! Once a channel has [too many](super::NetworkConfig::set_max_unacked_reliable_messages)
+//! unacknowledged messages, new messages on it are rejected.
+//!
+//! Once a channel has been fully acknowledged for a whole redelivery interval, the sending dispatcher
+//! forgets it and *releases* it, so the receiving dispatcher forgets it as well.
+//! Later messages between the same actors use a new channel.
+//! When a peer restarts, the receiving dispatcher forgets the channels from its previous incarnation,
+//! and the sending dispatcher drops those to it, except for the ones to named paths,
+//! which may still find a receiver in the new incarnation.
+use super::SocketAddr;
+use crate::{
+    actors::{ActorPath, SystemField, SystemPath},
+    messaging::NetMessage,
+    serialisation::{serialisation_ids, Deserialiser, SerError, SerId, Serialisable},
+};
+use bytes::{Buf, BufMut, Bytes};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    any::Any,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// A message on a reliable channel, as exchanged between dispatchers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ReliableMessage {
+    pub(crate) channel: Uuid,
+    pub(crate) seq: u64,
+    /// The lowest unacknowledged sequence number of the channel when this message was sent
+    ///
+    /// A receiver that does not know the channel yet, e.g. because it restarted, starts delivering there.
+    first: u64,
+    pub(crate) src: ActorPath,
+    pub(crate) dst: ActorPath,
+    ser_id: SerId,
+    data: Bytes,
+}
+
+impl ReliableMessage {
+    /// The message to hand to the receiving actor
+    fn into_net_message(self) -> NetMessage {
+        NetMessage::with_bytes(self.ser_id, self.src, self.dst, self.data)
+    }
+}
+
+impl Serialisable for ReliableMessage {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::RELIABLE_MESSAGE
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        let src = self.src.size_hint()?;
+        let dst = self.dst.size_hint()?;
+        Some(16 + 8 + 8 + src + dst + 8 + 4 + self.data.len())
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        buf.put_u128(self.channel.as_u128());
+        buf.put_u64(self.seq);
+        buf.put_u64(self.first);
+        self.src.serialise(buf)?;
+        self.dst.serialise(buf)?;
+        buf.put_u64(self.ser_id);
+        buf.put_u32(self.data.len() as u32);
+        buf.put_slice(&self.data);
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<ReliableMessage> for ReliableMessage {
+    const SER_ID: SerId = serialisation_ids::RELIABLE_MESSAGE;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<ReliableMessage, SerError> {
+        if buf.remaining() < 32 {
+            return Err(SerError::InvalidData(
+                "Could not get reliable message header".into(),
+            ));
+        }
+        let channel = Uuid::from_u128(buf.get_u128());
+        let seq = buf.get_u64();
+        let first = buf.get_u64();
+        let src = ActorPath::deserialise(buf)?;
+        let dst = ActorPath::deserialise(buf)?;
+        if buf.remaining() < 12 {
+            return Err(SerError::InvalidData(
+                "Could not get reliable message content header".into(),
+            ));
+        }
+        let ser_id = buf.get_u64();
+        let len = buf.get_u32() as usize;
+        if buf.remaining() < len {
+            return Err(SerError::InvalidData(format!(
+                "Reliable message is truncated: expected {} bytes, got {}",
+                len,
+                buf.remaining()
+            )));
+        }
+        let data = buf.copy_to_bytes(len);
+        Ok(ReliableMessage {
+            channel,
+            seq,
+            first,
+            src,
+            dst,
+            ser_id,
+            data,
+        })
+    }
+}
+
+/// Acknowledges all messages on `channel` up to and including `seq`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ReliableAck {
+    pub(crate) channel: Uuid,
+    pub(crate) seq: u64,
+}
+
+impl Serialisable for ReliableAck {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::RELIABLE_ACK
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(16 + 8)
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        buf.put_u128(self.channel.as_u128());
+        buf.put_u64(self.seq);
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<ReliableAck> for ReliableAck {
+    const SER_ID: SerId = serialisation_ids::RELIABLE_ACK;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<ReliableAck, SerError> {
+        if buf.remaining() < 24 {
+            return Err(SerError::InvalidData("Could not get reliable ack".into()));
+        }
+        let channel = Uuid::from_u128(buf.get_u128());
+        let seq = buf.get_u64();
+        Ok(ReliableAck { channel, seq })
+    }
+}
+
+/// Tells the receiving dispatcher that `channel` was fully acknowledged and will not be used anymore
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ReliableRelease {
+    pub(crate) channel: Uuid,
+}
+
+impl Serialisable for ReliableRelease {
+    fn ser_id(&self) -> SerId {
+        serialisation_ids::RELIABLE_RELEASE
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(16)
+    }
+
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        buf.put_u128(self.channel.as_u128());
+        Ok(())
+    }
+
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<ReliableRelease> for ReliableRelease {
+    const SER_ID: SerId = serialisation_ids::RELIABLE_RELEASE;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<ReliableRelease, SerError> {
+        if buf.remaining() < 16 {
+            return Err(SerError::InvalidData(
+                "Could not get reliable release".into(),
+            ));
+        }
+        let channel = Uuid::from_u128(buf.get_u128());
+        Ok(ReliableRelease { channel })
+    }
+}
+
+struct OutgoingChannel {
+    /// The address of the destination system, unless it is addressed by socket file
+    addr: Option<SocketAddr>,
+    next_seq: u64,
+    unacked: VecDeque<ReliableMessage>,
+    /// When the unacknowledged messages were last sent
+    sent_at: Instant,
+}
+
+/// The sending side of all reliable channels of a dispatcher
+pub(crate) struct ReliableSender {
+    ids: FxHashMap<(ActorPath, ActorPath), Uuid>,
+    channels: FxHashMap<Uuid, OutgoingChannel>,
+    /// Systems whose connection was lost and not re-established yet
+    lost: FxHashSet<SocketAddr>,
+    /// How many messages a channel keeps unacknowledged at most
+    max_unacked: usize,
+}
+
+impl ReliableSender {
+    pub(crate) fn new(max_unacked: usize) -> Self {
+        ReliableSender {
+            ids: FxHashMap::default(),
+            channels: FxHashMap::default(),
+            lost: FxHashSet::default(),
+            max_unacked,
+        }
+    }
+
+    /// Numbers the serialised message `data` from `src` to `dst`, and keeps it until it is acknowledged
+    ///
+    /// `addr` is the address of the system of `dst`, to which the message will be sent.
+    ///
+    /// Returns the message to send, or `Err` with the message as it would have been delivered,
+    /// if the channel from `src` to `dst` has too many unacknowledged messages already.
+    pub(crate) fn send(
+        &mut self,
+        src: ActorPath,
+        dst: ActorPath,
+        addr: Option<SocketAddr>,
+        ser_id: SerId,
+        data: Bytes,
+        now: Instant,
+    ) -> Result<ReliableMessage, Box<NetMessage>> {
+        let channel = *self
+            .ids
+            .entry((src.clone(), dst.clone()))
+            .or_insert_with(Uuid::new_v4);
+        let outgoing = self
+            .channels
+            .entry(channel)
+            .or_insert_with(|| OutgoingChannel {
+                addr,
+                next_seq: 1,
+                unacked: VecDeque::new(),
+                sent_at: now,
+            });
+        if outgoing.unacked.len() >= self.max_unacked {
+            return Err(Box::new(NetMessage::with_bytes(ser_id, src, dst, data)));
+        }
+        if outgoing.unacked.is_empty() {
+            outgoing.sent_at = now;
+        }
+        let seq = outgoing.next_seq;
+        let msg = ReliableMessage {
+            channel,
+            seq,
+            first: outgoing.unacked.front().map(|msg| msg.seq).unwrap_or(seq),
+            src,
+            dst,
+            ser_id,
+            data,
+        };
+        outgoing.next_seq += 1;
+        outgoing.unacked.push_back(msg.clone());
+        Ok(msg)
+    }
+
+    /// Forgets all messages acknowledged by `ack`
+    pub(crate) fn on_ack(&mut self, ack: ReliableAck) -> () {
+        if let Some(outgoing) = self.channels.get_mut(&ack.channel) {
+            while let Some(msg) = outgoing.unacked.front() {
+                if msg.seq > ack.seq {
+                    break;
+                }
+                outgoing.unacked.pop_front();
+            }
+        }
+    }
+
+    /// Returns the unacknowledged messages of the channels whose oldest message was sent at least `interval` ago,
+    /// but at most `limit` of them
+    ///
+    /// Channels to systems that are not `connected` are skipped, as they are sent again once they are
+    /// [reconnected](ReliableSender::reconnected). Channels that did not fit within the limit stay due.
+    pub(crate) fn due<F>(
+        &mut self,
+        now: Instant,
+        interval: Duration,
+        limit: usize,
+        connected: F,
+    ) -> Vec<ReliableMessage>
+    where
+        F: Fn(SocketAddr) -> bool,
+    {
+        let lost = &self.lost;
+        redeliver(&mut self.channels, now, limit, |outgoing| {
+            let reachable = match outgoing.addr {
+                Some(addr) => !lost.contains(&addr) && connected(addr),
+                None => true,
+            };
+            reachable && now.saturating_duration_since(outgoing.sent_at) >= interval
+        })
+    }
+
+    /// Must be called when the connection to the system at `addr` was lost
+    pub(crate) fn connection_lost(&mut self, addr: SocketAddr) -> () {
+        self.lost.insert(addr);
+    }
+
+    /// Returns the unacknowledged messages of all channels to the system at `addr`,
+    /// if the connection to it was [lost](ReliableSender::connection_lost) before, but at most `limit` of them
+    pub(crate) fn reconnected(
+        &mut self,
+        addr: SocketAddr,
+        now: Instant,
+        limit: usize,
+    ) -> Vec<ReliableMessage> {
+        if !self.lost.remove(&addr) {
+            return Vec::new();
+        }
+        redeliver(&mut self.channels, now, limit, |outgoing| {
+            outgoing.addr == Some(addr)
+        })
+    }
+
+    /// Forgets the channels that have been fully acknowledged for at least `interval`
+    ///
+    /// Returns the releases to send to the systems of their receivers.
+    pub(crate) fn release_idle(
+        &mut self,
+        now: Instant,
+        interval: Duration,
+    ) -> Vec<(SystemPath, ReliableRelease)> {
+        let channels = &mut self.channels;
+        let mut released = Vec::new();
+        self.ids
+            .retain(|(_, dst), channel| match channels.get(channel) {
+                Some(outgoing)
+                    if outgoing.unacked.is_empty()
+                        && now.saturating_duration_since(outgoing.sent_at) >= interval =>
+                {
+                    channels.remove(channel);
+                    let release = ReliableRelease { channel: *channel };
+                    released.push((dst.system().clone(), release));
+                    false
+                }
+                _ => true,
+            });
+        released
+    }
+
+    /// Drops all channels to the system at `addr`, along with their unacknowledged messages
+    ///
+    /// Returns the number of dropped messages.
+    pub(crate) fn drop_to(&mut self, addr: SocketAddr) -> usize {
+        self.lost.remove(&addr);
+        self.drop_where(|_, outgoing| outgoing.addr == Some(addr))
+    }
+
+    /// Drops the channels to the actors of the previous incarnation of the system at `addr`
+    ///
+    /// Only channels to named paths are kept, as their receivers may exist in the new incarnation as well.
+    ///
+    /// Returns the number of dropped messages.
+    pub(crate) fn restarted(&mut self, addr: SocketAddr) -> usize {
+        self.drop_where(|dst, outgoing| {
+            outgoing.addr == Some(addr) && matches!(dst, ActorPath::Unique(_))
+        })
+    }
+
+    fn drop_where<F>(&mut self, predicate: F) -> usize
+    where
+        F: Fn(&ActorPath, &OutgoingChannel) -> bool,
+    {
+        let channels = &mut self.channels;
+        let mut dropped = 0;
+        self.ids
+            .retain(|(_, dst), channel| match channels.get(channel) {
+                Some(outgoing) if predicate(dst, outgoing) => {
+                    dropped += outgoing.unacked.len();
+                    channels.remove(channel);
+                    false
+                }
+                _ => true,
+            });
+        dropped
+    }
+}
+
+/// Takes up to `limit` unacknowledged messages from the channels that match `predicate`, to send them again
+///
+/// The channels that waited the longest go first, so that none of them is starved by the limit.
+/// Only channels which were sent in full count as sent, the others stay due.
+fn redeliver<F>(
+    channels: &mut FxHashMap<Uuid, OutgoingChannel>,
+    now: Instant,
+    limit: usize,
+    predicate: F,
+) -> Vec<ReliableMessage>
+where
+    F: Fn(&OutgoingChannel) -> bool,
+{
+    let mut selected: Vec<&mut OutgoingChannel> = channels
+        .values_mut()
+        .filter(|outgoing| !outgoing.unacked.is_empty() && predicate(outgoing))
+        .collect();
+    selected.sort_by_key(|outgoing| outgoing.sent_at);
+    let mut messages = Vec::new();
+    for outgoing in selected {
+        let budget = limit.saturating_sub(messages.len());
+        if budget == 0 {
+            break;
+        }
+        if outgoing.unacked.len() <= budget {
+            outgoing.sent_at = now;
+        }
+        let first = outgoing.unacked.front().map(|msg| msg.seq).unwrap_or(0);
+        messages.extend(
+            outgoing
+                .unacked
+                .iter()
+                .take(budget)
+                .map(|msg| ReliableMessage {
+                    first,
+                    ..msg.clone()
+                }),
+        );
+    }
+    messages
+}
+
+/// The receiving side of all reliable channels of a dispatcher
+#[derive(Default)]
+pub(crate) struct ReliableReceiver {
+    channels: FxHashMap<Uuid, IncomingChannel>,
+}
+
+struct IncomingChannel {
+    /// The highest sequence number delivered so far
+    delivered: u64,
+    /// The address of the sending system, unless it is addressed by socket file
+    addr: Option<SocketAddr>,
+}
+
+impl ReliableReceiver {
+    /// Returns the message from the system at `addr` to deliver, unless it is a duplicate or arrived out of order,
+    /// along with the acknowledgement to send back
+    pub(crate) fn receive(
+        &mut self,
+        msg: ReliableMessage,
+        addr: Option<SocketAddr>,
+    ) -> (Option<NetMessage>, Option<ReliableAck>) {
+        let channel = msg.channel;
+        let incoming = self
+            .channels
+            .entry(channel)
+            .or_insert_with(|| IncomingChannel {
+                delivered: msg.first.saturating_sub(1),
+                addr,
+            });
+        let deliver = if msg.seq == incoming.delivered + 1 {
+            incoming.delivered = msg.seq;
+            Some(msg.into_net_message())
+        } else {
+            // Either a duplicate, or an earlier message is missing and will be redelivered
+            None
+        };
+        let ack = if incoming.delivered > 0 {
+            Some(ReliableAck {
+                channel,
+                seq: incoming.delivered,
+            })
+        } else {
+            None
+        };
+        (deliver, ack)
+    }
+
+    /// Forgets a channel its sender will not use anymore
+    pub(crate) fn on_release(&mut self, release: ReliableRelease) -> () {
+        self.channels.remove(&release.channel);
+    }
+
+    /// Forgets the channels from the previous incarnation of the system at `addr`
+    pub(crate) fn restarted(&mut self, addr: SocketAddr) -> () {
+        self.channels
+            .retain(|_, incoming| incoming.addr != Some(addr));
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::{NamedPath, Transport};
+    use bytes::BytesMut;
+
+    fn path(port: u16) -> ActorPath {
+        let system = SystemPath::new(Transport::TCP, "127.0.0.1".parse().unwrap(), port);
+        ActorPath::from((system, Uuid::new_v4()))
+    }
+
+    fn send(
+        sender: &mut ReliableSender,
+        src: &ActorPath,
+        dst: &ActorPath,
+        i: u8,
+    ) -> ReliableMessage {
+        sender
+            .send(
+                src.clone(),
+                dst.clone(),
+                dst.system().socket_addr(),
+                serialisation_ids::UNKNOWN,
+                Bytes::from(vec![i]),
+                Instant::now(),
+            )
+            .expect("Channel should have room")
+    }
+
+    #[test]
+    fn reliable_message_roundtrip() {
+        let mut sender = ReliableSender::new(64);
+        let msg = send(&mut sender, &path(1), &path(2), 7);
+        let mut buf = BytesMut::with_capacity(msg.size_hint().unwrap());
+        msg.serialise(&mut buf).expect("serialise");
+        let deserialised = ReliableMessage::deserialise(&mut buf.freeze()).expect("deserialise");
+        assert_eq!(msg, deserialised);
+
+        let ack = ReliableAck {
+            channel: msg.channel,
+            seq: 42,
+        };
+        let mut buf = BytesMut::with_capacity(ack.size_hint().unwrap());
+        ack.serialise(&mut buf).expect("serialise");
+        let deserialised = ReliableAck::deserialise(&mut buf.freeze()).expect("deserialise");
+        assert_eq!(ack, deserialised);
+    }
+
+    #[test]
+    fn redelivered_messages_are_delivered_once_and_in_order() {
+        let (src, dst) = (path(1), path(2));
+        let mut sender = ReliableSender::new(64);
+        let mut receiver = ReliableReceiver::default();
+        let first = send(&mut sender, &src, &dst, 1);
+        let second = send(&mut sender, &src, &dst, 2);
+        let third = send(&mut sender, &src, &dst, 3);
+        assert_eq!((first.seq, second.seq, third.seq), (1, 2, 3));
+
+        // The first message got lost, so the others can't be delivered yet
+        assert!(receiver.receive(second, None).0.is_none());
+        assert!(receiver.receive(third, None).0.is_none());
+
+        let now = Instant::now();
+        assert!(sender
+            .due(now, Duration::from_secs(60), 100, |_| true)
+            .is_empty());
+        let addr = dst.system().socket_addr().unwrap();
+        assert!(sender.reconnected(addr, now, 100).is_empty());
+        sender.connection_lost(addr);
+        let redelivered = sender.reconnected(addr, now, 100);
+        assert_eq!(redelivered.len(), 3);
+        let mut acks = Vec::new();
+        let mut delivered = Vec::new();
+        for msg in redelivered.iter().chain(redelivered.iter()) {
+            let (deliver, ack) = receiver.receive(msg.clone(), None);
+            delivered.extend(deliver);
+            acks.extend(ack);
+        }
+        let contents: Vec<u8> = delivered
+            .into_iter()
+            .map(|msg| match msg.data.data {
+                crate::messaging::HeapOrSer::Serialised(bytes) => bytes[0],
+                _ => panic!("Expected serialised data"),
+            })
+            .collect();
+        assert_eq!(contents, vec![1, 2, 3]);
+        assert!(acks.iter().all(|ack| ack.seq <= 3));
+
+        sender.on_ack(ReliableAck {
+            channel: first.channel,
+            seq: 2,
+        });
+        let remaining = sender.due(now, Duration::from_millis(0), 100, |_| true);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].seq, 3);
+        sender.on_ack(acks[acks.len() - 1]);
+        assert!(sender
+            .due(now, Duration::from_millis(0), 100, |_| true)
+            .is_empty());
+    }
+
+    #[test]
+    fn restarted_receivers_resume_at_the_first_unacknowledged_message() {
+        let (src, dst) = (path(1), path(2));
+        let mut sender = ReliableSender::new(64);
+        let mut receiver = ReliableReceiver::default();
+        let first = send(&mut sender, &src, &dst, 1);
+        let (_, ack) = receiver.receive(first.clone(), None);
+        sender.on_ack(ack.expect("ack"));
+        let second = send(&mut sender, &src, &dst, 2);
+        let third = send(&mut sender, &src, &dst, 3);
+
+        let mut restarted = ReliableReceiver::default();
+        // The third message overtook the redelivered second one
+        assert!(restarted.receive(third, None).0.is_none());
+        assert!(restarted.receive(second.clone(), None).0.is_some());
+        assert!(restarted.receive(first, None).0.is_none());
+        let (_, ack) = restarted.receive(second, None);
+        assert_eq!(ack.expect("ack").seq, 2);
+    }
+
+    #[test]
+    fn full_channels_reject_new_messages() {
+        let (src, dst) = (path(1), path(2));
+        let mut sender = ReliableSender::new(2);
+        let first = send(&mut sender, &src, &dst, 1);
+        send(&mut sender, &src, &dst, 2);
+        let rejected = sender
+            .send(
+                src.clone(),
+                dst.clone(),
+                dst.system().socket_addr(),
+                serialisation_ids::UNKNOWN,
+                Bytes::from(vec![3]),
+                Instant::now(),
+            )
+            .expect_err("Channel should be full");
+        assert_eq!(rejected.receiver, dst);
+        // Other channels are not affected
+        send(&mut sender, &src, &path(3), 3);
+        // Acknowledgements make room again
+        sender.on_ack(ReliableAck {
+            channel: first.channel,
+            seq: first.seq,
+        });
+        assert_eq!(send(&mut sender, &src, &dst, 4).seq, 3);
+    }
+
+    #[test]
+    fn channels_to_dropped_systems_are_forgotten() {
+        let (src, dst) = (path(1), path(2));
+        let mut sender = ReliableSender::new(64);
+        send(&mut sender, &src, &dst, 1);
+        send(&mut sender, &src, &path(3), 2);
+        assert_eq!(sender.drop_to(dst.system().socket_addr().unwrap()), 1);
+        assert_eq!(
+            sender
+                .due(Instant::now(), Duration::from_millis(0), 100, |_| true)
+                .len(),
+            1
+        );
+        // A new channel starts counting from the start again
+        assert_eq!(send(&mut sender, &src, &dst, 3).seq, 1);
+    }
+
+    #[test]
+    fn redelivery_is_limited_and_skips_unconnected_systems() {
+        let (src, dst) = (path(1), path(2));
+        let other = path(3);
+        let mut sender = ReliableSender::new(64);
+        for i in 0..3 {
+            send(&mut sender, &src, &dst, i);
+        }
+        send(&mut sender, &src, &other, 3);
+        let (addr, other_addr) = (
+            dst.system().socket_addr().unwrap(),
+            other.system().socket_addr().unwrap(),
+        );
+        let now = Instant::now();
+        let interval = Duration::from_millis(0);
+
+        let connected = |addr| addr != other_addr;
+        let due = sender.due(now, interval, 2, connected);
+        assert_eq!(
+            due.iter().map(|msg| msg.seq).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        // The channel did not fit, so it stays due
+        assert_eq!(sender.due(now, interval, 100, connected).len(), 3);
+
+        sender.connection_lost(addr);
+        assert!(sender.due(now, interval, 100, connected).is_empty());
+        assert_eq!(sender.reconnected(addr, now, 100).len(), 3);
+        assert_eq!(sender.due(now, interval, 100, |_| true).len(), 4);
+    }
+
+    #[test]
+    fn fully_acknowledged_channels_are_released() {
+        let (src, dst) = (path(1), path(2));
+        let mut sender = ReliableSender::new(64);
+        let mut receiver = ReliableReceiver::default();
+        let first = send(&mut sender, &src, &dst, 1);
+        let now = Instant::now();
+        let interval = Duration::from_millis(0);
+        assert!(sender.release_idle(now, interval).is_empty());
+
+        let (_, ack) = receiver.receive(first.clone(), None);
+        sender.on_ack(ack.expect("ack"));
+        let released = sender.release_idle(now, interval);
+        assert_eq!(released.len(), 1);
+        assert_eq!(&released[0].0, dst.system());
+        assert!(sender.ids.is_empty() && sender.channels.is_empty());
+        receiver.on_release(released[0].1);
+        assert!(receiver.channels.is_empty());
+
+        // Later messages use a new channel
+        let second = send(&mut sender, &src, &dst, 2);
+        assert_ne!(second.channel, first.channel);
+        assert!(receiver.receive(second, None).0.is_some());
+    }
+
+    #[test]
+    fn channels_of_restarted_systems_are_forgotten() {
+        let (src, dst) = (path(1), path(2));
+        let addr = dst.system().socket_addr().unwrap();
+        let named = ActorPath::Named(NamedPath::with_system(
+            dst.system().clone(),
+            vec!["named".to_string()],
+        ));
+        let mut sender = ReliableSender::new(64);
+        let mut receiver = ReliableReceiver::default();
+        send(&mut sender, &src, &dst, 1);
+        send(&mut sender, &src, &named, 2);
+        assert_eq!(sender.restarted(addr), 1);
+        let due = sender.due(Instant::now(), Duration::from_millis(0), 100, |_| true);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].dst, named);
+
+        receiver.receive(send(&mut sender, &dst, &src, 3), Some(addr));
+        receiver.receive(send(&mut sender, &named, &src, 4), None);
+        receiver.restarted(addr);
+        assert_eq!(receiver.channels.len(), 1);
+    }
+}
@@ -63,6 +63,9 @@
 //!     - Allow default broadcast and select actor paths on any node in the tree, not just where explicitly set via [set_routing_policy](KompactSystem::set_routing_policy).
 //!     - While this feature is convenient, it may open up your system to DoS attacks via broadcast on high-level nodes (e.g. `tcp://1.2.3.4:8000/*`).
 //!     - If you are concered about this security risk, you can disable this feature by using `--no-default-features`.
+//! - `tls`
+//!     - Build with support for encrypting network channels with TLS via [rustls](https://github.com/rustls/rustls).
+//!     - See [TlsConfig](net::tls::TlsConfig) and [NetworkConfig::set_tls_config](dispatch::NetworkConfig::set_tls_config).
 
 #![deny(missing_docs)]
 #![allow(clippy::unused_unit)]
@@ -102,6 +105,8 @@ use slog_async::Async;
 use std::convert::{From, Into};
 
 mod actors;
+/// Cluster membership for groups of Kompact systems
+pub mod cluster;
 /// Traits and structs for component API and internals
 pub mod component;
 mod dedicated_scheduler;
@@ -189,6 +194,7 @@ pub mod prelude {
             ActorRef,
             ActorRefFactory,
             ActorRefStrong,
+            Address,
             Dispatcher,
             DispatcherRef,
             Dispatching,
@@ -243,13 +249,26 @@ pub mod prelude {
             TwoWayChannel,
         },
         runtime::{KompactConfig, KompactSystem, SystemHandle},
-        supervision::{FaultContext, RecoveryHandler},
+        supervision::{
+            FaultContext,
+            LifecycleEvent,
+            LifecycleWatch,
+            LifecycleWatchable,
+            RecoveryHandler,
+        },
         Never,
     };
 
     pub use crate::{
         default_components::{CustomComponents, DeadletterBox, LocalDispatcher},
-        dispatch::{NetworkConfig, NetworkDispatcher},
+        dispatch::{
+            death_watch::{Terminated, TerminationReason},
+            network_status::{NetworkStatus, NetworkStatusPort, NetworkStatusRequest},
+            queue_manager::{OverflowPolicy, Undeliverable},
+            reachability::{ReachabilityIndication, ReachabilityPort, ReachabilityRequest},
+            NetworkConfig,
+            NetworkDispatcher,
+        },
         messaging::{
             DispatchEnvelope,
             MsgEnvelope,
@@ -273,6 +292,7 @@ pub mod prelude {
             on_dual_definition,
             promise,
             Ask,
+            AskError,
             Fulfillable,
             FutureCollection,
             FutureResultCollection,
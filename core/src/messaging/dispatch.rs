@@ -82,4 +82,50 @@ pub enum DispatchEnvelope {
     Event(EventEnvelope),
     /// Killed components send their BufferChunks to the Dispatcher for safe de-allocation
     LockedChunk(BufferChunk),
+    /// A request to notify the `watcher` with a [Terminated](crate::prelude::Terminated) message
+    /// once the actor at `watched` stops existing
+    Watch {
+        /// The actor to notify
+        watcher: DynActorRef,
+        /// The path of the actor to watch
+        watched: ActorPath,
+    },
+    /// Cancels an earlier [Watch](DispatchEnvelope::Watch) request
+    Unwatch {
+        /// The actor which no longer wants to be notified
+        watcher: DynActorRef,
+        /// The path of the watched actor
+        watched: ActorPath,
+    },
+    /// A request to send `msg` to `dst` from a temporary path,
+    /// and to fulfil `promise` with the first reply to that path
+    Ask {
+        /// The destination of the request
+        dst: ActorPath,
+        /// The request
+        msg: Box<dyn Serialisable>,
+        /// How long to wait for the reply
+        timeout: Duration,
+        /// Fulfilled with the reply, or an error if there is none in time
+        promise: KPromise<Result<NetMessage, AskError>>,
+    },
+    /// A request to deliver `msg` from `src` to `dst` at least once,
+    /// redelivering it until the receiving system acknowledges it
+    Reliable {
+        /// The source of the message
+        src: ActorPath,
+        /// The destination of the message
+        dst: ActorPath,
+        /// The message
+        msg: Box<dyn Serialisable>,
+    },
+    /// A potential network message that should overtake the bulk data queued for its destination
+    Prioritised {
+        /// The source of the message
+        src: ActorPath,
+        /// The destination of the message
+        dst: ActorPath,
+        /// The actual data to be dispatched
+        msg: DispatchData,
+    },
 }
@@ -1,7 +1,7 @@
 //! Message framing (serialization and deserialization into and from byte buffers)
 
 use crate::{
-    actors::{ActorPath, NamedPath, SystemField, SystemPath, Transport, UniquePath},
+    actors::{ActorPath, Address, NamedPath, SystemField, SystemPath, Transport, UniquePath},
     serialisation::{serialisation_ids, Deserialiser, SerError, SerId, Serialisable},
 };
 use bitfields::BitField;
@@ -19,6 +19,8 @@ pub enum AddressType {
     IPv6 = 1,
     /// A domain name
     Domain = 2,
+    /// The file path of a Unix domain socket
+    Uds = 3,
 }
 
 /// The type of path used
@@ -32,7 +34,7 @@ pub enum PathType {
 }
 
 impl BitField for AddressType {
-    const POS: usize = 2;
+    const POS: usize = 5;
     const WIDTH: usize = 2;
 }
 
@@ -77,6 +79,8 @@ impl TryFrom<u8> for AddressType {
         match x {
             x if x == AddressType::IPv4 as u8 => Ok(AddressType::IPv4),
             x if x == AddressType::IPv6 as u8 => Ok(AddressType::IPv6),
+            x if x == AddressType::Domain as u8 => Ok(AddressType::Domain),
+            x if x == AddressType::Uds as u8 => Ok(AddressType::Uds),
             _ => Err(SerError::InvalidType("Unsupported AddressType".into())),
         }
     }
@@ -102,6 +106,7 @@ impl TryFrom<u8> for Transport {
             x if x == Transport::LOCAL as u8 => Ok(Transport::LOCAL),
             x if x == Transport::UDP as u8 => Ok(Transport::UDP),
             x if x == Transport::TCP as u8 => Ok(Transport::TCP),
+            x if x == Transport::UDS as u8 => Ok(Transport::UDS),
             _ => Err(SerError::InvalidType(
                 "Unsupported transport protocol".into(),
             )),
@@ -118,6 +123,16 @@ impl<'a> From<&'a IpAddr> for AddressType {
     }
 }
 
+impl<'a> From<&'a Address> for AddressType {
+    fn from(addr: &'a Address) -> Self {
+        match addr {
+            Address::Ip(ip) => ip.into(),
+            Address::Uds(_) => AddressType::Uds,
+            Address::Hostname(_) => AddressType::Domain,
+        }
+    }
+}
+
 /// The header for a [system path](SystemPath)
 #[derive(Debug)]
 pub struct SystemPathHeader {
@@ -239,12 +254,15 @@ impl TryFrom<u8> for SystemPathHeader {
 ///
 /// # System Paths
 /// ```text
-/// +-------------------+-------------------+-----------------------+
-/// | Path type (1 bit) | Protocol (5 bits) | Address Type (2 bits) |
-/// +-------------------+-------------------+-----------------------+----------------+
+/// +-------------------+-----------------------+-------------------+
+/// | Path type (1 bit) | Address Type (2 bits) | Protocol (5 bits) |
+/// +-------------------+-----------------------+-------------------+----------------+
 /// |                   Address (4/16/ * bytes)                  ...| Port (2 bytes) |
 /// +---------------------------------------------------------------+----------------+
 /// ```
+///
+/// Unix domain socket addresses are length-prefixed (2 bytes) UTF-8 file paths.
+/// Domain names are length-prefixed (1 byte) ASCII hostnames.
 impl Serialisable for SystemPath {
     fn ser_id(&self) -> SerId {
         serialisation_ids::SYSTEM_PATH
@@ -254,8 +272,10 @@ impl Serialisable for SystemPath {
         let mut size: usize = 0;
         size += 1; // header
         size += match self.address() {
-            IpAddr::V4(_) => 4,  // IPv4 uses 4 bytes
-            IpAddr::V6(_) => 16, // IPv4 uses 16 bytes
+            Address::Ip(IpAddr::V4(_)) => 4,  // IPv4 uses 4 bytes
+            Address::Ip(IpAddr::V6(_)) => 16, // IPv4 uses 16 bytes
+            Address::Uds(path) => 2 + path.to_string_lossy().len(), // length-prefixed path
+            Address::Hostname(host) => 1 + host.len(), // length-prefixed hostname
         };
         size += 2; // port # (0-65_535)
         Some(size)
@@ -278,9 +298,17 @@ impl Serialisable for SystemPath {
 #[inline(always)]
 fn system_path_put_into_buf(path: &SystemPath, buf: &mut dyn BufMut) -> () {
     match *path.address() {
-        IpAddr::V4(ref ip) => buf.put_slice(&ip.octets()),
-        IpAddr::V6(ref ip) => buf.put_slice(&ip.octets()),
-        // TODO support named Domain
+        Address::Ip(IpAddr::V4(ref ip)) => buf.put_slice(&ip.octets()),
+        Address::Ip(IpAddr::V6(ref ip)) => buf.put_slice(&ip.octets()),
+        Address::Uds(ref socket_path) => {
+            let socket_path = socket_path.to_string_lossy();
+            buf.put_u16(socket_path.len() as u16);
+            buf.put_slice(socket_path.as_bytes());
+        }
+        Address::Hostname(ref host) => {
+            buf.put_u8(host.len() as u8);
+            buf.put_slice(host.as_bytes());
+        }
     }
     buf.put_u16(path.port());
 }
@@ -289,7 +317,7 @@ fn system_path_from_buf(buf: &mut dyn Buf) -> Result<(SystemPathHeader, SystemPa
     // Deserialize system path
     let fields: u8 = buf.get_u8();
     let header = SystemPathHeader::try_from(fields)?;
-    let address: IpAddr = match header.address_type {
+    let address: Address = match header.address_type {
         AddressType::IPv4 => {
             if buf.remaining() < 4 {
                 return Err(SerError::InvalidData(
@@ -298,7 +326,7 @@ fn system_path_from_buf(buf: &mut dyn Buf) -> Result<(SystemPathHeader, SystemPa
             } else {
                 let mut ip_bytes = [0u8; 4];
                 buf.copy_to_slice(&mut ip_bytes);
-                IpAddr::from(ip_bytes)
+                Address::Ip(IpAddr::from(ip_bytes))
             }
         }
         AddressType::IPv6 => {
@@ -309,15 +337,63 @@ fn system_path_from_buf(buf: &mut dyn Buf) -> Result<(SystemPathHeader, SystemPa
             } else {
                 let mut ip_bytes = [0u8; 16];
                 buf.copy_to_slice(&mut ip_bytes);
-                IpAddr::from(ip_bytes)
+                Address::Ip(IpAddr::from(ip_bytes))
             }
         }
         AddressType::Domain => {
-            unimplemented!();
+            let host_len = if buf.remaining() < 1 {
+                0
+            } else {
+                buf.get_u8() as usize
+            };
+            if host_len == 0 || buf.remaining() < host_len {
+                return Err(SerError::InvalidData(
+                    "Could not parse the hostname of a domain address".into(),
+                ));
+            } else {
+                let mut host_bytes = vec![0u8; host_len];
+                buf.copy_to_slice(&mut host_bytes);
+                let host = String::from_utf8(host_bytes)
+                    .map_err(|_| SerError::InvalidData("Hostname is not valid UTF-8".into()))?;
+                Address::Hostname(host)
+            }
+        }
+        AddressType::Uds => {
+            let path_len = if buf.remaining() < 2 {
+                0
+            } else {
+                buf.get_u16() as usize
+            };
+            if path_len == 0 || buf.remaining() < path_len {
+                return Err(SerError::InvalidData(
+                    "Could not parse the socket path of a UDS address".into(),
+                ));
+            } else {
+                let mut path_bytes = vec![0u8; path_len];
+                buf.copy_to_slice(&mut path_bytes);
+                let socket_path = String::from_utf8(path_bytes).map_err(|_| {
+                    SerError::InvalidData("UDS socket path is not valid UTF-8".into())
+                })?;
+                Address::Uds(socket_path.into())
+            }
         }
     };
     let port = buf.get_u16();
-    let system_path = SystemPath::new(header.protocol, address, port);
+    let system_path = match address {
+        Address::Ip(ip) if header.protocol.is_ip() => SystemPath::new(header.protocol, ip, port),
+        Address::Hostname(host) if header.protocol.is_ip() => {
+            SystemPath::with_hostname(header.protocol, host, port)
+                .map_err(|e| SerError::InvalidData(e.to_string()))?
+        }
+        Address::Uds(socket_path) if header.protocol == Transport::UDS => {
+            SystemPath::with_uds_path(socket_path)
+        }
+        _ => {
+            return Err(SerError::InvalidData(
+                "System Path address does not fit its Transport".into(),
+            ))
+        }
+    };
     Ok((header, system_path))
 }
 
@@ -487,6 +563,55 @@ mod serialisation_tests {
         assert_eq!(system_path, deserialised);
     }
 
+    #[test]
+    fn uds_actor_path_serequiv() {
+        let system_path = SystemPath::with_uds_path("/tmp/kompact/test.sock");
+        let named_path = ActorPath::Named(NamedPath::with_system(
+            system_path.clone(),
+            vec!["actor-name".into()],
+        ));
+        let unique_path = ActorPath::Unique(UniquePath::with_system(system_path, Uuid::new_v4()));
+        for path in [named_path, unique_path].iter() {
+            let header = SystemPathHeader::from_path(path);
+            assert_eq!(header.protocol, Transport::UDS);
+            assert_eq!(header.address_type, AddressType::Uds);
+
+            let size = Serialisable::size_hint(path).expect("Paths should have size hints");
+            let mut buf = BytesMut::with_capacity(size);
+            Serialisable::serialise(path, &mut buf).expect("UDS ActorPath should serialise");
+            assert_eq!(buf.len(), size);
+            let deser_path =
+                ActorPath::deserialise(&mut buf).expect("UDS ActorPath should deserialise");
+            assert_eq!(buf.len(), 0);
+            assert_eq!(path, &deser_path);
+        }
+    }
+
+    #[test]
+    fn hostname_actor_path_serequiv() {
+        let system_path = SystemPath::with_hostname(Transport::TCP, "my-host.example.com", 1234)
+            .expect("a valid hostname");
+        let named_path = ActorPath::Named(NamedPath::with_system(
+            system_path.clone(),
+            vec!["actor-name".into()],
+        ));
+        let unique_path = ActorPath::Unique(UniquePath::with_system(system_path, Uuid::new_v4()));
+        for path in [named_path, unique_path].iter() {
+            let header = SystemPathHeader::from_path(path);
+            assert_eq!(header.protocol, Transport::TCP);
+            assert_eq!(header.address_type, AddressType::Domain);
+
+            let size = Serialisable::size_hint(path).expect("Paths should have size hints");
+            let mut buf = BytesMut::with_capacity(size);
+            Serialisable::serialise(path, &mut buf).expect("Hostname ActorPath should serialise");
+            assert_eq!(buf.len(), size);
+            let deser_path =
+                ActorPath::deserialise(&mut buf).expect("Hostname ActorPath should deserialise");
+            assert_eq!(buf.len(), 0);
+            assert_eq!(path, &deser_path);
+        }
+    }
+
     #[test]
     fn actor_path_serequiv() {
         let expected_transport: Transport = Transport::TCP;
@@ -525,7 +650,7 @@ mod serialisation_tests {
                 .expect("UUID ActorPath Deserialisation should succeed");
             assert_eq!(buf.len(), 0);
             let deser_sys: &SystemPath = SystemField::system(&deser_path);
-            assert_eq!(deser_sys.address(), &expected_addr);
+            assert_eq!(deser_sys.address(), &Address::Ip(expected_addr));
             match deser_path {
                 ActorPath::Unique(ref up) => {
                     assert_eq!(up.id(), unique_id);
@@ -547,7 +672,7 @@ mod serialisation_tests {
                 .expect("Named ActorPath Deserialisation should succeed");
             assert_eq!(buf.len(), 0);
             let deser_sys: &SystemPath = SystemField::system(&deser_path);
-            assert_eq!(deser_sys.address(), &expected_addr);
+            assert_eq!(deser_sys.address(), &Address::Ip(expected_addr));
             match deser_path {
                 ActorPath::Unique(_) => panic!("expected Named path, got Unique path"),
                 ActorPath::Named(ref np) => {
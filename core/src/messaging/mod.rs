@@ -5,6 +5,7 @@ use crate::{
     net::{
         buffers::{BufferChunk, BufferEncoder, ChunkLease, ChunkRef},
         events::NetworkEvent,
+        flow_control::FrameCredit,
         frames::FRAME_HEAD_LEN,
     },
     serialisation::{
@@ -16,10 +17,10 @@ use crate::{
         Serialiser,
         TryClone,
     },
-    utils,
+    utils::{self, AskError, KPromise},
 };
 use bytes::{Buf, Bytes};
-use std::{any::Any, convert::TryFrom, ops::Deref, str::FromStr};
+use std::{any::Any, convert::TryFrom, ops::Deref, str::FromStr, time::Duration};
 use uuid::Uuid;
 mod net_message;
 pub use net_message::*;
@@ -49,7 +50,9 @@ pub enum MsgEnvelope<M: MessageBounds> {
     /// A message of the actor's `Message` type
     Typed(M),
     /// A message from the network
-    Net(NetMessage),
+    ///
+    /// Boxed, as it is much larger than most local messages.
+    Net(Box<NetMessage>),
 }
 
 /// Something that can resolved to some kind of path by the dispatcher
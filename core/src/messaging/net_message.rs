@@ -52,6 +52,8 @@ pub struct NetData {
     pub ser_id: SerId,
     /// The content of the data, which can be either heap allocated or serialised
     pub(crate) data: HeapOrSer,
+    /// The credit of the data frame the data was received in, granted again once the data is dropped
+    pub(crate) credit: Option<FrameCredit>,
 }
 
 /// Holder for data that is either heap-allocated or
@@ -127,6 +129,12 @@ impl NetMessage {
         }
     }
 
+    /// Holds on to the `credit` of the data frame this message was received in until the message is dropped
+    pub(crate) fn with_credit(mut self, credit: Option<FrameCredit>) -> NetMessage {
+        self.data.credit = credit;
+        self
+    }
+
     /// Return a reference to the `sender` field
     pub fn sender(&self) -> &ActorPath {
         &self.sender
@@ -334,7 +342,11 @@ impl TryClone for NetMessage {
 impl NetData {
     /// Create a new data instance from a serialisation id and some allocated data
     pub fn with(ser_id: SerId, data: HeapOrSer) -> Self {
-        NetData { ser_id, data }
+        NetData {
+            ser_id,
+            data,
+            credit: None,
+        }
     }
 
     /// Try to deserialise the data into a value of type `T`
@@ -431,7 +443,7 @@ impl NetData {
     where
         D: Deserialiser<T>,
     {
-        let NetData { ser_id, data } = self;
+        let NetData { ser_id, data, .. } = self;
         match data {
             HeapOrSer::Boxed(boxed_ser) => {
                 let b = boxed_ser.local().map_err(|_| {
@@ -464,10 +476,9 @@ impl NetData {
 
 impl TryClone for NetData {
     fn try_clone(&self) -> Result<Self, SerError> {
-        self.data.try_clone().map(|data| NetData {
-            ser_id: self.ser_id,
-            data,
-        })
+        self.data
+            .try_clone()
+            .map(|data| NetData::with(self.ser_id, data))
     }
 }
 
@@ -0,0 +1,111 @@
+//! Authentication of remote hosts during the channel handshake
+//!
+//! The host accepting a connection sends a challenge in its `Hello`,
+//! which the connecting host must answer in its `Start` along with a challenge of its own.
+//! The accepting host answers that challenge in its `Ack`, so both sides are authenticated
+//! before any data is exchanged. Hosts failing to answer are disconnected.
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt::{Debug, Formatter};
+use uuid::Uuid;
+
+/// Decides which remote hosts may connect to a [NetworkDispatcher](crate::dispatch::NetworkDispatcher)
+///
+/// Each side of a channel issues a challenge and verifies the other side's response to it.
+/// Challenges and responses may be at most `u16::MAX` bytes long, longer ones fail to encode.
+pub trait Authenticator: Send + Sync + Debug + 'static {
+    /// Create a new challenge to send to a remote host
+    ///
+    /// Challenges should never be reused.
+    fn challenge(&self) -> Vec<u8>;
+
+    /// Create the response to a `challenge` issued by a remote host
+    fn respond(&self, challenge: &[u8]) -> Vec<u8>;
+
+    /// Returns true if `response` is a valid answer to the `challenge` we issued
+    fn verify(&self, challenge: &[u8], response: &[u8]) -> bool;
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Authenticates remote hosts which know the same shared secret
+///
+/// Challenges are random nonces and responses are their HMAC-SHA256 under the shared secret.
+///
+/// # Example
+///
+/// ```
+/// use kompact::{net::auth::HmacAuthenticator, prelude::*};
+/// use std::sync::Arc;
+///
+/// let mut conf = NetworkConfig::default();
+/// conf.set_authenticator(Arc::new(HmacAuthenticator::new("our shared secret")));
+/// ```
+#[derive(Clone)]
+pub struct HmacAuthenticator {
+    secret: Vec<u8>,
+}
+
+impl HmacAuthenticator {
+    /// Create a new authenticator with the shared `secret`
+    pub fn new<S: Into<Vec<u8>>>(secret: S) -> Self {
+        HmacAuthenticator {
+            secret: secret.into(),
+        }
+    }
+
+    fn mac(&self, challenge: &[u8]) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(challenge);
+        mac
+    }
+}
+
+impl Authenticator for HmacAuthenticator {
+    fn challenge(&self) -> Vec<u8> {
+        let mut nonce = Vec::with_capacity(32);
+        nonce.extend_from_slice(Uuid::new_v4().as_bytes());
+        nonce.extend_from_slice(Uuid::new_v4().as_bytes());
+        nonce
+    }
+
+    fn respond(&self, challenge: &[u8]) -> Vec<u8> {
+        self.mac(challenge).finalize().into_bytes().to_vec()
+    }
+
+    fn verify(&self, challenge: &[u8], response: &[u8]) -> bool {
+        // Never accept an empty challenge, it would allow replaying responses
+        !challenge.is_empty() && self.mac(challenge).verify_slice(response).is_ok()
+    }
+}
+
+impl Debug for HmacAuthenticator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Never print the secret
+        f.debug_struct("HmacAuthenticator").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_authenticator_accepts_shared_secret() {
+        let ours = HmacAuthenticator::new("secret");
+        let theirs = HmacAuthenticator::new("secret");
+        let challenge = ours.challenge();
+        assert!(ours.verify(&challenge, &theirs.respond(&challenge)));
+    }
+
+    #[test]
+    fn hmac_authenticator_rejects_other_secret() {
+        let ours = HmacAuthenticator::new("secret");
+        let theirs = HmacAuthenticator::new("other secret");
+        let challenge = ours.challenge();
+        assert!(!ours.verify(&challenge, &theirs.respond(&challenge)));
+        assert!(!ours.verify(&challenge, &[]));
+        assert_ne!(challenge, ours.challenge());
+    }
+}
@@ -156,15 +156,25 @@ impl DecodeBuffer {
                     }
                     FrameType::StreamRequest => StreamRequest::decode_from(chunk_lease)
                         .map_err(|_| FramingError::InvalidFrame),
-                    FrameType::Hello => {
-                        Hello::decode_from(chunk_lease).map_err(|_| FramingError::InvalidFrame)
-                    }
-                    FrameType::Start => {
-                        Start::decode_from(chunk_lease).map_err(|_| FramingError::InvalidFrame)
-                    }
+                    FrameType::CreditUpdate => CreditUpdate::decode_from(chunk_lease)
+                        .map_err(|_| FramingError::InvalidFrame),
+                    // Handshake frames tell remote hosts with incompatible versions apart from invalid frames
+                    FrameType::Hello => Hello::decode_from(chunk_lease),
+                    FrameType::Start => Start::decode_from(chunk_lease),
                     FrameType::Ack => {
                         Ack::decode_from(chunk_lease).map_err(|_| FramingError::InvalidFrame)
                     }
+                    FrameType::Sequenced => {
+                        Sequenced::decode_from(chunk_lease).map_err(|_| FramingError::InvalidFrame)
+                    }
+                    FrameType::SelectiveAck => SelectiveAck::decode_from(chunk_lease)
+                        .map_err(|_| FramingError::InvalidFrame),
+                    FrameType::Fragment => {
+                        Fragment::decode_from(chunk_lease).map_err(|_| FramingError::InvalidFrame)
+                    }
+                    FrameType::Compressed => {
+                        Compressed::decode_from(chunk_lease).map_err(|_| FramingError::InvalidFrame)
+                    }
                     _ => Err(FramingError::UnsupportedFrameType),
                 }
             } else {
@@ -177,6 +187,9 @@ impl DecodeBuffer {
                 match head.frame_type() {
                     // Frames without content match here for expediency, Decoder doesn't allow 0 length.
                     FrameType::Bye => Ok(Frame::Bye()),
+                    FrameType::Heartbeat => Ok(Frame::Heartbeat()),
+                    FrameType::Idle => Ok(Frame::Idle()),
+                    FrameType::IdleAck => Ok(Frame::IdleAck()),
                     _ => Err(FramingError::NoData),
                 }
             } else {
@@ -13,8 +13,9 @@ pub mod chunk_lease;
 pub mod chunk_ref;
 pub(crate) mod decode_buffer;
 pub(crate) mod encode_buffer;
+pub(crate) mod stream_buffer;
 
-pub(crate) use self::buffer_pool::*;
+pub(crate) use self::{buffer_pool::*, stream_buffer::*};
 pub use self::{chunk_lease::*, chunk_ref::*, decode_buffer::*, encode_buffer::*};
 use std::path::PathBuf;
 
@@ -0,0 +1,78 @@
+use super::*;
+
+/// Collects the fragments of a streamed frame in a [BufferChunk](BufferChunk)
+/// allocated outside of any `BufferPool`
+///
+/// Streamed frames can be larger than a pool allows, so they are copied into a dedicated chunk
+/// sized for the whole frame instead.
+pub(crate) struct StreamBuffer {
+    buffer: BufferChunk,
+    write_offset: usize,
+}
+
+impl StreamBuffer {
+    /// Allocates a new StreamBuffer holding at most `capacity` bytes
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        StreamBuffer {
+            buffer: BufferChunk::new(capacity),
+            write_offset: 0,
+        }
+    }
+
+    /// Returns the number of bytes which may still be written into the buffer
+    pub(crate) fn writeable_len(&self) -> usize {
+        self.buffer.len() - self.write_offset
+    }
+
+    /// Copies all of `src` into the buffer
+    ///
+    /// Returns false, without copying anything, if `src` does not fit.
+    pub(crate) fn put<B: Buf>(&mut self, mut src: B) -> bool {
+        if src.remaining() > self.writeable_len() {
+            return false;
+        }
+        while src.has_remaining() {
+            let chunk = src.chunk();
+            let len = chunk.len();
+            unsafe {
+                self.buffer
+                    .get_slice(self.write_offset, self.write_offset + len)
+                    .copy_from_slice(chunk);
+            }
+            self.write_offset += len;
+            src.advance(len);
+        }
+        true
+    }
+
+    /// Returns the written bytes as a [ChunkRef](ChunkRef), along with the locked chunk backing it
+    ///
+    /// The chunk must not be dropped before it is [free](BufferChunk::free) again,
+    /// i.e. it should be handed to the `NetworkDispatcher` for garbage collection.
+    pub(crate) fn into_chunk_ref(mut self) -> (ChunkRef, BufferChunk) {
+        let lease = self.buffer.get_lease(0, self.write_offset);
+        self.buffer.lock();
+        (lease.into_chunk_ref(), self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_buffer_collects_fragments() {
+        let mut stream_buffer = StreamBuffer::with_capacity(10);
+        assert!(stream_buffer.put(&[1u8, 2, 3, 4][..]));
+        assert!(stream_buffer.put(&[5u8, 6, 7][..]));
+        assert!(!stream_buffer.put(&[8u8, 9, 10, 11][..]));
+        assert_eq!(stream_buffer.writeable_len(), 3);
+
+        let (mut chunk_ref, mut chunk) = stream_buffer.into_chunk_ref();
+        assert_eq!(chunk_ref.remaining(), 7);
+        assert_eq!(chunk_ref.copy_to_bytes(7).as_ref(), &[1, 2, 3, 4, 5, 6, 7]);
+        assert!(!chunk.free());
+        drop(chunk_ref);
+        assert!(chunk.free());
+    }
+}
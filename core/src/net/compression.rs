@@ -0,0 +1,269 @@
+//! Compression of the data frames sent over TCP channels
+//!
+//! Which codecs are available depends on the `lz4` and `zstd` features of this crate.
+//! Every host advertises the codecs it can decompress as [protocol features](super::frames::ProtocolVersions),
+//! and only compresses frames with its [configured](crate::dispatch::NetworkConfig::set_compression) codec
+//! if the remote host advertised it as well.
+//! Frames to other hosts are sent uncompressed, so hosts with different configurations can still talk to each other.
+use super::frames::{FrameHead, FrameType, FRAME_HEAD_LEN};
+use crate::messaging::SerialisedFrame;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::io;
+
+/// Feature bit for decompressing frames compressed with LZ4
+pub const FEATURE_LZ4: u32 = 0b10;
+/// Feature bit for decompressing frames compressed with zstd
+pub const FEATURE_ZSTD: u32 = 0b100;
+
+#[cfg(feature = "lz4")]
+const LZ4_SUPPORT: u32 = FEATURE_LZ4;
+#[cfg(not(feature = "lz4"))]
+const LZ4_SUPPORT: u32 = 0;
+#[cfg(feature = "zstd")]
+const ZSTD_SUPPORT: u32 = FEATURE_ZSTD;
+#[cfg(not(feature = "zstd"))]
+const ZSTD_SUPPORT: u32 = 0;
+
+/// Feature bits of the codecs this build can decompress
+pub const COMPRESSION_FEATURES: u32 = LZ4_SUPPORT | ZSTD_SUPPORT;
+
+/// Compression codecs for data frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Fast compression with LZ4, requires the `lz4` feature
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// Stronger compression with zstd at the given level, requires the `zstd` feature
+    ///
+    /// Level 0 picks zstd's default level.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+impl Compression {
+    /// The feature bit the remote host must advertise to receive frames compressed with this codec
+    pub fn feature(&self) -> u32 {
+        match *self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => FEATURE_LZ4,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(_) => FEATURE_ZSTD,
+        }
+    }
+
+    fn id(&self) -> u8 {
+        match *self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => 1,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(_) => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Compression> {
+        match id {
+            #[cfg(feature = "lz4")]
+            1 => Some(Compression::Lz4),
+            #[cfg(feature = "zstd")]
+            2 => Some(Compression::Zstd(0)),
+            _ => None,
+        }
+    }
+
+    fn compress(&self, _data: &[u8]) -> io::Result<Vec<u8>> {
+        match *self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Ok(lz4_flex::block::compress(_data)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => zstd::bulk::compress(_data, level),
+        }
+    }
+
+    fn decompress(&self, _data: &[u8], _original_len: usize) -> io::Result<Vec<u8>> {
+        match *self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => lz4_flex::block::decompress(_data, _original_len)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(_) => zstd::bulk::decompress(_data, _original_len),
+        }
+    }
+}
+
+/// How well the data frames sent to a remote host compressed
+///
+/// Counts all data frames large enough to be considered for compression,
+/// including those sent uncompressed because they did not get any smaller.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    /// The size of the frames before compression, in bytes
+    pub original_bytes: u64,
+    /// The size of the frames as they were sent, in bytes
+    pub compressed_bytes: u64,
+}
+
+impl CompressionStats {
+    /// The size of the frames as sent, relative to their original size
+    ///
+    /// Smaller is better, `1.0` means nothing was saved.
+    pub fn ratio(&self) -> f64 {
+        if self.original_bytes == 0 {
+            1.0
+        } else {
+            self.compressed_bytes as f64 / self.original_bytes as f64
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.original_bytes == 0
+    }
+}
+
+/// Body of a compressed data frame: codec id, uncompressed length, compressed payload
+const COMPRESSED_HEADER_LEN: usize = 1 + 4;
+
+/// Compresses `frame` with `codec` if it is a data frame with more than `threshold` bytes of payload
+///
+/// Frames which do not get any smaller are returned uncompressed.
+/// Returns the frame to send, along with its original and sent sizes if compression was attempted.
+pub(crate) fn compress_frame(
+    codec: Compression,
+    threshold: usize,
+    frame: SerialisedFrame,
+) -> (SerialisedFrame, Option<(usize, usize)>) {
+    let head_len = FRAME_HEAD_LEN as usize;
+    let len = frame.len();
+    if len <= head_len + threshold
+        || frame.bytes().len() < head_len
+        || FrameType::from(frame.bytes()[head_len - 1]) != FrameType::Data
+    {
+        return (frame, None);
+    }
+    let original = match frame {
+        SerialisedFrame::Bytes(bytes) => bytes,
+        SerialisedFrame::ChunkLease(mut chunk) => chunk.copy_to_bytes(len),
+        SerialisedFrame::ChunkRef(mut chunk) => chunk.copy_to_bytes(len),
+    };
+    let payload = &original[head_len..];
+    match codec.compress(payload) {
+        Ok(compressed) if compressed.len() + COMPRESSED_HEADER_LEN < payload.len() => {
+            let content_length = COMPRESSED_HEADER_LEN + compressed.len();
+            let mut buf = BytesMut::with_capacity(head_len + content_length);
+            FrameHead::new(FrameType::Compressed, content_length).encode_into(&mut buf);
+            buf.put_u8(codec.id());
+            buf.put_u32(payload.len() as u32);
+            buf.put_slice(&compressed);
+            let sent = buf.len();
+            (SerialisedFrame::Bytes(buf.freeze()), Some((len, sent)))
+        }
+        _ => (SerialisedFrame::Bytes(original), Some((len, len))),
+    }
+}
+
+/// Decompresses the body of a compressed data frame into the payload of the original data frame
+///
+/// Fails without decompressing anything if the payload would be larger than `max_len` bytes.
+pub(crate) fn decompress<B: Buf>(mut body: B, max_len: usize) -> io::Result<Bytes> {
+    if body.remaining() < COMPRESSED_HEADER_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Compressed frame too short",
+        ));
+    }
+    let id = body.get_u8();
+    let codec = Compression::from_id(id).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unsupported compression codec {}", id),
+        )
+    })?;
+    let original_len = body.get_u32() as usize;
+    if original_len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Compressed frame claims {} bytes, more than the maximum of {}",
+                original_len, max_len
+            ),
+        ));
+    }
+    let compressed = body.copy_to_bytes(body.remaining());
+    let payload = codec.decompress(&compressed, original_len)?;
+    if payload.len() != original_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Decompressed frame has the wrong length",
+        ));
+    }
+    Ok(Bytes::from(payload))
+}
+
+#[cfg(all(test, any(feature = "lz4", feature = "zstd")))]
+mod tests {
+    use super::*;
+
+    fn data_frame(payload: &[u8]) -> SerialisedFrame {
+        let mut buf = BytesMut::new();
+        FrameHead::new(FrameType::Data, payload.len()).encode_into(&mut buf);
+        buf.put_slice(payload);
+        SerialisedFrame::Bytes(buf.freeze())
+    }
+
+    fn codecs() -> Vec<Compression> {
+        vec![
+            #[cfg(feature = "lz4")]
+            Compression::Lz4,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(0),
+        ]
+    }
+
+    #[test]
+    fn compressed_frames_roundtrip() {
+        let payload: Vec<u8> = (0..4096).map(|i| (i % 7) as u8).collect();
+        for codec in codecs() {
+            let (frame, sizes) = compress_frame(codec, 1024, data_frame(&payload));
+            let (original, sent) = sizes.expect("compression attempted");
+            assert_eq!(original, payload.len() + FRAME_HEAD_LEN as usize);
+            assert!(sent < original);
+            assert_eq!(frame.len(), sent);
+            let mut bytes = match frame {
+                SerialisedFrame::Bytes(bytes) => bytes,
+                _ => unreachable!("compressed frames are Bytes"),
+            };
+            let head = FrameHead::decode_from(&mut bytes).expect("frame head");
+            assert_eq!(head.frame_type(), FrameType::Compressed);
+            assert_eq!(head.content_length(), bytes.remaining());
+            let decompressed = decompress(bytes.clone(), payload.len()).expect("decompressed");
+            assert_eq!(&decompressed[..], &payload[..]);
+            assert!(decompress(bytes, payload.len() - 1).is_err());
+        }
+    }
+
+    #[test]
+    fn small_and_incompressible_frames_are_sent_as_is() {
+        for codec in codecs() {
+            let (frame, sizes) = compress_frame(codec, 1024, data_frame(&[1, 2, 3]));
+            assert!(sizes.is_none());
+            assert_eq!(frame.len(), 3 + FRAME_HEAD_LEN as usize);
+
+            // Pseudo-random bytes do not compress
+            let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+            let payload: Vec<u8> = (0..2048)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    (state >> 32) as u8
+                })
+                .collect();
+            let (frame, sizes) = compress_frame(codec, 1024, data_frame(&payload));
+            let (original, sent) = sizes.expect("compression attempted");
+            assert_eq!(original, sent);
+            assert_eq!(
+                frame.bytes()[FRAME_HEAD_LEN as usize - 1],
+                FrameType::Data as u8
+            );
+        }
+    }
+}
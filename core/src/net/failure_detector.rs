@@ -0,0 +1,152 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// The number of most recent inter-arrival times the estimate is based on
+const MAX_SAMPLE_SIZE: usize = 1000;
+/// Lower bound for the standard deviation in ms, such that very regular heartbeats
+/// do not make the detector overly sensitive to the slightest delay
+const MIN_STD_DEVIATION: f64 = 100.0;
+
+/// A phi-accrual failure detector for a single remote host
+///
+/// Keeps a history of the inter-arrival times of heartbeats and expresses the suspicion
+/// that the remote host has failed as `phi = -log10(p)`, where `p` is the probability of a heartbeat
+/// still arriving after the time that has passed since the last one, assuming normally distributed inter-arrival times.
+/// That is, `phi = 1` means a 10% chance that a heartbeat is still on its way, `phi = 2` a 1% chance, and so on.
+pub(super) struct PhiAccrualFailureDetector {
+    intervals: VecDeque<f64>,
+    interval_sum: f64,
+    squared_interval_sum: f64,
+    /// Added to the mean inter-arrival time, in ms
+    acceptable_pause: f64,
+    last_heartbeat: Option<Instant>,
+}
+
+impl PhiAccrualFailureDetector {
+    /// Creates a detector for heartbeats which are expected every `heartbeat_interval`,
+    /// tolerating an additional delay of up to `acceptable_pause` without much suspicion
+    pub(super) fn new(heartbeat_interval: Duration, acceptable_pause: Duration) -> Self {
+        let mut detector = PhiAccrualFailureDetector {
+            intervals: VecDeque::with_capacity(MAX_SAMPLE_SIZE),
+            interval_sum: 0.0,
+            squared_interval_sum: 0.0,
+            acceptable_pause: millis(acceptable_pause),
+            last_heartbeat: None,
+        };
+        // Seed the history with a guess, such that the first few heartbeats are not judged too strictly
+        let mean = millis(heartbeat_interval);
+        let std_deviation = mean / 4.0;
+        detector.record(mean - std_deviation);
+        detector.record(mean + std_deviation);
+        detector
+    }
+
+    /// Records the arrival of a heartbeat at `now`
+    pub(super) fn heartbeat(&mut self, now: Instant) -> () {
+        if let Some(last_heartbeat) = self.last_heartbeat.replace(now) {
+            self.record(millis(now.saturating_duration_since(last_heartbeat)));
+        }
+    }
+
+    /// Returns the suspicion at `now` that the remote host has failed
+    ///
+    /// The suspicion is 0 until the first heartbeat arrived.
+    pub(super) fn phi(&self, now: Instant) -> f64 {
+        match self.last_heartbeat {
+            Some(last_heartbeat) => {
+                let elapsed = millis(now.saturating_duration_since(last_heartbeat));
+                let samples = self.intervals.len() as f64;
+                let mean = self.interval_sum / samples;
+                let variance = self.squared_interval_sum / samples - mean * mean;
+                let std_deviation = variance.max(0.0).sqrt().max(MIN_STD_DEVIATION);
+                phi(elapsed, mean + self.acceptable_pause, std_deviation)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn record(&mut self, interval: f64) -> () {
+        if self.intervals.len() >= MAX_SAMPLE_SIZE {
+            if let Some(dropped) = self.intervals.pop_front() {
+                self.interval_sum -= dropped;
+                self.squared_interval_sum -= dropped * dropped;
+            }
+        }
+        self.intervals.push_back(interval);
+        self.interval_sum += interval;
+        self.squared_interval_sum += interval * interval;
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Approximates `-log10` of the probability of a normally distributed value exceeding `elapsed`
+/// with the logistic approximation of the cumulative distribution function
+fn phi(elapsed: f64, mean: f64, std_deviation: f64) -> f64 {
+    let y = (elapsed - mean) / std_deviation;
+    let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+    if elapsed > mean {
+        -(e / (1.0 + e)).log10()
+    } else {
+        -(1.0 - 1.0 / (1.0 + e)).log10()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(1000);
+    const PAUSE: Duration = Duration::from_millis(3000);
+
+    fn detector_with_heartbeats(intervals: &[u64]) -> (PhiAccrualFailureDetector, Instant) {
+        let mut now = Instant::now();
+        let mut detector = PhiAccrualFailureDetector::new(INTERVAL, PAUSE);
+        detector.heartbeat(now);
+        for interval in intervals {
+            now += Duration::from_millis(*interval);
+            detector.heartbeat(now);
+        }
+        (detector, now)
+    }
+
+    #[test]
+    fn suspicion_grows_with_missing_heartbeats() {
+        let (detector, last) = detector_with_heartbeats(&[1000; 10]);
+        let suspicions: Vec<f64> = [0u64, 1000, 4000, 4500, 5000, 6000]
+            .iter()
+            .map(|elapsed| detector.phi(last + Duration::from_millis(*elapsed)))
+            .collect();
+        assert!(suspicions.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(suspicions[1] < 1.0);
+        assert!(suspicions[2] < 8.0);
+        assert!(suspicions[5] > 8.0);
+    }
+
+    #[test]
+    fn irregular_heartbeats_are_tolerated_longer() {
+        let (regular, regular_last) = detector_with_heartbeats(&[1000; 10]);
+        let (irregular, irregular_last) =
+            detector_with_heartbeats(&[200, 1800, 500, 1500, 100, 1900, 1000, 300, 1700, 1000]);
+        let elapsed = Duration::from_millis(5000);
+        assert!(irregular.phi(irregular_last + elapsed) < regular.phi(regular_last + elapsed));
+    }
+
+    #[test]
+    fn no_suspicion_before_first_heartbeat() {
+        let detector = PhiAccrualFailureDetector::new(INTERVAL, PAUSE);
+        assert_eq!(0.0, detector.phi(Instant::now() + INTERVAL * 100));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let (detector, _) = detector_with_heartbeats(&[1000; MAX_SAMPLE_SIZE + 10]);
+        assert_eq!(MAX_SAMPLE_SIZE, detector.intervals.len());
+        let mean = detector.interval_sum / detector.intervals.len() as f64;
+        assert!((mean - 1000.0).abs() < 1e-6);
+    }
+}
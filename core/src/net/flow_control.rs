@@ -0,0 +1,145 @@
+use mio::Waker;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+/// Wakes a network thread once one of its channels has released enough received data frames
+/// to grant the remote host more credits
+#[derive(Default)]
+pub(crate) struct CreditNotifier {
+    pending: AtomicBool,
+    waker: Option<Arc<Waker>>,
+}
+
+impl CreditNotifier {
+    /// Creates a notifier waking the network thread with `waker`,
+    /// or one that is only ever [checked](CreditNotifier::take_pending) if there is none
+    pub(crate) fn new(waker: Option<Arc<Waker>>) -> Self {
+        CreditNotifier {
+            pending: AtomicBool::new(false),
+            waker,
+        }
+    }
+
+    /// Returns true if a channel released enough frames since the last call
+    pub(crate) fn take_pending(&self) -> bool {
+        self.pending.swap(false, Ordering::AcqRel)
+    }
+
+    fn notify(&self) -> () {
+        if !self.pending.swap(true, Ordering::AcqRel) {
+            if let Some(waker) = &self.waker {
+                // The thread checks the pending flag on its next wake-up anyway
+                let _ = waker.wake();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for CreditNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreditNotifier")
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+/// The received data frames of a channel which were released since credits were last granted for them
+#[derive(Debug)]
+pub(crate) struct ReleasedFrames {
+    count: AtomicU32,
+    /// Notifies the network thread once this many frames were released
+    threshold: u32,
+    notifier: Arc<CreditNotifier>,
+}
+
+impl ReleasedFrames {
+    pub(crate) fn new(threshold: u32, notifier: Arc<CreditNotifier>) -> Self {
+        ReleasedFrames {
+            count: AtomicU32::new(0),
+            threshold: threshold.max(1),
+            notifier,
+        }
+    }
+
+    /// Returns the number of frames released since the last call, if there are at least `min` of them
+    pub(crate) fn take(&self, min: u32) -> u32 {
+        self.count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                if count >= min.max(1) {
+                    Some(0)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of released frames the network thread is notified about
+    pub(crate) fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    fn release(&self) -> () {
+        if self.count.fetch_add(1, Ordering::AcqRel) + 1 == self.threshold {
+            self.notifier.notify();
+        }
+    }
+}
+
+/// The credit a received data frame used up, granted to the remote host again once this is dropped
+///
+/// Travels along with the message delivered from the frame,
+/// so a receiver which does not keep up holds back the remote host.
+pub(crate) struct FrameCredit {
+    frames: Arc<ReleasedFrames>,
+}
+
+impl FrameCredit {
+    pub(crate) fn new(frames: Arc<ReleasedFrames>) -> Self {
+        FrameCredit { frames }
+    }
+}
+
+impl Drop for FrameCredit {
+    fn drop(&mut self) {
+        self.frames.release();
+    }
+}
+
+impl fmt::Debug for FrameCredit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FrameCredit")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn released_frames_notify_once_per_threshold() {
+        let notifier = Arc::new(CreditNotifier::default());
+        let frames = Arc::new(ReleasedFrames::new(2, notifier.clone()));
+        let credits: Vec<FrameCredit> = (0..5).map(|_| FrameCredit::new(frames.clone())).collect();
+        assert!(!notifier.take_pending());
+        let mut credits = credits.into_iter();
+        drop(credits.next());
+        assert!(!notifier.take_pending());
+        assert_eq!(0, frames.take(frames.threshold()));
+        drop(credits.next());
+        assert!(notifier.take_pending());
+        assert_eq!(2, frames.take(frames.threshold()));
+
+        drop(credits.next());
+        assert!(!notifier.take_pending());
+        assert_eq!(1, frames.take(1));
+        drop(credits);
+        assert!(notifier.take_pending());
+        assert_eq!(2, frames.take(1));
+    }
+}
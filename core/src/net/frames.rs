@@ -1,12 +1,12 @@
 //! Frames are the core of the message transport layer, allowing applications to build
 //! custom protocols atop this library.
 
-use bytes::{Buf, BufMut};
+use bytes::{Buf, BufMut, Bytes};
 
 //use bytes::IntoBuf;
-use std::{self, fmt::Debug};
+use std::{self, convert::TryFrom, fmt::Debug};
 
-use crate::net::buffers::ChunkLease;
+use crate::net::{buffers::ChunkLease, compression::COMPRESSION_FEATURES};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use uuid::Uuid;
 
@@ -16,6 +16,25 @@ pub const MAGIC_NUM: u32 = 0xC0A1BA11;
 // 192, 161, 186, 17
 /// Framehead has constant size: (frame length) + (magic) + (frame type)
 pub const FRAME_HEAD_LEN: u32 = 4 + 4 + 1;
+/// The newest wire protocol version spoken by this build
+///
+/// Version 2 added the system incarnation to the `Hello` and `Start` frames.
+/// Version 3 moved the address type of serialised system paths behind the transport bits,
+/// which it overlapped before, and runs the handshake on Unix domain socket streams.
+pub const PROTOCOL_VERSION: u8 = 3;
+/// The oldest wire protocol version still spoken by this build
+///
+/// Version 2 systems can not tell the address types of version 3 system paths apart.
+pub const MIN_PROTOCOL_VERSION: u8 = 3;
+/// Feature bit for sending heartbeats on idle channels
+pub const FEATURE_HEARTBEATS: u32 = 0b1;
+/// Feature bit for streaming large messages as a series of fragments
+pub const FEATURE_STREAMING: u32 = 0b1000;
+/// Feature bit for agreeing on closing idle channels with an `Idle` and `IdleAck` exchange
+pub const FEATURE_IDLE_CLOSE: u32 = 0b1_0000;
+/// Bit flags of the optional protocol features supported by this build
+pub const PROTOCOL_FEATURES: u32 =
+    FEATURE_HEARTBEATS | FEATURE_STREAMING | FEATURE_IDLE_CLOSE | COMPRESSION_FEATURES;
 
 /// Error messages for encoding/decoding
 #[derive(Debug)]
@@ -34,6 +53,8 @@ pub enum FramingError {
     OptionError,
     /// No data to extract frame from
     NoData,
+    /// The remote host speaks none of our protocol versions, so the rest of its frame can not be decoded
+    IncompatibleVersions(ProtocolVersions),
     /// IO errors wrapped into FramingError
     Io(std::io::Error),
 }
@@ -61,6 +82,20 @@ pub enum Frame {
     Ack(Ack),
     /// Bye to signal that a channel is closing.
     Bye(),
+    /// Numbered frame of Data, used for reliable UDP
+    Sequenced(Sequenced),
+    /// Acknowledges received `Sequenced` frames
+    SelectiveAck(SelectiveAck),
+    /// Part of a frame too large for a single UDP datagram, or for the buffers of a TCP channel
+    Fragment(Fragment),
+    /// Heartbeat to signal that an idle channel is still alive
+    Heartbeat(),
+    /// Frame of Data with a compressed payload
+    Compressed(Compressed),
+    /// Asks the remote host to close the channel because it was idle
+    Idle(),
+    /// Agrees to close the idle channel, after which no more data is sent on it
+    IdleAck(),
 }
 
 impl Frame {
@@ -74,6 +109,13 @@ impl Frame {
             Frame::Start(_) => FrameType::Start,
             Frame::Ack(_) => FrameType::Ack,
             Frame::Bye() => FrameType::Bye,
+            Frame::Sequenced(_) => FrameType::Sequenced,
+            Frame::SelectiveAck(_) => FrameType::SelectiveAck,
+            Frame::Fragment(_) => FrameType::Fragment,
+            Frame::Heartbeat() => FrameType::Heartbeat,
+            Frame::Compressed(_) => FrameType::Compressed,
+            Frame::Idle() => FrameType::Idle,
+            Frame::IdleAck() => FrameType::IdleAck,
         }
     }
 
@@ -100,6 +142,13 @@ impl Frame {
             Frame::Start(frame) => frame.encode_into(dst),
             Frame::Ack(frame) => frame.encode_into(dst),
             Frame::Bye() => Ok(()),
+            Frame::Sequenced(frame) => frame.encode_into(dst),
+            Frame::SelectiveAck(frame) => frame.encode_into(dst),
+            Frame::Fragment(frame) => frame.encode_into(dst),
+            Frame::Heartbeat() => Ok(()),
+            Frame::Compressed(frame) => frame.encode_into(dst),
+            Frame::Idle() => Ok(()),
+            Frame::IdleAck() => Ok(()),
         }
     }
 
@@ -112,6 +161,10 @@ impl Frame {
             Frame::Hello(ref frame) => frame.encoded_len(),
             Frame::Start(ref frame) => frame.encoded_len(),
             Frame::Ack(ref frame) => frame.encoded_len(),
+            Frame::Sequenced(ref frame) => frame.encoded_len(),
+            Frame::SelectiveAck(ref frame) => frame.encoded_len(),
+            Frame::Fragment(ref frame) => frame.encoded_len(),
+            Frame::Compressed(ref frame) => frame.encoded_len(),
             _ => 0,
         }
     }
@@ -145,20 +198,43 @@ pub struct Data {
     pub payload: ChunkLease,
 }
 
+/// Frame of Data with a compressed payload
+///
+/// See [compression](crate::net::compression) for when data frames are compressed.
+#[derive(Debug)]
+pub struct Compressed {
+    /// The codec, the length of the original payload, and the compressed payload
+    pub body: ChunkLease,
+}
+
 /// Hello, used to initiate network channels
 #[derive(Debug)]
 pub struct Hello {
+    /// The protocol versions and features supported by the host saying Hello
+    pub versions: ProtocolVersions,
     /// The Cannonical Address of the host saying Hello
     pub addr: SocketAddr,
+    /// The incarnation of the system saying Hello, which changes whenever it restarts
+    pub incarnation: Uuid,
+    /// Authentication challenge the receiver must answer in its Start, empty if not authenticating
+    pub challenge: Vec<u8>,
 }
 
 /// Hello, used to initiate network channels
 #[derive(Debug)]
 pub struct Start {
+    /// The protocol versions and features supported by the host sending the Start message
+    pub versions: ProtocolVersions,
     /// The Cannonical Address of the host sending the Start message
     pub addr: SocketAddr,
     /// "Channel ID", used as a tie-breaker in mutual connection requests
     pub id: Uuid,
+    /// The incarnation of the system sending the Start message, which changes whenever it restarts
+    pub incarnation: Uuid,
+    /// Response to the challenge of the Hello
+    pub response: Vec<u8>,
+    /// Authentication challenge the receiver must answer in its Ack, empty if not authenticating
+    pub challenge: Vec<u8>,
 }
 
 /// Hello, used to initiate network channels
@@ -166,6 +242,111 @@ pub struct Start {
 pub struct Ack {
     /// Ack where we're ready to start receiving from.
     pub offset: u128,
+    /// Response to the challenge of the Start
+    pub response: Vec<u8>,
+}
+
+/// Numbered frame of Data, retransmitted until the receiver acknowledges it
+#[derive(Debug)]
+pub struct Sequenced {
+    /// Identifies the sender's current numbering, which restarts at 1 in every session
+    pub session: u64,
+    /// The number of this frame
+    pub seq: u64,
+    /// The number of the previous frame to the same actor, 0 if there is none
+    pub prev: u64,
+    /// The contents of the Frame
+    pub payload: ChunkLease,
+}
+
+/// Acknowledges received `Sequenced` frames
+#[derive(Debug)]
+pub struct SelectiveAck {
+    /// The session of the acknowledged frames
+    pub session: u64,
+    /// All frames up to and including this number have been received
+    pub cumulative: u64,
+    /// Bit `i` is set if frame `cumulative + 2 + i` has been received
+    pub bitmap: u64,
+}
+
+/// Part of a frame too large for a single UDP datagram, or for the buffers of a TCP channel
+#[derive(Debug)]
+pub struct Fragment {
+    /// Identifies the fragmented frame among those from the same sender
+    pub message_id: u32,
+    /// The position of this fragment within the frame
+    pub index: u16,
+    /// The number of fragments the frame was split into
+    pub count: u16,
+    /// The part of the frame carried by this fragment
+    pub payload: ChunkLease,
+}
+
+/// The range of wire protocol versions and the optional features supported by a host
+///
+/// Exchanged in the `Hello` and `Start` frames, before anything else in the frames,
+/// such that hosts can refuse each other before decoding anything they may not understand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolVersions {
+    /// The oldest supported protocol version
+    pub min: u8,
+    /// The newest supported protocol version
+    pub max: u8,
+    /// Bit flags of the supported optional features
+    pub features: u32,
+}
+
+impl ProtocolVersions {
+    const ENCODED_LEN: usize = 1 + 1 + 4; // min + max + features
+
+    /// Create a new version range
+    pub fn new(min: u8, max: u8, features: u32) -> Self {
+        ProtocolVersions { min, max, features }
+    }
+
+    /// The versions and features supported by this build
+    pub fn local() -> Self {
+        ProtocolVersions::new(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, PROTOCOL_FEATURES)
+    }
+
+    /// Returns the highest version and the features supported by both `self` and `other`,
+    /// or `None` if they have no version in common.
+    pub fn negotiate(&self, other: &ProtocolVersions) -> Option<(u8, u32)> {
+        let version = self.max.min(other.max);
+        if version >= self.min.max(other.min) {
+            Some((version, self.features & other.features))
+        } else {
+            None
+        }
+    }
+
+    fn encode_into<B: BufMut>(&self, dst: &mut B) {
+        dst.put_u8(self.min);
+        dst.put_u8(self.max);
+        dst.put_u32(self.features);
+    }
+
+    fn decode_from<B: Buf>(src: &mut B) -> Result<Self, FramingError> {
+        if src.remaining() < Self::ENCODED_LEN {
+            return Err(FramingError::InvalidFrame);
+        }
+        let min = src.get_u8();
+        let max = src.get_u8();
+        let features = src.get_u32();
+        Ok(ProtocolVersions::new(min, max, features))
+    }
+
+    /// Decodes the versions at the front of a handshake frame, and refuses them
+    /// if they have none in common with ours, before anything behind them is decoded.
+    fn decode_compatible<B: Buf>(src: &mut B) -> Result<Self, FramingError> {
+        let versions = ProtocolVersions::decode_from(src)?;
+        if ProtocolVersions::local().negotiate(&versions).is_some() {
+            Ok(versions)
+        } else {
+            Err(FramingError::IncompatibleVersions(versions))
+        }
+    }
 }
 
 /// Byte-mappings for frame types
@@ -186,8 +367,22 @@ pub enum FrameType {
     Ack = 0x06,
     /// Bye to signal that a channel is closing.
     Bye = 0x07,
+    /// Numbered frame of Data, used for reliable UDP
+    Sequenced = 0x08,
+    /// Acknowledges received `Sequenced` frames
+    SelectiveAck = 0x09,
+    /// Part of a frame too large for a single UDP datagram, or for the buffers of a TCP channel
+    Fragment = 0x0A,
+    /// Heartbeat to signal that an idle channel is still alive
+    Heartbeat = 0x0B,
+    /// Frame of Data with a compressed payload
+    Compressed = 0x0C,
+    /// Asks the remote host to close the channel because it was idle
+    Idle = 0x0D,
+    /// Agrees to close the idle channel
+    IdleAck = 0x0E,
     /// Unknown frame type
-    Unknown = 0x08,
+    Unknown = 0xFF,
 }
 
 impl From<u8> for FrameType {
@@ -200,6 +395,13 @@ impl From<u8> for FrameType {
             0x05 => FrameType::Start,
             0x06 => FrameType::Ack,
             0x07 => FrameType::Bye,
+            0x08 => FrameType::Sequenced,
+            0x09 => FrameType::SelectiveAck,
+            0x0A => FrameType::Fragment,
+            0x0B => FrameType::Heartbeat,
+            0x0C => FrameType::Compressed,
+            0x0D => FrameType::Idle,
+            0x0E => FrameType::IdleAck,
             _ => FrameType::Unknown,
         }
     }
@@ -263,28 +465,64 @@ impl FrameHead {
 }
 
 impl StreamRequest {
-    #[allow(dead_code)]
-    pub(crate) fn new(credit_capacity: u32) -> Self {
+    /// Create a new credit request
+    pub fn new(credit_capacity: u32) -> Self {
         StreamRequest { credit_capacity }
     }
 }
 
+impl CreditUpdate {
+    /// Create a new credit grant
+    pub fn new(credit: u32) -> Self {
+        CreditUpdate { credit }
+    }
+}
+
 impl Hello {
     /// Create a new hello message
-    pub fn new(addr: SocketAddr) -> Self {
-        Hello { addr }
+    pub fn new(
+        versions: ProtocolVersions,
+        addr: SocketAddr,
+        incarnation: Uuid,
+        challenge: Vec<u8>,
+    ) -> Self {
+        Hello {
+            versions,
+            addr,
+            incarnation,
+            challenge,
+        }
     }
 
     /// Get the address sent in the Hello message
     pub fn addr(&self) -> SocketAddr {
         self.addr
     }
+
+    /// Get the incarnation sent in the Hello message
+    pub fn incarnation(&self) -> Uuid {
+        self.incarnation
+    }
 }
 
 impl Start {
     /// Create a new hello message
-    pub fn new(addr: SocketAddr, id: Uuid) -> Self {
-        Start { addr, id }
+    pub fn new(
+        versions: ProtocolVersions,
+        addr: SocketAddr,
+        id: Uuid,
+        incarnation: Uuid,
+        response: Vec<u8>,
+        challenge: Vec<u8>,
+    ) -> Self {
+        Start {
+            versions,
+            addr,
+            id,
+            incarnation,
+            response,
+            challenge,
+        }
     }
 
     /// Get the address sent in the Start message
@@ -296,6 +534,111 @@ impl Start {
     pub fn id(&self) -> Uuid {
         self.id
     }
+
+    /// Get the incarnation sent in the Start message
+    pub fn incarnation(&self) -> Uuid {
+        self.incarnation
+    }
+}
+
+impl Ack {
+    /// Create a new ack message
+    pub fn new(offset: u128, response: Vec<u8>) -> Self {
+        Ack { offset, response }
+    }
+}
+
+impl Sequenced {
+    /// The length of the fields in front of the payload
+    pub(crate) const HEADER_LEN: usize = 8 + 8 + 8; // session + seq + prev
+
+    /// Create a new numbered data frame
+    pub fn new(session: u64, seq: u64, prev: u64, payload: ChunkLease) -> Self {
+        Sequenced {
+            session,
+            seq,
+            prev,
+            payload,
+        }
+    }
+}
+
+impl SelectiveAck {
+    /// Create a new acknowledgement
+    pub fn new(session: u64, cumulative: u64, bitmap: u64) -> Self {
+        SelectiveAck {
+            session,
+            cumulative,
+            bitmap,
+        }
+    }
+}
+
+impl Fragment {
+    /// The length of the fields in front of the payload
+    pub(crate) const HEADER_LEN: usize = 4 + 2 + 2; // message_id + index + count
+
+    /// Create a new fragment
+    pub fn new(message_id: u32, index: u16, count: u16, payload: ChunkLease) -> Self {
+        Fragment {
+            message_id,
+            index,
+            count,
+            payload,
+        }
+    }
+}
+
+/// Puts `bytes` into `dst`, prefixed by their length.
+///
+/// Fails if there are more bytes than their length prefix can express.
+fn put_auth_bytes<B: BufMut>(dst: &mut B, bytes: &[u8]) -> Result<(), FramingError> {
+    let len = u16::try_from(bytes.len()).map_err(|_| FramingError::InvalidFrame)?;
+    dst.put_u16(len);
+    dst.put_slice(bytes);
+    Ok(())
+}
+
+/// Gets a socket address, written as its address family followed by its ip and port, from `src`.
+fn get_socket_addr<B: Buf>(src: &mut B) -> Result<SocketAddr, FramingError> {
+    if src.remaining() < 1 {
+        return Err(FramingError::InvalidFrame);
+    }
+    match src.get_u8() {
+        4 if src.remaining() >= 4 + 2 => {
+            let ip = Ipv4Addr::from(src.get_u32());
+            let port = src.get_u16();
+            Ok(SocketAddr::new(IpAddr::V4(ip), port))
+        }
+        6 if src.remaining() >= 16 + 2 => {
+            let ip = Ipv6Addr::from(src.get_u128());
+            let port = src.get_u16();
+            Ok(SocketAddr::new(IpAddr::V6(ip), port))
+        }
+        _ => Err(FramingError::InvalidFrame),
+    }
+}
+
+/// Gets a Uuid from `src`.
+fn get_uuid<B: Buf>(src: &mut B) -> Result<Uuid, FramingError> {
+    if src.remaining() < 16 {
+        return Err(FramingError::InvalidFrame);
+    }
+    Ok(Uuid::from_u128(src.get_u128()))
+}
+
+/// Gets length prefixed bytes written by `put_auth_bytes` from `src`.
+fn get_auth_bytes<B: Buf>(src: &mut B) -> Result<Vec<u8>, FramingError> {
+    if src.remaining() < 2 {
+        return Err(FramingError::InvalidFrame);
+    }
+    let len = src.get_u16() as usize;
+    if src.remaining() < len {
+        return Err(FramingError::InvalidFrame);
+    }
+    let mut bytes = vec![0; len];
+    src.copy_to_slice(&mut bytes);
+    Ok(bytes)
 }
 
 impl Data {
@@ -314,6 +657,36 @@ impl Data {
     }
 }
 
+impl Compressed {
+    /// Create a new compressed data frame
+    pub fn new(body: ChunkLease) -> Self {
+        Compressed { body }
+    }
+
+    /// Consumes this frame and returns the decompressed payload of the original data frame
+    ///
+    /// Fails if the payload would be larger than `max_len` bytes.
+    pub(crate) fn decompress(self, max_len: usize) -> std::io::Result<Bytes> {
+        super::compression::decompress(self.body, max_len)
+    }
+}
+
+impl FrameExt for Compressed {
+    fn decode_from(body: ChunkLease) -> Result<Frame, FramingError> {
+        Ok(Frame::Compressed(Compressed { body }))
+    }
+
+    fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
+        assert!(dst.remaining_mut() >= self.body.remaining());
+        dst.put(&mut self.body);
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.body.remaining()
+    }
+}
+
 impl FrameExt for Data {
     fn decode_from(payload: ChunkLease) -> Result<Frame, FramingError> {
         /*if src.remaining() < 12 {
@@ -341,7 +714,7 @@ impl FrameExt for Data {
 
 impl FrameExt for StreamRequest {
     fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
-        if src.remaining() < 8 {
+        if src.remaining() < 4 {
             return Err(FramingError::InvalidFrame);
         }
         //let stream_id: StreamId = src.get_u32_be().into();
@@ -366,84 +739,82 @@ impl FrameExt for StreamRequest {
 
 impl FrameExt for Hello {
     fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
-        match src.get_u8() {
-            4 => {
-                let ip = Ipv4Addr::from(src.get_u32());
-                let port = src.get_u16();
-                let addr = SocketAddr::new(IpAddr::V4(ip), port);
-                Ok(Frame::Hello(Hello::new(addr)))
-            }
-            6 => {
-                let ip = Ipv6Addr::from(src.get_u128());
-                let port = src.get_u16();
-                let addr = SocketAddr::new(IpAddr::V6(ip), port);
-                Ok(Frame::Hello(Hello::new(addr)))
-            }
-            _ => {
-                panic!("Faulty Hello Message!");
-            }
-        }
+        let versions = ProtocolVersions::decode_compatible(&mut src)?;
+        let addr = get_socket_addr(&mut src)?;
+        let incarnation = get_uuid(&mut src)?;
+        let challenge = get_auth_bytes(&mut src)?;
+        Ok(Frame::Hello(Hello::new(
+            versions,
+            addr,
+            incarnation,
+            challenge,
+        )))
     }
 
     fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
+        self.versions.encode_into(dst);
         match self.addr {
             SocketAddr::V4(v4) => {
                 dst.put_u8(4); // version
                 dst.put_slice(&v4.ip().octets()); // ip
                 dst.put_u16(v4.port()); // port
+                dst.put_u128(self.incarnation.as_u128()); // incarnation
+                put_auth_bytes(dst, &self.challenge)?;
                 Ok(())
             }
             SocketAddr::V6(v6) => {
                 dst.put_u8(6); // version
                 dst.put_slice(&v6.ip().octets()); // ip
                 dst.put_u16(v6.port()); // port
+                dst.put_u128(self.incarnation.as_u128()); // incarnation
+                put_auth_bytes(dst, &self.challenge)?;
                 Ok(())
             }
         }
     }
 
     fn encoded_len(&self) -> usize {
-        match self.addr {
-            SocketAddr::V4(_v4) => {
-                1 + 4 + 2 // version + ip + port
+        ProtocolVersions::ENCODED_LEN
+            + match self.addr {
+                SocketAddr::V4(_v4) => {
+                    1 + 4 + 2 + 16 + 2 + self.challenge.len() // version + ip + port + incarnation + challenge
+                }
+                SocketAddr::V6(_v6) => {
+                    1 + 16 + 2 + 16 + 2 + self.challenge.len() // version + ip + port + incarnation + challenge
+                }
             }
-            SocketAddr::V6(_v6) => {
-                1 + 16 + 2 // version + ip + port
-            }
-        }
     }
 }
 
 impl FrameExt for Start {
     fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
-        match src.get_u8() {
-            4 => {
-                let ip = Ipv4Addr::from(src.get_u32());
-                let port = src.get_u16();
-                let addr = SocketAddr::new(IpAddr::V4(ip), port);
-                let uuid = Uuid::from_u128(src.get_u128());
-                Ok(Frame::Start(Start::new(addr, uuid)))
-            }
-            6 => {
-                let ip = Ipv6Addr::from(src.get_u128());
-                let port = src.get_u16();
-                let addr = SocketAddr::new(IpAddr::V6(ip), port);
-                let uuid = Uuid::from_u128(src.get_u128());
-                Ok(Frame::Start(Start::new(addr, uuid)))
-            }
-            _ => {
-                panic!("Faulty Hello Message!");
-            }
-        }
+        let versions = ProtocolVersions::decode_compatible(&mut src)?;
+        let addr = get_socket_addr(&mut src)?;
+        let uuid = get_uuid(&mut src)?;
+        let incarnation = get_uuid(&mut src)?;
+        let response = get_auth_bytes(&mut src)?;
+        let challenge = get_auth_bytes(&mut src)?;
+        Ok(Frame::Start(Start::new(
+            versions,
+            addr,
+            uuid,
+            incarnation,
+            response,
+            challenge,
+        )))
     }
 
     fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
+        self.versions.encode_into(dst);
         match self.addr {
             SocketAddr::V4(v4) => {
                 dst.put_u8(4); // version
                 dst.put_slice(&v4.ip().octets()); // ip
                 dst.put_u16(v4.port()); // port
                 dst.put_u128(self.id.as_u128()); //id
+                dst.put_u128(self.incarnation.as_u128()); // incarnation
+                put_auth_bytes(dst, &self.response)?;
+                put_auth_bytes(dst, &self.challenge)?;
                 Ok(())
             }
             SocketAddr::V6(v6) => {
@@ -451,50 +822,221 @@ impl FrameExt for Start {
                 dst.put_slice(&v6.ip().octets()); // ip
                 dst.put_u16(v6.port()); // port
                 dst.put_u128(self.id.as_u128()); //id
+                dst.put_u128(self.incarnation.as_u128()); // incarnation
+                put_auth_bytes(dst, &self.response)?;
+                put_auth_bytes(dst, &self.challenge)?;
                 Ok(())
             }
         }
     }
 
     fn encoded_len(&self) -> usize {
-        match self.addr {
-            SocketAddr::V4(_v4) => {
-                1 + 4 + 2 + 16 // version + ip + port + uuid
-            }
-            SocketAddr::V6(_v6) => {
-                1 + 16 + 2 + 16 // version + ip + port + uuid
+        ProtocolVersions::ENCODED_LEN
+            + match self.addr {
+                SocketAddr::V4(_v4) => {
+                    // version + ip + port + uuid + incarnation + response + challenge
+                    1 + 4 + 2 + 16 + 16 + 2 + self.response.len() + 2 + self.challenge.len()
+                }
+                SocketAddr::V6(_v6) => {
+                    // version + ip + port + uuid + incarnation + response + challenge
+                    1 + 16 + 2 + 16 + 16 + 2 + self.response.len() + 2 + self.challenge.len()
+                }
             }
-        }
     }
 }
 
 impl FrameExt for Ack {
     fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
-        Ok(Frame::Ack(Ack {
-            offset: src.get_u128(),
-        }))
+        if src.remaining() < 16 {
+            return Err(FramingError::InvalidFrame);
+        }
+        let offset = src.get_u128();
+        let response = get_auth_bytes(&mut src)?;
+        Ok(Frame::Ack(Ack::new(offset, response)))
     }
 
     fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
         dst.put_u128(self.offset);
+        put_auth_bytes(dst, &self.response)?;
         Ok(())
     }
 
     fn encoded_len(&self) -> usize {
-        16
+        16 + 2 + self.response.len() // offset + response
     }
 }
 
 impl FrameExt for CreditUpdate {
-    fn decode_from(_src: ChunkLease) -> Result<Frame, FramingError> {
-        unimplemented!()
+    fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
+        if src.remaining() < 4 {
+            return Err(FramingError::InvalidFrame);
+        }
+        let credit = src.get_u32();
+        Ok(Frame::CreditUpdate(CreditUpdate { credit }))
+    }
+
+    fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
+        assert!(dst.remaining_mut() >= self.encoded_len());
+        dst.put_u32(self.credit);
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        4 // credit
+    }
+}
+
+impl FrameExt for Sequenced {
+    fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
+        if src.remaining() < Sequenced::HEADER_LEN {
+            return Err(FramingError::InvalidFrame);
+        }
+        let session = src.get_u64();
+        let seq = src.get_u64();
+        let prev = src.get_u64();
+        Ok(Frame::Sequenced(Sequenced::new(session, seq, prev, src)))
+    }
+
+    /// This method _COPIES_ the payload into `dst`, just like for `Data` frames
+    fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
+        assert!(dst.remaining_mut() >= self.encoded_len());
+        dst.put_u64(self.session);
+        dst.put_u64(self.seq);
+        dst.put_u64(self.prev);
+        while self.payload.has_remaining() {
+            let chunk_len = self.payload.chunk().len();
+            dst.put_slice(self.payload.chunk());
+            self.payload.advance(chunk_len);
+        }
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        Sequenced::HEADER_LEN + self.payload.remaining()
+    }
+}
+
+impl FrameExt for SelectiveAck {
+    fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
+        if src.remaining() < 24 {
+            return Err(FramingError::InvalidFrame);
+        }
+        let session = src.get_u64();
+        let cumulative = src.get_u64();
+        let bitmap = src.get_u64();
+        Ok(Frame::SelectiveAck(SelectiveAck::new(
+            session, cumulative, bitmap,
+        )))
     }
 
-    fn encode_into<B: BufMut>(&mut self, _dst: &mut B) -> Result<(), FramingError> {
-        unimplemented!()
+    fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
+        assert!(dst.remaining_mut() >= self.encoded_len());
+        dst.put_u64(self.session);
+        dst.put_u64(self.cumulative);
+        dst.put_u64(self.bitmap);
+        Ok(())
     }
 
     fn encoded_len(&self) -> usize {
-        4 // stream_id + credit
+        8 + 8 + 8 // session + cumulative + bitmap
+    }
+}
+
+impl FrameExt for Fragment {
+    fn decode_from(mut src: ChunkLease) -> Result<Frame, FramingError> {
+        if src.remaining() < Fragment::HEADER_LEN {
+            return Err(FramingError::InvalidFrame);
+        }
+        let message_id = src.get_u32();
+        let index = src.get_u16();
+        let count = src.get_u16();
+        if index >= count {
+            return Err(FramingError::InvalidFrame);
+        }
+        Ok(Frame::Fragment(Fragment::new(
+            message_id, index, count, src,
+        )))
+    }
+
+    /// This method _COPIES_ the payload into `dst`, just like for `Data` frames
+    fn encode_into<B: BufMut>(&mut self, dst: &mut B) -> Result<(), FramingError> {
+        assert!(dst.remaining_mut() >= self.encoded_len());
+        dst.put_u32(self.message_id);
+        dst.put_u16(self.index);
+        dst.put_u16(self.count);
+        while self.payload.has_remaining() {
+            let chunk_len = self.payload.chunk().len();
+            dst.put_slice(self.payload.chunk());
+            self.payload.advance(chunk_len);
+        }
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        Fragment::HEADER_LEN + self.payload.remaining()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    #[test]
+    fn oversized_auth_bytes_are_not_encoded() {
+        let addr = "127.0.0.1:1234".parse().unwrap();
+        let challenge = vec![0u8; u16::MAX as usize];
+        let mut hello = Frame::Hello(Hello::new(
+            ProtocolVersions::local(),
+            addr,
+            Uuid::new_v4(),
+            challenge,
+        ));
+        let mut bytes = BytesMut::with_capacity(hello.encoded_len() + FRAME_HEAD_LEN as usize);
+        assert!(hello.encode_into(&mut bytes).is_ok());
+
+        let response = vec![0u8; u16::MAX as usize + 1];
+        let mut ack = Frame::Ack(Ack::new(0, response));
+        let mut bytes = BytesMut::with_capacity(ack.encoded_len() + FRAME_HEAD_LEN as usize);
+        assert!(matches!(
+            ack.encode_into(&mut bytes),
+            Err(FramingError::InvalidFrame)
+        ));
+    }
+
+    #[test]
+    fn versions_are_negotiated_across_ranges() {
+        let older = ProtocolVersions::new(1, 3, FEATURE_HEARTBEATS);
+        let newer = ProtocolVersions::new(2, 5, FEATURE_HEARTBEATS | FEATURE_STREAMING);
+        // The newest common version and the common features, no matter who asks
+        assert_eq!(older.negotiate(&newer), Some((3, FEATURE_HEARTBEATS)));
+        assert_eq!(newer.negotiate(&older), Some((3, FEATURE_HEARTBEATS)));
+        let oldest = ProtocolVersions::new(1, 1, FEATURE_HEARTBEATS);
+        assert_eq!(older.negotiate(&oldest), Some((1, FEATURE_HEARTBEATS)));
+        assert_eq!(newer.negotiate(&oldest), None);
+        assert_eq!(oldest.negotiate(&newer), None);
+
+        // A future build which still speaks our newest version
+        let local = ProtocolVersions::local();
+        let future = ProtocolVersions::new(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION + 2, u32::MAX);
+        assert_eq!(
+            local.negotiate(&future),
+            Some((PROTOCOL_VERSION, PROTOCOL_FEATURES))
+        );
+        let mut bytes = BytesMut::new();
+        future.encode_into(&mut bytes);
+        assert_eq!(
+            ProtocolVersions::decode_compatible(&mut bytes.freeze()).expect("compatible"),
+            future
+        );
+
+        // A future build which dropped it
+        let beyond = ProtocolVersions::new(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 2, u32::MAX);
+        let mut bytes = BytesMut::new();
+        beyond.encode_into(&mut bytes);
+        assert!(matches!(
+            ProtocolVersions::decode_compatible(&mut bytes.freeze()),
+            Err(FramingError::IncompatibleVersions(versions)) if versions == beyond
+        ));
     }
 }
@@ -4,22 +4,51 @@ use arc_swap::ArcSwap;
 use dispatch::lookup::ActorStore;
 use net::events::NetworkEvent;
 
-use std::{io, net::SocketAddr, sync::Arc, thread};
+use std::{
+    convert::TryFrom,
+    hash::{Hash, Hasher},
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+};
+use uuid::Uuid;
 
 use crate::{
     messaging::DispatchData,
-    net::{events::DispatchEvent, frames::*, network_thread::NetworkThread},
+    net::{
+        events::DispatchEvent,
+        frames::*,
+        network_thread::{ConnectionCounts, NetworkThread, Shard},
+        retry::RetryStrategy,
+    },
     prelude::NetworkConfig,
 };
 use crossbeam_channel::{unbounded as channel, RecvError, SendError, Sender};
+use hocon::Hocon;
 use mio::{Interest, Waker};
+use rustc_hash::FxHasher;
 
+pub mod auth;
 #[allow(missing_docs)]
 pub mod buffers;
+pub mod compression;
+pub(crate) mod failure_detector;
+pub(crate) mod flow_control;
 pub mod frames;
 pub(crate) mod network_channel;
 pub(crate) mod network_thread;
+pub(crate) mod reliable_udp;
+pub mod resolver;
+pub mod retry;
+pub(crate) mod streaming;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub(crate) mod udp_fragments;
 pub(crate) mod udp_state;
+#[cfg(unix)]
+pub(crate) mod uds_state;
 
 /// The state of a connection
 #[derive(Debug)]
@@ -30,6 +59,8 @@ pub enum ConnectionState {
     Initializing,
     /// Connected with a confirmed canonical SocketAddr
     Connected(SocketAddr),
+    /// Connected, but the remote host has not granted any credits for sending more data
+    Blocked,
     /// Already closed
     Closed,
     /// Threw an error
@@ -40,12 +71,19 @@ pub(crate) enum Protocol {
     TCP,
     UDP,
 }
-impl From<Transport> for Protocol {
-    fn from(t: Transport) -> Self {
+impl TryFrom<Transport> for Protocol {
+    type Error = NetworkBridgeErr;
+
+    /// Fails for transports which are not routed by socket address,
+    /// i.e. local ones and UDS, which is routed by socket path with `Bridge::route_uds`
+    fn try_from(t: Transport) -> Result<Self, Self::Error> {
         match t {
-            Transport::TCP => Protocol::TCP,
-            Transport::UDP => Protocol::UDP,
-            _ => unimplemented!("Unsupported Protocol"),
+            Transport::TCP => Ok(Protocol::TCP),
+            Transport::UDP => Ok(Protocol::UDP),
+            other => Err(NetworkBridgeErr::Other(format!(
+                "Unsupported Protocol: {}",
+                other
+            ))),
         }
     }
 }
@@ -54,8 +92,9 @@ impl From<Transport> for Protocol {
 pub mod events {
 
     use super::ConnectionState;
-    use crate::net::frames::*;
-    use std::net::SocketAddr;
+    use crate::net::{compression::CompressionStats, frames::*};
+    use std::{net::SocketAddr, path::PathBuf};
+    use uuid::Uuid;
 
     use crate::messaging::DispatchData;
 
@@ -68,6 +107,30 @@ pub mod events {
         Data(Frame),
         /// The NetworkThread lost connection to the remote host and rejects the frame
         RejectedData(SocketAddr, DispatchData),
+        /// The remote host failed to authenticate during the handshake and was disconnected
+        AuthenticationFailed(SocketAddr),
+        /// The failure detector's suspicion that the remote host has failed, reported once every heartbeat interval
+        Suspicion(SocketAddr, f64),
+        /// The remote host told us the incarnation of its system during the handshake,
+        /// right before the connection to it is reported as established
+        Incarnation(SocketAddr, Uuid),
+        /// How well the data frames sent to the remote host compressed, reported once every heartbeat interval
+        Compression(SocketAddr, CompressionStats),
+        /// The channel to the remote host is closing because it was idle, whichever host closed it.
+        /// Sent right before the channel is reported as closed.
+        Idle(SocketAddr),
+        /// The handshake on the Unix domain socket stream to the system listening at the `PathBuf` completed
+        UdsConnected(PathBuf),
+        /// The stream to the system listening at the `PathBuf` closed or could not be opened,
+        /// its messages are queued while connecting again
+        UdsLost(PathBuf),
+        /// The `NetworkThread` gave up on the system listening at the `PathBuf`
+        UdsDropped(PathBuf),
+        /// Messages to the system listening at the `PathBuf` were dropped
+        UdsDroppedMessages(PathBuf, usize),
+        /// Reliable UDP messages to the remote host were dropped,
+        /// as it did not acknowledge them in time or too many were waiting to be sent
+        UdpDroppedMessages(SocketAddr, usize),
     }
 
     /// BridgeEvents emitted to the network `Bridge`
@@ -75,14 +138,32 @@ pub mod events {
     pub enum DispatchEvent {
         /// Send the `SerialisedFrame` to receiver associated with the `SocketAddr`
         SendTCP(SocketAddr, DispatchData),
-        /// Send the `SerialisedFrame` to receiver associated with the `SocketAddr`
-        SendUDP(SocketAddr, DispatchData),
+        /// Send the `SerialisedFrame` to receiver associated with the `SocketAddr`,
+        /// ahead of the frames already queued on the channel
+        SendPriorityTCP(SocketAddr, DispatchData),
+        /// Send the `SerialisedFrame` to receiver associated with the `SocketAddr`,
+        /// ordered with respect to the other frames on the lane given by the `u64`
+        SendUDP(SocketAddr, u64, DispatchData),
+        /// Send the `SerialisedFrame` to the receiver listening on the Unix domain socket at the `PathBuf`
+        SendUDS(PathBuf, DispatchData),
         /// Tells the network thread to Stop
         Stop,
         /// Tells the `NetworkThread` to open up a channel to the `SocketAddr`
         Connect(SocketAddr),
         /// Acknowledges a closed channel, required to ensure FIFO ordering under connection loss
         ClosedAck(SocketAddr),
+        /// Tells the `NetworkThread` to close the channel to the `SocketAddr`
+        Disconnect(SocketAddr),
+        /// Tells the `NetworkThread` to close the channel to the `SocketAddr` and refuse any traffic from it
+        Block(SocketAddr),
+        /// Tells the `NetworkThread` to accept traffic from the `SocketAddr` again
+        Unblock(SocketAddr),
+        /// Tells the `NetworkThread` to close its least recently used idle channel accepted from remote hosts
+        /// if `true`, or requested by us otherwise, should the connection limit in that direction still be reached
+        MakeRoom(bool),
+        /// Tells the `NetworkThread` that the hostname was resolved to the `SocketAddr`,
+        /// so the TLS certificate of the remote host is verified against the hostname
+        Resolved(String, SocketAddr),
     }
 
     /// Errors emitted byt the network `Bridge`
@@ -104,9 +185,11 @@ pub mod events {
 }
 
 /// The configuration for the network `Bridge`
-#[allow(dead_code)]
+///
+/// Settings made here take precedence over those in the `bridge_config` section of the system's HOCON config.
+#[derive(Clone, Debug, Default)]
 pub struct BridgeConfig {
-    retry_strategy: RetryStrategy,
+    retry_strategy: Option<RetryStrategy>,
 }
 
 impl BridgeConfig {
@@ -116,44 +199,58 @@ impl BridgeConfig {
     pub fn new() -> Self {
         BridgeConfig::default()
     }
-}
 
-#[allow(dead_code)]
-enum RetryStrategy {
-    ExponentialBackoff { base_ms: u64, num_tries: usize },
-}
+    /// Tries to deserialise a configuration instance from the `bridge_config` section of the given `config`
+    ///
+    /// Settings missing from the `config` are left unset.
+    pub fn from_config(config: &Hocon) -> Self {
+        BridgeConfig {
+            retry_strategy: RetryStrategy::from_config(config),
+        }
+    }
 
-#[allow(dead_code)]
-impl Default for BridgeConfig {
-    fn default() -> Self {
-        let retry_strategy = RetryStrategy::ExponentialBackoff {
-            base_ms: 100,
-            num_tries: 5,
-        };
-        BridgeConfig { retry_strategy }
+    /// Configures how to retry connecting to remote addresses
+    ///
+    /// If no strategy is configured here or in HOCON, the dispatcher waits
+    /// [connection_retry_interval](NetworkConfig::set_connection_retry_interval) between attempts and gives up after
+    /// [max_connection_retry_attempts](NetworkConfig::set_max_connection_retry_attempts) retries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `strategy` is [invalid](RetryStrategy::validate).
+    pub fn set_retry_strategy(&mut self, strategy: RetryStrategy) {
+        strategy.validate();
+        self.retry_strategy = Some(strategy);
+    }
+
+    /// Returns the configured retry strategy, if any
+    pub fn get_retry_strategy(&self) -> Option<&RetryStrategy> {
+        self.retry_strategy.as_ref()
     }
 }
 
-/// Bridge to Network Threads. Routes outbound messages to the correct network thread. Single threaded for now.
+/// Bridge to Network Threads. Routes outbound messages to the correct network thread.
+///
+/// Channels are sharded over the [configured](NetworkConfig::set_network_threads) number of threads
+/// by the address of the remote system, see [shard_index].
+/// UDP and UDS traffic always goes through the first thread, which owns those sockets.
 pub struct Bridge {
-    /// Network-specific configuration
-    //cfg: BridgeConfig,
     /// Core logger; shared with network thread
     log: KompactLogger,
     /// Shared actor reference lookup table
     // lookup: Arc<ArcSwap<ActorStore>>,
-    /// Network Thread stuff:
-    // network_thread: Box<NetworkThread>,
-    // ^ Can we avoid storing this by moving it into itself?
-    network_input_queue: Sender<events::DispatchEvent>,
-    waker: Waker,
+    /// Network Thread stuff, one entry per thread:
+    network_input_queues: Vec<Sender<events::DispatchEvent>>,
+    wakers: Vec<Arc<Waker>>,
     /// Tokio Runtime
     // tokio_runtime: Option<Runtime>,
     /// Reference back to the Kompact dispatcher
     dispatcher: Option<DispatcherRef>,
     /// Socket the network actually bound on
     bound_addr: Option<SocketAddr>,
-    shutdown_future: KFuture<()>,
+    /// Socket the network is advertised at to remote systems
+    advertised_addr: Option<SocketAddr>,
+    shutdown_futures: Vec<KFuture<()>>,
 }
 
 impl Bridge {
@@ -162,45 +259,98 @@ impl Bridge {
     /// # Returns
     /// A tuple consisting of the new Bridge object and the network event receiver.
     /// The receiver will allow responding to [NetworkEvent]s for external state management.
+    ///
+    /// # Errors
+    /// If the sockets can not be bound or the TLS config can not be set up
     pub fn new(
         lookup: Arc<ArcSwap<ActorStore>>,
         network_thread_log: KompactLogger,
         bridge_log: KompactLogger,
         addr: SocketAddr,
         dispatcher_ref: DispatcherRef,
+        incarnation: Uuid,
         network_config: &NetworkConfig,
-    ) -> (Self, SocketAddr) {
+    ) -> Result<(Self, SocketAddr), NetworkBridgeErr> {
+        let thread_count = network_config.get_network_threads();
+        let mut network_threads = Vec::with_capacity(thread_count);
+        let mut network_input_queues = Vec::with_capacity(thread_count);
+        let mut wakers = Vec::with_capacity(thread_count);
+        let mut shutdown_futures = Vec::with_capacity(thread_count);
+        let mut shards = Vec::with_capacity(thread_count);
+        // The first thread binds the sockets, the others share its addresses
         let (sender, receiver) = channel();
         let (shutdown_p, shutdown_f) = promise();
-        let (mut network_thread, waker) = NetworkThread::new(
-            network_thread_log,
+        let (network_thread, waker) = NetworkThread::new(
+            network_thread_log.clone(),
             addr,
-            lookup,
+            lookup.clone(),
             receiver,
             shutdown_p,
             dispatcher_ref.clone(),
+            incarnation,
             network_config.clone(),
-        );
+        )?;
         let bound_addr = network_thread.addr;
+        let advertised_addr = network_thread.advertised_addr;
+        network_threads.push(network_thread);
+        network_input_queues.push(sender);
+        wakers.push(Arc::new(waker));
+        shutdown_futures.push(shutdown_f);
+        for _ in 1..thread_count {
+            let (sender, receiver) = channel();
+            let (shutdown_p, shutdown_f) = promise();
+            let (network_thread, waker) = NetworkThread::without_listener(
+                network_thread_log.clone(),
+                bound_addr,
+                advertised_addr,
+                lookup.clone(),
+                receiver,
+                shutdown_p,
+                dispatcher_ref.clone(),
+                incarnation,
+                network_config.clone(),
+            )?;
+            network_threads.push(network_thread);
+            network_input_queues.push(sender);
+            wakers.push(Arc::new(waker));
+            shutdown_futures.push(shutdown_f);
+        }
+        for ((network_thread, events), waker) in network_threads
+            .iter()
+            .zip(network_input_queues.iter())
+            .zip(wakers.iter())
+        {
+            shards.push(Shard {
+                hand_offs: network_thread.hand_off_sender(),
+                events: events.clone(),
+                waker: waker.clone(),
+            });
+        }
+        let connections = Arc::new(ConnectionCounts::default());
         let bridge = Bridge {
-            // cfg: BridgeConfig::default(),
             log: bridge_log,
             // lookup,
-            network_input_queue: sender,
-            waker,
+            network_input_queues,
+            wakers,
             dispatcher: Some(dispatcher_ref),
             bound_addr: Some(bound_addr),
-            shutdown_future: shutdown_f,
+            advertised_addr: Some(advertised_addr),
+            shutdown_futures,
         };
-        if let Err(e) = thread::Builder::new()
-            .name("network_thread".to_string())
-            .spawn(move || {
+        for (index, mut network_thread) in network_threads.into_iter().enumerate() {
+            network_thread.set_shards(index, shards.clone(), connections.clone());
+            let name = if index == 0 {
+                "network_thread".to_string()
+            } else {
+                format!("network_thread-{}", index)
+            };
+            if let Err(e) = thread::Builder::new().name(name).spawn(move || {
                 network_thread.run();
-            })
-        {
-            panic!("Failed to start a Network Thread, error: {:?}", e);
+            }) {
+                panic!("Failed to start a Network Thread, error: {:?}", e);
+            }
         }
-        (bridge, bound_addr)
+        Ok((bridge, bound_addr))
     }
 
     /// Sets the dispatcher reference, returning the previously stored one
@@ -211,9 +361,10 @@ impl Bridge {
     /// Stops the bridge
     pub fn stop(self) -> Result<(), NetworkBridgeErr> {
         debug!(self.log, "Stopping NetworkBridge...");
-        self.network_input_queue.send(DispatchEvent::Stop)?;
-        self.waker.wake()?;
-        self.shutdown_future.wait(); // should block until something is sent
+        self.broadcast(|| DispatchEvent::Stop)?;
+        for shutdown_future in self.shutdown_futures {
+            shutdown_future.wait(); // should block until something is sent
+        }
         debug!(self.log, "Stopped NetworkBridge.");
         Ok(())
     }
@@ -223,6 +374,40 @@ impl Bridge {
         &self.bound_addr
     }
 
+    /// Returns the address remote systems reach us at, if already bound
+    ///
+    /// This is the local address, unless [advertised](NetworkConfig::set_advertised_addr) otherwise.
+    pub fn advertised_addr(&self) -> &Option<SocketAddr> {
+        &self.advertised_addr
+    }
+
+    /// Sends `event` to the network thread with the given `index` and makes sure that it will wake up.
+    fn send_to(&self, index: usize, event: DispatchEvent) -> Result<(), NetworkBridgeErr> {
+        self.network_input_queues[index].send(event)?;
+        self.wakers[index].wake()?;
+        Ok(())
+    }
+
+    /// Sends `event` to the network thread driving the channel to `addr`.
+    fn send_to_owner(
+        &self,
+        addr: &SocketAddr,
+        event: DispatchEvent,
+    ) -> Result<(), NetworkBridgeErr> {
+        self.send_to(shard_index(addr, self.network_input_queues.len()), event)
+    }
+
+    /// Sends an event created by `event` to every network thread.
+    fn broadcast<F>(&self, event: F) -> Result<(), NetworkBridgeErr>
+    where
+        F: Fn() -> DispatchEvent,
+    {
+        for index in 0..self.network_input_queues.len() {
+            self.send_to(index, event())?;
+        }
+        Ok(())
+    }
+
     /// Forwards `serialized` to the NetworkThread and makes sure that it will wake up.
     pub(crate) fn route(
         &self,
@@ -231,19 +416,42 @@ impl Bridge {
         protocol: Protocol,
     ) -> Result<(), NetworkBridgeErr> {
         match protocol {
-            Protocol::TCP => {
-                let _ = self
-                    .network_input_queue
-                    .send(DispatchEvent::SendTCP(addr, data))?;
-            }
-            Protocol::UDP => {
-                let _ = self
-                    .network_input_queue
-                    .send(DispatchEvent::SendUDP(addr, data))?;
-            }
+            Protocol::TCP => self.send_to_owner(&addr, DispatchEvent::SendTCP(addr, data)),
+            Protocol::UDP => self.send_to(SOCKET_THREAD, DispatchEvent::SendUDP(addr, 0, data)),
         }
-        self.waker.wake()?;
-        Ok(())
+    }
+
+    /// Forwards `data` to the NetworkThread for sending via TCP to `addr` in the channel's priority lane.
+    ///
+    /// The frame overtakes the bulk data queued on the channel, but stays ordered with the other prioritised frames.
+    pub(crate) fn route_prioritised(
+        &self,
+        addr: SocketAddr,
+        data: DispatchData,
+    ) -> Result<(), NetworkBridgeErr> {
+        self.send_to_owner(&addr, DispatchEvent::SendPriorityTCP(addr, data))
+    }
+
+    /// Forwards `data` to the NetworkThread for sending via UDP to `addr`.
+    ///
+    /// When UDP is [reliable](NetworkConfig::set_reliable_udp), messages with the same `lane`
+    /// are delivered in the order they were sent, while messages on other lanes never wait for them.
+    pub(crate) fn route_udp(
+        &self,
+        addr: SocketAddr,
+        lane: u64,
+        data: DispatchData,
+    ) -> Result<(), NetworkBridgeErr> {
+        self.send_to(SOCKET_THREAD, DispatchEvent::SendUDP(addr, lane, data))
+    }
+
+    /// Forwards `data` to the NetworkThread for the system listening on the Unix domain socket at `path`.
+    pub(crate) fn route_uds(
+        &self,
+        path: PathBuf,
+        data: DispatchData,
+    ) -> Result<(), NetworkBridgeErr> {
+        self.send_to(SOCKET_THREAD, DispatchEvent::SendUDS(path, data))
     }
 
     /// Attempts to establish a TCP connection to the provided `addr`.
@@ -257,23 +465,61 @@ impl Bridge {
     /// If the provided protocol is not supported
     pub fn connect(&self, proto: Transport, addr: SocketAddr) -> Result<(), NetworkBridgeErr> {
         match proto {
-            Transport::TCP => {
-                self.network_input_queue
-                    .send(events::DispatchEvent::Connect(addr))?;
-                self.waker.wake()?;
-                Ok(())
-            }
+            Transport::TCP => self.send_to_owner(&addr, events::DispatchEvent::Connect(addr)),
             _other => Err(NetworkBridgeErr::Other("Bad Protocol".to_string())),
         }
     }
 
     /// Acknowledges a closed channel, required to ensure FIFO ordering under connection loss
     pub fn ack_closed(&self, addr: SocketAddr) -> Result<(), NetworkBridgeErr> {
-        self.network_input_queue
-            .send(events::DispatchEvent::ClosedAck(addr))?;
-        self.waker.wake()?;
-        Ok(())
+        // Channels which closed before their handshake completed live on the thread which accepted them
+        self.broadcast(|| events::DispatchEvent::ClosedAck(addr))
+    }
+
+    /// Closes the channel to `addr`, if there is one
+    pub fn disconnect(&self, addr: SocketAddr) -> Result<(), NetworkBridgeErr> {
+        // The channel may still be handshaking on the thread which accepted it, or on its way to the owner
+        self.broadcast(|| events::DispatchEvent::Disconnect(addr))
+    }
+
+    /// Closes the channel to `addr`, if there is one, and refuses any traffic from `addr` until it is unblocked
+    pub fn block(&self, addr: SocketAddr) -> Result<(), NetworkBridgeErr> {
+        self.broadcast(|| events::DispatchEvent::Block(addr))
+    }
+
+    /// Accepts traffic from `addr` again
+    pub fn unblock(&self, addr: SocketAddr) -> Result<(), NetworkBridgeErr> {
+        self.broadcast(|| events::DispatchEvent::Unblock(addr))
+    }
+
+    /// Remembers that `hostname` resolved to `addr`,
+    /// which authenticates the remote host of channels requested to `addr` by `hostname`
+    pub(crate) fn resolved(
+        &self,
+        hostname: String,
+        addr: SocketAddr,
+    ) -> Result<(), NetworkBridgeErr> {
+        self.send_to_owner(&addr, events::DispatchEvent::Resolved(hostname, addr))
+    }
+}
+
+/// The index of the network thread which binds the sockets
+///
+/// The other threads only drive TCP channels, so all UDP and UDS traffic is pinned to this one.
+const SOCKET_THREAD: usize = 0;
+
+/// Returns the index of the network thread, out of `shards`, which drives the channel to `addr`
+///
+/// The index only depends on the address, so all traffic to the same remote system
+/// goes through the same thread, which keeps it in FIFO order.
+pub(crate) fn shard_index(addr: &SocketAddr, shards: usize) -> usize {
+    if shards <= 1 {
+        return 0;
     }
+    let mut hasher = FxHasher::default();
+    addr.hash(&mut hasher);
+    let hash = hasher.finish();
+    ((hash ^ (hash >> 32)) % shards as u64) as usize
 }
 
 /// Errors which the NetworkBridge might return, not used for now.
@@ -283,6 +529,10 @@ pub enum NetworkBridgeErr {
     Binding(String),
     /// Something went wrong with the thread
     Thread(String),
+    /// The hostname of the given system path could not be resolved
+    Resolution(String),
+    /// The [TLS config](crate::dispatch::NetworkConfig::set_tls_config) could not be set up
+    Tls(String),
     /// Something else went wrong
     Other(String),
 }
@@ -336,10 +586,15 @@ pub(crate) fn broken_pipe(err: &io::Error) -> bool {
 
 /// A module with helper functions for testing network configurations/implementations
 pub mod net_test_helpers {
-    use crate::prelude::*;
+    use crate::{cluster::ClusterPort, prelude::*};
     use std::{
         collections::VecDeque,
         fmt::{Debug, Formatter},
+        marker::PhantomData,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
         time::{SystemTime, UNIX_EPOCH},
     };
 
@@ -499,7 +754,10 @@ pub mod net_test_helpers {
         target: ActorPath,
         /// The number of `PongMsg` received
         pub count: u64,
+        /// The number of `PingMsg` returned as [Undeliverable](crate::prelude::Undeliverable)
+        pub rejected: u64,
         eager: bool,
+        prioritised: bool,
     }
 
     impl PingerAct {
@@ -510,7 +768,9 @@ pub mod net_test_helpers {
                 ctx: ComponentContext::uninitialised(),
                 target,
                 count: 0,
+                rejected: 0,
                 eager: false,
+                prioritised: false,
             }
         }
 
@@ -521,21 +781,42 @@ pub mod net_test_helpers {
                 ctx: ComponentContext::uninitialised(),
                 target,
                 count: 0,
+                rejected: 0,
                 eager: true,
+                prioritised: false,
             }
         }
-    }
 
-    impl ComponentLifecycle for PingerAct {
-        fn on_start(&mut self) -> Handled {
-            debug!(self.ctx.log(), "Starting");
+        /// Creates a new `PingerAct` sending messages using
+        /// [Prioritised Lazy Serialisation](crate::actors::ActorPath#method.tell_prioritised)
+        pub fn new_prioritised(target: ActorPath) -> PingerAct {
+            PingerAct {
+                ctx: ComponentContext::uninitialised(),
+                target,
+                count: 0,
+                rejected: 0,
+                eager: false,
+                prioritised: true,
+            }
+        }
+
+        fn ping(&mut self, i: u64) -> () {
             if self.eager {
                 self.target
-                    .tell_serialised(PingMsg { i: 0 }, self)
+                    .tell_serialised(PingMsg { i }, self)
                     .expect("serialise");
+            } else if self.prioritised {
+                self.target.tell_prioritised(PingMsg { i }, self);
             } else {
-                self.target.tell(PingMsg { i: 0 }, self);
+                self.target.tell(PingMsg { i }, self);
             }
+        }
+    }
+
+    impl ComponentLifecycle for PingerAct {
+        fn on_start(&mut self) -> Handled {
+            debug!(self.ctx.log(), "Starting");
+            self.ping(0);
             Handled::Ok
         }
     }
@@ -548,22 +829,20 @@ pub mod net_test_helpers {
         }
 
         fn receive_network(&mut self, msg: NetMessage) -> Handled {
-            match msg.try_deserialise::<PongMsg, PingPongSer>() {
-                Ok(pong) => {
+            match_deser! {msg; {
+                pong: PongMsg [PingPongSer] => {
                     debug!(self.ctx.log(), "Got msg {:?}", pong);
                     self.count += 1;
                     if self.count < PING_COUNT {
-                        if self.eager {
-                            self.target
-                                .tell_serialised(PingMsg { i: pong.i + 1 }, self)
-                                .expect("serialise");
-                        } else {
-                            self.target.tell(PingMsg { i: pong.i + 1 }, self);
-                        }
+                        self.ping(pong.i + 1);
                     }
-                }
-                Err(e) => error!(self.ctx.log(), "Error deserialising PongMsg: {:?}", e),
-            }
+                },
+                undeliverable: Undeliverable [Undeliverable] => {
+                    debug!(self.ctx.log(), "Ping to {} was rejected", undeliverable.receiver);
+                    self.rejected += 1;
+                },
+                !Err(e) => error!(self.ctx.log(), "Error deserialising PongMsg: {:?}", e),
+            }}
             Handled::Ok
         }
     }
@@ -1085,4 +1364,203 @@ pub mod net_test_helpers {
             Handled::Ok
         }
     }
+
+    /// A port whose indications a [Collector] collects
+    ///
+    /// Indications usually arrive via the port, but may also be extracted from network messages
+    /// by overriding [from_message](Collected::from_message).
+    pub trait Collected: Port + 'static {
+        /// Extracts an indication from `msg`, or hands `msg` back if it does not carry one
+        fn from_message(msg: NetMessage) -> Result<Self::Indication, Box<UnpackError<NetMessage>>> {
+            Err(Box::new(UnpackError::NoIdMatch(msg)))
+        }
+    }
+
+    impl Collected for ReachabilityPort {}
+    impl Collected for NetworkStatusPort {}
+    impl Collected for ClusterPort {}
+
+    /// A port which is never connected, for [Collector]s of network messages carrying a `T`
+    #[derive(Debug)]
+    pub struct Messages<T>(PhantomData<T>);
+
+    impl<T> Port for Messages<T>
+    where
+        T: Send + Clone + Debug + 'static,
+    {
+        type Indication = T;
+        type Request = Never;
+    }
+
+    /// Collects the receivers of messages, for use as a deadletter box
+    impl Collected for Messages<ActorPath> {
+        fn from_message(msg: NetMessage) -> Result<ActorPath, Box<UnpackError<NetMessage>>> {
+            Ok(msg.receiver)
+        }
+    }
+
+    impl Collected for Messages<Terminated> {
+        fn from_message(msg: NetMessage) -> Result<Terminated, Box<UnpackError<NetMessage>>> {
+            msg.try_deserialise::<Terminated, Terminated>()
+                .map_err(Box::new)
+        }
+    }
+
+    /// Collects `String` messages along with their senders
+    impl Collected for Messages<(ActorPath, String)> {
+        fn from_message(
+            msg: NetMessage,
+        ) -> Result<(ActorPath, String), Box<UnpackError<NetMessage>>> {
+            let sender = msg.sender.clone();
+            msg.try_deserialise::<String, String>()
+                .map(|content| (sender, content))
+                .map_err(Box::new)
+        }
+    }
+
+    /// A component which records everything it receives on its `port`, or extracts from network messages
+    #[derive(ComponentDefinition)]
+    pub struct Collector<P: Collected> {
+        ctx: ComponentContext<Self>,
+        /// Must be connected to a provider of `P`, unless everything arrives via network messages
+        pub port: RequiredPort<P>,
+        /// All indications received so far
+        pub received: Vec<P::Indication>,
+        /// Counts the indications received so far, for collectors which can't be accessed directly
+        count: Option<Arc<AtomicUsize>>,
+        notify_ready: Option<KPromise<()>>,
+        watched: Option<ActorPath>,
+    }
+
+    impl<P: Collected> Collector<P> {
+        /// Creates a new `Collector`
+        pub fn new() -> Self {
+            Collector {
+                ctx: ComponentContext::uninitialised(),
+                port: RequiredPort::uninitialised(),
+                received: Vec::new(),
+                count: None,
+                notify_ready: None,
+                watched: None,
+            }
+        }
+
+        /// Creates a new `Collector` adding every indication it receives to `count`,
+        /// which fulfils `notify_ready` once started
+        ///
+        /// This is meant for [system components](KompactConfig::system_components).
+        pub fn counting(count: Arc<AtomicUsize>, notify_ready: KPromise<()>) -> Self {
+            Collector {
+                count: Some(count),
+                notify_ready: Some(notify_ready),
+                ..Collector::new()
+            }
+        }
+
+        fn collect(&mut self, indication: P::Indication) -> Handled {
+            debug!(self.ctx.log(), "Got {:?}", indication);
+            if let Some(count) = &self.count {
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+            self.received.push(indication);
+            Handled::Ok
+        }
+    }
+
+    impl Collector<Messages<Terminated>> {
+        /// Creates a new `Collector` which watches `watched` and records the [Terminated] notifications it receives
+        pub fn watching(watched: ActorPath) -> Self {
+            Collector {
+                watched: Some(watched),
+                ..Collector::new()
+            }
+        }
+    }
+
+    impl<P: Collected> ComponentLifecycle for Collector<P> {
+        fn on_start(&mut self) -> Handled {
+            if let Some(watched) = &self.watched {
+                self.ctx.watch(watched);
+            }
+            if let Some(promise) = self.notify_ready.take() {
+                promise.fulfil(()).unwrap_or(())
+            }
+            Handled::Ok
+        }
+    }
+
+    impl<P: Collected> Require<P> for Collector<P> {
+        fn handle(&mut self, indication: P::Indication) -> Handled {
+            self.collect(indication)
+        }
+    }
+
+    impl<P: Collected> Actor for Collector<P> {
+        type Message = Never;
+
+        fn receive_local(&mut self, _msg: Self::Message) -> Handled {
+            unimplemented!();
+        }
+
+        fn receive_network(&mut self, msg: NetMessage) -> Handled {
+            match P::from_message(msg) {
+                Ok(indication) => self.collect(indication),
+                Err(e) => {
+                    error!(self.ctx.log(), "Not collecting message: {:?}", e);
+                    Handled::Ok
+                }
+            }
+        }
+    }
+
+    /// Records the reachability changes reported by the `NetworkDispatcher`
+    ///
+    /// Must be connected via [connect_reachability_port](KompactSystem::connect_reachability_port).
+    pub type ReachabilityWatcher = Collector<ReachabilityPort>;
+    /// Records the connection status changes reported by the `NetworkDispatcher`
+    ///
+    /// Must be connected via [connect_network_status_port](KompactSystem::connect_network_status_port).
+    pub type NetworkStatusWatcher = Collector<NetworkStatusPort>;
+    /// Records the membership changes reported by a `ClusterMembership` component
+    pub type ClusterWatcher = Collector<ClusterPort>;
+    /// Watches an actor and records the [Terminated] notifications it receives
+    pub type DeathWatcher = Collector<Messages<Terminated>>;
+    /// Records the `String` messages published to the topics it is subscribed to, along with their senders
+    pub type TopicSubscriber = Collector<Messages<(ActorPath, String)>>;
+    /// A deadletter box which counts the messages it receives
+    pub type CountingDeadletterBox = Collector<Messages<ActorPath>>;
+
+    /// An actor which replies to every `String` message with the same string
+    #[derive(ComponentDefinition)]
+    pub struct EchoAct {
+        ctx: ComponentContext<Self>,
+    }
+    impl EchoAct {
+        /// Creates a new `EchoAct`
+        pub fn new() -> Self {
+            EchoAct {
+                ctx: ComponentContext::uninitialised(),
+            }
+        }
+    }
+    ignore_lifecycle!(EchoAct);
+    impl Actor for EchoAct {
+        type Message = Never;
+
+        fn receive_local(&mut self, _msg: Self::Message) -> Handled {
+            unimplemented!();
+        }
+
+        fn receive_network(&mut self, msg: NetMessage) -> Handled {
+            let sender = msg.sender.clone();
+            match_deser! {msg; {
+                content: String [String] => {
+                    debug!(self.ctx.log(), "Echoing {} to {}", content, sender);
+                    sender.tell(content, self);
+                },
+                !Err(e) => error!(self.ctx.log(), "Error deserialising String: {:?}", e),
+            }}
+            Handled::Ok
+        }
+    }
 }
@@ -2,11 +2,30 @@ use super::*;
 use crate::{
     messaging::SerialisedFrame,
     net::{
-        buffers::{BufferChunk, DecodeBuffer},
-        frames::{Ack, Frame, FramingError, Hello, Start, FRAME_HEAD_LEN},
+        auth::Authenticator,
+        buffers::{BufferChunk, ChunkRef, DecodeBuffer},
+        compression::{compress_frame, Compression, CompressionStats},
+        failure_detector::PhiAccrualFailureDetector,
+        flow_control::{CreditNotifier, FrameCredit, ReleasedFrames},
+        frames::{
+            Ack,
+            CreditUpdate,
+            Fragment,
+            Frame,
+            FramingError,
+            Hello,
+            ProtocolVersions,
+            Start,
+            StreamRequest,
+            FEATURE_HEARTBEATS,
+            FEATURE_IDLE_CLOSE,
+            FEATURE_STREAMING,
+            FRAME_HEAD_LEN,
+        },
+        streaming::{OutgoingStream, StreamError, StreamReassembly},
     },
 };
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use mio::{net::TcpStream, Token};
 use network_thread::*;
 use std::{
@@ -16,9 +35,14 @@ use std::{
     io,
     io::{Error, ErrorKind, Read, Write},
     net::{Shutdown::Both, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 use uuid::Uuid;
 
+/// Credit value granted by hosts which have flow control disabled
+const UNLIMITED_CREDITS: u32 = u32::MAX;
+
 /// Received connection: Initialising -> Say Hello, Receive Start -> Connected, Send Ack
 /// Requested connection: Requested -> Receive Hello -> Initialised -> Send Start, Receive Ack -> Connected
 pub(crate) enum ChannelState {
@@ -34,39 +58,180 @@ pub(crate) enum ChannelState {
     Closed(SocketAddr, Uuid),
 }
 
+/// The queues [TcpChannel::try_drain] writes frames from
+#[derive(Clone, Copy, Debug)]
+enum Lane {
+    Control,
+    Priority,
+    Outbound,
+}
+
+/// A data frame held back until the remote host grants credits for it
+enum PendingFrame {
+    Frame(SerialisedFrame),
+    /// A streamed frame, whose remaining fragments are only created once there are credits for them
+    Stream(OutgoingStream),
+}
+
 pub(crate) struct TcpChannel {
     stream: TcpStream,
     outbound_queue: VecDeque<SerialisedFrame>,
+    /// Prioritised frames, sent ahead of the outbound queue whenever no frame in it is partially written
+    priority_queue: VecDeque<SerialisedFrame>,
+    /// Control frames such as credit updates and heartbeats, sent in the priority lane ahead of the prioritised frames
+    ///
+    /// Unlike data frames, they are never handed back to be sent on another channel.
+    control_queue: VecDeque<SerialisedFrame>,
+    /// Whether the frame at the front of the outbound queue has been partially written to the stream
+    outbound_partial: bool,
+    /// Whether the frame at the front of the priority queue has been partially written to the stream
+    priority_partial: bool,
     pub token: Token,
     input_buffer: DecodeBuffer,
     pub state: ChannelState,
     pub messages: u32,
     own_addr: SocketAddr,
+    /// The incarnation of our own system, sent in the Hello or Start
+    incarnation: Uuid,
+    /// The incarnation of the remote system, received in its Hello or Start
+    remote_incarnation: Option<Uuid>,
     nodelay: bool,
+    /// Frames held back until the remote host grants more credits
+    pending_queue: VecDeque<PendingFrame>,
+    /// Prioritised frames held back until the remote host grants more credits, they get the next credits
+    pending_priority_queue: VecDeque<SerialisedFrame>,
+    /// Data frames we may still send before running out of credits
+    send_credits: u32,
+    /// Data frames received and released since we last granted credits for them
+    released: Arc<ReleasedFrames>,
+    /// Data frames the remote host may still send before we grant it more credits
+    receive_credits: u32,
+    credit_window: u32,
+    /// The most credits the remote host granted at once, which bounds the pending queues
+    remote_window: u32,
+    blocked: bool,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    /// The challenge we issued to the remote host
+    challenge: Vec<u8>,
+    /// The challenge the remote host issued to us in its Start, answered in our Ack
+    remote_challenge: Vec<u8>,
+    /// The protocol version negotiated with the remote host
+    protocol_version: u8,
+    /// The optional protocol features supported by both hosts
+    protocol_features: u32,
+    failure_detector: PhiAccrualFailureDetector,
+    /// Whether anything was sent since the last heartbeat tick
+    sent_since_tick: bool,
+    /// Whether anything was received since the last heartbeat tick
+    received_since_tick: bool,
+    /// The codec to compress large data frames with, if the remote host supports it
+    compression: Option<Compression>,
+    /// Data frames with larger payloads than this are compressed
+    compression_threshold: usize,
+    /// How well the data frames compressed since the stats were last taken
+    compression_stats: CompressionStats,
+    /// Frames larger than this are streamed as fragments, if the remote host supports it
+    stream_threshold: usize,
+    /// The largest frame streamed to the remote host
+    max_streamed_size: usize,
+    /// The largest fragment streamed frames are split into
+    fragment_size: usize,
+    /// The id of the next frame streamed to the remote host
+    next_stream_id: u32,
+    /// The frames being streamed from the remote host
+    streams: StreamReassembly,
+    /// Whether the channel was accepted from the remote host, rather than requested by us
+    inbound: bool,
+    /// When a data frame was last sent or received on the channel
+    last_used: Instant,
+    /// Whether we asked the remote host to close the idle channel, and wait for its `IdleAck`
+    idle_requested: bool,
+    /// Whether we agreed to close the idle channel, and must not send any more data on it
+    idle_acked: bool,
+    /// The channel's share of the connection limit, given back once it is closed
+    slot: Option<ConnectionSlot>,
+    #[cfg(feature = "tls")]
+    tls: Option<rustls::Connection>,
 }
 
 impl TcpChannel {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
         token: Token,
         buffer_chunk: BufferChunk,
         state: ChannelState,
         own_addr: SocketAddr,
+        incarnation: Uuid,
         network_config: &NetworkConfig,
+        credit_notifier: Arc<CreditNotifier>,
     ) -> Self {
         let input_buffer = DecodeBuffer::new(buffer_chunk, network_config.get_buffer_config());
+        let credit_window = network_config.get_flow_control_window();
+        let inbound = matches!(state, ChannelState::Initialising);
         TcpChannel {
             stream,
             outbound_queue: VecDeque::new(),
+            priority_queue: VecDeque::new(),
+            control_queue: VecDeque::new(),
+            outbound_partial: false,
+            priority_partial: false,
             token,
             input_buffer,
             state,
             messages: 0,
             own_addr,
+            incarnation,
+            remote_incarnation: None,
             nodelay: network_config.get_tcp_nodelay(),
+            pending_queue: VecDeque::new(),
+            pending_priority_queue: VecDeque::new(),
+            send_credits: 0,
+            released: Arc::new(ReleasedFrames::new(credit_window / 2, credit_notifier)),
+            receive_credits: 0,
+            credit_window,
+            remote_window: 0,
+            blocked: false,
+            authenticator: network_config.get_authenticator().clone(),
+            challenge: Vec::new(),
+            remote_challenge: Vec::new(),
+            protocol_version: 0,
+            protocol_features: 0,
+            failure_detector: PhiAccrualFailureDetector::new(
+                Duration::from_millis(network_config.get_heartbeat_interval()),
+                Duration::from_millis(network_config.get_acceptable_heartbeat_pause()),
+            ),
+            sent_since_tick: false,
+            received_since_tick: false,
+            compression: network_config.get_compression(),
+            compression_threshold: network_config.get_compression_threshold(),
+            compression_stats: CompressionStats::default(),
+            stream_threshold: network_config.get_stream_threshold(),
+            max_streamed_size: network_config.get_max_streamed_size(),
+            fragment_size: network_config.get_buffer_config().chunk_size,
+            next_stream_id: 0,
+            streams: StreamReassembly::new(
+                network_config.get_max_streamed_size(),
+                network_config.get_max_partial_streams(),
+            ),
+            inbound,
+            last_used: Instant::now(),
+            idle_requested: false,
+            idle_acked: false,
+            slot: None,
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
 
+    /// Encrypts all further traffic on the channel with the TLS `session`.
+    ///
+    /// Must be called before anything is sent or received on the channel.
+    #[cfg(feature = "tls")]
+    pub fn wrap_tls(&mut self, session: rustls::Connection) -> () {
+        self.tls = Some(session);
+    }
+
     /// This is "network unsafe" to use. Please use the other interfaces for reading/writing.
     pub fn stream_mut(&mut self) -> &mut TcpStream {
         &mut self.stream
@@ -85,18 +250,105 @@ impl TcpChannel {
         matches!(self.state, ChannelState::Closed(_, _))
     }
 
+    /// Returns true if the channel has not completed the handshake yet
+    pub fn handshaking(&self) -> bool {
+        !self.connected() && !self.closed()
+    }
+
+    /// Returns true if the channel was accepted from the remote host, rather than requested by us
+    pub fn inbound(&self) -> bool {
+        self.inbound
+    }
+
+    /// Counts the channel towards the connection limit until it is closed
+    pub fn hold_slot(&mut self, slot: ConnectionSlot) -> () {
+        self.slot = Some(slot);
+    }
+
+    /// Returns true if the channel counts towards the connection limit
+    pub fn holds_slot(&self) -> bool {
+        self.slot.is_some()
+    }
+
+    /// Returns when the channel was last used, if it is connected and has no frames queued
+    pub fn idle_since(&self) -> Option<Instant> {
+        let queued = !self.outbound_queue.is_empty()
+            || !self.priority_queue.is_empty()
+            || !self.pending_queue.is_empty()
+            || !self.pending_priority_queue.is_empty();
+        if self.connected() && !queued {
+            Some(self.last_used)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the remote host must agree before the idle channel is closed
+    pub fn agrees_on_idle(&self) -> bool {
+        self.protocol_features & FEATURE_IDLE_CLOSE != 0
+    }
+
+    /// Returns true if either host asked to close the idle channel and it was not used since
+    pub fn closing_idle(&self) -> bool {
+        self.idle_requested || self.idle_acked
+    }
+
+    /// Returns true if we agreed to close the idle channel, and must not send any more data on it
+    pub fn idle_acked(&self) -> bool {
+        self.idle_acked
+    }
+
+    /// Asks the remote host to close the idle channel, which it agrees to with an `IdleAck`
+    ///
+    /// Must only be called while the channel is [idle](TcpChannel::idle_since),
+    /// and the remote host [agrees on idle channels](TcpChannel::agrees_on_idle).
+    pub fn request_idle_close(&mut self) -> () {
+        self.idle_requested = true;
+        self.send_frame(Frame::Idle());
+    }
+
+    /// Agrees to close the channel after the remote host asked to, unless we have frames queued for it
+    ///
+    /// Once agreed, the remote host says Bye after everything it sent so far, and we must not send
+    /// any more data frames. Returns true if we agreed.
+    pub fn ack_idle_close(&mut self) -> bool {
+        if self.idle_acked || self.idle_since().is_none() {
+            return false;
+        }
+        self.idle_acked = true;
+        self.send_frame(Frame::IdleAck());
+        true
+    }
+
+    /// Says Bye on the idle channel, once the remote host agreed to close it or if it does not have to agree
+    ///
+    /// Returns the frames which were queued but not sent, which must be sent on a new channel.
+    pub fn close_idle(&mut self) -> Vec<SerialisedFrame> {
+        let unsent = self.take_outbound();
+        self.send_frame(Frame::Bye());
+        // Whatever could not be written is our own Bye, which must not be sent on a new channel
+        self.outbound_queue.clear();
+        unsent
+    }
+
     /// Internal helper function for special frames
-    fn send_frame(&mut self, mut frame: Frame) -> () {
-        let len = frame.encoded_len() + FRAME_HEAD_LEN as usize;
-        let mut bytes = BytesMut::with_capacity(len);
-        bytes.truncate(len);
-        if let Ok(()) = frame.encode_into(&mut bytes) {
-            self.outbound_queue
-                .push_back(SerialisedFrame::Bytes(bytes.freeze()));
-            // If there is a fatal error during a handshake the connection will be re-attempted
-            let _ = self.try_drain();
+    fn send_frame(&mut self, frame: Frame) -> () {
+        let frame = encode_frame(frame);
+        self.outbound_queue.push_back(frame);
+        // If there is a fatal error during a handshake the connection will be re-attempted
+        let _ = self.try_drain();
+    }
+
+    /// Like [send_frame](TcpChannel::send_frame), but sends the frame in the priority lane once connected,
+    /// such that the control frames do not wait for the data frames they govern
+    fn send_control_frame(&mut self, frame: Frame) -> () {
+        if self.handshaking() {
+            // Keep the order of the handshake
+            self.send_frame(frame);
         } else {
-            panic!("Failed to encode bytes for Frame {:?}", frame.frame_type());
+            let frame = encode_frame(frame);
+            self.control_queue.push_back(frame);
+            let _ = self.try_drain();
         }
     }
 
@@ -112,7 +364,13 @@ impl TcpChannel {
     pub fn initialise(&mut self, addr: &SocketAddr) -> () {
         if let ChannelState::Initialising = self.state {
             // We must send enqueue Hello and await reply
-            let hello = Frame::Hello(Hello::new(*addr));
+            self.challenge = self.new_challenge();
+            let hello = Frame::Hello(Hello::new(
+                ProtocolVersions::local(),
+                *addr,
+                self.incarnation,
+                self.challenge.clone(),
+            ));
             self.send_frame(hello);
         }
     }
@@ -121,26 +379,95 @@ impl TcpChannel {
     pub fn handle_hello(&mut self, hello: Hello) -> () {
         if let ChannelState::Requested(_, id) = self.state {
             // Has now received Hello(addr), must send Start(addr, uuid) and await ack
-            let start = Frame::Start(Start::new(self.own_addr, id));
+            let response = self.respond(&hello.challenge);
+            self.challenge = self.new_challenge();
+            let start = Frame::Start(Start::new(
+                ProtocolVersions::local(),
+                self.own_addr,
+                id,
+                self.incarnation,
+                response,
+                self.challenge.clone(),
+            ));
             self.send_frame(start);
+            self.grant_initial_credits();
+            self.remote_incarnation = Some(hello.incarnation);
             self.state = ChannelState::Initialised(hello.addr, id);
         }
     }
 
     /// Must be called when we Ack the channel. This means that the sender can start using the channel
     /// The receiver of the Ack must accept the Ack and use the channel.
-    pub fn handle_start(&mut self, addr: &SocketAddr, id: Uuid) -> () {
+    pub fn handle_start(&mut self, addr: &SocketAddr, id: Uuid, incarnation: Uuid) -> () {
         if let ChannelState::Initialising = self.state {
             // Method called because we received Start and want to send Ack.
-            let ack = Frame::Ack(Ack { offset: 0 }); // we don't use offsets yet.
+            let response = self.respond(&self.remote_challenge);
+            let ack = Frame::Ack(Ack::new(0, response)); // we don't use offsets yet.
             self.stream
                 .set_nodelay(self.nodelay)
                 .expect("set nodelay failed");
             self.send_frame(ack);
+            self.grant_initial_credits();
+            self.remote_incarnation = Some(incarnation);
             self.state = ChannelState::Connected(*addr, id);
         }
     }
 
+    /// The incarnation the remote system sent during the handshake, if it got that far
+    pub fn remote_incarnation(&self) -> Option<Uuid> {
+        self.remote_incarnation
+    }
+
+    /// Must be called with the versions received in the Hello or Start frame, before it is handled.
+    ///
+    /// Returns false if the remote host does not speak any protocol version we do.
+    pub fn negotiate_versions(&mut self, remote: &ProtocolVersions) -> bool {
+        match ProtocolVersions::local().negotiate(remote) {
+            Some((version, features)) => {
+                self.protocol_version = version;
+                self.protocol_features = features;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Must be called when a Start frame is received on the channel, before it is handled.
+    ///
+    /// Returns false if the remote host failed to answer our challenge.
+    pub fn authenticate_start(&mut self, start: &Start) -> bool {
+        self.remote_challenge = start.challenge.clone();
+        self.verify(&start.response)
+    }
+
+    /// Must be called when an Ack frame is received on the channel, before it is handled.
+    ///
+    /// Returns false if the remote host failed to answer our challenge.
+    pub fn authenticate_ack(&self, ack: &Ack) -> bool {
+        self.verify(&ack.response)
+    }
+
+    fn new_challenge(&self) -> Vec<u8> {
+        match &self.authenticator {
+            Some(authenticator) => authenticator.challenge(),
+            None => Vec::new(),
+        }
+    }
+
+    fn respond(&self, challenge: &[u8]) -> Vec<u8> {
+        match &self.authenticator {
+            Some(authenticator) => authenticator.respond(challenge),
+            None => Vec::new(),
+        }
+    }
+
+    fn verify(&self, response: &[u8]) -> bool {
+        match &self.authenticator {
+            Some(authenticator) => authenticator.verify(&self.challenge, response),
+            None => true,
+        }
+    }
+
     /// Returns true if it transitioned, false if it's not starting.
     pub fn handle_ack(&mut self) -> bool {
         if let ChannelState::Initialised(addr, id) = self.state {
@@ -157,15 +484,134 @@ impl TcpChannel {
         }
     }
 
+    /// Must be called once every heartbeat interval while the channel is connected.
+    ///
+    /// Sends a heartbeat if nothing else was sent since the last call, and feeds the failure detector.
+    /// Any traffic received since the last call counts as a heartbeat of the remote host.
+    /// Returns the suspicion that the remote host has failed,
+    /// or `None` if the remote host does not send heartbeats.
+    pub fn heartbeat(&mut self, now: Instant) -> Option<f64> {
+        if self.protocol_features & FEATURE_HEARTBEATS == 0 {
+            return None;
+        }
+        if !self.sent_since_tick {
+            self.send_control_frame(Frame::Heartbeat());
+        }
+        self.sent_since_tick = false;
+        if self.received_since_tick {
+            self.failure_detector.heartbeat(now);
+            self.received_since_tick = false;
+        }
+        Some(self.failure_detector.phi(now))
+    }
+
+    /// Grants the remote host its initial window of credits, sent along with `Start` and `Ack`.
+    fn grant_initial_credits(&mut self) -> () {
+        let credit = if self.credit_window == 0 {
+            UNLIMITED_CREDITS
+        } else {
+            self.credit_window
+        };
+        self.receive_credits = self.credit_window;
+        self.send_control_frame(Frame::CreditUpdate(CreditUpdate::new(credit)));
+    }
+
+    /// Must be called when a CreditUpdate frame is received on the channel.
+    ///
+    /// Moves as many pending frames as the new credits allow to the outbound queues, prioritised frames first.
+    /// Returns true if the channel was blocked and has now caught up with its pending frames.
+    pub fn handle_credit_update(&mut self, credit_update: CreditUpdate) -> bool {
+        self.remote_window = self.remote_window.max(credit_update.credit);
+        if credit_update.credit == UNLIMITED_CREDITS || self.send_credits == UNLIMITED_CREDITS {
+            self.send_credits = UNLIMITED_CREDITS;
+        } else {
+            self.send_credits = self
+                .send_credits
+                .saturating_add(credit_update.credit)
+                .min(UNLIMITED_CREDITS - 1);
+        }
+        while self.send_credits > 0 {
+            if let Some(frame) = self.pending_priority_queue.pop_front() {
+                self.consume_credit();
+                self.push_prioritised(frame);
+            } else if let Some(frame) = self.pop_pending() {
+                self.consume_credit();
+                self.push_outbound(frame);
+            } else {
+                break;
+            }
+        }
+        if self.blocked && self.pending_priority_queue.is_empty() && self.pending_queue.is_empty() {
+            self.blocked = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Must be called when a StreamRequest frame is received on the channel.
+    ///
+    /// The remote host has run out of credits, hand it the ones of the frames released so far.
+    pub fn handle_stream_request(&mut self, _request: StreamRequest) -> () {
+        self.grant_credits(1);
+    }
+
+    /// Grants the remote host the credits of the received data frames which were released,
+    /// once they make up half the window.
+    pub fn grant_released_credits(&mut self) -> () {
+        self.grant_credits(self.released.threshold());
+    }
+
+    fn grant_credits(&mut self, min: u32) -> () {
+        let credits = self.released.take(min);
+        if credits > 0 {
+            self.receive_credits = self.receive_credits.saturating_add(credits);
+            self.send_control_frame(Frame::CreditUpdate(CreditUpdate::new(credits)));
+        }
+    }
+
+    fn consume_credit(&mut self) -> () {
+        if self.send_credits != UNLIMITED_CREDITS {
+            self.send_credits -= 1;
+        }
+    }
+
+    /// Returns true if the pending queues hold as many frames as the remote host ever granted credits for at once
+    ///
+    /// A streamed frame counts once, as its fragments are only created once they can be sent.
+    /// Frames sent to a full channel must be handed back to the `NetworkDispatcher`,
+    /// which holds on to them until the channel has caught up.
+    pub fn pending_full(&self) -> bool {
+        let pending = self.pending_priority_queue.len() + self.pending_queue.len();
+        pending >= self.remote_window.max(1) as usize
+    }
+
+    /// Returns true if the channel is holding back frames waiting for credits
+    #[allow(dead_code)]
+    pub fn blocked(&self) -> bool {
+        self.blocked
+    }
+
     pub fn swap_buffer(&mut self, new_buffer: &mut BufferChunk) -> () {
         self.input_buffer.swap_buffer(new_buffer);
     }
 
     pub fn take_outbound(&mut self) -> Vec<SerialisedFrame> {
         let mut ret = Vec::new();
+        while let Some(frame) = self.priority_queue.pop_front() {
+            ret.push(frame);
+        }
         while let Some(frame) = self.outbound_queue.pop_front() {
             ret.push(frame);
         }
+        while let Some(frame) = self.pending_priority_queue.pop_front() {
+            ret.push(frame);
+        }
+        while let Some(frame) = self.pop_pending() {
+            ret.push(frame);
+        }
+        self.outbound_partial = false;
+        self.priority_partial = false;
         ret
     }
 
@@ -180,14 +626,22 @@ impl TcpChannel {
                 self.input_buffer.advance_writeable(read_bytes);
                 read_bytes = 0;
             }
-            if let Some(mut buf) = self.input_buffer.get_writeable() {
-                match self.stream.read(&mut buf) {
+            if let Some(buf) = self.input_buffer.get_writeable() {
+                #[cfg(feature = "tls")]
+                let read = match self.tls.as_mut() {
+                    Some(session) => super::tls::read_tls(session, &mut self.stream, buf),
+                    None => self.stream.read(buf),
+                };
+                #[cfg(not(feature = "tls"))]
+                let read = self.stream.read(buf);
+                match read {
                     Ok(0) => {
                         return Ok(sum_read_bytes);
                     }
                     Ok(n) => {
                         sum_read_bytes += n;
                         read_bytes = n;
+                        self.received_since_tick = true;
                         // continue looping and reading
                     }
                     Err(err) if would_block(&err) => {
@@ -219,6 +673,12 @@ impl TcpChannel {
         if let Ok(()) = bye.encode_into(&mut bye_bytes) {
             self.outbound_queue
                 .push_back(SerialisedFrame::Bytes(bye_bytes.freeze()));
+            #[cfg(feature = "tls")]
+            {
+                if let Some(session) = self.tls.as_mut() {
+                    session.send_close_notify();
+                }
+            }
             let _ = self.try_drain(); // Try to drain outgoing
             let _ = self.receive(); // Try to drain incoming
         } else {
@@ -231,6 +691,7 @@ impl TcpChannel {
     /// `true` means that it can safely be dropped.
     pub fn shutdown(&mut self) -> bool {
         let _ = self.stream.shutdown(Both); // Discard errors while closing channels for now...
+        self.slot = None;
         match self.state {
             ChannelState::Connected(addr, id) => {
                 self.state = ChannelState::Closed(addr, id);
@@ -251,40 +712,253 @@ impl TcpChannel {
         }
     }
 
+    /// Must be called when a data carrying frame is received on the channel.
+    ///
+    /// Consumes one of the credits granted to the remote host, which is returned as a [FrameCredit]
+    /// to be kept along with the data of the frame. The remote host is only granted the credit again
+    /// once it is dropped, see [grant_released_credits](TcpChannel::grant_released_credits).
+    /// Returns the reason the frame must be refused if the remote host had no credits left,
+    /// or has not completed the handshake.
+    /// Channels we are closing completed the handshake, so their remote hosts may still send data.
+    pub fn receive_data(&mut self) -> Result<Option<FrameCredit>, &'static str> {
+        if self.handshaking() {
+            return Err("the remote host sent data before completing the handshake");
+        }
+        self.last_used = Instant::now();
+        // The remote host is still using the channel, so it may not agree to close it
+        self.idle_requested = false;
+        if self.credit_window == 0 {
+            // Unlimited credits, there is nothing to count
+            return Ok(None);
+        }
+        if self.receive_credits == 0 {
+            return Err("the remote host sent data frames without credits");
+        }
+        self.receive_credits -= 1;
+        Ok(Some(FrameCredit::new(self.released.clone())))
+    }
+
     /// Enqueues the frame for sending on the channel.
     /// Enquing to a non-connected channel is disallowed.
-    pub fn enqueue_serialised(&mut self, serialized: SerialisedFrame) -> () {
-        self.outbound_queue.push_back(serialized);
+    ///
+    /// If the remote host has not granted enough credits the frame is held back until it does.
+    /// Returns true if the channel became blocked by this frame.
+    pub fn enqueue_serialised(&mut self, serialized: SerialisedFrame) -> bool {
+        self.last_used = Instant::now();
+        if self.send_credits > 0 && self.pending_queue.is_empty() {
+            self.consume_credit();
+            self.push_outbound(serialized);
+            false
+        } else {
+            self.pending_queue
+                .push_back(PendingFrame::Frame(serialized));
+            self.block()
+        }
+    }
+
+    /// Enqueues the frame for sending on the channel, ahead of the frames enqueued with
+    /// [enqueue_serialised](TcpChannel::enqueue_serialised).
+    /// Enquing to a non-connected channel is disallowed.
+    ///
+    /// Prioritised frames consume credits like any other data frame, but get the credits before the others.
+    /// Returns true if the channel became blocked by this frame.
+    pub fn enqueue_prioritised(&mut self, serialized: SerialisedFrame) -> bool {
+        self.last_used = Instant::now();
+        if self.send_credits > 0 && self.pending_priority_queue.is_empty() {
+            self.consume_credit();
+            self.push_prioritised(serialized);
+            false
+        } else {
+            self.pending_priority_queue.push_back(serialized);
+            self.block()
+        }
+    }
+
+    /// Asks the remote host for more credits, unless the channel is already blocked.
+    ///
+    /// Returns true if the channel became blocked.
+    fn block(&mut self) -> bool {
+        if self.blocked {
+            false
+        } else {
+            self.blocked = true;
+            let pending = self.pending_priority_queue.len() + self.pending_queue.len();
+            self.send_control_frame(Frame::StreamRequest(StreamRequest::new(pending as u32)));
+            true
+        }
+    }
+
+    /// Returns true if a frame of `size` bytes should be streamed to the remote host
+    pub fn streams(&self, size: usize) -> bool {
+        size > self.stream_threshold && self.protocol_features & FEATURE_STREAMING != 0
+    }
+
+    /// Returns true if a frame with the given size hint may have to be streamed to the remote host
+    ///
+    /// Frames without a size hint must be serialised to find out.
+    pub fn may_stream(&self, size_hint: Option<usize>) -> bool {
+        match size_hint {
+            Some(size) => self.streams(size),
+            None => self.protocol_features & FEATURE_STREAMING != 0,
+        }
+    }
+
+    /// Splits the serialised data `frame` into fragments and enqueues them for sending on the channel.
+    ///
+    /// The frame is compressed as a whole first, if the channel compresses data frames.
+    /// Each fragment consumes a credit of its own, fragments without credits are only created once they arrive.
+    /// Returns true if the channel became blocked by the fragments,
+    /// or `None` if the frame is too large to be streamed.
+    pub fn enqueue_streamed(&mut self, frame: Bytes) -> Option<bool> {
+        if frame.len() > self.max_streamed_size {
+            return None;
+        }
+        let frame = match self.compress(SerialisedFrame::Bytes(frame)) {
+            SerialisedFrame::Bytes(frame) => frame,
+            // Compressing a frame never changes how it is stored
+            other => Bytes::copy_from_slice(other.bytes()),
+        };
+        let mut stream = OutgoingStream::new(frame, self.next_stream_id, self.fragment_size)?;
+        self.next_stream_id = self.next_stream_id.wrapping_add(1);
+        self.last_used = Instant::now();
+        if self.pending_queue.is_empty() {
+            while self.send_credits > 0 {
+                match stream.next() {
+                    Some(fragment) => {
+                        self.consume_credit();
+                        self.outbound_queue
+                            .push_back(SerialisedFrame::Bytes(fragment));
+                    }
+                    None => return Some(false),
+                }
+            }
+        }
+        if stream.remaining() == 0 {
+            return Some(false);
+        }
+        self.pending_queue.push_back(PendingFrame::Stream(stream));
+        Some(self.block())
+    }
+
+    /// Removes the next frame from the pending queue, creating it first if it is the fragment of a streamed frame
+    fn pop_pending(&mut self) -> Option<SerialisedFrame> {
+        match self.pending_queue.pop_front()? {
+            PendingFrame::Frame(frame) => Some(frame),
+            PendingFrame::Stream(mut stream) => {
+                let fragment = stream.next();
+                if stream.remaining() > 0 {
+                    self.pending_queue.push_front(PendingFrame::Stream(stream));
+                }
+                fragment.map(SerialisedFrame::Bytes)
+            }
+        }
+    }
+
+    /// Must be called when a Fragment frame is received on the channel.
+    ///
+    /// Returns the streamed frame once all of its fragments have arrived, along with the chunk backing it,
+    /// which must not be dropped before it is [free](BufferChunk::free) again.
+    pub fn handle_fragment(
+        &mut self,
+        fragment: Fragment,
+    ) -> Result<Option<(ChunkRef, BufferChunk)>, StreamError> {
+        self.streams.insert(fragment)
+    }
+
+    /// Queues the data `frame` for sending, compressed if it is large enough and the remote host supports it
+    fn push_outbound(&mut self, frame: SerialisedFrame) -> () {
+        let frame = self.compress(frame);
+        self.outbound_queue.push_back(frame);
+    }
+
+    /// Like [push_outbound](TcpChannel::push_outbound), but queues the frame in the priority lane
+    fn push_prioritised(&mut self, frame: SerialisedFrame) -> () {
+        let frame = self.compress(frame);
+        self.priority_queue.push_back(frame);
+    }
+
+    fn compress(&mut self, frame: SerialisedFrame) -> SerialisedFrame {
+        match self.compression {
+            Some(codec) if self.protocol_features & codec.feature() != 0 => {
+                let (frame, sizes) = compress_frame(codec, self.compression_threshold, frame);
+                if let Some((original, sent)) = sizes {
+                    self.compression_stats.original_bytes += original as u64;
+                    self.compression_stats.compressed_bytes += sent as u64;
+                }
+                frame
+            }
+            _ => frame,
+        }
+    }
+
+    /// Returns how well the data frames compressed since the last call,
+    /// or `None` if no frames were large enough to be compressed
+    pub fn take_compression_stats(&mut self) -> Option<CompressionStats> {
+        if self.compression_stats.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.compression_stats))
+        }
     }
 
     /// Tries to drain the outbound buffer into
     pub fn try_drain(&mut self) -> io::Result<usize> {
         let mut sent_bytes: usize = 0;
         let mut interrupts = 0;
-        while let Some(mut serialized_frame) = self.outbound_queue.pop_front() {
+        #[cfg(feature = "tls")]
+        {
+            // Flush records left over from the handshake or earlier writes first
+            if let Some(session) = self.tls.as_mut() {
+                super::tls::flush_tls(session, &mut self.stream)?;
+            }
+        }
+        // Frames in the priority lane may only overtake the outbound queue at frame boundaries
+        loop {
+            let lane = if self.outbound_partial {
+                Lane::Outbound
+            } else if self.priority_partial {
+                Lane::Priority
+            } else if !self.control_queue.is_empty() {
+                Lane::Control
+            } else if !self.priority_queue.is_empty() {
+                Lane::Priority
+            } else {
+                Lane::Outbound
+            };
+            let mut serialized_frame = match self.queue(lane).pop_front() {
+                Some(frame) => frame,
+                None => break,
+            };
             match self.write_serialized(&serialized_frame) {
                 Ok(n) => {
                     sent_bytes += n;
-                    match &mut serialized_frame {
-                        // Split the data and continue sending the rest later if we sent less than the full frame
+                    self.sent_since_tick |= n > 0;
+                    // Split the data and continue sending the rest later if we sent less than the full frame
+                    let partial = match &mut serialized_frame {
                         SerialisedFrame::Bytes(bytes) => {
-                            if n < bytes.len() {
-                                let _ = bytes.split_to(n); // Discard the already sent split off part.
-                                self.outbound_queue.push_front(serialized_frame);
-                            }
+                            let partial = n < bytes.len();
+                            let _ = bytes.split_to(n); // Discard the already sent split off part.
+                            partial
                         }
                         SerialisedFrame::ChunkLease(chunk) => {
-                            if n < chunk.remaining() {
-                                chunk.advance(n);
-                                self.outbound_queue.push_front(serialized_frame);
-                            }
+                            let partial = n < chunk.remaining();
+                            chunk.advance(n);
+                            partial
                         }
                         SerialisedFrame::ChunkRef(chunk) => {
-                            if n < chunk.remaining() {
-                                chunk.advance(n);
-                                self.outbound_queue.push_front(serialized_frame);
-                            }
+                            let partial = n < chunk.remaining();
+                            chunk.advance(n);
+                            partial
                         }
+                    };
+                    if partial {
+                        self.requeue(lane, serialized_frame);
+                    }
+                    match lane {
+                        Lane::Outbound => self.outbound_partial = partial,
+                        Lane::Priority => self.priority_partial = partial,
+                        // Control frames come first anyway
+                        Lane::Control => (),
                     }
                     // Continue looping for the next message
                 }
@@ -292,12 +966,12 @@ impl TcpChannel {
                 // connection is not actually ready to perform this I/O operation.
                 Err(ref err) if would_block(err) => {
                     // re-insert the data at the front of the buffer and return
-                    self.outbound_queue.push_front(serialized_frame);
+                    self.requeue(lane, serialized_frame);
                     return Ok(sent_bytes);
                 }
                 Err(err) if interrupted(&err) => {
                     // re-insert the data at the front of the buffer
-                    self.outbound_queue.push_front(serialized_frame);
+                    self.requeue(lane, serialized_frame);
                     interrupts += 1;
                     if interrupts >= MAX_INTERRUPTS {
                         return Err(err);
@@ -305,7 +979,7 @@ impl TcpChannel {
                 }
                 // Other errors we'll consider fatal.
                 Err(err) => {
-                    self.outbound_queue.push_front(serialized_frame);
+                    self.requeue(lane, serialized_frame);
                     return Err(err);
                 }
             }
@@ -313,28 +987,70 @@ impl TcpChannel {
         Ok(sent_bytes)
     }
 
+    fn queue(&mut self, lane: Lane) -> &mut VecDeque<SerialisedFrame> {
+        match lane {
+            Lane::Control => &mut self.control_queue,
+            Lane::Priority => &mut self.priority_queue,
+            Lane::Outbound => &mut self.outbound_queue,
+        }
+    }
+
+    /// Puts a frame which was not (completely) written back at the front of its queue
+    fn requeue(&mut self, lane: Lane, frame: SerialisedFrame) -> () {
+        self.queue(lane).push_front(frame);
+    }
+
     /// No direct writing allowed, Must use other interface.
     fn write_serialized(&mut self, serialized: &SerialisedFrame) -> io::Result<usize> {
         match serialized {
-            SerialisedFrame::ChunkLease(chunk) => self.stream.write(chunk.chunk()),
-            SerialisedFrame::Bytes(bytes) => self.stream.write(bytes.chunk()),
-            SerialisedFrame::ChunkRef(chunkref) => self.stream.write(chunkref.chunk()),
+            SerialisedFrame::ChunkLease(chunk) => self.write_stream(chunk.chunk()),
+            SerialisedFrame::Bytes(bytes) => self.write_stream(bytes.chunk()),
+            SerialisedFrame::ChunkRef(chunkref) => self.write_stream(chunkref.chunk()),
         }
     }
 
+    /// Writes to the stream, through the TLS session if there is one.
+    fn write_stream(&mut self, buf: &[u8]) -> io::Result<usize> {
+        #[cfg(feature = "tls")]
+        {
+            if let Some(session) = self.tls.as_mut() {
+                return super::tls::write_tls(session, &mut self.stream, buf);
+            }
+        }
+        self.stream.write(buf)
+    }
+
     /// Destroys the channel and returns the Buffer
     pub(crate) fn destroy(self) -> BufferChunk {
         self.input_buffer.destroy()
     }
 }
 
+/// Encodes a frame which is not backed by a pooled buffer, such as a handshake or control frame
+fn encode_frame(mut frame: Frame) -> SerialisedFrame {
+    let len = frame.encoded_len() + FRAME_HEAD_LEN as usize;
+    let mut bytes = BytesMut::with_capacity(len);
+    bytes.truncate(len);
+    if let Ok(()) = frame.encode_into(&mut bytes) {
+        SerialisedFrame::Bytes(bytes.freeze())
+    } else {
+        panic!("Failed to encode bytes for Frame {:?}", frame.frame_type());
+    }
+}
+
 impl std::fmt::Debug for TcpChannel {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TcpChannel")
             .field("State", &self.state)
             .field("Messages", &self.messages)
+            .field("Protocol Version", &self.protocol_version)
+            .field("Protocol Features", &self.protocol_features)
             .field("Decode Buffer", &self.input_buffer)
             .field("Outbound Queue", &self.outbound_queue.len())
+            .field("Priority Queue", &self.priority_queue.len())
+            .field("Control Queue", &self.control_queue.len())
+            .field("Pending Queue", &self.pending_queue.len())
+            .field("Send Credits", &self.send_credits)
             .finish()
     }
 }
@@ -408,3 +1124,179 @@ impl Ord for SocketWrapper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::NetworkConfig;
+    use std::net::{TcpListener, TcpStream as StdTcpStream};
+
+    fn connected_channel() -> (TcpChannel, StdTcpStream) {
+        channel_with_credits(UNLIMITED_CREDITS)
+    }
+
+    fn channel_with_credits(credits: u32) -> (TcpChannel, StdTcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("listener");
+        let addr = listener.local_addr().expect("listener address");
+        let stream = StdTcpStream::connect(addr).expect("connection");
+        let (receiver, _) = listener.accept().expect("accepted connection");
+        stream.set_nonblocking(true).expect("non-blocking stream");
+        let network_config = NetworkConfig::default();
+        let mut channel = TcpChannel::new(
+            TcpStream::from_std(stream),
+            Token(0),
+            BufferChunk::new(network_config.get_buffer_config().chunk_size),
+            ChannelState::Connected(addr, Uuid::new_v4()),
+            addr,
+            Uuid::new_v4(),
+            &network_config,
+            Arc::new(CreditNotifier::default()),
+        );
+        channel.handle_credit_update(CreditUpdate::new(credits));
+        (channel, receiver)
+    }
+
+    #[test]
+    fn prioritised_frames_overtake_outbound_frames() {
+        let (mut channel, mut receiver) = connected_channel();
+        // Large enough to fill the socket buffers, so the first frame is only partially written
+        let first = vec![1u8; 32 * 1024 * 1024];
+        let second = vec![2u8; 1024];
+        let prioritised = vec![3u8; 16];
+        assert!(!channel.enqueue_serialised(SerialisedFrame::Bytes(Bytes::from(first.clone()))));
+        assert!(!channel.enqueue_serialised(SerialisedFrame::Bytes(Bytes::from(second.clone()))));
+        channel.try_drain().expect("drained");
+        assert!(channel.outbound_partial);
+        assert!(
+            !channel.enqueue_prioritised(SerialisedFrame::Bytes(Bytes::from(prioritised.clone())))
+        );
+
+        let expected: Vec<u8> = [first, prioritised, second].concat();
+        let mut received = vec![0u8; expected.len()];
+        let mut read = 0;
+        while read < received.len() {
+            channel.try_drain().expect("drained");
+            read += receiver.read(&mut received[read..]).expect("read");
+        }
+        // The prioritised frame waits for the partially written frame, but not for the one after it
+        assert!(received == expected);
+        assert!(!channel.outbound_partial);
+    }
+
+    #[test]
+    fn control_frames_overtake_outbound_frames() {
+        let (mut channel, mut receiver) = connected_channel();
+        let first = vec![1u8; 32 * 1024 * 1024];
+        let second = vec![2u8; 1024];
+        assert!(!channel.enqueue_serialised(SerialisedFrame::Bytes(Bytes::from(first.clone()))));
+        assert!(!channel.enqueue_serialised(SerialisedFrame::Bytes(Bytes::from(second.clone()))));
+        channel.try_drain().expect("drained");
+        assert!(channel.outbound_partial);
+        channel.grant_initial_credits();
+        let credit_update = CreditUpdate::new(channel.credit_window);
+        let credit_update = match encode_frame(Frame::CreditUpdate(credit_update)) {
+            SerialisedFrame::Bytes(bytes) => bytes.to_vec(),
+            _ => unreachable!("Control frames are encoded into Bytes"),
+        };
+
+        let expected: Vec<u8> = [first, credit_update, second].concat();
+        let mut received = vec![0u8; expected.len()];
+        let mut read = 0;
+        while read < received.len() {
+            channel.try_drain().expect("drained");
+            read += receiver.read(&mut received[read..]).expect("read");
+        }
+        // The credit update waits for the partially written frame, but not for the one after it
+        assert!(received == expected);
+    }
+
+    #[test]
+    fn control_frames_are_not_handed_back() {
+        let (mut channel, _receiver) = connected_channel();
+        let first = vec![1u8; 32 * 1024 * 1024];
+        assert!(!channel.enqueue_serialised(SerialisedFrame::Bytes(Bytes::from(first))));
+        channel.try_drain().expect("drained");
+        channel.grant_initial_credits();
+        // Only data frames may be sent on another channel
+        assert_eq!(1, channel.take_outbound().len());
+        assert_eq!(1, channel.control_queue.len());
+    }
+
+    #[test]
+    fn channels_with_queued_frames_are_not_idle() {
+        let (mut channel, mut receiver) = connected_channel();
+        let idle_since = channel.idle_since().expect("idle channel");
+        assert!(!channel.inbound());
+        assert!(!channel.enqueue_serialised(SerialisedFrame::Bytes(Bytes::from(vec![1u8; 16]))));
+        assert!(channel.idle_since().is_none());
+
+        channel.try_drain().expect("drained");
+        let mut received = [0u8; 16];
+        receiver.read_exact(&mut received).expect("read");
+        assert!(channel.idle_since().expect("idle channel") >= idle_since);
+    }
+
+    #[test]
+    fn data_without_credits_is_refused() {
+        let (mut channel, _receiver) = connected_channel();
+        channel.credit_window = 4;
+        channel.released = Arc::new(ReleasedFrames::new(2, Arc::new(CreditNotifier::default())));
+        assert!(channel.receive_data().is_err());
+
+        channel.grant_initial_credits();
+        let held: Vec<_> = (0..4)
+            .map(|_| channel.receive_data().expect("credit"))
+            .collect();
+        // The credits are only granted again once the frames were released
+        channel.grant_released_credits();
+        assert!(channel.receive_data().is_err());
+        drop(held);
+        channel.grant_released_credits();
+        assert_eq!(4, channel.receive_credits);
+        for _ in 0..12 {
+            assert!(channel.receive_data().is_ok());
+            channel.grant_released_credits();
+        }
+        // Released credits are granted again every half window
+        assert!(channel.receive_credits >= 2);
+    }
+
+    #[test]
+    fn pending_queues_are_bounded_by_the_remote_window() {
+        let (mut channel, _receiver) = channel_with_credits(2);
+        let frame = || SerialisedFrame::Bytes(Bytes::from(vec![1u8; 16]));
+        assert!(!channel.enqueue_serialised(frame()));
+        assert!(!channel.enqueue_serialised(frame()));
+        assert!(channel.enqueue_serialised(frame()));
+        assert!(!channel.pending_full());
+        assert!(!channel.enqueue_prioritised(frame()));
+        assert!(channel.pending_full());
+
+        assert!(channel.handle_credit_update(CreditUpdate::new(2)));
+        assert!(!channel.pending_full());
+    }
+
+    #[test]
+    fn streamed_fragments_are_created_as_credits_arrive() {
+        let (mut channel, _receiver) = channel_with_credits(2);
+        channel.fragment_size = 128;
+        let remaining = |channel: &TcpChannel| match channel.pending_queue.front() {
+            Some(PendingFrame::Stream(stream)) => stream.remaining(),
+            _ => 0,
+        };
+        // Split into 10 fragments
+        let frame = Bytes::from(vec![1u8; 1000]);
+        assert_eq!(Some(true), channel.enqueue_streamed(frame));
+        // The fragments without credits are held back as a single pending frame
+        assert_eq!(1, channel.pending_queue.len());
+        assert_eq!(8, remaining(&channel));
+
+        assert!(!channel.handle_credit_update(CreditUpdate::new(3)));
+        assert_eq!(1, channel.pending_queue.len());
+        assert_eq!(5, remaining(&channel));
+        assert!(!channel.pending_full());
+
+        assert!(channel.handle_credit_update(CreditUpdate::new(100)));
+        assert!(channel.pending_queue.is_empty());
+    }
+}
@@ -1,28 +1,37 @@
 use super::*;
+#[cfg(unix)]
+use crate::net::uds_state::{bind_uds, UdsState};
 use crate::{
     dispatch::NetworkConfig,
-    messaging::{DispatchEnvelope, EventEnvelope},
+    messaging::{DispatchEnvelope, EventEnvelope, NetMessage, SerialisedFrame},
     net::{
         buffers::{BufferChunk, BufferPool, EncodeBuffer},
+        compression::decompress,
+        flow_control::CreditNotifier,
         network_channel::{ChannelState, TcpChannel},
+        streaming::StreamError,
         udp_state::UdpState,
         ConnectionState,
     },
 };
-use crossbeam_channel::Receiver as Recv;
+use crossbeam_channel::{Receiver as Recv, Sender};
 use mio::{
     net::{TcpListener, TcpStream, UdpSocket},
     Events,
     Poll,
     Token,
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
     collections::VecDeque,
     io,
     net::{Shutdown, SocketAddr},
-    sync::Arc,
-    time::Duration,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
     usize,
 };
 use uuid::Uuid;
@@ -40,7 +49,8 @@ const TCP_SERVER: Token = Token(0);
 const UDP_SOCKET: Token = Token(1);
 // Used for identifying the dispatcher/input queue
 const DISPATCHER: Token = Token(2);
-const START_TOKEN: Token = Token(3);
+const UDS_SERVER: Token = Token(3);
+const START_TOKEN: Token = Token(4);
 const MAX_POLL_EVENTS: usize = 1024;
 /// How many times to retry on interrupt before we give up
 pub const MAX_INTERRUPTS: i32 = 9;
@@ -53,10 +63,14 @@ pub struct NetworkThread {
     log: KompactLogger,
     /// The SocketAddr the network thread is bound to and listening on
     pub addr: SocketAddr,
+    /// The SocketAddr the network thread introduces itself with in handshakes
+    pub advertised_addr: SocketAddr,
     //connection_events: UnboundedSender<NetworkEvent>,
     lookup: Arc<ArcSwap<ActorStore>>,
     tcp_listener: Option<TcpListener>,
     udp_state: Option<UdpState>,
+    #[cfg(unix)]
+    uds_state: Option<UdsState>,
     poll: Poll,
     // Contains K,V=Remote SocketAddr, Output buffer; Token for polling; Input-buffer,
     channel_map: FxHashMap<SocketAddr, TcpChannel>,
@@ -74,6 +88,114 @@ pub struct NetworkThread {
     retry_queue: VecDeque<(Token, bool, bool, usize)>,
     out_of_buffers: bool,
     encode_buffer: EncodeBuffer,
+    /// When to send the next heartbeats and check the suspicion of the connected hosts
+    next_heartbeat: Instant,
+    /// Hosts we refuse to communicate with
+    blocked: FxHashSet<SocketAddr>,
+    /// The incarnation of our system, exchanged during handshakes
+    incarnation: Uuid,
+    /// The index of this thread among the threads of its [Bridge]
+    shard: usize,
+    /// All the threads of the [Bridge], including this one, in shard order
+    shards: Vec<Shard>,
+    /// Channels handed over to this thread by the other threads
+    hand_offs: Recv<HandOff>,
+    hand_off_sender: Sender<HandOff>,
+    /// The open channels of all the threads of the [Bridge]
+    connections: Arc<ConnectionCounts>,
+    /// The thread asked to close an idle channel next, when the connection limit is reached
+    /// and none of the channels of this thread are idle
+    next_evicting_shard: usize,
+    /// Wakes this thread once a channel can grant more credits for the data frames it released
+    credit_notifier: Arc<CreditNotifier>,
+    #[cfg(feature = "tls")]
+    tls_context: Option<super::tls::TlsContext>,
+    /// The hostnames remote addresses were resolved from, which their TLS certificates are verified against
+    #[cfg(feature = "tls")]
+    hostnames: FxHashMap<SocketAddr, String>,
+}
+
+/// A channel moving from the thread that accepted it to the thread owning its remote address
+///
+/// Hand-offs happen when the handshake reveals the canonical address of the remote system.
+pub(crate) struct HandOff {
+    channel: TcpChannel,
+    registered_addr: SocketAddr,
+    remote_addr: SocketAddr,
+    id: Uuid,
+    incarnation: Uuid,
+    versions: ProtocolVersions,
+}
+
+/// How to reach one of the network threads of a [Bridge]
+#[derive(Clone)]
+pub(crate) struct Shard {
+    pub(crate) hand_offs: Sender<HandOff>,
+    pub(crate) events: Sender<DispatchEvent>,
+    pub(crate) waker: Arc<Waker>,
+}
+
+/// The number of open TCP channels in either direction, shared by the network threads of a [Bridge]
+///
+/// The [connection limits](NetworkConfig::set_max_inbound_connections) apply to these counts,
+/// no matter which thread drives the channels.
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionCounts {
+    inbound: AtomicUsize,
+    outbound: AtomicUsize,
+}
+
+impl ConnectionCounts {
+    fn count(&self, inbound: bool) -> &AtomicUsize {
+        if inbound {
+            &self.inbound
+        } else {
+            &self.outbound
+        }
+    }
+
+    /// Counts another `inbound` or outbound channel, unless there are `max_connections` already
+    pub(crate) fn try_acquire(
+        self: &Arc<Self>,
+        inbound: bool,
+        max_connections: Option<usize>,
+    ) -> Option<ConnectionSlot> {
+        self.count(inbound)
+            .fetch_update(
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+                |count| match max_connections {
+                    Some(max_connections) if count >= max_connections => None,
+                    _ => Some(count + 1),
+                },
+            )
+            .ok()
+            .map(|_| ConnectionSlot {
+                counts: self.clone(),
+                inbound,
+            })
+    }
+
+    /// Returns true if there are `max_connections` `inbound` or outbound channels already
+    pub(crate) fn full(&self, inbound: bool, max_connections: Option<usize>) -> bool {
+        let count = self.count(inbound).load(Ordering::SeqCst);
+        matches!(max_connections, Some(max_connections) if count >= max_connections)
+    }
+}
+
+/// A channel counted in the [ConnectionCounts], which is no longer counted once this is dropped
+#[derive(Debug)]
+pub(crate) struct ConnectionSlot {
+    counts: Arc<ConnectionCounts>,
+    inbound: bool,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.counts
+            .count(self.inbound)
+            .fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 /// Return values for IO Operations on the [NetworkChannel](net::network_channel::NetworkChannel) abstraction
@@ -82,8 +204,12 @@ pub(super) enum IOReturn {
     SwapBuffer,
     Close,
     None,
-    Start(SocketAddr, Uuid),
+    Start(SocketAddr, Uuid, Uuid, ProtocolVersions),
     Ack,
+    Rejected,
+    Incompatible(ProtocolVersions),
+    /// The remote host broke the protocol for the given reason
+    Violation(&'static str),
 }
 
 impl NetworkThread {
@@ -91,6 +217,10 @@ impl NetworkThread {
     /// The `input_queue` is used to send DispatchEvents to the thread but they won't be read unless
     /// the `dispatcher_registration` is activated to wake up the thread.
     /// `network_thread_sender` is used to confirm shutdown of the thread.
+    /// `incarnation` identifies the running system towards remote hosts.
+    ///
+    /// Fails if the sockets can not be bound or the [TLS config](NetworkConfig::set_tls_config) is invalid.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         log: KompactLogger,
         addr: SocketAddr,
@@ -98,93 +228,240 @@ impl NetworkThread {
         input_queue: Recv<DispatchEvent>,
         shutdown_promise: KPromise<()>,
         dispatcher_ref: DispatcherRef,
+        incarnation: Uuid,
         network_config: NetworkConfig,
-    ) -> (NetworkThread, Waker) {
+    ) -> Result<(NetworkThread, Waker), NetworkBridgeErr> {
         // Set-up the Listener
         debug!(
             log,
             "NetworkThread starting, trying to bind listener to address {}", &addr
         );
-        match bind_with_retries(&addr, MAX_BIND_RETRIES, &log) {
-            Ok(mut tcp_listener) => {
-                let actual_addr = tcp_listener.local_addr().expect("could not get real addr");
-                let logger = log.new(o!("addr" => format!("{}", actual_addr)));
-                let mut udp_socket =
-                    UdpSocket::bind(actual_addr).expect("could not bind UDP on TCP port");
-
-                // Set up polling for the Dispatcher and the listener.
-                let poll = Poll::new().expect("failed to create Poll instance in NetworkThread");
-
-                // Register Listener
-                let registry = poll.registry();
-                registry
-                    .register(&mut tcp_listener, TCP_SERVER, Interest::READABLE)
-                    .expect("failed to register TCP SERVER");
+        let mut tcp_listener = bind_with_retries(&addr, MAX_BIND_RETRIES, &log).map_err(|e| {
+            NetworkBridgeErr::Binding(format!("Failed to bind to address {}: {:?}", addr, e))
+        })?;
+        let actual_addr = tcp_listener.local_addr().expect("could not get real addr");
+        let advertised_addr = match network_config.get_advertised_addr() {
+            Some(advertised) if advertised.port() == 0 => {
+                SocketAddr::new(advertised.ip(), actual_addr.port())
+            }
+            Some(advertised) => advertised,
+            None => actual_addr,
+        };
+        let logger = log.new(o!("addr" => format!("{}", actual_addr)));
+        let mut udp_socket = UdpSocket::bind(actual_addr).map_err(|e| {
+            NetworkBridgeErr::Binding(format!(
+                "Failed to bind UDP on TCP port {}: {:?}",
+                actual_addr, e
+            ))
+        })?;
+
+        // Set up polling for the Dispatcher and the listener.
+        let poll = Poll::new().expect("failed to create Poll instance in NetworkThread");
+
+        // Register Listener
+        let registry = poll.registry();
+        registry
+            .register(&mut tcp_listener, TCP_SERVER, Interest::READABLE)
+            .expect("failed to register TCP SERVER");
+        registry
+            .register(
+                &mut udp_socket,
+                UDP_SOCKET,
+                Interest::READABLE | Interest::WRITABLE,
+            )
+            .expect("failed to register UDP SOCKET");
+        #[cfg(unix)]
+        let uds_state = match network_config.get_uds_path() {
+            Some(path) => {
+                let mut uds_listener = bind_uds(path).map_err(|e| {
+                    NetworkBridgeErr::Binding(format!(
+                        "Failed to bind UDS {}: {:?}",
+                        path.display(),
+                        e
+                    ))
+                })?;
                 registry
-                    .register(
-                        &mut udp_socket,
-                        UDP_SOCKET,
-                        Interest::READABLE | Interest::WRITABLE,
-                    )
-                    .expect("failed to register UDP SOCKET");
-
-                // Create waker for Dispatch
-                let waker = Waker::new(poll.registry(), DISPATCHER)
-                    .expect("failed to create Waker for DISPATCHER");
-
-                let mut buffer_pool = BufferPool::with_config(
-                    &network_config.get_buffer_config(),
-                    &network_config.get_custom_allocator(),
-                );
+                    .register(&mut uds_listener, UDS_SERVER, Interest::READABLE)
+                    .expect("failed to register UDS SERVER");
+                Some(UdsState::new(
+                    path.to_path_buf(),
+                    uds_listener,
+                    logger.clone(),
+                    advertised_addr,
+                    incarnation,
+                    &network_config,
+                ))
+            }
+            None => None,
+        };
 
-                let encode_buffer = EncodeBuffer::with_config(
-                    &network_config.get_buffer_config(),
-                    &network_config.get_custom_allocator(),
-                );
+        NetworkThread::with_sockets(
+            logger,
+            actual_addr,
+            advertised_addr,
+            poll,
+            Some(tcp_listener),
+            Some(udp_socket),
+            #[cfg(unix)]
+            uds_state,
+            lookup,
+            input_queue,
+            shutdown_promise,
+            dispatcher_ref,
+            incarnation,
+            network_config,
+        )
+    }
 
-                let udp_buffer = buffer_pool
-                    .get_buffer()
-                    .expect("Could not get buffer for setting up UDP");
-
-                let udp_state =
-                    UdpState::new(udp_socket, udp_buffer, logger.clone(), &network_config);
-                let channel_map: FxHashMap<SocketAddr, TcpChannel> = FxHashMap::default();
-                let token_map: FxHashMap<Token, SocketAddr> = FxHashMap::default();
-
-                (
-                    NetworkThread {
-                        log: logger,
-                        addr: actual_addr,
-                        lookup,
-                        tcp_listener: Some(tcp_listener),
-                        udp_state: Some(udp_state),
-                        poll,
-                        channel_map,
-                        token_map,
-                        token: START_TOKEN,
-                        input_queue,
-                        buffer_pool,
-                        sent_bytes: 0,
-                        received_bytes: 0,
-                        sent_msgs: 0,
-                        stopped: false,
-                        shutdown_promise: Some(shutdown_promise),
-                        dispatcher_ref,
-                        network_config,
-                        retry_queue: VecDeque::new(),
-                        out_of_buffers: false,
-                        encode_buffer,
-                    },
-                    waker,
-                )
-            }
-            Err(e) => {
-                panic!(
-                    "NetworkThread failed to bind to address: {:?}, addr {:?}",
-                    e, &addr
-                );
-            }
-        }
+    /// Creates a struct for an additional NetworkThread, which does not listen on any socket itself.
+    ///
+    /// It only drives the channels it requests, and those handed over to it by the listening thread.
+    /// `addr` and `advertised_addr` are the addresses of the listening thread.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn without_listener(
+        log: KompactLogger,
+        addr: SocketAddr,
+        advertised_addr: SocketAddr,
+        lookup: Arc<ArcSwap<ActorStore>>,
+        input_queue: Recv<DispatchEvent>,
+        shutdown_promise: KPromise<()>,
+        dispatcher_ref: DispatcherRef,
+        incarnation: Uuid,
+        network_config: NetworkConfig,
+    ) -> Result<(NetworkThread, Waker), NetworkBridgeErr> {
+        let poll = Poll::new().expect("failed to create Poll instance in NetworkThread");
+        NetworkThread::with_sockets(
+            log.new(o!("addr" => format!("{}", addr))),
+            addr,
+            advertised_addr,
+            poll,
+            None,
+            None,
+            #[cfg(unix)]
+            None,
+            lookup,
+            input_queue,
+            shutdown_promise,
+            dispatcher_ref,
+            incarnation,
+            network_config,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn with_sockets(
+        logger: KompactLogger,
+        addr: SocketAddr,
+        advertised_addr: SocketAddr,
+        poll: Poll,
+        tcp_listener: Option<TcpListener>,
+        udp_socket: Option<UdpSocket>,
+        #[cfg(unix)] uds_state: Option<UdsState>,
+        lookup: Arc<ArcSwap<ActorStore>>,
+        input_queue: Recv<DispatchEvent>,
+        shutdown_promise: KPromise<()>,
+        dispatcher_ref: DispatcherRef,
+        incarnation: Uuid,
+        network_config: NetworkConfig,
+    ) -> Result<(NetworkThread, Waker), NetworkBridgeErr> {
+        // Create waker for Dispatch
+        let waker =
+            Waker::new(poll.registry(), DISPATCHER).expect("failed to create Waker for DISPATCHER");
+
+        let mut buffer_pool = BufferPool::with_config(
+            &network_config.get_buffer_config(),
+            &network_config.get_custom_allocator(),
+        );
+
+        let encode_buffer = EncodeBuffer::with_config(
+            &network_config.get_buffer_config(),
+            &network_config.get_custom_allocator(),
+        );
+
+        let udp_state = udp_socket.map(|udp_socket| {
+            let udp_buffer = buffer_pool
+                .get_buffer()
+                .expect("Could not get buffer for setting up UDP");
+            UdpState::new(udp_socket, udp_buffer, logger.clone(), &network_config)
+        });
+        let channel_map: FxHashMap<SocketAddr, TcpChannel> = FxHashMap::default();
+        let heartbeat_interval = Duration::from_millis(network_config.get_heartbeat_interval());
+        let token_map: FxHashMap<Token, SocketAddr> = FxHashMap::default();
+        let (hand_off_sender, hand_offs) = crossbeam_channel::unbounded();
+
+        #[cfg(feature = "tls")]
+        let tls_context = match network_config.get_tls_config() {
+            Some(tls_config) => Some(
+                tls_config
+                    .build_context()
+                    .map_err(|e| NetworkBridgeErr::Tls(format!("{}", e)))?,
+            ),
+            None => None,
+        };
+
+        Ok((
+            NetworkThread {
+                log: logger,
+                addr,
+                advertised_addr,
+                lookup,
+                tcp_listener,
+                udp_state,
+                #[cfg(unix)]
+                uds_state,
+                poll,
+                channel_map,
+                token_map,
+                token: START_TOKEN,
+                input_queue,
+                buffer_pool,
+                sent_bytes: 0,
+                received_bytes: 0,
+                sent_msgs: 0,
+                stopped: false,
+                shutdown_promise: Some(shutdown_promise),
+                dispatcher_ref,
+                network_config,
+                retry_queue: VecDeque::new(),
+                out_of_buffers: false,
+                encode_buffer,
+                next_heartbeat: Instant::now() + heartbeat_interval,
+                blocked: FxHashSet::default(),
+                incarnation,
+                shard: 0,
+                shards: Vec::new(),
+                hand_offs,
+                hand_off_sender,
+                connections: Arc::new(ConnectionCounts::default()),
+                next_evicting_shard: 0,
+                credit_notifier: Arc::new(CreditNotifier::default()),
+                #[cfg(feature = "tls")]
+                tls_context,
+                #[cfg(feature = "tls")]
+                hostnames: FxHashMap::default(),
+            },
+            waker,
+        ))
+    }
+
+    /// Returns the queue other network threads hand channels over to this one with
+    pub(crate) fn hand_off_sender(&self) -> Sender<HandOff> {
+        self.hand_off_sender.clone()
+    }
+
+    /// Makes this thread the `index`-th of the `shards` which share the channels of a [Bridge]
+    /// and the `connections` counted towards its limits
+    pub(crate) fn set_shards(
+        &mut self,
+        index: usize,
+        shards: Vec<Shard>,
+        connections: Arc<ConnectionCounts>,
+    ) -> () {
+        self.shard = index;
+        self.next_evicting_shard = index;
+        self.credit_notifier = Arc::new(CreditNotifier::new(Some(shards[index].waker.clone())));
+        self.shards = shards;
+        self.connections = connections;
     }
 
     /// Event loop, spawn a thread calling this method start the thread.
@@ -210,9 +487,37 @@ impl NetworkThread {
                 Some(Duration::from_secs(0))
             }; // Timeout immediately if retries are waiting
 
+            // Wake up in time for the next retransmission or reassembly timeout of UDP messages
+            if let Some(udp_timeout) = self
+                .udp_state
+                .as_ref()
+                .and_then(|udp_state| udp_state.next_timeout(Instant::now()))
+            {
+                timeout = Some(timeout.map_or(udp_timeout, |timeout| timeout.min(udp_timeout)));
+            }
+            // Wake up in time to connect UDS streams again
+            #[cfg(unix)]
+            if let Some(uds_timeout) = self
+                .uds_state
+                .as_ref()
+                .and_then(|uds_state| uds_state.next_timeout(Instant::now()))
+            {
+                timeout = Some(timeout.map_or(uds_timeout, |timeout| timeout.min(uds_timeout)));
+            }
+            // Wake up in time for the next heartbeat
+            let heartbeat_timeout = self
+                .next_heartbeat
+                .saturating_duration_since(Instant::now());
+            timeout =
+                Some(timeout.map_or(heartbeat_timeout, |timeout| timeout.min(heartbeat_timeout)));
+
             self.poll
                 .poll(&mut events, timeout)
                 .expect("Error when calling Poll");
+            self.handle_udp_timeouts();
+            #[cfg(unix)]
+            self.handle_uds_retries();
+            self.handle_heartbeats();
 
             for (token, readable, writeable, retries) in events
                 .iter()
@@ -287,31 +592,22 @@ impl NetworkThread {
                                 warn!(self.log, "Error during UDP reading: {}", e);
                             }
                         }
-                        use dispatch::lookup::{ActorLookup, LookupResult};
-
-                        // Forward the data frame to the correct actor
-                        let lease_lookup = self.lookup.load();
-                        for envelope in udp_state.incoming_messages.drain(..) {
-                            match lease_lookup.get_by_actor_path(&envelope.receiver) {
-                                LookupResult::Ref(actor) => {
-                                    actor.enqueue(envelope);
-                                }
-                                LookupResult::Group(group) => {
-                                    group.route(envelope, &self.log);
-                                }
-                                LookupResult::None => {
-                                    debug!(self.log, "Could not find actor reference for destination: {:?}, dropping message", envelope.receiver);
+                        if udp_state.pending_messages() > 0 {
+                            // Send the acknowledgements for reliable messages
+                            match udp_state.try_write() {
+                                Ok(n) => {
+                                    self.sent_bytes += n as u64;
                                 }
-                                LookupResult::Err(e) => {
-                                    error!(
-                                        self.log,
-                                        "An error occurred during local actor lookup for destination: {:?}, dropping message. The error was: {}",
-                                        envelope.receiver,
-                                        e
-                                    );
+                                Err(e) => {
+                                    warn!(self.log, "Error during UDP sending: {}", e);
                                 }
                             }
                         }
+                        deliver_messages(
+                            &self.lookup,
+                            &self.log,
+                            udp_state.incoming_messages.drain(..),
+                        );
                     }
                 } else {
                     debug!(self.log, "Poll triggered for removed UDP socket");
@@ -319,7 +615,29 @@ impl NetworkThread {
             }
             DISPATCHER => {
                 // Message available from Dispatcher, clear the poll readiness before receiving
+                self.receive_hand_offs();
                 self.receive_dispatch();
+                self.grant_released_credits();
+            }
+            #[cfg(unix)]
+            UDS_SERVER => {
+                let accepted = match self.uds_state {
+                    Some(ref mut uds_state) => uds_state
+                        .accept(self.poll.registry(), &mut self.token, &mut self.buffer_pool)
+                        .unwrap_or_else(|e| {
+                            debug!(self.log, "Error while accepting UDS stream {:?}", e);
+                            Vec::new()
+                        }),
+                    None => Vec::new(),
+                };
+                // Say Hello on the accepted streams
+                for token in accepted {
+                    self.handle_uds_event(token, false, true, 0);
+                }
+            }
+            #[cfg(unix)]
+            token if self.is_uds_stream(&token) => {
+                self.handle_uds_event(token, readable, writeable, retries);
             }
             token => {
                 // lookup its corresponding addr
@@ -354,16 +672,15 @@ impl NetworkThread {
                         _ => {}
                     }
 
-                    match self.decode(&addr) {
-                        IOReturn::Start(remote_addr, id) => {
-                            self.handle_start(token, remote_addr, id);
-                        }
-                        IOReturn::Close => {
-                            // Remove and deregister
-                            close_channel = true;
-                        }
-                        _ => (),
+                    if let IOReturn::Close = self.decode_frames(token) {
+                        // Remove and deregister
+                        close_channel = true;
                     }
+                    // The handshake may have moved the channel to its canonical address, or dropped it
+                    let addr = match self.token_map.get(&token) {
+                        Some(addr) => *addr,
+                        None => return,
+                    };
                     if swap_buffer {
                         // Buffer full, we swap it and register for poll again
                         if let Some(channel) = self.channel_map.get_mut(&addr) {
@@ -409,7 +726,10 @@ impl NetworkThread {
                         }
                         // We retry the event such that the read is performed with the new buffer.
                     }
-                    if close_channel {
+                    // Channels we closed ourselves may still be read until the NetworkDispatcher acks the closing
+                    if close_channel
+                        && matches!(self.channel_map.get(&addr), Some(channel) if !channel.closed())
+                    {
                         self.close_channel(addr);
                     }
                 }
@@ -417,6 +737,260 @@ impl NetworkThread {
         }
     }
 
+    /// Sends the reliable UDP messages which have not been acknowledged in time again,
+    /// and drops the UDP messages whose fragments did not arrive in time
+    fn handle_udp_timeouts(&mut self) -> () {
+        if let Some(ref mut udp_state) = self.udp_state {
+            udp_state.handle_timeouts(Instant::now());
+            if udp_state.pending_messages() > 0 {
+                match udp_state.try_write() {
+                    Ok(n) => {
+                        self.sent_bytes += n as u64;
+                    }
+                    Err(e) => {
+                        warn!(self.log, "Error during UDP retransmission: {}", e);
+                    }
+                }
+            }
+        }
+        self.forward_udp_events();
+    }
+
+    /// Tells the dispatcher which reliable UDP messages were dropped
+    fn forward_udp_events(&mut self) -> () {
+        if let Some(ref mut udp_state) = self.udp_state {
+            for event in udp_state.events.drain(..) {
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(event)));
+            }
+        }
+    }
+
+    /// Grants more credits on the channels whose received data frames were released by their receivers
+    fn grant_released_credits(&mut self) -> () {
+        if self.credit_notifier.take_pending() {
+            for channel in self.channel_map.values_mut().filter(|c| c.connected()) {
+                channel.grant_released_credits();
+            }
+        }
+    }
+
+    /// Sends heartbeats on the idle channels once every heartbeat interval,
+    /// and reports the suspicion of each connected host to the `NetworkDispatcher`
+    fn handle_heartbeats(&mut self) -> () {
+        let now = Instant::now();
+        if now < self.next_heartbeat {
+            return;
+        }
+        self.next_heartbeat =
+            now + Duration::from_millis(self.network_config.get_heartbeat_interval());
+        for (addr, channel) in self.channel_map.iter_mut() {
+            if !channel.connected() {
+                continue;
+            }
+            if let Some(phi) = channel.heartbeat(now) {
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::Suspicion(*addr, phi),
+                    )));
+            }
+            if let Some(stats) = channel.take_compression_stats() {
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::Compression(*addr, stats),
+                    )));
+            }
+        }
+        if let Some(idle_timeout) = self.network_config.get_idle_timeout() {
+            let idle_timeout = Duration::from_millis(idle_timeout);
+            let idle: Vec<SocketAddr> = self
+                .channel_map
+                .iter()
+                .filter(|(_, channel)| {
+                    !channel.closing_idle()
+                        && matches!(
+                            channel.idle_since(),
+                            Some(since) if now.duration_since(since) >= idle_timeout
+                        )
+                })
+                .map(|(addr, _)| *addr)
+                .collect();
+            for addr in idle {
+                debug!(self.log, "Closing the idle channel to {}", &addr);
+                self.request_idle_close(addr);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_uds_stream(&self, token: &Token) -> bool {
+        match self.uds_state {
+            Some(ref uds_state) => uds_state.contains(token),
+            None => false,
+        }
+    }
+
+    #[cfg(unix)]
+    fn handle_uds_event(&mut self, token: Token, readable: bool, writeable: bool, retries: usize) {
+        let uds_state = match self.uds_state {
+            Some(ref mut uds_state) => uds_state,
+            None => return,
+        };
+        let mut close = false;
+        let mut refuse = false;
+        if readable {
+            match uds_state.try_read(token) {
+                Ok((n, ioret)) => {
+                    self.received_bytes += n as u64;
+                    match ioret {
+                        IOReturn::Close => close = true,
+                        IOReturn::Rejected => refuse = true,
+                        IOReturn::SwapBuffer => {
+                            if let Some(mut new_buffer) = self.buffer_pool.get_buffer() {
+                                uds_state.swap_buffer(token, &mut new_buffer);
+                                self.buffer_pool.return_buffer(new_buffer);
+                                self.out_of_buffers = false;
+                                // We do not count successful swaps in the retries, stay at the same count
+                                self.retry_queue
+                                    .push_back((token, readable, writeable, retries));
+                            } else if retries
+                                <= self.network_config.get_max_connection_retry_attempts() as usize
+                            {
+                                error!(self.log, "Could not get UDS buffer, retries: {}", retries);
+                                self.out_of_buffers = true;
+                                self.retry_queue.push_back((
+                                    token,
+                                    readable,
+                                    writeable,
+                                    retries + 1,
+                                ));
+                            } else {
+                                error!(
+                                    self.log,
+                                    "Giving up on reading UDS stream, waiting for buffers timed out"
+                                );
+                                close = true;
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                Err(e) => {
+                    warn!(self.log, "Error during UDS reading: {}", e);
+                    close = true;
+                }
+            }
+            deliver_messages(
+                &self.lookup,
+                &self.log,
+                uds_state.incoming_messages.drain(..),
+            );
+        }
+        // Reading may have queued handshake frames, or released the messages waiting for the handshake
+        if !refuse {
+            match uds_state.try_write(token) {
+                Ok(n) => {
+                    self.sent_bytes += n as u64;
+                }
+                Err(e) => {
+                    warn!(self.log, "Error during UDS sending: {}", e);
+                    close = true;
+                }
+            }
+        }
+        if refuse {
+            uds_state.refuse(token, self.poll.registry(), &mut self.buffer_pool);
+        } else if close {
+            uds_state.close(token, self.poll.registry(), &mut self.buffer_pool);
+        }
+        self.forward_uds_events();
+    }
+
+    /// Connects the UDS streams whose retry interval passed
+    #[cfg(unix)]
+    fn handle_uds_retries(&mut self) -> () {
+        if let Some(ref mut uds_state) = self.uds_state {
+            uds_state.handle_retries(
+                Instant::now(),
+                self.poll.registry(),
+                &mut self.token,
+                &mut self.buffer_pool,
+            );
+        }
+        self.forward_uds_events();
+    }
+
+    /// Tells the dispatcher what happened to the UDS streams
+    #[cfg(unix)]
+    fn forward_uds_events(&mut self) -> () {
+        if let Some(ref mut uds_state) = self.uds_state {
+            for event in uds_state.events.drain(..) {
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(event)));
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn send_uds(&mut self, path: PathBuf, data: DispatchData) -> () {
+        let uds_state = match self.uds_state {
+            Some(ref mut uds_state) => uds_state,
+            None => {
+                warn!(
+                    self.log,
+                    "Dropping UDS message to {} as no UDS socket is configured.",
+                    path.display()
+                );
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::UdsDroppedMessages(path, 1),
+                    )));
+                return;
+            }
+        };
+        let frame = match data {
+            DispatchData::Serialised(frame) => frame,
+            _ => match self
+                .encode_buffer
+                .get_buffer_encoder()
+                .and_then(|mut buf| data.into_serialised(&mut buf))
+            {
+                Ok(frame) => frame,
+                Err(e) => {
+                    warn!(self.log, "Error serialising message: {}", e);
+                    return;
+                }
+            },
+        };
+        if let Some(token) = uds_state.enqueue_serialised(
+            path,
+            frame,
+            self.poll.registry(),
+            &mut self.token,
+            &mut self.buffer_pool,
+        ) {
+            match uds_state.try_write(token) {
+                Ok(n) => {
+                    self.sent_bytes += n as u64;
+                }
+                Err(e) => {
+                    warn!(self.log, "Error during UDS sending: {}", e);
+                    uds_state.close(token, self.poll.registry(), &mut self.buffer_pool);
+                }
+            }
+        }
+        self.forward_uds_events();
+    }
+
+    #[cfg(not(unix))]
+    fn send_uds(&mut self, path: PathBuf, _data: DispatchData) -> () {
+        warn!(
+            self.log,
+            "Dropping UDS message to {} as UDS is only supported on Unix.",
+            path.display()
+        );
+    }
+
     /// During channel initialization the threeway handshake to establish connections culminates with this function
     /// The Start(remote_addr, id) is received by the host on the receiving end of the channel initialisation.
     /// The decision is made here and now.
@@ -425,7 +999,38 @@ impl NetworkThread {
     ///     The other connection has not started and does not have a known UUID: it will be killed, this channel will start.
     ///     The connection has already started, in which case this channel must be killed.
     ///     The connection has a known UUID but is not connected: Use the UUID as a tie breaker for which to kill and which to keep.
-    fn handle_start(&mut self, token: Token, remote_addr: SocketAddr, id: Uuid) -> () {
+    /// Before any of that, the protocol version is negotiated and the channel is rejected if there is no common version.
+    /// Channels from blocked hosts are refused right away.
+    /// Returns false if the channel was rejected.
+    fn handle_start(
+        &mut self,
+        token: Token,
+        remote_addr: SocketAddr,
+        id: Uuid,
+        incarnation: Uuid,
+        versions: ProtocolVersions,
+    ) -> bool {
+        if self.blocked.contains(&remote_addr) {
+            debug!(
+                self.log,
+                "Refusing channel from blocked host {}", &remote_addr
+            );
+            if let Some(registered_addr) = self.token_map.get(&token).copied() {
+                self.drop_channel(registered_addr);
+            }
+            return false;
+        }
+        if let Some(registered_addr) = self.token_map.get(&token).copied() {
+            if let Some(channel) = self.channel_map.get_mut(&registered_addr) {
+                if !channel.negotiate_versions(&versions) {
+                    self.reject_channel(
+                        registered_addr,
+                        incompatible_versions(registered_addr, &versions),
+                    );
+                    return false;
+                }
+            }
+        }
         if let Some(registered_addr) = self.token_map.remove(&token) {
             if remote_addr == registered_addr {
                 // The channel we received the start on was already registered with the appropriate address.
@@ -461,7 +1066,7 @@ impl NetworkThread {
                                 channel.graceful_shutdown();
                                 self.channel_map.insert(remote_addr, other_channel);
                                 // It will be driven to completion on its own.
-                                return;
+                                return true;
                             }
                         }
                         // We will keep this channel, not the other channel
@@ -498,7 +1103,7 @@ impl NetworkThread {
                     self.log,
                     "Sending ack for {}, {}", &remote_addr, &channel.token.0
                 );
-                channel.handle_start(&remote_addr, id);
+                channel.handle_start(&remote_addr, id, incarnation);
                 channel.token = token;
                 self.token_map.insert(token, remote_addr);
                 if let Err(e) = self.poll.registry().reregister(
@@ -512,14 +1117,9 @@ impl NetworkThread {
                     );
                 };
 
-                self.dispatcher_ref
-                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
-                        NetworkEvent::Connection(
-                            remote_addr,
-                            ConnectionState::Connected(remote_addr),
-                        ),
-                    )));
+                self.notify_connected(remote_addr, Some(incarnation));
             }
+            true
         } else {
             panic!(
                 "No address registered for a token which yielded a hello msg, \
@@ -528,15 +1128,113 @@ impl NetworkThread {
         }
     }
 
+    /// Returns the index of the thread which drives the channel to `addr`
+    fn owner_of(&self, addr: &SocketAddr) -> usize {
+        if self.shards.len() > 1 {
+            shard_index(addr, self.shards.len())
+        } else {
+            self.shard
+        }
+    }
+
+    /// Moves the channel registered with `token` to the thread with index `owner`,
+    /// which starts it there
+    fn hand_off(
+        &mut self,
+        token: Token,
+        owner: usize,
+        remote_addr: SocketAddr,
+        id: Uuid,
+        incarnation: Uuid,
+        versions: ProtocolVersions,
+    ) -> () {
+        let registered_addr = match self.token_map.remove(&token) {
+            Some(registered_addr) => registered_addr,
+            None => return,
+        };
+        if let Some(mut channel) = self.channel_map.remove(&registered_addr) {
+            let _ = self.poll.registry().deregister(channel.stream_mut());
+            debug!(
+                self.log,
+                "Handing channel from {} over to network thread {}", &remote_addr, owner
+            );
+            let shard = &self.shards[owner];
+            let hand_off = HandOff {
+                channel,
+                registered_addr,
+                remote_addr,
+                id,
+                incarnation,
+                versions,
+            };
+            if shard.hand_offs.send(hand_off).is_err() {
+                debug!(
+                    self.log,
+                    "Network thread {} is gone, dropping channel from {}", owner, &remote_addr
+                );
+            } else if let Err(e) = shard.waker.wake() {
+                warn!(self.log, "Failed to wake network thread {}: {:?}", owner, e);
+            }
+        }
+    }
+
+    /// Registers and starts the channels the other threads handed over to this one
+    fn receive_hand_offs(&mut self) -> () {
+        while let Ok(hand_off) = self.hand_offs.try_recv() {
+            let HandOff {
+                mut channel,
+                registered_addr,
+                remote_addr,
+                id,
+                incarnation,
+                versions,
+            } = hand_off;
+            let token = self.token;
+            self.next_token();
+            channel.token = token;
+            if let Err(e) = self.poll.registry().register(
+                channel.stream_mut(),
+                token,
+                Interest::READABLE | Interest::WRITABLE,
+            ) {
+                error!(
+                    self.log,
+                    "Failed to register polling for {}\n{:?}", &remote_addr, e
+                );
+            }
+            self.token_map.insert(token, registered_addr);
+            self.channel_map.insert(registered_addr, channel);
+            if self.handle_start(token, remote_addr, id, incarnation, versions)
+                && self.decode_frames(token) == IOReturn::Close
+            {
+                if let Some(addr) = self.token_map.get(&token).copied() {
+                    self.close_channel(addr);
+                }
+            }
+        }
+    }
+
     fn handle_ack(&mut self, addr: &SocketAddr) -> () {
         if let Some(channel) = self.channel_map.get_mut(addr) {
             debug!(self.log, "Handling ack for {}", addr);
             channel.handle_ack();
+            let incarnation = channel.remote_incarnation();
+            self.notify_connected(*addr, incarnation);
+        }
+    }
+
+    /// Tells the dispatcher about a newly connected channel, and the incarnation of the system at its end
+    fn notify_connected(&self, addr: SocketAddr, incarnation: Option<Uuid>) -> () {
+        if let Some(incarnation) = incarnation {
             self.dispatcher_ref
                 .tell(DispatchEnvelope::Event(EventEnvelope::Network(
-                    NetworkEvent::Connection(*addr, ConnectionState::Connected(*addr)),
+                    NetworkEvent::Incarnation(addr, incarnation),
                 )));
         }
+        self.dispatcher_ref
+            .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                NetworkEvent::Connection(addr, ConnectionState::Connected(addr)),
+            )));
     }
 
     fn try_write(&mut self, addr: &SocketAddr) -> IOReturn {
@@ -592,68 +1290,238 @@ impl NetworkThread {
         ret
     }
 
+    /// Decodes the frames received on the channel to `addr` until it has no complete frame left,
+    /// or a frame requires a state transition only the caller can make.
     fn decode(&mut self, addr: &SocketAddr) -> IOReturn {
-        let mut ret = IOReturn::None;
-        // ret is used as return place-holder and internal flow-control.
         if let Some(channel) = self.channel_map.get_mut(addr) {
             loop {
-                match channel.decode() {
+                let frame = channel.decode();
+                let carries_data = matches!(
+                    frame,
+                    Ok(Frame::Data(_)) | Ok(Frame::Compressed(_)) | Ok(Frame::Fragment(_))
+                );
+                // Kept along with the delivered message, or dropped right away if there is none
+                let credit = if carries_data {
+                    match channel.receive_data() {
+                        Ok(credit) => credit,
+                        Err(reason) => return IOReturn::Violation(reason),
+                    }
+                } else {
+                    None
+                };
+                match frame {
                     Err(FramingError::NoData) => {
-                        // Done
-                        return ret;
+                        // Done, hand back the credits of the messages dropped meanwhile
+                        channel.grant_released_credits();
+                        return IOReturn::None;
                     }
                     Ok(Frame::Data(fr)) => {
-                        use dispatch::lookup::{ActorLookup, LookupResult};
                         use serialisation::ser_helpers::deserialise_chunk_lease;
 
                         // Forward the data frame to the correct actor
-                        let lease_lookup = self.lookup.load();
                         let buf = fr.payload();
-                        let envelope = deserialise_chunk_lease(buf).expect("s11n errors");
-                        match lease_lookup.get_by_actor_path(&envelope.receiver) {
-                            LookupResult::Ref(actor) => {
-                                actor.enqueue(envelope);
-                            }
-                            LookupResult::Group(group) => {
-                                group.route(envelope, &self.log);
+                        let envelope = deserialise_chunk_lease(buf)
+                            .expect("s11n errors")
+                            .with_credit(credit);
+                        deliver_messages(&self.lookup, &self.log, std::iter::once(envelope));
+                    }
+                    Ok(Frame::Compressed(compressed)) => {
+                        use serialisation::ser_helpers::deserialise_bytes;
+
+                        let max_len = self.network_config.get_max_decompressed_size();
+                        let decoded = compressed
+                            .decompress(max_len)
+                            .map_err(|e| e.to_string())
+                            .and_then(|payload| {
+                                deserialise_bytes(payload).map_err(|e| e.to_string())
+                            });
+                        match decoded {
+                            Ok(envelope) => deliver_messages(
+                                &self.lookup,
+                                &self.log,
+                                std::iter::once(envelope.with_credit(credit)),
+                            ),
+                            Err(e) => {
+                                warn!(
+                                    self.log,
+                                    "Could not decompress frame from {}, dropping message: {}",
+                                    addr,
+                                    e
+                                );
                             }
-                            LookupResult::None => {
-                                warn!(self.log, "Could not find actor reference for destination: {:?}, dropping message", envelope.receiver);
+                        }
+                    }
+                    Ok(Frame::Fragment(fragment)) => {
+                        use bytes::Buf;
+                        use serialisation::ser_helpers::{
+                            deserialise_bytes,
+                            deserialise_chunk_ref,
+                        };
+
+                        match channel.handle_fragment(fragment) {
+                            Ok(Some((mut frame, chunk))) => {
+                                // The streamed frame still has its FrameHead, which the deserialisers do not expect
+                                let frame_type =
+                                    FrameType::from(frame.chunk()[FRAME_HEAD_LEN as usize - 1]);
+                                frame.advance(FRAME_HEAD_LEN as usize);
+                                let decoded = if frame_type == FrameType::Compressed {
+                                    let max_len = self.network_config.get_max_decompressed_size();
+                                    decompress(frame, max_len)
+                                        .map_err(|e| e.to_string())
+                                        .and_then(|payload| {
+                                            deserialise_bytes(payload).map_err(|e| e.to_string())
+                                        })
+                                } else {
+                                    deserialise_chunk_ref(frame).map_err(|e| e.to_string())
+                                };
+                                match decoded {
+                                    Ok(envelope) => deliver_messages(
+                                        &self.lookup,
+                                        &self.log,
+                                        std::iter::once(envelope.with_credit(credit)),
+                                    ),
+                                    Err(e) => {
+                                        warn!(
+                                            self.log,
+                                            "Could not deserialise streamed message from {}, dropping it: {}",
+                                            addr,
+                                            e
+                                        );
+                                    }
+                                }
+                                // The chunk may only be deallocated once the message has been dropped
+                                self.dispatcher_ref
+                                    .tell(DispatchEnvelope::LockedChunk(chunk));
                             }
-                            LookupResult::Err(e) => {
-                                error!(
+                            Ok(None) => {}
+                            Err(StreamError::Invalid(reason)) => {
+                                warn!(
                                     self.log,
-                                    "An error occurred during local actor lookup for destination: {:?}, dropping message. The error was: {}",
-                                    envelope.receiver,
-                                    e
+                                    "Invalid fragment from {}, dropping streamed message: {}",
+                                    addr,
+                                    reason
                                 );
                             }
+                            Err(StreamError::Limit(reason)) => {
+                                return IOReturn::Violation(reason);
+                            }
                         }
                     }
                     Ok(Frame::Hello(hello)) => {
+                        if !channel.negotiate_versions(&hello.versions) {
+                            return IOReturn::Incompatible(hello.versions);
+                        }
                         // Channel handles hello internally. We can continue decoding.
                         debug!(self.log, "Handling Hello({}) from {}", &hello.addr, &addr);
                         channel.handle_hello(hello);
                     }
                     Ok(Frame::Start(start)) => {
+                        if !channel.authenticate_start(&start) {
+                            return IOReturn::Rejected;
+                        }
                         // Channel handles hello internally. NetworkThread decides in next state transition
-                        return IOReturn::Start(start.addr, start.id);
+                        return IOReturn::Start(
+                            start.addr,
+                            start.id,
+                            start.incarnation,
+                            start.versions,
+                        );
                     }
-                    Ok(Frame::Ack(_)) => {
-                        // We need to handle Acks immediately outside of the loop, then continue the loop
-                        ret = IOReturn::Ack;
-                        break;
+                    Ok(Frame::CreditUpdate(credit_update)) => {
+                        if channel.handle_credit_update(credit_update) {
+                            debug!(self.log, "Channel to {} caught up, unblocking", &addr);
+                            self.dispatcher_ref
+                                .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                                    NetworkEvent::Connection(
+                                        *addr,
+                                        ConnectionState::Connected(*addr),
+                                    ),
+                                )));
+                        }
+                        match channel.try_drain() {
+                            Err(ref err) if broken_pipe(err) => {
+                                return IOReturn::Close;
+                            }
+                            Ok(n) => {
+                                self.sent_bytes += n as u64;
+                            }
+                            Err(e) => {
+                                error!(
+                                    self.log,
+                                    "Unhandled error while writing to {}\n{:?}", addr, e
+                                );
+                            }
+                        }
+                    }
+                    Ok(Frame::StreamRequest(request)) => {
+                        debug!(
+                            self.log,
+                            "{} is out of credits, requesting {}", &addr, request.credit_capacity
+                        );
+                        channel.handle_stream_request(request);
+                    }
+                    Ok(Frame::Ack(ack)) => {
+                        if !channel.authenticate_ack(&ack) {
+                            return IOReturn::Rejected;
+                        }
+                        // Acks are handled outside of the loop, before decoding the frames behind them
+                        return IOReturn::Ack;
                     }
                     Ok(Frame::Bye()) => {
                         debug!(self.log, "Received Bye from {}", &addr);
                         return IOReturn::Close;
                     }
+                    Ok(Frame::Idle()) => {
+                        // The remote host says Bye once it got our IdleAck, after everything it sent so far
+                        if channel.connected() && channel.ack_idle_close() {
+                            debug!(self.log, "{} is closing the idle channel", &addr);
+                            self.dispatcher_ref.tell(DispatchEnvelope::Event(
+                                EventEnvelope::Network(NetworkEvent::Idle(*addr)),
+                            ));
+                        } else {
+                            debug!(
+                                self.log,
+                                "Not closing the channel to {}, it is still in use", &addr
+                            );
+                        }
+                    }
+                    Ok(Frame::IdleAck()) if channel.connected() => {
+                        // The remote host sends nothing after its IdleAck, so nothing can be lost
+                        debug!(self.log, "{} agreed to close the idle channel", &addr);
+                        self.dispatcher_ref
+                            .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                                NetworkEvent::Idle(*addr),
+                            )));
+                        for unsent in channel.close_idle() {
+                            self.dispatcher_ref.tell(DispatchEnvelope::Event(
+                                EventEnvelope::Network(NetworkEvent::RejectedData(
+                                    *addr,
+                                    DispatchData::Serialised(unsent),
+                                )),
+                            ));
+                        }
+                        return IOReturn::Close;
+                    }
+                    Ok(Frame::Heartbeat()) => {
+                        // Only keeps the failure detector happy, which already noticed the traffic
+                    }
+                    Ok(frame) => {
+                        // The remaining frames are only sent over UDP
+                        warn!(self.log, "Unexpected frame {:?} from {}", frame, &addr);
+                    }
                     Err(FramingError::InvalidMagicNum((check, slice))) => {
                         // There is no way to recover from this error right now. Would need resending mechanism
                         // or accept data loss and close the channel.
                         panic!("NetworkThread {} Unaligned buffer error for {}. {:?}, Magic_num: {:X}, Slice:{:?}",
                                self.addr, &addr, channel, check, slice);
                     }
+                    Err(FramingError::IncompatibleVersions(versions)) => {
+                        return IOReturn::Incompatible(versions);
+                    }
+                    Err(FramingError::InvalidFrame) if channel.handshaking() => {
+                        // There is no way to complete the handshake without the frame
+                        return IOReturn::Violation("invalid frame during the handshake");
+                    }
                     Err(FramingError::InvalidFrame) => {
                         // Bad but not fatal error
                         error!(self.log, "Invalid Frame received on channel {:?}", channel);
@@ -661,22 +1529,49 @@ impl NetworkThread {
                     Err(e) => {
                         error!(self.log, "Unhandled error {:?} from {:?}", &e, &addr);
                     }
-                    Ok(other_frame) => error!(
-                        self.log,
-                        "Received unexpected frame type {:?} from {:?}",
-                        other_frame.frame_type(),
-                        channel
-                    ),
                 }
             }
         }
-        match ret {
-            IOReturn::Ack => {
-                self.handle_ack(addr);
-                // We must continue decoding after.
-                self.decode(addr)
+        IOReturn::None
+    }
+
+    /// Decodes the frames received on the channel registered with `token`, making the state transitions
+    /// the handshake frames among them require in between.
+    ///
+    /// Returns `IOReturn::None` if the channel was rejected or handed over to another thread,
+    /// in which case the token no longer belongs to it.
+    fn decode_frames(&mut self, token: Token) -> IOReturn {
+        loop {
+            let addr = match self.token_map.get(&token) {
+                Some(addr) => *addr,
+                None => return IOReturn::None,
+            };
+            match self.decode(&addr) {
+                IOReturn::Start(remote_addr, id, incarnation, versions) => {
+                    let owner = self.owner_of(&remote_addr);
+                    if owner != self.shard {
+                        self.hand_off(token, owner, remote_addr, id, incarnation, versions);
+                        return IOReturn::None;
+                    }
+                    if !self.handle_start(token, remote_addr, id, incarnation, versions) {
+                        return IOReturn::None;
+                    }
+                }
+                IOReturn::Ack => self.handle_ack(&addr),
+                IOReturn::Rejected => {
+                    self.reject_channel(addr, NetworkEvent::AuthenticationFailed(addr));
+                    return IOReturn::None;
+                }
+                IOReturn::Incompatible(versions) => {
+                    self.reject_channel(addr, incompatible_versions(addr, &versions));
+                    return IOReturn::None;
+                }
+                IOReturn::Violation(reason) => {
+                    self.drop_violating(addr, reason);
+                    return IOReturn::None;
+                }
+                ret => return ret,
             }
-            _ => ret,
         }
     }
 
@@ -716,6 +1611,21 @@ impl NetworkThread {
                 }
             }
         }
+        let slot = match self.make_room(false) {
+            Some(slot) => slot,
+            None => {
+                // Every outbound channel is busy, let the dispatcher retry later
+                warn!(
+                    self.log,
+                    "Not connecting to {} yet, too many outbound connections", &addr
+                );
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::Connection(addr, ConnectionState::Closed),
+                    )));
+                return;
+            }
+        };
         // Fetch a buffer before we make the request
         if let Some(buffer) = self.buffer_pool.get_buffer() {
             debug!(self.log, "Requesting connection to {}", &addr);
@@ -726,6 +1636,7 @@ impl NetworkThread {
                         &addr,
                         ChannelState::Requested(addr, Uuid::new_v4()),
                         buffer,
+                        slot,
                     );
                 }
                 Err(e) => {
@@ -747,9 +1658,18 @@ impl NetworkThread {
     #[allow(irrefutable_let_patterns)]
     fn accept_stream(&mut self) -> io::Result<()> {
         while let (stream, addr) = (self.tcp_listener.as_ref().unwrap()).accept()? {
-            if let Some(buffer) = self.buffer_pool.get_buffer() {
+            let slot = self.make_room(true);
+            if slot.is_none() {
+                // Every inbound channel is busy, the remote host will have to try again later
+                warn!(
+                    self.log,
+                    "Refusing connection from {}, too many inbound connections", &addr
+                );
+                // The remote host may already have given up on the connection, keep accepting anyway
+                let _ = stream.shutdown(Shutdown::Both);
+            } else if let (Some(slot), Some(buffer)) = (slot, self.buffer_pool.get_buffer()) {
                 debug!(self.log, "Accepting connection from {}", &addr);
-                self.store_stream(stream, &addr, ChannelState::Initialising, buffer);
+                self.store_stream(stream, &addr, ChannelState::Initialising, buffer, slot);
             } else {
                 // If we can't get a buffer we reject the channel immediately
                 stream.shutdown(Shutdown::Both)?;
@@ -764,19 +1684,49 @@ impl NetworkThread {
         addr: &SocketAddr,
         state: ChannelState,
         buffer: BufferChunk,
+        slot: ConnectionSlot,
     ) {
+        #[cfg(feature = "tls")]
+        let session = match &self.tls_context {
+            Some(tls_context) => {
+                let session = if let ChannelState::Requested(_, _) = state {
+                    tls_context.connect(addr, self.hostnames.get(addr).map(String::as_str))
+                } else {
+                    tls_context.accept()
+                };
+                match session {
+                    Ok(session) => Some(session),
+                    Err(e) => {
+                        error!(self.log, "Failed to start TLS session with {}: {}", addr, e);
+                        let _ = stream.shutdown(Shutdown::Both);
+                        self.buffer_pool.return_buffer(buffer);
+                        return;
+                    }
+                }
+            }
+            None => None,
+        };
         self.token_map.insert(self.token, *addr);
         let mut channel = TcpChannel::new(
             stream,
             self.token,
             buffer,
             state,
-            self.addr,
+            self.advertised_addr,
+            self.incarnation,
             &self.network_config,
+            self.credit_notifier.clone(),
         );
+        channel.hold_slot(slot);
+        #[cfg(feature = "tls")]
+        {
+            if let Some(session) = session {
+                channel.wrap_tls(session);
+            }
+        }
         debug!(self.log, "Saying Hello to {}", addr);
         // Whatever error is thrown here will be re-triggered and handled later.
-        channel.initialise(&self.addr);
+        channel.initialise(&self.advertised_addr);
         if let Err(e) = self.poll.registry().register(
             channel.stream_mut(),
             self.token,
@@ -792,63 +1742,29 @@ impl NetworkThread {
         while let Ok(event) = self.input_queue.try_recv() {
             match event {
                 DispatchEvent::SendTCP(addr, data) => {
-                    self.sent_msgs += 1;
-                    // Get the token corresponding to the connection
-                    if let Some(channel) = self.channel_map.get_mut(&addr) {
-                        // The stream is already set-up, buffer the package and wait for writable event
-                        if channel.connected() {
-                            match data {
-                                DispatchData::Serialised(frame) => {
-                                    channel.enqueue_serialised(frame);
-                                }
-                                _ => {
-                                    if let Err(e) = self
-                                        .encode_buffer
-                                        .get_buffer_encoder()
-                                        .and_then(|mut buf| {
-                                            channel.enqueue_serialised(
-                                                data.into_serialised(&mut buf)?,
-                                            );
-                                            Ok(())
-                                        })
-                                    {
-                                        warn!(self.log, "Error serialising message: {}", e);
-                                    }
-                                }
-                            }
-                        } else {
-                            debug!(self.log, "Dispatch trying to route to non connected channel {:?}, rejecting the message", channel);
-                            self.dispatcher_ref.tell(DispatchEnvelope::Event(
-                                EventEnvelope::Network(NetworkEvent::RejectedData(addr, data)),
-                            ));
-                            break;
-                        }
-                    } else {
-                        // The stream isn't set-up, request connection, set-it up and try to send the message
-                        debug!(self.log, "Dispatch trying to route to unrecognized address {}, rejecting the message", addr);
-                        self.dispatcher_ref
-                            .tell(DispatchEnvelope::Event(EventEnvelope::Network(
-                                NetworkEvent::RejectedData(addr, data),
-                            )));
+                    if !self.send_tcp(addr, data, false) {
                         break;
                     }
-                    if let IOReturn::Close = self.try_write(&addr) {
-                        self.close_channel(addr);
+                }
+                DispatchEvent::SendPriorityTCP(addr, data) => {
+                    if !self.send_tcp(addr, data, true) {
+                        break;
                     }
                 }
-                DispatchEvent::SendUDP(addr, data) => {
+                DispatchEvent::SendUDP(addr, lane, data) => {
                     self.sent_msgs += 1;
                     // Get the token corresponding to the connection
                     if let Some(ref mut udp_state) = self.udp_state {
                         match data {
                             DispatchData::Serialised(frame) => {
-                                udp_state.enqueue_serialised(addr, frame);
+                                udp_state.enqueue_serialised(addr, lane, frame);
                             }
                             _ => {
                                 if let Err(e) =
                                     self.encode_buffer.get_buffer_encoder().and_then(|mut buf| {
                                         udp_state.enqueue_serialised(
                                             addr,
+                                            lane,
                                             data.into_serialised(&mut buf)?,
                                         );
                                         Ok(())
@@ -867,6 +1783,7 @@ impl NetworkThread {
                                 debug!(self.log, "UDP erro debug info: {:?}", e);
                             }
                         }
+                        self.forward_udp_events();
                     } else {
                         self.dispatcher_ref
                             .tell(DispatchEnvelope::Event(EventEnvelope::Network(
@@ -878,6 +1795,10 @@ impl NetworkThread {
                         );
                     }
                 }
+                DispatchEvent::SendUDS(path, data) => {
+                    self.sent_msgs += 1;
+                    self.send_uds(path, data);
+                }
                 DispatchEvent::Stop => {
                     self.stop();
                 }
@@ -889,8 +1810,157 @@ impl NetworkThread {
                     debug!(self.log, "Got DispatchEvent::ClosedAck({})", addr);
                     self.handle_closed_ack(addr);
                 }
+                DispatchEvent::Disconnect(addr) => {
+                    debug!(self.log, "Got DispatchEvent::Disconnect({})", addr);
+                    self.disconnect(addr);
+                }
+                DispatchEvent::Block(addr) => {
+                    debug!(self.log, "Got DispatchEvent::Block({})", addr);
+                    self.blocked.insert(addr);
+                    if let Some(ref mut udp_state) = self.udp_state {
+                        udp_state.block(addr);
+                    }
+                    self.disconnect(addr);
+                }
+                DispatchEvent::Unblock(addr) => {
+                    debug!(self.log, "Got DispatchEvent::Unblock({})", addr);
+                    self.blocked.remove(&addr);
+                    if let Some(ref mut udp_state) = self.udp_state {
+                        udp_state.unblock(&addr);
+                    }
+                }
+                DispatchEvent::MakeRoom(inbound) => {
+                    let max_connections = if inbound {
+                        self.network_config.get_max_inbound_connections()
+                    } else {
+                        self.network_config.get_max_outbound_connections()
+                    };
+                    if self.connections.full(inbound, max_connections) {
+                        self.evict_idle(inbound);
+                    }
+                }
+                #[cfg(feature = "tls")]
+                DispatchEvent::Resolved(hostname, addr) => {
+                    self.hostnames.insert(addr, hostname);
+                }
+                #[cfg(not(feature = "tls"))]
+                DispatchEvent::Resolved(..) => (),
+            }
+        }
+    }
+
+    /// Sends `data` on the TCP channel to `addr`, in its priority lane if `prioritised`
+    ///
+    /// Prioritised messages are never streamed, as their fragments would hold up the bulk data just the same.
+    /// Returns false if the channel is not connected and the data was rejected.
+    fn send_tcp(&mut self, addr: SocketAddr, data: DispatchData, prioritised: bool) -> bool {
+        use serialisation::ser_helpers::{framed_size_hint, serialise_msg_to_bytes};
+
+        self.sent_msgs += 1;
+        // Get the token corresponding to the connection
+        if let Some(channel) = self.channel_map.get_mut(&addr) {
+            // The stream is already set-up, buffer the package and wait for writable event
+            if channel.connected() && channel.idle_acked() {
+                // The NetworkDispatcher holds on to the message until the channel is established again
+                debug!(
+                    self.log,
+                    "Channel to {} is closing for being idle, rejecting the message", &addr
+                );
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::RejectedData(addr, data),
+                    )));
+            } else if channel.connected() && channel.pending_full() {
+                // The NetworkDispatcher holds on to the message until the channel caught up
+                debug!(
+                    self.log,
+                    "Channel to {} has too many frames waiting for credits, rejecting the message",
+                    &addr
+                );
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::RejectedData(addr, data),
+                    )));
+            } else if channel.connected() {
+                let blocked = match data {
+                    DispatchData::Serialised(frame) if prioritised => {
+                        channel.enqueue_prioritised(frame)
+                    }
+                    DispatchData::Serialised(frame) => channel.enqueue_serialised(frame),
+                    DispatchData::Lazy(ser, src, dst)
+                        if !prioritised
+                            && channel.may_stream(framed_size_hint(&src, &dst, &*ser)) =>
+                    {
+                        match serialise_msg_to_bytes(&src, &dst, &*ser) {
+                            Ok(frame) if !channel.streams(frame.len()) => {
+                                // Only unhinted messages turn out to be small enough
+                                channel.enqueue_serialised(SerialisedFrame::Bytes(frame))
+                            }
+                            Ok(frame) => channel.enqueue_streamed(frame).unwrap_or_else(|| {
+                                warn!(
+                                    self.log,
+                                    "Message to {} too large to be streamed, dropping it", &dst
+                                );
+                                false
+                            }),
+                            Err(e) => {
+                                warn!(self.log, "Error serialising message: {}", e);
+                                false
+                            }
+                        }
+                    }
+                    _ => {
+                        match self.encode_buffer.get_buffer_encoder().and_then(|mut buf| {
+                            let frame = data.into_serialised(&mut buf)?;
+                            Ok(if prioritised {
+                                channel.enqueue_prioritised(frame)
+                            } else {
+                                channel.enqueue_serialised(frame)
+                            })
+                        }) {
+                            Ok(blocked) => blocked,
+                            Err(e) => {
+                                warn!(self.log, "Error serialising message: {}", e);
+                                false
+                            }
+                        }
+                    }
+                };
+                if blocked {
+                    debug!(self.log, "Channel to {} ran out of credits", &addr);
+                    self.dispatcher_ref
+                        .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                            NetworkEvent::Connection(addr, ConnectionState::Blocked),
+                        )));
+                }
+            } else {
+                debug!(
+                    self.log,
+                    "Dispatch trying to route to non connected channel {:?}, rejecting the message",
+                    channel
+                );
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::RejectedData(addr, data),
+                    )));
+                return false;
             }
+        } else {
+            // The stream isn't set-up, request connection, set-it up and try to send the message
+            debug!(
+                self.log,
+                "Dispatch trying to route to unrecognized address {}, rejecting the message", addr
+            );
+            self.dispatcher_ref
+                .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                    NetworkEvent::RejectedData(addr, data),
+                )));
+            return false;
+        }
+        if let IOReturn::Close = self.try_write(&addr) {
+            self.close_channel(addr);
         }
+        true
     }
 
     fn close_channel(&mut self, addr: SocketAddr) -> () {
@@ -911,6 +1981,168 @@ impl NetworkThread {
         }
     }
 
+    /// Says Bye on the established channel to `addr` and closes it,
+    /// or drops the channel right away if it is still handshaking
+    fn disconnect(&mut self, addr: SocketAddr) -> () {
+        match self.channel_map.get_mut(&addr) {
+            Some(channel) if channel.connected() => {
+                channel.graceful_shutdown();
+                self.close_channel(addr);
+            }
+            Some(channel) if !channel.closed() => {
+                debug!(self.log, "Dropping handshaking channel to {}", &addr);
+                self.drop_channel(addr);
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::Connection(addr, ConnectionState::Closed),
+                    )));
+            }
+            _ => debug!(
+                self.log,
+                "No established channel to disconnect from {}", &addr
+            ),
+        }
+    }
+
+    /// Closes the idle channel to `addr`, or asks the remote host to agree first if it supports that
+    ///
+    /// Returns true if the channel was closed right away.
+    fn request_idle_close(&mut self, addr: SocketAddr) -> bool {
+        match self.channel_map.get_mut(&addr) {
+            Some(channel) if channel.agrees_on_idle() => {
+                channel.request_idle_close();
+                false
+            }
+            Some(_) => {
+                self.close_idle(addr);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Says Bye on the established channel to `addr` after it was idle, and closes it
+    ///
+    /// Unlike a [disconnect](NetworkThread::disconnect), neither host re-establishes the channel
+    /// before it has messages to send.
+    fn close_idle(&mut self, addr: SocketAddr) -> () {
+        if let Some(channel) = self.channel_map.get_mut(&addr) {
+            self.dispatcher_ref
+                .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                    NetworkEvent::Idle(addr),
+                )));
+            for unsent in channel.close_idle() {
+                self.dispatcher_ref
+                    .tell(DispatchEnvelope::Event(EventEnvelope::Network(
+                        NetworkEvent::RejectedData(addr, DispatchData::Serialised(unsent)),
+                    )));
+            }
+            self.close_channel(addr);
+        }
+    }
+
+    /// Counts another `inbound` or outbound channel towards the configured limit,
+    /// closing the least recently used idle channel in the same direction if necessary.
+    ///
+    /// Returns `None` if the limit is reached. Closing an idle channel to make room usually takes
+    /// the remote host's agreement, so the new channel only fits once that is done.
+    fn make_room(&mut self, inbound: bool) -> Option<ConnectionSlot> {
+        let max_connections = if inbound {
+            self.network_config.get_max_inbound_connections()
+        } else {
+            self.network_config.get_max_outbound_connections()
+        };
+        if let Some(slot) = self.connections.try_acquire(inbound, max_connections) {
+            return Some(slot);
+        }
+        match self.evict_idle(inbound) {
+            Some(true) => self.connections.try_acquire(inbound, max_connections),
+            Some(false) => None,
+            None => {
+                // The idle channels may belong to the other threads
+                if self.shards.len() > 1 {
+                    let mut shard = (self.next_evicting_shard + 1) % self.shards.len();
+                    if shard == self.shard {
+                        shard = (shard + 1) % self.shards.len();
+                    }
+                    self.next_evicting_shard = shard;
+                    let shard = &self.shards[shard];
+                    if shard.events.send(DispatchEvent::MakeRoom(inbound)).is_ok() {
+                        if let Err(e) = shard.waker.wake() {
+                            warn!(self.log, "Failed to wake network thread: {:?}", e);
+                        }
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Closes the least recently used idle `inbound` or outbound channel of this thread
+    ///
+    /// Returns `Some(true)` if a channel was closed right away, `Some(false)` if a channel is closing,
+    /// and `None` if none of the channels are idle.
+    fn evict_idle(&mut self, inbound: bool) -> Option<bool> {
+        let channels = self
+            .channel_map
+            .iter()
+            .filter(|(_, channel)| channel.inbound() == inbound && channel.holds_slot());
+        if channels.clone().any(|(_, channel)| channel.closing_idle()) {
+            // Room is being made already
+            return Some(false);
+        }
+        let (_, addr) = channels
+            .filter_map(|(addr, channel)| channel.idle_since().map(|since| (since, *addr)))
+            .min_by_key(|(since, _)| *since)?;
+        debug!(
+            self.log,
+            "Closing the idle channel to {} to make room", &addr
+        );
+        Some(self.request_idle_close(addr))
+    }
+
+    /// Closes the channel to `addr`, whose remote host broke the protocol for the given `reason`
+    ///
+    /// Channels which completed the handshake go through the closing procedure, such that
+    /// the `NetworkDispatcher` re-routes their queued frames. Any other channel is rejected.
+    fn drop_violating(&mut self, addr: SocketAddr, reason: &'static str) -> () {
+        match self.channel_map.get(&addr) {
+            Some(channel) if channel.connected() => {
+                warn!(self.log, "Closing channel to {}: {}", &addr, reason);
+                self.close_channel(addr);
+            }
+            Some(channel) if channel.closed() => {
+                warn!(self.log, "Dropping closed channel to {}: {}", &addr, reason);
+            }
+            _ => self.reject_channel(addr, protocol_violation(addr, reason)),
+        }
+    }
+
+    /// Drops a channel refused during the handshake without going through the closing procedure,
+    /// and informs the `NetworkDispatcher` with the `reason` for the refusal.
+    fn reject_channel(&mut self, addr: SocketAddr, reason: NetworkEvent) -> () {
+        if self.drop_channel(addr) {
+            warn!(self.log, "Dropping channel to {}: {:?}", &addr, &reason);
+            self.dispatcher_ref
+                .tell(DispatchEnvelope::Event(EventEnvelope::Network(reason)));
+        }
+    }
+
+    /// Drops a channel without going through the closing procedure.
+    ///
+    /// Returns false if there was no channel to `addr`.
+    fn drop_channel(&mut self, addr: SocketAddr) -> bool {
+        if let Some(mut channel) = self.channel_map.remove(&addr) {
+            self.token_map.remove(&channel.token);
+            let _ = self.poll.registry().deregister(channel.stream_mut());
+            channel.shutdown();
+            self.buffer_pool.return_buffer(channel.destroy());
+            true
+        } else {
+            false
+        }
+    }
+
     fn handle_closed_ack(&mut self, addr: SocketAddr) -> () {
         if let Some(channel) = self.channel_map.remove(&addr) {
             match channel.state {
@@ -946,12 +2178,27 @@ impl NetworkThread {
         if let Some(mut udp_state) = self.udp_state.take() {
             self.poll.registry().deregister(&mut udp_state.socket).ok();
             let count = udp_state.pending_messages();
+            let unacked = udp_state.unacked_messages();
+            let partial = udp_state.partial_messages();
             drop(udp_state);
             debug!(
                 self.log,
-                "Dropped its UDP socket with message count {}", count
+                "Dropped its UDP socket with message count {}, {} unacknowledged and {} partially received messages",
+                count,
+                unacked,
+                partial
             );
         }
+        #[cfg(unix)]
+        {
+            if let Some(uds_state) = self.uds_state.take() {
+                let count = uds_state.stop(self.poll.registry(), &mut self.buffer_pool);
+                debug!(
+                    self.log,
+                    "Dropped its UDS socket with message count {}", count
+                );
+            }
+        }
         self.stopped = true;
         debug!(self.log, "Stopped.");
     }
@@ -962,6 +2209,68 @@ impl NetworkThread {
     }
 }
 
+/// Forwards `messages` to the actors they are addressed to
+fn deliver_messages(
+    lookup: &ArcSwap<ActorStore>,
+    log: &KompactLogger,
+    messages: impl Iterator<Item = NetMessage>,
+) -> () {
+    use dispatch::lookup::{ActorLookup, LookupResult};
+
+    let lease_lookup = lookup.load();
+    for envelope in messages {
+        match lease_lookup.get_by_actor_path(&envelope.receiver) {
+            LookupResult::Ref(actor) => {
+                actor.enqueue(envelope);
+            }
+            LookupResult::Group(group) => {
+                group.route(envelope, log);
+            }
+            LookupResult::None => {
+                warn!(
+                    log,
+                    "Could not find actor reference for destination: {:?}, dropping message",
+                    envelope.receiver
+                );
+            }
+            LookupResult::Err(e) => {
+                error!(
+                    log,
+                    "An error occurred during local actor lookup for destination: {:?}, dropping message. The error was: {}",
+                    envelope.receiver,
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// The reason for rejecting a remote host which does not speak any protocol version we do
+fn incompatible_versions(addr: SocketAddr, remote: &ProtocolVersions) -> NetworkEvent {
+    let local = ProtocolVersions::local();
+    NetworkEvent::Connection(
+        addr,
+        ConnectionState::Error(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Incompatible protocol versions, remote host speaks {}..={}, we speak {}..={}",
+                remote.min, remote.max, local.min, local.max
+            ),
+        )),
+    )
+}
+
+/// The reason for rejecting a remote host which broke the protocol during the handshake
+fn protocol_violation(addr: SocketAddr, reason: &'static str) -> NetworkEvent {
+    NetworkEvent::Connection(
+        addr,
+        ConnectionState::Error(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Protocol violation: {}", reason),
+        )),
+    )
+}
+
 fn bind_with_retries(
     addr: &SocketAddr,
     retries: usize,
@@ -1005,12 +2314,23 @@ mod tests {
         }
     }
 
-    #[allow(unused_must_use)]
     fn setup_two_threads() -> (
         NetworkThread,
         Sender<DispatchEvent>,
         NetworkThread,
         Sender<DispatchEvent>,
+    ) {
+        setup_two_threads_with_config(NetworkConfig::default())
+    }
+
+    #[allow(unused_must_use)]
+    fn setup_two_threads_with_config(
+        network_config: NetworkConfig,
+    ) -> (
+        NetworkThread,
+        Sender<DispatchEvent>,
+        NetworkThread,
+        Sender<DispatchEvent>,
     ) {
         let mut cfg = KompactConfig::new();
         cfg.system_components(DeadletterBox::new, NetworkConfig::default().build());
@@ -1034,8 +2354,10 @@ mod tests {
             input_queue_1_receiver,
             dispatch_shutdown_sender1,
             dispatcher_ref.clone(),
-            NetworkConfig::default(),
-        );
+            Uuid::new_v4(),
+            network_config.clone(),
+        )
+        .expect("NetworkThread");
 
         let (network_thread2, _) = NetworkThread::new(
             logger,
@@ -1044,8 +2366,10 @@ mod tests {
             input_queue_2_receiver,
             dispatch_shutdown_sender2,
             dispatcher_ref,
-            NetworkConfig::default(),
-        );
+            Uuid::new_v4(),
+            network_config,
+        )
+        .expect("NetworkThread");
         (
             network_thread1,
             input_queue_1_sender,
@@ -1054,6 +2378,27 @@ mod tests {
         )
     }
 
+    #[test]
+    fn connection_slots_are_shared() -> () {
+        let counts = Arc::new(ConnectionCounts::default());
+        let first = counts
+            .try_acquire(true, Some(2))
+            .expect("first inbound slot");
+        let second = counts
+            .try_acquire(true, Some(2))
+            .expect("second inbound slot");
+        assert!(counts.try_acquire(true, Some(2)).is_none());
+        assert!(counts.full(true, Some(2)));
+        // The directions are counted apart
+        assert!(!counts.full(false, Some(2)));
+        assert!(counts.try_acquire(false, Some(1)).is_some());
+        drop(first);
+        assert!(!counts.full(true, Some(2)));
+        assert!(counts.try_acquire(true, Some(2)).is_some());
+        drop(second);
+        assert!(!counts.full(true, None));
+    }
+
     #[test]
     fn merge_connections_basic() -> () {
         // Sets up two NetworkThreads and does mutual connection request
@@ -1194,6 +2539,168 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flow_control_blocks_and_resumes() -> () {
+        // Sets up two NetworkThreads with a tiny credit window and sends more frames than it allows
+        let mut network_config = NetworkConfig::default();
+        network_config.set_flow_control_window(4);
+        let (mut thread1, _input_queue_1_sender, mut thread2, input_queue_2_sender) =
+            setup_two_threads_with_config(network_config);
+        let addr1 = thread1.addr;
+        let addr2 = thread2.addr;
+        // 2 Requests connection to 1, 1 accepts it and they complete the handshake
+        input_queue_2_sender.send(DispatchEvent::Connect(addr1));
+        thread2.receive_dispatch();
+        thread::sleep(Duration::from_millis(100));
+        thread1.accept_stream();
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(100));
+            poll_and_handle(&mut thread2);
+            thread::sleep(Duration::from_millis(100));
+            poll_and_handle(&mut thread1);
+        }
+        assert!(thread2.channel_map.get(&addr1).unwrap().connected());
+
+        let src: ActorPath =
+            UniquePath::new(Transport::TCP, addr2.ip(), addr2.port(), Uuid::new_v4()).into();
+        let dst: ActorPath =
+            UniquePath::new(Transport::TCP, addr1.ip(), addr1.port(), Uuid::new_v4()).into();
+        for _ in 0..10 {
+            input_queue_2_sender.send(DispatchEvent::SendTCP(
+                addr1,
+                DispatchData::Lazy(Box::new("flow".to_string()), src.clone(), dst.clone()),
+            ));
+        }
+        thread2.receive_dispatch();
+        // Only the initial window may be sent, as many frames are held back,
+        // and the rest is handed back to the dispatcher
+        assert!(thread2.channel_map.get(&addr1).unwrap().blocked());
+        assert!(thread2.channel_map.get(&addr1).unwrap().pending_full());
+
+        // 1 consumes the frames and hands out new credits until 2 has caught up
+        for _ in 0..5 {
+            thread::sleep(Duration::from_millis(100));
+            poll_and_handle(&mut thread1);
+            thread::sleep(Duration::from_millis(100));
+            poll_and_handle(&mut thread2);
+        }
+        assert!(!thread2.channel_map.get(&addr1).unwrap().blocked());
+        // Start, CreditUpdate, StreamRequest and the 8 Data frames which were not handed back
+        assert_eq!(thread1.channel_map.get(&addr2).unwrap().messages, 11);
+    }
+
+    #[test]
+    fn incompatible_protocol_version_rejected() -> () {
+        use bytes::BytesMut;
+        use std::io::Write;
+
+        let (mut thread1, _input_queue_1_sender, _thread2, _input_queue_2_sender) =
+            setup_two_threads();
+        // Connect with a raw stream which only speaks a future protocol version
+        let mut stream = std::net::TcpStream::connect(thread1.addr).expect("connect");
+        thread::sleep(Duration::from_millis(100));
+        thread1.accept_stream();
+        assert_eq!(thread1.channel_map.len(), 1);
+
+        let versions = ProtocolVersions::new(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 1, 0);
+        let mut start = Frame::Start(Start::new(
+            versions,
+            stream.local_addr().unwrap(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Vec::new(),
+            Vec::new(),
+        ));
+        let mut bytes = BytesMut::with_capacity(start.encoded_len() + FRAME_HEAD_LEN as usize);
+        start.encode_into(&mut bytes).expect("encode Start");
+        stream.write_all(&bytes).expect("write Start");
+        thread::sleep(Duration::from_millis(100));
+        poll_and_handle(&mut thread1);
+
+        // The channel must be dropped instead of started
+        assert!(thread1.channel_map.is_empty());
+        assert!(thread1.token_map.is_empty());
+    }
+
+    #[test]
+    fn data_before_handshake_rejected() -> () {
+        use bytes::BytesMut;
+        use serialisation::ser_helpers::serialise_msg_to_bytes;
+        use std::io::Write;
+
+        let (_thread1, _input_queue_1_sender, mut thread2, input_queue_2_sender) =
+            setup_two_threads();
+        // 2 connects to a raw listener, which says Hello and sends data without waiting for the Start
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("listener");
+        let raw_addr = listener.local_addr().unwrap();
+        input_queue_2_sender.send(DispatchEvent::Connect(raw_addr));
+        thread2.receive_dispatch();
+        let (mut stream, _) = listener.accept().expect("accepted connection");
+        assert!(thread2.channel_map.contains_key(&raw_addr));
+
+        let mut hello = Frame::Hello(Hello::new(
+            ProtocolVersions::local(),
+            raw_addr,
+            Uuid::new_v4(),
+            Vec::new(),
+        ));
+        let mut bytes = BytesMut::with_capacity(hello.encoded_len() + FRAME_HEAD_LEN as usize);
+        hello.encode_into(&mut bytes).expect("encode Hello");
+        stream.write_all(&bytes).expect("write Hello");
+        let src: ActorPath = UniquePath::new(
+            Transport::TCP,
+            raw_addr.ip(),
+            raw_addr.port(),
+            Uuid::new_v4(),
+        )
+        .into();
+        let dst: ActorPath = UniquePath::new(
+            Transport::TCP,
+            thread2.addr.ip(),
+            thread2.addr.port(),
+            Uuid::new_v4(),
+        )
+        .into();
+        let data = serialise_msg_to_bytes(&src, &dst, &"early".to_string()).expect("serialise");
+        stream.write_all(&data).expect("write Data");
+        thread::sleep(Duration::from_millis(100));
+        poll_and_handle(&mut thread2);
+
+        // The channel must be dropped before the data frame is delivered
+        assert!(!thread2.channel_map.contains_key(&raw_addr));
+        assert!(thread2.token_map.is_empty());
+    }
+
+    #[test]
+    fn first_protocol_version_rejected() -> () {
+        use bytes::{BufMut, BytesMut};
+        use std::io::Write;
+
+        let (_thread1, _input_queue_1_sender, mut thread2, input_queue_2_sender) =
+            setup_two_threads();
+        // 2 connects to a raw listener, which says Hello in the layout of the first protocol version
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("listener");
+        let raw_addr = listener.local_addr().unwrap();
+        input_queue_2_sender.send(DispatchEvent::Connect(raw_addr));
+        thread2.receive_dispatch();
+        let (mut stream, _) = listener.accept().expect("accepted connection");
+        assert!(thread2.channel_map.contains_key(&raw_addr));
+
+        let mut bytes = BytesMut::with_capacity(FRAME_HEAD_LEN as usize + 1 + 4 + 2);
+        FrameHead::new(FrameType::Hello, 1 + 4 + 2).encode_into(&mut bytes);
+        bytes.put_u8(4);
+        bytes.put_slice(&[127, 0, 0, 1]);
+        bytes.put_u16(raw_addr.port());
+        stream.write_all(&bytes).expect("write Hello");
+        thread::sleep(Duration::from_millis(100));
+        thread2.try_read(&raw_addr);
+        // The address family and ip are taken for the versions, which must be refused before decoding further
+        match thread2.decode(&raw_addr) {
+            IOReturn::Incompatible(versions) => assert_eq!((4, 127), (versions.min, versions.max)),
+            ret => panic!("Expected the Hello to be incompatible, got {:?}", ret),
+        }
+    }
+
     #[test]
     fn network_thread_custom_buffer_config() -> () {
         let addr = "127.0.0.1:0".parse().expect("Address should work");
@@ -1224,8 +2731,10 @@ mod tests {
             input_queue_1_receiver,
             dispatch_shutdown_sender1,
             dispatcher_ref,
+            Uuid::new_v4(),
             network_config,
-        );
+        )
+        .expect("NetworkThread");
         // Assert that the buffer_pool is created correctly
         let (pool_size, _) = network_thread.buffer_pool.get_pool_sizes();
         assert_eq!(pool_size, 13); // initial_pool_size
@@ -0,0 +1,363 @@
+use super::*;
+use bytes::{BufMut, Bytes, BytesMut};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// How far beyond the last gap-free frame a receiver accepts frames.
+/// Frames further ahead are dropped without acknowledgement and will be retransmitted.
+const RECEIVE_WINDOW: u64 = 4096;
+/// The number of frames beyond the cumulative acknowledgement covered by the bitmap
+const ACK_BITMAP_LEN: u64 = 64;
+/// Retransmission intervals stop doubling after this many retransmissions
+const MAX_BACKOFF_SHIFT: u8 = 6;
+
+/// The sending side of reliable UDP towards a single remote host
+///
+/// Numbers every frame and keeps it until the remote host acknowledges it,
+/// retransmitting it whenever its retransmission interval runs out.
+/// At most `window` frames are unacknowledged at once, the frames beyond wait until acknowledgements free up the window.
+pub(super) struct ReliableSender {
+    session: u64,
+    next_seq: u64,
+    /// The last frame sent on each lane, so the receiver can order the frames per lane
+    lanes: FxHashMap<u64, u64>,
+    unacked: BTreeMap<u64, InFlight>,
+    window: usize,
+    /// Frames waiting for room in the window, with their lanes
+    waiting: VecDeque<(u64, Bytes)>,
+}
+
+struct InFlight {
+    datagram: Bytes,
+    sent_at: Instant,
+    retransmits: u8,
+}
+
+impl InFlight {
+    fn deadline(&self, interval: Duration) -> Instant {
+        self.sent_at + interval * (1 << self.retransmits.min(MAX_BACKOFF_SHIFT))
+    }
+}
+
+impl ReliableSender {
+    /// Starts a new session, numbering the frames from 1
+    ///
+    /// The `window` is capped at what the receiver accepts beyond its last gap-free frame.
+    pub(super) fn new(window: usize) -> Self {
+        ReliableSender {
+            session: Uuid::new_v4().as_u128() as u64,
+            next_seq: 1,
+            lanes: FxHashMap::default(),
+            unacked: BTreeMap::new(),
+            window: window.clamp(1, RECEIVE_WINDOW as usize),
+            waiting: VecDeque::new(),
+        }
+    }
+
+    /// Returns the number of frames which have not been acknowledged yet
+    pub(super) fn in_flight(&self) -> usize {
+        self.unacked.len()
+    }
+
+    /// Returns the number of frames waiting for room in the window
+    pub(super) fn waiting(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Returns the datagram to send for `payload` on `lane`,
+    /// or `None` if the window is full and the frame has to wait for acknowledgements
+    pub(super) fn send(&mut self, lane: u64, payload: &[u8], now: Instant) -> Option<Bytes> {
+        if self.unacked.len() < self.window && self.waiting.is_empty() {
+            Some(self.sequence(lane, payload, now))
+        } else {
+            self.waiting
+                .push_back((lane, Bytes::copy_from_slice(payload)));
+            None
+        }
+    }
+
+    /// Numbers `payload` as the next frame on `lane` and returns the datagram to send
+    fn sequence(&mut self, lane: u64, payload: &[u8], now: Instant) -> Bytes {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let prev = self.lanes.insert(lane, seq).unwrap_or(0);
+        let content_length = Sequenced::HEADER_LEN + payload.len();
+        let mut buf = BytesMut::with_capacity(FRAME_HEAD_LEN as usize + content_length);
+        FrameHead::new(FrameType::Sequenced, content_length).encode_into(&mut buf);
+        buf.put_u64(self.session);
+        buf.put_u64(seq);
+        buf.put_u64(prev);
+        buf.put_slice(payload);
+        let datagram = buf.freeze();
+        self.unacked.insert(
+            seq,
+            InFlight {
+                datagram: datagram.clone(),
+                sent_at: now,
+                retransmits: 0,
+            },
+        );
+        datagram
+    }
+
+    /// Forgets all frames acknowledged by `ack`,
+    /// and returns the datagrams for the waiting frames which fit into the window now
+    pub(super) fn acknowledge(&mut self, ack: &SelectiveAck, now: Instant) -> Vec<Bytes> {
+        if ack.session != self.session {
+            return Vec::new();
+        }
+        self.unacked = self.unacked.split_off(&(ack.cumulative + 1));
+        for i in 0..ACK_BITMAP_LEN {
+            if ack.bitmap & (1 << i) != 0 {
+                self.unacked.remove(&(ack.cumulative + 2 + i));
+            }
+        }
+        let mut released = Vec::new();
+        while self.unacked.len() < self.window {
+            match self.waiting.pop_front() {
+                Some((lane, payload)) => released.push(self.sequence(lane, &payload, now)),
+                None => break,
+            }
+        }
+        released
+    }
+
+    /// Returns the datagrams whose retransmission interval ran out by `now`
+    ///
+    /// Returns `Err` with the number of unacknowledged and waiting frames if one of them
+    /// has already been retransmitted `max_retransmits` times.
+    pub(super) fn due(
+        &mut self,
+        now: Instant,
+        interval: Duration,
+        max_retransmits: u8,
+    ) -> Result<Vec<Bytes>, usize> {
+        let mut due = Vec::new();
+        for in_flight in self.unacked.values_mut() {
+            if in_flight.deadline(interval) <= now {
+                if in_flight.retransmits >= max_retransmits {
+                    return Err(self.unacked.len() + self.waiting.len());
+                }
+                in_flight.retransmits += 1;
+                in_flight.sent_at = now;
+                due.push(in_flight.datagram.clone());
+            }
+        }
+        Ok(due)
+    }
+
+    /// Returns when the next retransmission is due, if any frames are unacknowledged
+    pub(super) fn next_deadline(&self, interval: Duration) -> Option<Instant> {
+        self.unacked
+            .values()
+            .map(|in_flight| in_flight.deadline(interval))
+            .min()
+    }
+}
+
+/// The receiving side of reliable UDP from a single remote host
+///
+/// Drops duplicate frames and holds back each frame until the previous frame on its lane
+/// has been delivered, such that frames on different lanes never wait for each other.
+pub(super) struct ReliableReceiver<M> {
+    session: u64,
+    /// All frames up to and including this one have been received
+    cumulative: u64,
+    /// Received frames beyond `cumulative + 1`
+    received: BTreeSet<u64>,
+    /// Received frames waiting for their predecessor, keyed by the predecessor
+    waiting: FxHashMap<u64, (u64, M)>,
+    waiting_seqs: FxHashSet<u64>,
+}
+
+impl<M> ReliableReceiver<M> {
+    pub(super) fn new(session: u64) -> Self {
+        ReliableReceiver {
+            session,
+            cumulative: 0,
+            received: BTreeSet::new(),
+            waiting: FxHashMap::default(),
+            waiting_seqs: FxHashSet::default(),
+        }
+    }
+
+    /// The session the received frames are numbered in
+    pub(super) fn session(&self) -> u64 {
+        self.session
+    }
+
+    /// Records the receipt of frame `seq`
+    ///
+    /// Returns `false` if the frame is a duplicate or too far ahead, and should thus be dropped.
+    pub(super) fn accept(&mut self, seq: u64) -> bool {
+        if seq <= self.cumulative
+            || seq > self.cumulative + RECEIVE_WINDOW
+            || self.received.contains(&seq)
+        {
+            return false;
+        }
+        if seq == self.cumulative + 1 {
+            self.cumulative = seq;
+            while self.received.remove(&(self.cumulative + 1)) {
+                self.cumulative += 1;
+            }
+        } else {
+            self.received.insert(seq);
+        }
+        true
+    }
+
+    /// Passes the accepted frame `seq` to `deliver`, unless its predecessor `prev` has not been delivered yet,
+    /// in which case the frame is held back until then
+    pub(super) fn deliver<F>(&mut self, seq: u64, prev: u64, msg: M, mut deliver: F) -> ()
+    where
+        F: FnMut(M),
+    {
+        if prev != 0 && !self.is_delivered(prev) {
+            self.waiting.insert(prev, (seq, msg));
+            self.waiting_seqs.insert(seq);
+            return;
+        }
+        deliver(msg);
+        let mut delivered = seq;
+        while let Some((next, msg)) = self.waiting.remove(&delivered) {
+            self.waiting_seqs.remove(&next);
+            deliver(msg);
+            delivered = next;
+        }
+    }
+
+    /// Returns the acknowledgement for all frames received so far
+    pub(super) fn ack(&self) -> SelectiveAck {
+        let mut bitmap = 0u64;
+        for seq in self
+            .received
+            .range((self.cumulative + 2)..(self.cumulative + 2 + ACK_BITMAP_LEN))
+        {
+            bitmap |= 1 << (seq - self.cumulative - 2);
+        }
+        SelectiveAck::new(self.session, self.cumulative, bitmap)
+    }
+
+    fn is_delivered(&self, seq: u64) -> bool {
+        (seq <= self.cumulative || self.received.contains(&seq))
+            && !self.waiting_seqs.contains(&seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn seq_of(datagram: &Bytes) -> u64 {
+        let offset = FRAME_HEAD_LEN as usize + 8;
+        u64::from_be_bytes(datagram[offset..offset + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn sender_forgets_acknowledged_frames() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(16);
+        for i in 0..5u8 {
+            assert!(sender.send(0, &[i], now).is_some());
+        }
+        assert_eq!(5, sender.in_flight());
+        // 1 and 2 received, 3 lost, 4 received
+        assert!(sender
+            .acknowledge(&SelectiveAck::new(sender.session, 2, 0b01), now)
+            .is_empty());
+        assert_eq!(2, sender.in_flight());
+        // Acks for other sessions are ignored
+        sender.acknowledge(&SelectiveAck::new(sender.session + 1, 5, 0), now);
+        assert_eq!(2, sender.in_flight());
+
+        let due = sender.due(now + INTERVAL, INTERVAL, 3).unwrap();
+        let due_seqs: Vec<u64> = due.iter().map(seq_of).collect();
+        assert_eq!(vec![3, 5], due_seqs);
+        // The interval doubles after a retransmission
+        assert!(sender
+            .due(now + INTERVAL * 2, INTERVAL, 3)
+            .unwrap()
+            .is_empty());
+        assert_eq!(Some(now + INTERVAL * 3), sender.next_deadline(INTERVAL));
+    }
+
+    #[test]
+    fn sender_gives_up_after_max_retransmits() {
+        let mut now = Instant::now();
+        let mut sender = ReliableSender::new(1);
+        sender.send(0, &[1], now);
+        sender.send(0, &[2], now);
+        for _ in 0..2 {
+            now = sender.next_deadline(INTERVAL).unwrap();
+            assert_eq!(1, sender.due(now, INTERVAL, 2).unwrap().len());
+        }
+        now = sender.next_deadline(INTERVAL).unwrap();
+        // The waiting frame is given up on as well
+        assert_eq!(Err(2), sender.due(now, INTERVAL, 2));
+    }
+
+    #[test]
+    fn sender_waits_for_room_in_the_window() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(2);
+        let sent: Vec<Option<Bytes>> = (0..5u8).map(|i| sender.send(0, &[i], now)).collect();
+        assert!(sent[0].is_some() && sent[1].is_some());
+        assert!(sent[2..].iter().all(Option::is_none));
+        assert_eq!(2, sender.in_flight());
+        assert_eq!(3, sender.waiting());
+
+        let released = sender.acknowledge(&SelectiveAck::new(sender.session, 1, 0), now);
+        assert_eq!(vec![3], released.iter().map(seq_of).collect::<Vec<u64>>());
+        let released = sender.acknowledge(&SelectiveAck::new(sender.session, 3, 0), now);
+        assert_eq!(
+            vec![4, 5],
+            released.iter().map(seq_of).collect::<Vec<u64>>()
+        );
+        assert_eq!(2, sender.in_flight());
+        assert_eq!(0, sender.waiting());
+    }
+
+    #[test]
+    fn receiver_drops_duplicates_and_acks_selectively() {
+        let mut receiver = ReliableReceiver::<u64>::new(7);
+        assert!(receiver.accept(1));
+        assert!(receiver.accept(3));
+        assert!(receiver.accept(5));
+        assert!(!receiver.accept(3));
+        assert!(!receiver.accept(1));
+        let ack = receiver.ack();
+        assert_eq!(7, ack.session);
+        assert_eq!(1, ack.cumulative);
+        assert_eq!(0b101, ack.bitmap);
+        assert!(receiver.accept(2));
+        let ack = receiver.ack();
+        assert_eq!(3, ack.cumulative);
+        assert_eq!(0b1, ack.bitmap);
+        assert!(!receiver.accept(3 + RECEIVE_WINDOW + 1));
+    }
+
+    #[test]
+    fn receiver_orders_per_lane() {
+        let mut receiver = ReliableReceiver::<u64>::new(7);
+        let mut delivered = Vec::new();
+        // Lane A: 1 -> 3 -> 4, lane B: 2 -> 5
+        // Frame 1 is lost at first.
+        for (seq, prev) in [(3, 1), (2, 0), (4, 3), (5, 2)].iter() {
+            assert!(receiver.accept(*seq));
+            receiver.deliver(*seq, *prev, *seq, |msg| delivered.push(msg));
+        }
+        // Lane B is not blocked by the loss on lane A
+        assert_eq!(vec![2, 5], delivered);
+        assert!(receiver.accept(1));
+        receiver.deliver(1, 0, 1, |msg| delivered.push(msg));
+        assert_eq!(vec![2, 5, 1, 3, 4], delivered);
+    }
+}
@@ -0,0 +1,238 @@
+//! Resolution of the hostnames in [system paths](crate::prelude::SystemPath)
+//!
+//! Paths may address remote systems by hostname instead of IP address.
+//! The [NetworkDispatcher](crate::dispatch::NetworkDispatcher) resolves those hostnames
+//! with the [Resolver] in its [NetworkConfig](crate::dispatch::NetworkConfig) before connecting,
+//! and caches the results for a while. Cached addresses are re-resolved once connecting to them fails.
+//! Lookups run on a separate thread, while the dispatcher holds back messages for the hostname.
+use futures::channel::oneshot;
+use rustc_hash::FxHashMap;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Looks up the socket addresses of remote hosts by their hostname
+pub trait Resolver: Send + Sync + Debug + 'static {
+    /// Returns the socket addresses `host` can be reached at on `port`
+    ///
+    /// Resolving may block, as it is never called on the dispatcher's thread.
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>>;
+}
+
+/// Resolves hostnames with the name service of the operating system
+///
+/// This is the default [Resolver].
+#[derive(Clone, Debug, Default)]
+pub struct DnsResolver;
+
+impl Resolver for DnsResolver {
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        (host, port).to_socket_addrs().map(|addrs| addrs.collect())
+    }
+}
+
+/// Resolves hostnames from a fixed in-memory table
+///
+/// Mostly useful for tests.
+///
+/// # Example
+///
+/// ```
+/// use kompact::{net::resolver::StaticResolver, prelude::*};
+/// use std::sync::Arc;
+///
+/// let mut resolver = StaticResolver::new();
+/// resolver.insert("localhost", "127.0.0.1".parse().unwrap());
+/// let mut conf = NetworkConfig::default();
+/// conf.set_resolver(Arc::new(resolver));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StaticResolver {
+    table: HashMap<String, Vec<IpAddr>>,
+}
+
+impl StaticResolver {
+    /// Create a new resolver with an empty table
+    pub fn new() -> Self {
+        StaticResolver::default()
+    }
+
+    /// Resolves `host` to `ip`, in addition to any addresses inserted before
+    pub fn insert<S: Into<String>>(&mut self, host: S, ip: IpAddr) {
+        self.table
+            .entry(host.into().to_ascii_lowercase())
+            .or_default()
+            .push(ip);
+    }
+}
+
+impl Resolver for StaticResolver {
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        match self.table.get(&host.to_ascii_lowercase()) {
+            Some(ips) => Ok(ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect()),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Unknown host {}", host),
+            )),
+        }
+    }
+}
+
+struct CachedAddr {
+    addr: SocketAddr,
+    expires: Instant,
+}
+
+/// Caches the addresses a [Resolver] returned until their time-to-live runs out
+pub(crate) struct ResolverCache {
+    resolver: Arc<dyn Resolver>,
+    ttl: Duration,
+    entries: FxHashMap<(String, u16), CachedAddr>,
+}
+
+impl ResolverCache {
+    pub(crate) fn new(resolver: Arc<dyn Resolver>, ttl: Duration) -> Self {
+        ResolverCache {
+            resolver,
+            ttl,
+            entries: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the cached address for `host` on `port`, if any, without resolving it
+    ///
+    /// The entry may have expired already, see [expired](ResolverCache::expired).
+    pub(crate) fn cached(&self, host: &str, port: u16) -> Option<SocketAddr> {
+        self.entries
+            .get(&(host.to_string(), port))
+            .map(|entry| entry.addr)
+    }
+
+    /// Returns `true` if there is no live cache entry for `host` on `port` at `now`
+    pub(crate) fn expired(&self, host: &str, port: u16, now: Instant) -> bool {
+        self.entries
+            .get(&(host.to_string(), port))
+            .filter(|entry| entry.expires > now)
+            .is_none()
+    }
+
+    /// Asks the resolver for the addresses of `host` on `port` on a separate thread
+    ///
+    /// Resolvers may block for a long time, so they must never be called on the dispatcher's thread.
+    /// The returned handle completes with the result, which should be passed to
+    /// [insert](ResolverCache::insert).
+    pub(crate) fn resolve_in_background(
+        &self,
+        host: String,
+        port: u16,
+    ) -> oneshot::Receiver<io::Result<Vec<SocketAddr>>> {
+        let (tx, rx) = oneshot::channel();
+        let resolver = self.resolver.clone();
+        // If the thread can not be spawned, the sender is dropped and the receiver cancelled
+        let _ = thread::Builder::new()
+            .name("kompact-resolver".to_string())
+            .spawn(move || {
+                let _ = tx.send(resolver.resolve(&host, port));
+            });
+        rx
+    }
+
+    /// Caches the first of the resolved `addrs` for `host` on `port` and returns it
+    pub(crate) fn insert(
+        &mut self,
+        host: String,
+        port: u16,
+        addrs: Vec<SocketAddr>,
+        now: Instant,
+    ) -> io::Result<SocketAddr> {
+        let addr = addrs.into_iter().next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No addresses for host {}", host),
+            )
+        })?;
+        let expires = now + self.ttl;
+        self.entries
+            .insert((host, port), CachedAddr { addr, expires });
+        Ok(addr)
+    }
+
+    /// Forgets all hostnames resolving to `addr`, so they are resolved again on next use
+    ///
+    /// Returns `true` if any entries were forgotten.
+    pub(crate) fn invalidate(&mut self, addr: SocketAddr) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.addr != addr);
+        self.entries.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    struct CountingResolver {
+        inner: StaticResolver,
+        lookups: AtomicUsize,
+    }
+
+    impl Resolver for CountingResolver {
+        fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.inner.resolve(host, port)
+        }
+    }
+
+    #[test]
+    fn static_resolver_resolves_known_hosts() {
+        let mut resolver = StaticResolver::new();
+        resolver.insert("MyHost", "10.0.0.1".parse().unwrap());
+        let addrs = resolver.resolve("myhost", 1234).expect("resolved");
+        assert_eq!(addrs, vec!["10.0.0.1:1234".parse().unwrap()]);
+        assert!(resolver.resolve("otherhost", 1234).is_err());
+    }
+
+    #[test]
+    fn resolver_cache_expires_and_invalidates_entries() {
+        let mut inner = StaticResolver::new();
+        inner.insert("myhost", "10.0.0.1".parse().unwrap());
+        let resolver = Arc::new(CountingResolver {
+            inner,
+            lookups: AtomicUsize::new(0),
+        });
+        let mut cache = ResolverCache::new(resolver.clone(), Duration::from_secs(10));
+        let now = Instant::now();
+        assert!(cache.expired("myhost", 1234, now));
+        let addrs = futures::executor::block_on(cache.resolve_in_background("myhost".into(), 1234))
+            .expect("completed")
+            .expect("resolved");
+        let addr = cache
+            .insert("myhost".into(), 1234, addrs, now)
+            .expect("an address");
+        assert_eq!(addr, "10.0.0.1:1234".parse().unwrap());
+        assert_eq!(resolver.lookups.load(Ordering::SeqCst), 1);
+        assert!(!cache.expired("myhost", 1234, now));
+        assert!(cache.expired("myhost", 1234, now + Duration::from_secs(11)));
+
+        assert_eq!(cache.cached("myhost", 1234), Some(addr));
+        assert!(cache.invalidate(addr));
+        assert!(!cache.invalidate(addr));
+        assert_eq!(cache.cached("myhost", 1234), None);
+        assert!(cache
+            .insert("myhost".into(), 1234, Vec::new(), now)
+            .is_err());
+        assert!(
+            futures::executor::block_on(cache.resolve_in_background("otherhost".into(), 1234))
+                .expect("completed")
+                .is_err()
+        );
+    }
+}
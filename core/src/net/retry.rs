@@ -0,0 +1,280 @@
+//! Strategies for re-establishing lost or failed connections
+//!
+//! The [NetworkDispatcher](crate::dispatch::NetworkDispatcher) keeps track of the retries
+//! for each remote address separately. After every attempt at connecting to an address, it asks its
+//! [RetryPolicy] how long to wait before checking on the connection, and whether to try again
+//! if the connection has not been established by then.
+//! Once the policy runs out of attempts, the dispatcher gives up on the address
+//! and drops all messages queued for it.
+//!
+//! The policy is chosen via [BridgeConfig::set_retry_strategy](crate::net::BridgeConfig::set_retry_strategy)
+//! or the `bridge_config.retry_strategy` section of the system's HOCON config.
+use crate::utils::random_fraction;
+use hocon::Hocon;
+use std::{convert::TryFrom, fmt::Debug, sync::Arc, time::Duration};
+
+/// Decides when and how often to retry connecting to a remote address
+pub trait RetryPolicy: Send + Sync + Debug + 'static {
+    /// Returns how long to wait after the `attempt`-th attempt at connecting before checking on the connection
+    ///
+    /// The initial attempt is attempt `0`, the first retry is attempt `1`, and so on.
+    fn retry_delay(&self, attempt: u32) -> Duration;
+
+    /// Returns the number of retries after which to give up, or `None` to retry forever
+    fn max_attempts(&self) -> Option<u32>;
+}
+
+/// The retry strategies that come with Kompact, and a way to plug in custom ones
+///
+/// # Example
+///
+/// ```
+/// use kompact::{
+///     net::{retry::RetryStrategy, BridgeConfig},
+///     prelude::*,
+/// };
+/// use std::time::Duration;
+///
+/// let mut bridge_config = BridgeConfig::new();
+/// bridge_config.set_retry_strategy(RetryStrategy::ExponentialBackoff {
+///     base: Duration::from_millis(100),
+///     max_delay: Duration::from_secs(10),
+///     jitter: 0.2,
+///     max_attempts: None,
+/// });
+/// let mut conf = NetworkConfig::default();
+/// conf.set_bridge_config(bridge_config);
+/// ```
+///
+/// The same strategy can be configured in HOCON:
+///
+/// ```text
+/// bridge_config.retry_strategy {
+///     type = exponential
+///     base = 100 ms
+///     max_delay = 10 s
+///     jitter = 0.2
+/// }
+/// ```
+///
+/// Durations without a unit are in milliseconds.
+/// Omitting `max_attempts` retries forever.
+/// A `fixed` strategy takes an `interval` instead of `base`, `max_delay`, and `jitter`.
+#[derive(Clone, Debug)]
+pub enum RetryStrategy {
+    /// Waits the same `interval` after every attempt
+    Fixed {
+        /// The time to wait after every attempt
+        interval: Duration,
+        /// The number of retries after which to give up, or `None` to retry forever
+        max_attempts: Option<u32>,
+    },
+    /// Doubles the time to wait after every attempt, starting at `base`, up to at most `max_delay`
+    ExponentialBackoff {
+        /// The time to wait after the initial attempt
+        base: Duration,
+        /// The maximum time to wait after any attempt
+        max_delay: Duration,
+        /// The fraction of each delay, between `0.0` and `1.0`, which is randomly shaved off it
+        ///
+        /// This keeps systems which lost their connections at the same time
+        /// from all retrying at the same time, too.
+        jitter: f64,
+        /// The number of retries after which to give up, or `None` to retry forever
+        max_attempts: Option<u32>,
+    },
+    /// A user-defined policy
+    Custom(Arc<dyn RetryPolicy>),
+}
+
+impl RetryStrategy {
+    /// Tries to read a strategy from the `bridge_config.retry_strategy` section of the given `config`
+    ///
+    /// Returns `None` if the section is missing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the section names an unknown `type` of strategy, lacks one of its required durations,
+    /// or has a `max_attempts` which is negative or too large.
+    pub fn from_config(config: &Hocon) -> Option<Self> {
+        let section = &config["bridge_config"]["retry_strategy"];
+        let strategy_type = section["type"].as_string()?;
+        let duration = |key: &str| {
+            section[key]
+                .as_milliseconds()
+                .map(|millis| Duration::from_micros((millis * 1000.0) as u64))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "bridge_config.retry_strategy.{} must be a duration for a {} strategy",
+                        key, strategy_type
+                    )
+                })
+        };
+        let max_attempts = section["max_attempts"].as_i64().map(|attempts| {
+            u32::try_from(attempts).unwrap_or_else(|_| {
+                panic!(
+                    "bridge_config.retry_strategy.max_attempts must be between 0 and {}, not {}",
+                    u32::MAX,
+                    attempts
+                )
+            })
+        });
+        let strategy = match strategy_type.as_str() {
+            "fixed" => RetryStrategy::Fixed {
+                interval: duration("interval"),
+                max_attempts,
+            },
+            "exponential" => RetryStrategy::ExponentialBackoff {
+                base: duration("base"),
+                max_delay: duration("max_delay"),
+                jitter: section["jitter"].as_f64().unwrap_or(0.0),
+                max_attempts,
+            },
+            other => panic!(
+                "Unknown bridge_config.retry_strategy.type {}, expected fixed or exponential",
+                other
+            ),
+        };
+        strategy.validate();
+        Some(strategy)
+    }
+
+    /// Performs basic sanity checks on the strategy and panics if it is invalid.
+    pub fn validate(&self) {
+        if let RetryStrategy::ExponentialBackoff {
+            base,
+            max_delay,
+            jitter,
+            ..
+        } = self
+        {
+            if base > max_delay {
+                panic!("base may not be greater than max_delay");
+            }
+            if !(0.0..=1.0).contains(jitter) {
+                panic!("jitter must be between 0.0 and 1.0");
+            }
+        }
+    }
+}
+
+impl RetryPolicy for RetryStrategy {
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        match self {
+            RetryStrategy::Fixed { interval, .. } => *interval,
+            RetryStrategy::ExponentialBackoff {
+                base,
+                max_delay,
+                jitter,
+                ..
+            } => {
+                let factor = 2u32.saturating_pow(attempt.min(31));
+                let delay = base
+                    .checked_mul(factor)
+                    .map_or(*max_delay, |delay| delay.min(*max_delay));
+                delay.mul_f64(1.0 - jitter * random_fraction())
+            }
+            RetryStrategy::Custom(policy) => policy.retry_delay(attempt),
+        }
+    }
+
+    fn max_attempts(&self) -> Option<u32> {
+        match self {
+            RetryStrategy::Fixed { max_attempts, .. } => *max_attempts,
+            RetryStrategy::ExponentialBackoff { max_attempts, .. } => *max_attempts,
+            RetryStrategy::Custom(policy) => policy.max_attempts(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hocon::HoconLoader;
+
+    fn load(config: &str) -> Hocon {
+        HoconLoader::new()
+            .load_str(config)
+            .expect("config")
+            .hocon()
+            .expect("hocon")
+    }
+
+    #[test]
+    fn exponential_backoff_is_capped() {
+        let strategy = RetryStrategy::ExponentialBackoff {
+            base: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            jitter: 0.0,
+            max_attempts: None,
+        };
+        let delays: Vec<u128> = (0..6)
+            .map(|attempt| strategy.retry_delay(attempt).as_millis())
+            .chain(std::iter::once(strategy.retry_delay(u32::MAX).as_millis()))
+            .collect();
+        assert_eq!(vec![100, 200, 400, 800, 1000, 1000, 1000], delays);
+        assert_eq!(None, strategy.max_attempts());
+    }
+
+    #[test]
+    fn jitter_shortens_delays() {
+        let strategy = RetryStrategy::ExponentialBackoff {
+            base: Duration::from_millis(1000),
+            max_delay: Duration::from_millis(1000),
+            jitter: 0.5,
+            max_attempts: Some(3),
+        };
+        for _ in 0..100 {
+            let delay = strategy.retry_delay(0);
+            assert!(delay > Duration::from_millis(500));
+            assert!(delay <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn strategies_are_read_from_config() {
+        match RetryStrategy::from_config(&load(
+            r#"bridge_config.retry_strategy { type = fixed, interval = 2 s, max_attempts = 3 }"#,
+        )) {
+            Some(RetryStrategy::Fixed {
+                interval,
+                max_attempts,
+            }) => {
+                assert_eq!(Duration::from_secs(2), interval);
+                assert_eq!(Some(3), max_attempts);
+            }
+            other => panic!("Expected a fixed strategy, got {:?}", other),
+        }
+        match RetryStrategy::from_config(&load(
+            r#"bridge_config.retry_strategy { type = exponential, base = 50, max_delay = 5 s, jitter = 0.1 }"#,
+        )) {
+            Some(RetryStrategy::ExponentialBackoff {
+                base,
+                max_delay,
+                jitter,
+                max_attempts,
+            }) => {
+                assert_eq!(Duration::from_millis(50), base);
+                assert_eq!(Duration::from_secs(5), max_delay);
+                assert!((jitter - 0.1).abs() < f64::EPSILON);
+                assert_eq!(None, max_attempts);
+            }
+            other => panic!("Expected an exponential strategy, got {:?}", other),
+        }
+        assert!(RetryStrategy::from_config(&load("a = 7")).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown bridge_config.retry_strategy.type")]
+    fn unknown_strategies_are_refused() {
+        RetryStrategy::from_config(&load(r#"bridge_config.retry_strategy.type = linear"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "bridge_config.retry_strategy.max_attempts must be between")]
+    fn negative_attempts_are_refused() {
+        RetryStrategy::from_config(&load(
+            r#"bridge_config.retry_strategy { type = fixed, interval = 2 s, max_attempts = -1 }"#,
+        ));
+    }
+}
@@ -0,0 +1,262 @@
+//! Streaming of messages too large for the network buffers over TCP channels
+//!
+//! Lazily serialised messages whose size hint exceeds the
+//! [stream threshold](crate::dispatch::NetworkConfig::set_stream_threshold) are serialised onto the heap,
+//! compressed if the channel compresses data frames,
+//! and sent as a series of [fragments](super::frames::Fragment), each of which fits into a single `BufferChunk`.
+//! The fragments are only created as the remote host grants credits for them.
+//! The receiver copies the fragments into a chunk of their own and delivers the message backed by a `ChunkRef`,
+//! so neither host needs a `BufferConfig` large enough for the whole message.
+//! Messages to hosts which did not advertise the [streaming feature](super::frames::FEATURE_STREAMING)
+//! are sent as regular data frames.
+//! Receivers bound the [size](crate::dispatch::NetworkConfig::set_max_streamed_size) of streamed messages
+//! and [how many](crate::dispatch::NetworkConfig::set_max_partial_streams) may be incomplete at once,
+//! and close channels whose remote host exceeds either limit.
+use super::{
+    buffers::{BufferChunk, ChunkRef, StreamBuffer},
+    frames::Fragment,
+    udp_fragments::{encode_fragment, fragment_layout},
+};
+use bytes::{Buf, Bytes};
+use rustc_hash::FxHashMap;
+
+/// A serialised frame being streamed as fragment frames of at most `fragment_size` bytes
+///
+/// Iterating creates the fragments one at a time, so only those the channel has credits for are ever held in memory.
+pub(crate) struct OutgoingStream {
+    frame: Bytes,
+    message_id: u32,
+    fragment_len: usize,
+    next_index: u16,
+    count: u16,
+}
+
+impl OutgoingStream {
+    /// Returns `None` if `frame` would need more fragments than can be numbered.
+    pub(crate) fn new(frame: Bytes, message_id: u32, fragment_size: usize) -> Option<Self> {
+        let (fragment_len, count) = fragment_layout(&frame, fragment_size)?;
+        Some(OutgoingStream {
+            frame,
+            message_id,
+            fragment_len,
+            next_index: 0,
+            count,
+        })
+    }
+
+    /// The number of fragments which were not created yet
+    pub(crate) fn remaining(&self) -> usize {
+        (self.count - self.next_index) as usize
+    }
+}
+
+impl Iterator for OutgoingStream {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Bytes> {
+        if self.next_index >= self.count {
+            return None;
+        }
+        let start = self.next_index as usize * self.fragment_len;
+        let end = (start + self.fragment_len).min(self.frame.len());
+        let fragment = encode_fragment(
+            self.message_id,
+            self.next_index,
+            self.count,
+            &self.frame[start..end],
+        );
+        self.next_index += 1;
+        Some(fragment)
+    }
+}
+
+/// Why a fragment could not be added to its streamed frame
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum StreamError {
+    /// The fragment does not fit the frame being reassembled, which is dropped
+    Invalid(&'static str),
+    /// The remote host exceeded a limit on streamed frames
+    Limit(&'static str),
+}
+
+/// Collects the fragments streamed over a TCP channel until their frames are complete
+///
+/// TCP delivers the fragments of each frame in order, so they are copied into place as they arrive.
+pub(crate) struct StreamReassembly {
+    partials: FxHashMap<u32, Partial>,
+    max_size: usize,
+    max_partials: usize,
+}
+
+struct Partial {
+    buffer: StreamBuffer,
+    next_index: u16,
+    count: u16,
+}
+
+impl StreamReassembly {
+    /// Creates a reassembly for frames of at most `max_size` bytes,
+    /// of which at most `max_partials` may be incomplete at the same time
+    pub(crate) fn new(max_size: usize, max_partials: usize) -> Self {
+        StreamReassembly {
+            partials: FxHashMap::default(),
+            max_size,
+            max_partials,
+        }
+    }
+
+    /// Adds the next `fragment` of a streamed frame
+    ///
+    /// Returns the complete frame, along with the chunk backing it, once its last fragment has arrived.
+    /// The chunk must not be dropped before it is [free](BufferChunk::free) again.
+    pub(crate) fn insert(
+        &mut self,
+        fragment: Fragment,
+    ) -> Result<Option<(ChunkRef, BufferChunk)>, StreamError> {
+        let Fragment {
+            message_id,
+            index,
+            count,
+            payload,
+        } = fragment;
+        if index == 0 {
+            // All but the last fragment are equally large, so this bounds the size of the frame
+            let capacity = payload.remaining() * count as usize;
+            if capacity == 0 {
+                return Err(StreamError::Invalid("Empty fragment"));
+            }
+            if capacity > self.max_size {
+                return Err(StreamError::Limit("Streamed frame too large"));
+            }
+            if !self.partials.contains_key(&message_id) && self.partials.len() >= self.max_partials
+            {
+                return Err(StreamError::Limit("Too many partially streamed frames"));
+            }
+            self.partials.insert(
+                message_id,
+                Partial {
+                    buffer: StreamBuffer::with_capacity(capacity),
+                    next_index: 0,
+                    count,
+                },
+            );
+        }
+        match self.partials.get(&message_id) {
+            Some(partial) if partial.next_index == index && partial.count == count => (),
+            _ => {
+                self.partials.remove(&message_id);
+                return Err(StreamError::Invalid("Fragment out of order"));
+            }
+        }
+        let partial = self.partials.get_mut(&message_id).expect("checked above");
+        if !partial.buffer.put(payload) {
+            self.partials.remove(&message_id);
+            return Err(StreamError::Invalid("Fragment larger than the first one"));
+        }
+        partial.next_index += 1;
+        if partial.next_index < partial.count {
+            return Ok(None);
+        }
+        let partial = self.partials.remove(&message_id).expect("checked above");
+        Ok(Some(partial.buffer.into_chunk_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::{
+        buffers::{BufferConfig, DecodeBuffer},
+        udp_fragments::decode_fragments,
+    };
+
+    /// Creates all fragments `frame` is streamed as at once
+    fn stream_frames(frame: &[u8], message_id: u32, fragment_size: usize) -> Option<Vec<Bytes>> {
+        OutgoingStream::new(Bytes::copy_from_slice(frame), message_id, fragment_size)
+            .map(|stream| stream.collect())
+    }
+
+    #[test]
+    fn streamed_frames_are_reassembled() {
+        let frame: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let mut stream = OutgoingStream::new(Bytes::from(frame.clone()), 3, 128).expect("stream");
+        assert_eq!(10, stream.remaining());
+        let first = stream.next().expect("first fragment");
+        assert_eq!(9, stream.remaining());
+        let frames: Vec<Bytes> = std::iter::once(first).chain(stream).collect();
+        assert_eq!(
+            crate::net::udp_fragments::fragment(&frame, 3, 128).expect("fragments"),
+            frames
+        );
+        assert!(frames.iter().all(|frame| frame.len() <= 128));
+
+        let mut reassembly = StreamReassembly::new(2048, 1);
+        let mut decode_buffer = DecodeBuffer::new(BufferChunk::new(4096), &BufferConfig::default());
+        let mut fragments = decode_fragments(&mut decode_buffer, frames);
+        let last = fragments.pop().expect("last fragment");
+        for fragment in fragments {
+            assert!(reassembly.insert(fragment).expect("in order").is_none());
+        }
+        assert_eq!(1, reassembly.partials.len());
+        let (mut reassembled, mut chunk) = reassembly
+            .insert(last)
+            .expect("in order")
+            .expect("complete frame");
+        assert_eq!(0, reassembly.partials.len());
+        assert_eq!(&frame[..], &reassembled.copy_to_bytes(frame.len())[..]);
+        assert!(!chunk.free());
+        drop(reassembled);
+        assert!(chunk.free());
+    }
+
+    #[test]
+    fn fragments_out_of_order_are_refused() {
+        let frame = vec![1u8; 300];
+        let mut reassembly = StreamReassembly::new(2048, 1);
+        let mut decode_buffer = DecodeBuffer::new(BufferChunk::new(4096), &BufferConfig::default());
+        let mut fragments = decode_fragments(
+            &mut decode_buffer,
+            stream_frames(&frame, 1, 128).expect("fragments"),
+        );
+        assert!(reassembly.insert(fragments.remove(0)).is_ok());
+        assert_eq!(
+            Err(StreamError::Invalid("Fragment out of order")),
+            reassembly.insert(fragments.remove(1)).map(|_| ())
+        );
+        assert_eq!(0, reassembly.partials.len());
+        assert!(reassembly.insert(fragments.remove(0)).is_err());
+    }
+
+    #[test]
+    fn streams_beyond_the_limits_are_refused() {
+        let frame = vec![1u8; 1000];
+        let mut reassembly = StreamReassembly::new(2048, 1);
+        let mut decode_buffer =
+            DecodeBuffer::new(BufferChunk::new(16384), &BufferConfig::default());
+        let mut first = decode_fragments(
+            &mut decode_buffer,
+            stream_frames(&frame, 1, 128).expect("fragments"),
+        );
+        let mut second = decode_fragments(
+            &mut decode_buffer,
+            stream_frames(&frame, 2, 128).expect("fragments"),
+        );
+        assert!(reassembly.insert(first.remove(0)).is_ok());
+        assert_eq!(
+            Err(StreamError::Limit("Too many partially streamed frames")),
+            reassembly.insert(second.remove(0)).map(|_| ())
+        );
+
+        let large = vec![1u8; 3000];
+        let mut large = decode_fragments(
+            &mut decode_buffer,
+            stream_frames(&large, 3, 128).expect("fragments"),
+        );
+        let mut reassembly = StreamReassembly::new(2048, 2);
+        assert_eq!(
+            Err(StreamError::Limit("Streamed frame too large")),
+            reassembly.insert(large.remove(0)).map(|_| ())
+        );
+        assert_eq!(0, reassembly.partials.len());
+    }
+}
@@ -0,0 +1,250 @@
+//! TLS encryption for the TCP channels of the [NetworkDispatcher](crate::dispatch::NetworkDispatcher)
+//!
+//! Only available with the `tls` feature enabled.
+use rustls::{
+    server::AllowAnyAuthenticatedClient,
+    Certificate,
+    ClientConfig,
+    ClientConnection,
+    Connection,
+    PrivateKey,
+    RootCertStore,
+    ServerConfig,
+    ServerConnection,
+    ServerName,
+};
+use std::{
+    convert::TryFrom,
+    fmt::Formatter,
+    fs::File,
+    io,
+    io::{BufReader, Error, ErrorKind, Read, Write},
+    net::SocketAddr,
+    path::Path,
+    sync::Arc,
+};
+
+/// Certificates and keys used to encrypt the TCP channels of a [NetworkDispatcher](crate::dispatch::NetworkDispatcher)
+///
+/// All certificates and keys are DER encoded.
+/// Remote hosts are authenticated by checking that their certificate chain
+/// is signed by one of the trusted certificates and issued for the hostname or IP address
+/// their system is addressed by.
+///
+/// # Example
+///
+/// ```no_run
+/// use kompact::{net::tls::TlsConfig, prelude::*};
+///
+/// let mut tls_config = TlsConfig::from_pem_files("node.crt", "node.key", "ca.crt")
+///     .expect("tls config");
+/// tls_config.set_mutual_auth(true);
+/// let mut network_config = NetworkConfig::default();
+/// network_config.set_tls_config(tls_config);
+/// ```
+#[derive(Clone)]
+pub struct TlsConfig {
+    certificate_chain: Vec<Vec<u8>>,
+    private_key: Vec<u8>,
+    trusted_certificates: Vec<Vec<u8>>,
+    mutual_auth: bool,
+}
+
+impl TlsConfig {
+    /// Create a new config from a DER encoded certificate chain, its private key and the trusted CA certificates
+    ///
+    /// The private key may be PKCS#8, PKCS#1 (RSA) or SEC1 (EC) encoded.
+    pub fn new(
+        certificate_chain: Vec<Vec<u8>>,
+        private_key: Vec<u8>,
+        trusted_certificates: Vec<Vec<u8>>,
+    ) -> Self {
+        TlsConfig {
+            certificate_chain,
+            private_key,
+            trusted_certificates,
+            mutual_auth: false,
+        }
+    }
+
+    /// Create a new config by reading the certificate chain, private key and trusted CA certificates from PEM files
+    pub fn from_pem_files<P: AsRef<Path>>(
+        certificate_chain: P,
+        private_key: P,
+        trusted_certificates: P,
+    ) -> io::Result<Self> {
+        let certificate_chain = read_pem_certificates(certificate_chain)?;
+        let private_key = read_pem_private_key(private_key)?;
+        let trusted_certificates = read_pem_certificates(trusted_certificates)?;
+        Ok(TlsConfig::new(
+            certificate_chain,
+            private_key,
+            trusted_certificates,
+        ))
+    }
+
+    /// Configures whether hosts accepting a connection require the connecting host to present a trusted certificate.
+    ///
+    /// When disabled only the accepting host is authenticated.
+    ///
+    /// Default value is `false`.
+    pub fn set_mutual_auth(&mut self, mutual_auth: bool) {
+        self.mutual_auth = mutual_auth;
+    }
+
+    /// Returns whether hosts accepting a connection require the connecting host to present a trusted certificate.
+    pub fn get_mutual_auth(&self) -> bool {
+        self.mutual_auth
+    }
+
+    /// Builds the client and server configurations shared by all channels of a network thread.
+    pub(crate) fn build_context(&self) -> Result<TlsContext, rustls::Error> {
+        let mut roots = RootCertStore::empty();
+        for certificate in &self.trusted_certificates {
+            roots.add(&Certificate(certificate.clone()))?;
+        }
+        let certificate_chain: Vec<Certificate> = self
+            .certificate_chain
+            .iter()
+            .cloned()
+            .map(Certificate)
+            .collect();
+        let private_key = PrivateKey(self.private_key.clone());
+
+        let server_builder = ServerConfig::builder().with_safe_defaults();
+        let server = if self.mutual_auth {
+            server_builder
+                .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots.clone()).boxed())
+        } else {
+            server_builder.with_no_client_auth()
+        }
+        .with_single_cert(certificate_chain.clone(), private_key.clone())?;
+
+        let client_builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        let client = if self.mutual_auth {
+            client_builder.with_client_auth_cert(certificate_chain, private_key)?
+        } else {
+            client_builder.with_no_client_auth()
+        };
+
+        Ok(TlsContext {
+            server: Arc::new(server),
+            client: Arc::new(client),
+        })
+    }
+}
+
+impl std::fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Never print the private key
+        f.debug_struct("TlsConfig")
+            .field("Certificates", &self.certificate_chain.len())
+            .field("Trusted Certificates", &self.trusted_certificates.len())
+            .field("Mutual Auth", &self.mutual_auth)
+            .finish()
+    }
+}
+
+fn read_pem_certificates<P: AsRef<Path>>(path: P) -> io::Result<Vec<Vec<u8>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::certs(&mut reader)
+}
+
+fn read_pem_private_key<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    use rustls_pemfile::Item;
+
+    let mut reader = BufReader::new(File::open(path)?);
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => return Ok(key),
+            _ => (),
+        }
+    }
+    Err(Error::new(ErrorKind::InvalidData, "No private key found"))
+}
+
+/// The client and server configurations used to wrap the channels of a network thread
+pub(crate) struct TlsContext {
+    server: Arc<ServerConfig>,
+    client: Arc<ClientConfig>,
+}
+
+impl TlsContext {
+    /// Creates the server side of a TLS session for an accepted stream
+    pub fn accept(&self) -> Result<Connection, rustls::Error> {
+        ServerConnection::new(self.server.clone()).map(Connection::from)
+    }
+
+    /// Creates the client side of a TLS session for a stream requested to `addr`
+    ///
+    /// The remote certificate must be issued for `hostname`, if `addr` was resolved from one,
+    /// and for the IP address of `addr` otherwise.
+    pub fn connect(
+        &self,
+        addr: &SocketAddr,
+        hostname: Option<&str>,
+    ) -> Result<Connection, rustls::Error> {
+        let server_name = match hostname {
+            Some(hostname) => ServerName::try_from(hostname)
+                .map_err(|_| rustls::Error::General(format!("Invalid hostname {}", hostname)))?,
+            None => ServerName::IpAddress(addr.ip()),
+        };
+        ClientConnection::new(self.client.clone(), server_name).map(Connection::from)
+    }
+}
+
+/// Reads decrypted bytes from `session` into `buf`, pulling encrypted records from `stream` as needed.
+///
+/// Mirrors the semantics of [Read](std::io::Read) on a non-blocking socket:
+/// `Ok(0)` means the stream was closed and `WouldBlock` means there is nothing to read yet.
+pub(crate) fn read_tls<S: Read + Write>(
+    session: &mut Connection,
+    stream: &mut S,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    loop {
+        match session.reader().read(buf) {
+            Ok(n) => return Ok(n),
+            Err(ref err) if err.kind() == ErrorKind::WouldBlock => (),
+            Err(err) => return Err(err),
+        }
+        if session.read_tls(stream)? == 0 {
+            return Ok(0);
+        }
+        session
+            .process_new_packets()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        // The handshake may need to respond before any data becomes readable
+        flush_tls(session, stream)?;
+    }
+}
+
+/// Encrypts as much of `buf` as `session` will buffer and tries to flush it to `stream`.
+pub(crate) fn write_tls<S: Write>(
+    session: &mut Connection,
+    stream: &mut S,
+    buf: &[u8],
+) -> io::Result<usize> {
+    let n = session.writer().write(buf)?;
+    flush_tls(session, stream)?;
+    if n == 0 && !buf.is_empty() {
+        // The session buffer is full, we must wait until the stream is writable again
+        Err(Error::new(ErrorKind::WouldBlock, "TLS send buffer full"))
+    } else {
+        Ok(n)
+    }
+}
+
+/// Writes pending encrypted records from `session` to `stream` until done or the stream would block.
+pub(crate) fn flush_tls<S: Write>(session: &mut Connection, stream: &mut S) -> io::Result<()> {
+    while session.wants_write() {
+        match session.write_tls(stream) {
+            Ok(_) => (),
+            Err(ref err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,375 @@
+#[cfg(test)]
+use super::buffers::DecodeBuffer;
+use super::*;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rustc_hash::FxHashMap;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// The size of the frame head and fields in front of the payload of each fragment
+const FRAGMENT_OVERHEAD: usize = FRAME_HEAD_LEN as usize + Fragment::HEADER_LEN;
+
+/// Splits `frame` into datagrams of at most `max_packet_size` bytes
+///
+/// Returns `None` if `frame` would need more fragments than can be numbered.
+pub(super) fn fragment(
+    frame: &[u8],
+    message_id: u32,
+    max_packet_size: usize,
+) -> Option<Vec<Bytes>> {
+    let (fragment_len, count) = fragment_layout(frame, max_packet_size)?;
+    let fragments = frame
+        .chunks(fragment_len)
+        .enumerate()
+        .map(|(index, payload)| encode_fragment(message_id, index as u16, count, payload))
+        .collect();
+    Some(fragments)
+}
+
+/// Returns the payload size of the fragments of at most `max_packet_size` bytes `frame` is split into,
+/// along with their number
+///
+/// Returns `None` if `frame` would need more fragments than can be numbered.
+pub(super) fn fragment_layout(frame: &[u8], max_packet_size: usize) -> Option<(usize, u16)> {
+    let fragment_len = max_packet_size.checked_sub(FRAGMENT_OVERHEAD)?;
+    if fragment_len == 0 {
+        return None;
+    }
+    let count = frame.chunks(fragment_len).len();
+    if count > u16::MAX as usize {
+        return None;
+    }
+    Some((fragment_len, count as u16))
+}
+
+/// Encodes the `index`-th of the `count` fragments of the frame with `message_id`
+pub(super) fn encode_fragment(message_id: u32, index: u16, count: u16, payload: &[u8]) -> Bytes {
+    let content_length = Fragment::HEADER_LEN + payload.len();
+    let mut buf = BytesMut::with_capacity(FRAME_HEAD_LEN as usize + content_length);
+    FrameHead::new(FrameType::Fragment, content_length).encode_into(&mut buf);
+    buf.put_u32(message_id);
+    buf.put_u16(index);
+    buf.put_u16(count);
+    buf.put_slice(payload);
+    buf.freeze()
+}
+
+/// Decodes the fragment frames in `frames` as a receiver would
+///
+/// The fragments borrow their payload from `decode_buffer`, which must thus outlive them.
+#[cfg(test)]
+pub(super) fn decode_fragments(
+    decode_buffer: &mut DecodeBuffer,
+    frames: Vec<Bytes>,
+) -> Vec<Fragment> {
+    frames
+        .into_iter()
+        .map(|frame| {
+            let buf = decode_buffer.get_writeable().expect("buffer space");
+            buf[..frame.len()].copy_from_slice(&frame);
+            decode_buffer.advance_writeable(frame.len());
+            match decode_buffer.get_frame() {
+                Ok(Frame::Fragment(fragment)) => fragment,
+                other => panic!("Expected a fragment, got {:?}", other),
+            }
+        })
+        .collect()
+}
+
+/// Collects the fragments received via UDP until their frames are complete
+///
+/// The frames each host may have missing fragments at once, and the bytes of their fragments,
+/// are limited. The oldest frames of a host are dropped to stay within its limits.
+pub(super) struct Reassembly {
+    partials: FxHashMap<(SocketAddr, u32), Partial>,
+    /// The number of partial frames and the bytes of their fragments for each host
+    usage: FxHashMap<SocketAddr, Usage>,
+    max_partials: usize,
+    max_bytes: usize,
+    /// Frames dropped to stay within the limits since the last call to [take_evicted](Reassembly::take_evicted)
+    evicted: usize,
+}
+
+struct Partial {
+    fragments: Vec<Option<Bytes>>,
+    missing: usize,
+    bytes: usize,
+    started_at: Instant,
+}
+
+#[derive(Default)]
+struct Usage {
+    partials: usize,
+    bytes: usize,
+}
+
+impl Partial {
+    fn new(count: usize, now: Instant) -> Self {
+        Partial {
+            fragments: vec![None; count],
+            missing: count,
+            bytes: 0,
+            started_at: now,
+        }
+    }
+}
+
+impl Reassembly {
+    /// Creates a reassembly keeping at most `max_partials` frames and `max_bytes` of fragments for each host
+    pub(super) fn new(max_partials: usize, max_bytes: usize) -> Self {
+        Reassembly {
+            partials: FxHashMap::default(),
+            usage: FxHashMap::default(),
+            max_partials,
+            max_bytes,
+            evicted: 0,
+        }
+    }
+
+    /// Returns the number of frames with missing fragments
+    pub(super) fn len(&self) -> usize {
+        self.partials.len()
+    }
+
+    /// Returns the number of frames dropped to stay within the limits since the last call, and resets it
+    pub(super) fn take_evicted(&mut self) -> usize {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// Adds `fragment` from `source` and returns the complete frame once all of its fragments have arrived
+    pub(super) fn insert(
+        &mut self,
+        source: SocketAddr,
+        mut fragment: Fragment,
+        now: Instant,
+    ) -> Option<Bytes> {
+        let key = (source, fragment.message_id);
+        let count = fragment.count as usize;
+        if matches!(self.partials.get(&key), Some(partial) if partial.fragments.len() != count) {
+            // A new frame reusing the id of a partial one, which is hopefully long gone
+            self.remove(&key);
+        }
+        if !self.partials.contains_key(&key) {
+            while self.usage.get(&source).map_or(0, |usage| usage.partials) >= self.max_partials {
+                self.evict_oldest(source);
+            }
+            self.partials.insert(key, Partial::new(count, now));
+            self.usage.entry(source).or_default().partials += 1;
+        }
+        let partial = self.partials.get_mut(&key)?;
+        let slot = &mut partial.fragments[fragment.index as usize];
+        if slot.is_none() {
+            let len = fragment.payload.remaining();
+            *slot = Some(fragment.payload.copy_to_bytes(len));
+            partial.missing -= 1;
+            partial.bytes += len;
+            let usage = self.usage.entry(source).or_default();
+            usage.bytes += len;
+            while self.usage.get(&source).map_or(0, |usage| usage.bytes) > self.max_bytes {
+                self.evict_oldest(source);
+            }
+        }
+        let partial = self.partials.get(&key)?;
+        if partial.missing > 0 {
+            return None;
+        }
+        let partial = self.remove(&key)?;
+        let len = partial.bytes;
+        let mut frame = BytesMut::with_capacity(len);
+        for payload in partial.fragments.iter().flatten() {
+            frame.put_slice(payload);
+        }
+        Some(frame.freeze())
+    }
+
+    /// Drops the partial frame from `source` whose first fragment arrived first
+    fn evict_oldest(&mut self, source: SocketAddr) -> () {
+        let oldest = self
+            .partials
+            .iter()
+            .filter(|((addr, _), _)| *addr == source)
+            .min_by_key(|(_, partial)| partial.started_at)
+            .map(|(key, _)| *key);
+        match oldest {
+            Some(key) => {
+                self.remove(&key);
+                self.evicted += 1;
+            }
+            None => {
+                self.usage.remove(&source);
+            }
+        }
+    }
+
+    /// Removes the partial frame for `key`, releasing its share of the usage of its host
+    fn remove(&mut self, key: &(SocketAddr, u32)) -> Option<Partial> {
+        let partial = self.partials.remove(key)?;
+        if let Some(usage) = self.usage.get_mut(&key.0) {
+            usage.partials -= 1;
+            usage.bytes -= partial.bytes;
+            if usage.partials == 0 {
+                self.usage.remove(&key.0);
+            }
+        }
+        Some(partial)
+    }
+
+    /// Drops all frames still missing fragments `timeout` after their first fragment arrived
+    ///
+    /// Returns the number of dropped frames.
+    pub(super) fn collect_garbage(&mut self, now: Instant, timeout: Duration) -> usize {
+        let expired: Vec<(SocketAddr, u32)> = self
+            .partials
+            .iter()
+            .filter(|(_, partial)| partial.started_at + timeout <= now)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in expired.iter() {
+            self.remove(key);
+        }
+        expired.len()
+    }
+
+    /// Returns when the oldest frame missing fragments is due to be dropped, if there is any
+    pub(super) fn next_deadline(&self, timeout: Duration) -> Option<Instant> {
+        self.partials
+            .values()
+            .map(|partial| partial.started_at + timeout)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::buffers::{BufferChunk, BufferConfig};
+
+    const SOURCE: &str = "127.0.0.1:1234";
+
+    fn decode_buffer() -> DecodeBuffer {
+        DecodeBuffer::new(BufferChunk::new(4096), &BufferConfig::default())
+    }
+
+    #[test]
+    fn fragments_are_reassembled_in_any_order() {
+        let frame: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let datagrams = fragment(&frame, 7, 128).expect("fragments");
+        assert_eq!(10, datagrams.len());
+        assert!(datagrams.iter().all(|datagram| datagram.len() <= 128));
+
+        let source = SOURCE.parse().unwrap();
+        let now = Instant::now();
+        let mut reassembly = Reassembly::new(64, 1024 * 1024);
+        let mut decode_buffer = decode_buffer();
+        let mut fragments = decode_fragments(&mut decode_buffer, datagrams);
+        let last = fragments.remove(0);
+        for fragment in fragments.into_iter().rev() {
+            assert!(reassembly.insert(source, fragment, now).is_none());
+        }
+        assert_eq!(1, reassembly.len());
+        let reassembled = reassembly
+            .insert(source, last, now)
+            .expect("complete frame");
+        assert_eq!(&frame[..], &reassembled[..]);
+        assert_eq!(0, reassembly.len());
+    }
+
+    #[test]
+    fn incomplete_frames_are_collected() {
+        let frame = vec![1u8; 300];
+        let source = SOURCE.parse().unwrap();
+        let timeout = Duration::from_millis(100);
+        let now = Instant::now();
+        let mut reassembly = Reassembly::new(64, 1024 * 1024);
+        let mut decode_buffer = decode_buffer();
+        let mut fragments = decode_fragments(&mut decode_buffer, fragment(&frame, 1, 128).unwrap());
+        assert!(reassembly
+            .insert(source, fragments.remove(0), now)
+            .is_none());
+        assert_eq!(Some(now + timeout), reassembly.next_deadline(timeout));
+        assert_eq!(0, reassembly.collect_garbage(now, timeout));
+        assert_eq!(1, reassembly.collect_garbage(now + timeout, timeout));
+        assert_eq!(None, reassembly.next_deadline(timeout));
+    }
+
+    #[test]
+    fn oldest_partial_frames_are_evicted() {
+        let frame = vec![1u8; 300];
+        let source = SOURCE.parse().unwrap();
+        let other = "127.0.0.1:4321".parse().unwrap();
+        let now = Instant::now();
+        let later = |millis| now + Duration::from_millis(millis);
+        let mut reassembly = Reassembly::new(2, 1024 * 1024);
+        let mut decode_buffer = decode_buffer();
+        let mut first = decode_fragments(&mut decode_buffer, fragment(&frame, 1, 128).unwrap());
+        let mut second = decode_fragments(&mut decode_buffer, fragment(&frame, 2, 128).unwrap());
+        let mut third = decode_fragments(&mut decode_buffer, fragment(&frame, 3, 128).unwrap());
+        let mut others = decode_fragments(&mut decode_buffer, fragment(&frame, 1, 128).unwrap());
+        assert!(reassembly.insert(source, first.remove(0), now).is_none());
+        assert!(reassembly.insert(other, others.remove(0), now).is_none());
+        assert!(reassembly
+            .insert(source, second.remove(0), later(1))
+            .is_none());
+        assert_eq!(3, reassembly.len());
+        assert_eq!(0, reassembly.take_evicted());
+
+        // Only the first frame of the same host makes room for the third
+        assert!(reassembly
+            .insert(source, third.remove(0), later(2))
+            .is_none());
+        assert_eq!(3, reassembly.len());
+        assert_eq!(1, reassembly.take_evicted());
+
+        let last = second.pop().expect("last fragment");
+        assert!(reassembly
+            .insert(source, second.remove(0), later(3))
+            .is_none());
+        let reassembled = reassembly
+            .insert(source, last, later(3))
+            .expect("complete frame");
+        assert_eq!(&frame[..], &reassembled[..]);
+        let last = others.pop().expect("last fragment");
+        assert!(reassembly
+            .insert(other, others.remove(0), later(3))
+            .is_none());
+        let reassembled = reassembly
+            .insert(other, last, later(3))
+            .expect("complete frame");
+        assert_eq!(&frame[..], &reassembled[..]);
+        assert_eq!(0, reassembly.take_evicted());
+        assert_eq!(1, reassembly.len());
+    }
+
+    #[test]
+    fn partial_bytes_are_bounded() {
+        let frame = vec![1u8; 300];
+        let source = SOURCE.parse().unwrap();
+        let now = Instant::now();
+        let fragment_len = 128 - FRAGMENT_OVERHEAD;
+        let mut reassembly = Reassembly::new(64, 2 * fragment_len);
+        let mut decode_buffer = decode_buffer();
+        let mut first = decode_fragments(&mut decode_buffer, fragment(&frame, 1, 128).unwrap());
+        let mut second = decode_fragments(&mut decode_buffer, fragment(&frame, 2, 128).unwrap());
+        assert!(reassembly.insert(source, first.remove(0), now).is_none());
+        assert!(reassembly.insert(source, first.remove(0), now).is_none());
+        assert_eq!(0, reassembly.take_evicted());
+        let later = now + Duration::from_millis(1);
+        assert!(reassembly.insert(source, second.remove(0), later).is_none());
+        assert_eq!(1, reassembly.take_evicted());
+        assert_eq!(1, reassembly.len());
+        // A frame larger than the limit on its own is dropped as well
+        assert!(reassembly.insert(source, second.remove(0), later).is_none());
+        assert!(reassembly.insert(source, second.remove(0), later).is_none());
+        assert_eq!(1, reassembly.take_evicted());
+        assert_eq!(0, reassembly.len());
+    }
+
+    #[test]
+    fn oversized_frames_are_refused() {
+        assert!(fragment(&[0u8; 10], 1, FRAGMENT_OVERHEAD).is_none());
+        let frame = vec![0u8; (u16::MAX as usize + 1) * 2];
+        assert!(fragment(&frame, 1, FRAGMENT_OVERHEAD + 2).is_none());
+    }
+}
@@ -1,16 +1,30 @@
 use super::*;
 use crate::{
     messaging::{NetMessage, SerialisedFrame},
-    net::buffers::{BufferChunk, DecodeBuffer},
+    net::{
+        buffers::{BufferChunk, DecodeBuffer},
+        reliable_udp::{ReliableReceiver, ReliableSender},
+        udp_fragments::{fragment, Reassembly},
+    },
+    serialisation::ser_helpers::{deserialise_bytes, deserialise_chunk_lease},
 };
+use bytes::{Buf, Bytes, BytesMut};
 use mio::net::UdpSocket;
 use network_thread::*;
-use std::{cmp::min, collections::VecDeque, io, net::SocketAddr};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    cmp::min,
+    collections::VecDeque,
+    io,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
-// Note that this is a theoretical IPv4 limit.
+// Note that this is a theoretical IPv4 limit, i.e. 65535 bytes minus the IP and UDP headers.
 // This may be violated with IPv6 jumbograms.
 // More importantly, individual OSs can have their limit much lower!
-const MAX_PACKET_SIZE: usize = 65535;
+// Larger frames are split into fragments of at most this size.
+const MAX_PACKET_SIZE: usize = 65507;
 
 pub(super) struct UdpState {
     logger: KompactLogger,
@@ -19,6 +33,25 @@ pub(super) struct UdpState {
     input_buffer: DecodeBuffer,
     pub(super) incoming_messages: VecDeque<NetMessage>,
     max_packet_size: usize,
+    /// Send all messages reliably, if `Some`
+    reliable: Option<ReliableConfig>,
+    senders: FxHashMap<SocketAddr, ReliableSender>,
+    receivers: FxHashMap<SocketAddr, ReliableReceiver<Option<NetMessage>>>,
+    pending_acks: FxHashSet<SocketAddr>,
+    next_message_id: u32,
+    reassembly: Reassembly,
+    reassembly_timeout: Duration,
+    /// Datagrams from these hosts are dropped unread
+    blocked: FxHashSet<SocketAddr>,
+    /// What happened to the reliable messages since the `NetworkThread` last forwarded the events to the dispatcher
+    pub(super) events: Vec<NetworkEvent>,
+}
+
+struct ReliableConfig {
+    retransmit_interval: Duration,
+    max_retransmits: u8,
+    send_window: usize,
+    max_waiting: Option<usize>,
 }
 
 impl UdpState {
@@ -38,22 +71,84 @@ impl UdpState {
             input_buffer: DecodeBuffer::new(buffer_chunk, network_config.get_buffer_config()),
             incoming_messages: VecDeque::new(),
             max_packet_size,
+            reliable: if network_config.get_reliable_udp() {
+                Some(ReliableConfig {
+                    retransmit_interval: Duration::from_millis(
+                        network_config.get_udp_retransmit_interval(),
+                    ),
+                    max_retransmits: network_config.get_max_udp_retransmits(),
+                    send_window: network_config.get_udp_send_window(),
+                    max_waiting: network_config.get_max_queued_messages(),
+                })
+            } else {
+                None
+            },
+            senders: FxHashMap::default(),
+            receivers: FxHashMap::default(),
+            pending_acks: FxHashSet::default(),
+            next_message_id: 0,
+            reassembly: Reassembly::new(
+                network_config.get_max_udp_partial_messages(),
+                network_config.get_max_udp_partial_bytes(),
+            ),
+            reassembly_timeout: Duration::from_millis(network_config.get_udp_reassembly_timeout()),
+            blocked: FxHashSet::default(),
+            events: Vec::new(),
         }
     }
 
+    /// Drops all datagrams from `addr` from now on, and forgets about its reliable messages
+    pub(super) fn block(&mut self, addr: SocketAddr) -> () {
+        self.blocked.insert(addr);
+        self.senders.remove(&addr);
+        self.receivers.remove(&addr);
+        self.pending_acks.remove(&addr);
+    }
+
+    /// Accepts datagrams from `addr` again
+    pub(super) fn unblock(&mut self, addr: &SocketAddr) -> () {
+        self.blocked.remove(addr);
+    }
+
     pub(super) fn pending_messages(&self) -> usize {
         self.outbound_queue.len()
     }
 
+    /// Returns the number of messages of which only some fragments have been received
+    pub(super) fn partial_messages(&self) -> usize {
+        self.reassembly.len()
+    }
+
+    /// Returns the number of reliable messages which have not been acknowledged yet,
+    /// including those waiting for room in the send window
+    pub(super) fn unacked_messages(&self) -> usize {
+        self.senders
+            .values()
+            .map(|sender| sender.in_flight() + sender.waiting())
+            .sum()
+    }
+
     pub(super) fn try_write(&mut self) -> io::Result<usize> {
         let mut sent_bytes: usize = 0;
         let mut interrupts = 0;
         while let Some((addr, mut frame)) = self.outbound_queue.pop_front() {
             frame.make_contiguous();
+            if frame.len() > self.max_packet_size {
+                self.enqueue_fragments(addr, &frame);
+                continue;
+            }
             match self.socket.send_to(frame.bytes(), addr) {
                 Ok(n) => {
-                    // This really shouldn't happen, and can lead to inconsistent network messages
-                    assert_eq!(n, frame.len(), "A UDP frame was written incompletely!");
+                    if n < frame.len() {
+                        // The receiver can't decode the truncated datagram, so it is as good as lost
+                        warn!(
+                            self.logger,
+                            "A UDP frame to {} was written incompletely ({} of {} bytes)",
+                            addr,
+                            n,
+                            frame.len()
+                        );
+                    }
                     sent_bytes += n;
                 }
                 Err(ref err) if would_block(err) => {
@@ -79,7 +174,25 @@ impl UdpState {
         Ok(sent_bytes)
     }
 
+    /// Reads all available datagrams and queues the acknowledgements for reliable ones
     pub(super) fn try_read(&mut self) -> io::Result<(usize, IOReturn)> {
+        let res = self.read_datagrams();
+        for addr in self.pending_acks.drain() {
+            if let Some(receiver) = self.receivers.get(&addr) {
+                let mut ack = Frame::SelectiveAck(receiver.ack());
+                let mut buf = BytesMut::with_capacity(FRAME_HEAD_LEN as usize + ack.encoded_len());
+                if let Err(e) = ack.encode_into(&mut buf) {
+                    warn!(self.logger, "Could not encode UDP ack to {}: {:?}", addr, e);
+                    continue;
+                }
+                self.outbound_queue
+                    .push_back((addr, SerialisedFrame::Bytes(buf.freeze())));
+            }
+        }
+        res
+    }
+
+    fn read_datagrams(&mut self) -> io::Result<(usize, IOReturn)> {
         let mut received_bytes: usize = 0;
         let mut interrupts = 0;
         loop {
@@ -97,6 +210,10 @@ impl UdpState {
                         debug!(self.logger, "Got empty UDP datagram from {}", addr);
                         return Ok((received_bytes, IOReturn::None));
                     }
+                    Ok((n, addr)) if self.blocked.contains(&addr) => {
+                        // Leave the datagram to be overwritten by the next one
+                        received_bytes += n;
+                    }
                     Ok((n, addr)) => {
                         received_bytes += n;
                         self.input_buffer.advance_writeable(n);
@@ -125,7 +242,6 @@ impl UdpState {
     fn decode_message(&mut self, source: SocketAddr) {
         match self.input_buffer.get_frame() {
             Ok(Frame::Data(frame)) => {
-                use serialisation::ser_helpers::deserialise_chunk_lease;
                 let buf = frame.payload();
                 match deserialise_chunk_lease(buf) {
                     Ok(envelope) => self.incoming_messages.push_back(envelope),
@@ -137,6 +253,35 @@ impl UdpState {
                     }
                 }
             }
+            Ok(Frame::Sequenced(frame)) => {
+                let payload = frame.payload;
+                self.receive_sequenced(source, frame.session, frame.seq, frame.prev, || {
+                    deserialise_chunk_lease(payload)
+                })
+            }
+            Ok(Frame::Fragment(fragment)) => {
+                let frame = self.reassembly.insert(source, fragment, Instant::now());
+                let evicted = self.reassembly.take_evicted();
+                if evicted > 0 {
+                    warn!(
+                        self.logger,
+                        "Dropped {} partial UDP messages from {} to make room for newer ones",
+                        evicted,
+                        source
+                    );
+                }
+                if let Some(frame) = frame {
+                    self.decode_reassembled(source, frame);
+                }
+            }
+            Ok(Frame::SelectiveAck(ack)) => {
+                if let Some(sender) = self.senders.get_mut(&source) {
+                    for datagram in sender.acknowledge(&ack, Instant::now()) {
+                        self.outbound_queue
+                            .push_back((source, SerialisedFrame::Bytes(datagram)));
+                    }
+                }
+            }
             Ok(frame) => {
                 warn!(
                     self.logger,
@@ -152,8 +297,219 @@ impl UdpState {
         }
     }
 
-    pub(super) fn enqueue_serialised(&mut self, addr: SocketAddr, frame: SerialisedFrame) -> () {
-        self.outbound_queue.push_back((addr, frame));
+    /// Decodes a frame reassembled from its fragments
+    fn decode_reassembled(&mut self, source: SocketAddr, mut frame: Bytes) -> () {
+        let head = match FrameHead::decode_from(&mut frame) {
+            Ok(head) if head.content_length() == frame.remaining() => head,
+            _ => {
+                warn!(
+                    self.logger,
+                    "Could not decode UDP frame reassembled from fragments from {}", source
+                );
+                return;
+            }
+        };
+        match head.frame_type() {
+            FrameType::Data => match deserialise_bytes(frame) {
+                Ok(envelope) => self.incoming_messages.push_back(envelope),
+                Err(e) => {
+                    warn!(
+                        self.logger,
+                        "Could not deserialise UDP frame from {}: {}", source, e
+                    );
+                }
+            },
+            FrameType::Sequenced if frame.remaining() >= Sequenced::HEADER_LEN => {
+                let session = frame.get_u64();
+                let seq = frame.get_u64();
+                let prev = frame.get_u64();
+                self.receive_sequenced(source, session, seq, prev, || deserialise_bytes(frame))
+            }
+            frame_type => {
+                warn!(
+                    self.logger,
+                    "Reassembled unexpected frame {:?} from UDP fragments from {}",
+                    frame_type,
+                    source
+                );
+            }
+        }
+    }
+
+    /// Splits `frame` into fragments, which are sent before anything else in the outbound queue
+    fn enqueue_fragments(&mut self, addr: SocketAddr, frame: &SerialisedFrame) -> () {
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+        match fragment(frame.bytes(), message_id, self.max_packet_size) {
+            Some(fragments) => {
+                for datagram in fragments.into_iter().rev() {
+                    self.outbound_queue
+                        .push_front((addr, SerialisedFrame::Bytes(datagram)));
+                }
+            }
+            None => {
+                warn!(
+                    self.logger,
+                    "Dropping UDP frame of {} bytes to {}, as it is too large even for fragmentation",
+                    frame.len(),
+                    addr
+                );
+            }
+        }
+    }
+
+    fn receive_sequenced<F>(
+        &mut self,
+        source: SocketAddr,
+        session: u64,
+        seq: u64,
+        prev: u64,
+        deserialise: F,
+    ) -> ()
+    where
+        F: FnOnce() -> Result<NetMessage, SerError>,
+    {
+        let receiver = self
+            .receivers
+            .entry(source)
+            .or_insert_with(|| ReliableReceiver::new(session));
+        if receiver.session() != session {
+            debug!(
+                self.logger,
+                "New reliable UDP session from {}, the remote host may have restarted", source
+            );
+            *receiver = ReliableReceiver::new(session);
+        }
+        self.pending_acks.insert(source);
+        if !receiver.accept(seq) {
+            // Retransmission of a frame we have already received, or too far ahead
+            return;
+        }
+        let msg = match deserialise() {
+            Ok(envelope) => Some(envelope),
+            Err(e) => {
+                warn!(
+                    self.logger,
+                    "Could not deserialise UDP frame from {}: {}", source, e
+                );
+                None
+            }
+        };
+        let incoming_messages = &mut self.incoming_messages;
+        receiver.deliver(seq, prev, msg, |msg| {
+            if let Some(envelope) = msg {
+                incoming_messages.push_back(envelope);
+            }
+        });
+    }
+
+    /// Queues `frame` for sending to `addr`
+    ///
+    /// If UDP is reliable, the frame is numbered on `lane` and kept for retransmission until `addr` acknowledges it.
+    /// Once the send window to `addr` is full, the frame waits for acknowledgements,
+    /// or is dropped if too many frames are waiting already.
+    pub(super) fn enqueue_serialised(
+        &mut self,
+        addr: SocketAddr,
+        lane: u64,
+        mut frame: SerialisedFrame,
+    ) -> () {
+        if let Some(ref config) = self.reliable {
+            let send_window = config.send_window;
+            let sender = self
+                .senders
+                .entry(addr)
+                .or_insert_with(|| ReliableSender::new(send_window));
+            if matches!(config.max_waiting, Some(max) if sender.waiting() >= max) {
+                debug!(
+                    self.logger,
+                    "Too many reliable UDP messages to {} are waiting, dropping the message", addr
+                );
+                self.events.push(NetworkEvent::UdpDroppedMessages(addr, 1));
+                return;
+            }
+            frame.make_contiguous();
+            // Replace the head of the data frame with the head of a sequenced frame
+            let payload = &frame.bytes()[FRAME_HEAD_LEN as usize..];
+            if let Some(datagram) = sender.send(lane, payload, Instant::now()) {
+                self.outbound_queue
+                    .push_back((addr, SerialisedFrame::Bytes(datagram)));
+            }
+        } else {
+            self.outbound_queue.push_back((addr, frame));
+        }
+    }
+
+    /// Retransmits reliable messages and drops partially received messages which have run out of time
+    pub(super) fn handle_timeouts(&mut self, now: Instant) -> () {
+        let dropped = self
+            .reassembly
+            .collect_garbage(now, self.reassembly_timeout);
+        if dropped > 0 {
+            warn!(
+                self.logger,
+                "Dropped {} UDP messages, as some of their fragments did not arrive in time",
+                dropped
+            );
+        }
+        self.retransmit(now);
+    }
+
+    /// Queues the reliable messages which have not been acknowledged in time for sending again
+    ///
+    /// Gives up on all unacknowledged messages to a host once one of them was retransmitted too often.
+    fn retransmit(&mut self, now: Instant) -> () {
+        let ReliableConfig {
+            retransmit_interval,
+            max_retransmits,
+            ..
+        } = match self.reliable {
+            Some(ref config) => config,
+            None => return,
+        };
+        let mut unreachable = Vec::new();
+        for (addr, sender) in self.senders.iter_mut() {
+            match sender.due(now, *retransmit_interval, *max_retransmits) {
+                Ok(due) => {
+                    for datagram in due {
+                        self.outbound_queue
+                            .push_back((*addr, SerialisedFrame::Bytes(datagram)));
+                    }
+                }
+                Err(unacked) => {
+                    warn!(
+                        self.logger,
+                        "Dropping {} unacknowledged UDP messages to {}, as it did not respond in time",
+                        unacked,
+                        addr
+                    );
+                    self.events
+                        .push(NetworkEvent::UdpDroppedMessages(*addr, unacked));
+                    unreachable.push(*addr);
+                }
+            }
+        }
+        for addr in unreachable {
+            // The next message will start a new session
+            self.senders.remove(&addr);
+        }
+    }
+
+    /// Returns how long until `handle_timeouts` has something to do,
+    /// if any reliable messages are unacknowledged or any messages are partially received
+    pub(super) fn next_timeout(&self, now: Instant) -> Option<Duration> {
+        let retransmit = self.reliable.as_ref().and_then(|config| {
+            self.senders
+                .values()
+                .filter_map(|sender| sender.next_deadline(config.retransmit_interval))
+                .min()
+        });
+        let reassembly = self.reassembly.next_deadline(self.reassembly_timeout);
+        retransmit
+            .into_iter()
+            .chain(reassembly)
+            .min()
+            .map(|deadline| deadline.saturating_duration_since(now))
     }
 
     pub(super) fn swap_buffer(&mut self, new_buffer: &mut BufferChunk) -> () {
@@ -0,0 +1,691 @@
+use super::*;
+use crate::{
+    messaging::{NetMessage, SerialisedFrame},
+    net::{
+        auth::Authenticator,
+        buffers::{BufferChunk, BufferConfig, BufferPool, DecodeBuffer},
+    },
+};
+use bytes::{Buf, BytesMut};
+use mio::{
+    net::{UnixListener, UnixStream},
+    Registry,
+    Token,
+};
+use network_thread::*;
+use rustc_hash::FxHashMap;
+use std::{
+    collections::VecDeque,
+    io,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The Unix domain socket of a `NetworkThread` and the streams to and from other systems on the host
+///
+/// Each system connects to the socket of every system it sends messages to, so every stream
+/// carries messages in one direction only. Replies travel on the stream opened by the other side.
+///
+/// Streams go through the same Hello, Start and Ack handshake as TCP channels, which negotiates
+/// the protocol version and authenticates both systems, before they carry any messages.
+/// Messages are queued until then, and while a lost stream is re-established.
+/// Systems on the same host can not fail without closing their sockets, so no heartbeats are sent.
+pub(super) struct UdsState {
+    logger: KompactLogger,
+    path: PathBuf,
+    pub(super) listener: UnixListener,
+    streams: FxHashMap<Token, UdsStream>,
+    peers: FxHashMap<PathBuf, UdsPeer>,
+    pub(super) incoming_messages: VecDeque<NetMessage>,
+    /// What happened to the streams since the `NetworkThread` last forwarded the events to the dispatcher
+    pub(super) events: Vec<NetworkEvent>,
+    buffer_config: BufferConfig,
+    /// The address we introduce ourselves with in handshakes
+    own_addr: SocketAddr,
+    /// The incarnation of our system, exchanged during handshakes
+    incarnation: Uuid,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    max_queued_messages: Option<usize>,
+    max_connection_attempts: u8,
+    connection_retry_interval: Duration,
+}
+
+/// How far the handshake on a stream got
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UdsStreamState {
+    /// We connected and wait for the Hello of the remote system
+    AwaitingHello,
+    /// We sent our Start and wait for the remote system to Ack it
+    AwaitingAck,
+    /// We accepted the stream, said Hello and wait for the Start of the remote system
+    AwaitingStart,
+    /// The handshake completed, the stream carries messages
+    Connected,
+}
+
+struct UdsStream {
+    stream: UnixStream,
+    /// The socket we connected to, `None` for accepted streams
+    peer: Option<PathBuf>,
+    input_buffer: DecodeBuffer,
+    outbound_queue: VecDeque<SerialisedFrame>,
+    state: UdsStreamState,
+    /// The challenge we issued to the remote system
+    challenge: Vec<u8>,
+}
+
+/// A system we send messages to
+struct UdsPeer {
+    /// The stream to the system, unless we are waiting to connect again
+    token: Option<Token>,
+    /// Messages waiting for the handshake to complete
+    queue: VecDeque<SerialisedFrame>,
+    /// Failed connection attempts since the last completed handshake
+    attempts: u8,
+    /// When to connect again after a failed attempt
+    retry_at: Option<Instant>,
+}
+
+/// What to do with a stream after decoding the frames received on it
+enum Decoded {
+    /// Keep the stream
+    Keep,
+    /// The remote system refused us or broke the protocol, give up on it
+    Refuse(String),
+}
+
+impl UdsState {
+    pub(super) fn new(
+        path: PathBuf,
+        listener: UnixListener,
+        logger: KompactLogger,
+        own_addr: SocketAddr,
+        incarnation: Uuid,
+        network_config: &NetworkConfig,
+    ) -> Self {
+        UdsState {
+            logger,
+            path,
+            listener,
+            streams: FxHashMap::default(),
+            peers: FxHashMap::default(),
+            incoming_messages: VecDeque::new(),
+            events: Vec::new(),
+            buffer_config: network_config.get_buffer_config().clone(),
+            own_addr,
+            incarnation,
+            authenticator: network_config.get_authenticator().clone(),
+            max_queued_messages: network_config.get_max_queued_messages(),
+            max_connection_attempts: network_config.get_max_connection_retry_attempts(),
+            connection_retry_interval: Duration::from_millis(
+                network_config.get_connection_retry_interval(),
+            ),
+        }
+    }
+
+    /// Returns true if `token` belongs to one of our streams
+    pub(super) fn contains(&self, token: &Token) -> bool {
+        self.streams.contains_key(token)
+    }
+
+    /// Accepts all pending streams, numbering them from `next_token` onwards, and says Hello on them
+    ///
+    /// Returns the tokens of the accepted streams, which should then be written to.
+    pub(super) fn accept(
+        &mut self,
+        registry: &Registry,
+        next_token: &mut Token,
+        buffer_pool: &mut BufferPool,
+    ) -> io::Result<Vec<Token>> {
+        let mut accepted = Vec::new();
+        loop {
+            let (mut stream, _) = match self.listener.accept() {
+                Ok(accepted) => accepted,
+                Err(ref err) if would_block(err) => return Ok(accepted),
+                Err(err) => return Err(err),
+            };
+            let buffer = match buffer_pool.get_buffer() {
+                Some(buffer) => buffer,
+                None => {
+                    error!(
+                        self.logger,
+                        "No buffer available for UDS stream, dropping it"
+                    );
+                    continue;
+                }
+            };
+            let token = *next_token;
+            *next_token = Token(token.0 + 1);
+            registry.register(&mut stream, token, Interest::READABLE | Interest::WRITABLE)?;
+            debug!(self.logger, "Accepted UDS stream, Token({})", token.0);
+            let challenge = self.new_challenge();
+            let mut uds_stream = UdsStream {
+                stream,
+                peer: None,
+                input_buffer: DecodeBuffer::new(buffer, &self.buffer_config),
+                outbound_queue: VecDeque::new(),
+                state: UdsStreamState::AwaitingStart,
+                challenge: challenge.clone(),
+            };
+            uds_stream.send_frame(Frame::Hello(Hello::new(
+                ProtocolVersions::local(),
+                self.own_addr,
+                self.incarnation,
+                challenge,
+            )));
+            self.streams.insert(token, uds_stream);
+            accepted.push(token);
+        }
+    }
+
+    /// Queues `frame` for the system listening at `path`, connecting to it if necessary
+    ///
+    /// Returns the token of the stream to `path` once its handshake completed, which should then be written to.
+    pub(super) fn enqueue_serialised(
+        &mut self,
+        path: PathBuf,
+        frame: SerialisedFrame,
+        registry: &Registry,
+        next_token: &mut Token,
+        buffer_pool: &mut BufferPool,
+    ) -> Option<Token> {
+        let peer = self.peers.entry(path.clone()).or_insert_with(UdsPeer::new);
+        if let Some(token) = peer.token {
+            if let Some(uds_stream) = self.streams.get_mut(&token) {
+                if uds_stream.state == UdsStreamState::Connected {
+                    uds_stream.outbound_queue.push_back(frame);
+                    return Some(token);
+                }
+            }
+        }
+        if matches!(self.max_queued_messages, Some(max) if peer.queue.len() >= max) {
+            debug!(
+                self.logger,
+                "Queue for UDS stream to {} is full, dropping the message",
+                path.display()
+            );
+            self.events.push(NetworkEvent::UdsDroppedMessages(path, 1));
+            return None;
+        }
+        peer.queue.push_back(frame);
+        if peer.token.is_none() && peer.retry_at.is_none() {
+            self.connect(path, registry, next_token, buffer_pool);
+        }
+        None
+    }
+
+    /// Connects to the system listening at `path`, which then says Hello
+    fn connect(
+        &mut self,
+        path: PathBuf,
+        registry: &Registry,
+        next_token: &mut Token,
+        buffer_pool: &mut BufferPool,
+    ) -> () {
+        let buffer = match buffer_pool.get_buffer() {
+            Some(buffer) => buffer,
+            None => {
+                error!(
+                    self.logger,
+                    "No buffer available for UDS stream to {}",
+                    path.display()
+                );
+                self.failed_attempt(path);
+                return;
+            }
+        };
+        let token = *next_token;
+        let connected = UnixStream::connect(&path).and_then(|mut stream| {
+            registry.register(&mut stream, token, Interest::READABLE | Interest::WRITABLE)?;
+            Ok(stream)
+        });
+        match connected {
+            Ok(stream) => {
+                *next_token = Token(token.0 + 1);
+                debug!(
+                    self.logger,
+                    "Connected UDS stream to {}, Token({})",
+                    path.display(),
+                    token.0
+                );
+                self.streams.insert(
+                    token,
+                    UdsStream {
+                        stream,
+                        peer: Some(path.clone()),
+                        input_buffer: DecodeBuffer::new(buffer, &self.buffer_config),
+                        outbound_queue: VecDeque::new(),
+                        state: UdsStreamState::AwaitingHello,
+                        challenge: Vec::new(),
+                    },
+                );
+                if let Some(peer) = self.peers.get_mut(&path) {
+                    peer.token = Some(token);
+                }
+            }
+            Err(e) => {
+                debug!(
+                    self.logger,
+                    "Could not connect UDS stream to {}: {}",
+                    path.display(),
+                    e
+                );
+                buffer_pool.return_buffer(buffer);
+                self.failed_attempt(path);
+            }
+        }
+    }
+
+    /// Schedules another connection attempt to the system listening at `path`,
+    /// or gives up on it and drops its queue once it ran out of attempts
+    fn failed_attempt(&mut self, path: PathBuf) -> () {
+        let peer = match self.peers.get_mut(&path) {
+            Some(peer) => peer,
+            None => return,
+        };
+        peer.token = None;
+        peer.attempts = peer.attempts.saturating_add(1);
+        if peer.attempts > self.max_connection_attempts {
+            self.give_up(path, "ran out of connection attempts");
+        } else {
+            if peer.attempts == 1 {
+                self.events.push(NetworkEvent::UdsLost(path));
+            }
+            peer.retry_at = Some(Instant::now() + self.connection_retry_interval);
+        }
+    }
+
+    /// Forgets the system listening at `path` along with the messages queued for it
+    fn give_up(&mut self, path: PathBuf, reason: &str) -> () {
+        if let Some(peer) = self.peers.remove(&path) {
+            warn!(
+                self.logger,
+                "Giving up on UDS stream to {}, {}, dropping {} messages",
+                path.display(),
+                reason,
+                peer.queue.len()
+            );
+            if !peer.queue.is_empty() {
+                let dropped = peer.queue.len();
+                self.events
+                    .push(NetworkEvent::UdsDroppedMessages(path.clone(), dropped));
+            }
+            self.events.push(NetworkEvent::UdsDropped(path));
+        }
+    }
+
+    /// Connects again to the systems whose retry interval passed by `now`
+    pub(super) fn handle_retries(
+        &mut self,
+        now: Instant,
+        registry: &Registry,
+        next_token: &mut Token,
+        buffer_pool: &mut BufferPool,
+    ) -> () {
+        let due: Vec<PathBuf> = self
+            .peers
+            .iter_mut()
+            .filter(|(_, peer)| matches!(peer.retry_at, Some(retry_at) if retry_at <= now))
+            .map(|(path, peer)| {
+                peer.retry_at = None;
+                path.clone()
+            })
+            .collect();
+        for path in due {
+            self.connect(path, registry, next_token, buffer_pool);
+        }
+    }
+
+    /// Returns how long until the next connection attempt is due, if any is scheduled
+    pub(super) fn next_timeout(&self, now: Instant) -> Option<Duration> {
+        self.peers
+            .values()
+            .filter_map(|peer| peer.retry_at)
+            .min()
+            .map(|retry_at| retry_at.saturating_duration_since(now))
+    }
+
+    pub(super) fn try_write(&mut self, token: Token) -> io::Result<usize> {
+        let mut sent_bytes: usize = 0;
+        let mut interrupts = 0;
+        if let Some(UdsStream {
+            stream,
+            outbound_queue,
+            ..
+        }) = self.streams.get_mut(&token)
+        {
+            while let Some(mut frame) = outbound_queue.pop_front() {
+                match stream.write(frame.bytes()) {
+                    Ok(n) => {
+                        sent_bytes += n;
+                        if n < frame.len() {
+                            // Continue sending the rest later
+                            advance_frame(&mut frame, n);
+                            outbound_queue.push_front(frame);
+                        }
+                    }
+                    Err(ref err) if would_block(err) => {
+                        outbound_queue.push_front(frame);
+                        return Ok(sent_bytes);
+                    }
+                    Err(err) if interrupted(&err) => {
+                        outbound_queue.push_front(frame);
+                        interrupts += 1;
+                        if interrupts >= MAX_INTERRUPTS {
+                            return Err(err);
+                        }
+                    }
+                    // Other errors we'll consider fatal.
+                    Err(err) => {
+                        outbound_queue.push_front(frame);
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        Ok(sent_bytes)
+    }
+
+    /// Reads from the stream for `token` and handles the frames received on it
+    ///
+    /// Returns `IOReturn::Rejected` if the stream must be [refused](UdsState::refuse).
+    pub(super) fn try_read(&mut self, token: Token) -> io::Result<(usize, IOReturn)> {
+        let mut received_bytes: usize = 0;
+        let mut read_bytes: usize = 0;
+        let mut interrupts = 0;
+        let uds_stream = match self.streams.get_mut(&token) {
+            Some(uds_stream) => uds_stream,
+            None => return Ok((0, IOReturn::None)),
+        };
+        let ret = loop {
+            // Keep all the read bytes in the buffer without overwriting
+            if read_bytes > 0 {
+                uds_stream.input_buffer.advance_writeable(read_bytes);
+                read_bytes = 0;
+            }
+            if let Some(buf) = uds_stream.input_buffer.get_writeable() {
+                match uds_stream.stream.read(buf) {
+                    Ok(0) => break IOReturn::Close,
+                    Ok(n) => {
+                        received_bytes += n;
+                        read_bytes = n;
+                    }
+                    Err(ref err) if would_block(err) => break IOReturn::None,
+                    Err(err) if interrupted(&err) => {
+                        interrupts += 1;
+                        if interrupts >= MAX_INTERRUPTS {
+                            return Err(err);
+                        }
+                    }
+                    Err(err) => return Err(err),
+                }
+            } else {
+                break IOReturn::SwapBuffer;
+            }
+        };
+        match self.decode(token) {
+            Decoded::Keep => Ok((received_bytes, ret)),
+            Decoded::Refuse(reason) => {
+                warn!(self.logger, "Refusing UDS stream: {}", reason);
+                Ok((received_bytes, IOReturn::Rejected))
+            }
+        }
+    }
+
+    /// Handles the frames received on the stream for `token`, driving its handshake
+    fn decode(&mut self, token: Token) -> Decoded {
+        loop {
+            let uds_stream = match self.streams.get_mut(&token) {
+                Some(uds_stream) => uds_stream,
+                None => return Decoded::Keep,
+            };
+            let frame = match uds_stream.input_buffer.get_frame() {
+                Ok(frame) => frame,
+                Err(FramingError::NoData) => return Decoded::Keep,
+                Err(FramingError::IncompatibleVersions(versions)) => {
+                    return Decoded::Refuse(format!(
+                        "incompatible protocol versions {}..={}",
+                        versions.min, versions.max
+                    ));
+                }
+                Err(e) => return Decoded::Refuse(format!("could not decode frame: {:?}", e)),
+            };
+            match (uds_stream.state, frame) {
+                (UdsStreamState::AwaitingHello, Frame::Hello(hello)) => {
+                    let response = respond(&self.authenticator, &hello.challenge);
+                    let challenge = new_challenge(&self.authenticator);
+                    uds_stream.challenge = challenge.clone();
+                    uds_stream.send_frame(Frame::Start(Start::new(
+                        ProtocolVersions::local(),
+                        self.own_addr,
+                        Uuid::new_v4(),
+                        self.incarnation,
+                        response,
+                        challenge,
+                    )));
+                    uds_stream.state = UdsStreamState::AwaitingAck;
+                }
+                (UdsStreamState::AwaitingStart, Frame::Start(start)) => {
+                    if !verify(&self.authenticator, &uds_stream.challenge, &start.response) {
+                        return Decoded::Refuse("the remote system failed to authenticate".into());
+                    }
+                    let response = respond(&self.authenticator, &start.challenge);
+                    uds_stream.send_frame(Frame::Ack(Ack::new(0, response)));
+                    uds_stream.state = UdsStreamState::Connected;
+                }
+                (UdsStreamState::AwaitingAck, Frame::Ack(ack)) => {
+                    if !verify(&self.authenticator, &uds_stream.challenge, &ack.response) {
+                        return Decoded::Refuse("the remote system failed to authenticate".into());
+                    }
+                    uds_stream.state = UdsStreamState::Connected;
+                    if let Some(path) = uds_stream.peer.clone() {
+                        if let Some(peer) = self.peers.get_mut(&path) {
+                            peer.attempts = 0;
+                            uds_stream.outbound_queue.append(&mut peer.queue);
+                        }
+                        debug!(self.logger, "UDS stream to {} connected", path.display());
+                        self.events.push(NetworkEvent::UdsConnected(path));
+                    }
+                }
+                (UdsStreamState::Connected, Frame::Data(frame)) if uds_stream.peer.is_none() => {
+                    use serialisation::ser_helpers::deserialise_chunk_lease;
+                    match deserialise_chunk_lease(frame.payload()) {
+                        Ok(envelope) => self.incoming_messages.push_back(envelope),
+                        Err(e) => {
+                            warn!(self.logger, "Could not deserialise UDS frame: {}", e);
+                        }
+                    }
+                }
+                (state, frame) => {
+                    return Decoded::Refuse(format!(
+                        "unexpected {:?} frame in state {:?}",
+                        frame.frame_type(),
+                        state
+                    ));
+                }
+            }
+        }
+    }
+
+    pub(super) fn swap_buffer(&mut self, token: Token, new_buffer: &mut BufferChunk) -> () {
+        if let Some(uds_stream) = self.streams.get_mut(&token) {
+            uds_stream.input_buffer.swap_buffer(new_buffer);
+        }
+    }
+
+    /// Drops the stream for `token`
+    ///
+    /// The messages still queued on a stream we connected are queued again
+    /// for the next connection attempt, unless the remote system hung up on our Start,
+    /// as it does when we fail to authenticate.
+    pub(super) fn close(
+        &mut self,
+        token: Token,
+        registry: &Registry,
+        buffer_pool: &mut BufferPool,
+    ) -> () {
+        if let Some((path, state)) = self.remove_stream(token, registry, buffer_pool) {
+            debug!(self.logger, "UDS stream to {} closed", path.display());
+            if state == UdsStreamState::AwaitingAck {
+                self.give_up(path, "the remote system refused the handshake");
+            } else if matches!(self.peers.get(&path), Some(peer) if peer.queue.is_empty()) {
+                // Nothing to send, the next message connects again
+                self.peers.remove(&path);
+            } else {
+                self.failed_attempt(path);
+            }
+        }
+    }
+
+    /// Drops the stream for `token` because the remote system refused us or broke the protocol,
+    /// along with the messages queued for it
+    pub(super) fn refuse(
+        &mut self,
+        token: Token,
+        registry: &Registry,
+        buffer_pool: &mut BufferPool,
+    ) -> () {
+        if let Some((path, _)) = self.remove_stream(token, registry, buffer_pool) {
+            self.give_up(path, "the handshake failed");
+        }
+    }
+
+    /// Removes the stream for `token`, moving the messages still queued on it back to its peer
+    ///
+    /// Returns the path of the peer and how far the handshake got if we connected the stream.
+    fn remove_stream(
+        &mut self,
+        token: Token,
+        registry: &Registry,
+        buffer_pool: &mut BufferPool,
+    ) -> Option<(PathBuf, UdsStreamState)> {
+        let mut uds_stream = self.streams.remove(&token)?;
+        let _ = registry.deregister(&mut uds_stream.stream);
+        buffer_pool.return_buffer(uds_stream.input_buffer.destroy());
+        let path = uds_stream.peer.take()?;
+        let peer = self.peers.get_mut(&path)?;
+        if peer.token != Some(token) {
+            return None;
+        }
+        if uds_stream.state == UdsStreamState::Connected {
+            // Only messages are left once connected, a partially written one is sent again in full
+            while let Some(frame) = uds_stream.outbound_queue.pop_back() {
+                peer.queue.push_front(frame);
+            }
+        }
+        Some((path, uds_stream.state))
+    }
+
+    /// Closes all streams and removes our socket file
+    ///
+    /// Returns the number of messages which could not be sent.
+    pub(super) fn stop(mut self, registry: &Registry, buffer_pool: &mut BufferPool) -> usize {
+        let tokens: Vec<Token> = self.streams.keys().copied().collect();
+        let mut pending = 0;
+        for token in tokens {
+            let _ = self.try_write(token);
+            if let Some(mut uds_stream) = self.streams.remove(&token) {
+                if uds_stream.peer.is_some() && uds_stream.state == UdsStreamState::Connected {
+                    pending += uds_stream.outbound_queue.len();
+                }
+                let _ = registry.deregister(&mut uds_stream.stream);
+                buffer_pool.return_buffer(uds_stream.input_buffer.destroy());
+            }
+        }
+        pending += self
+            .peers
+            .values()
+            .map(|peer| peer.queue.len())
+            .sum::<usize>();
+        let _ = registry.deregister(&mut self.listener);
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!(
+                self.logger,
+                "Could not remove UDS socket file {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+        pending
+    }
+
+    fn new_challenge(&self) -> Vec<u8> {
+        new_challenge(&self.authenticator)
+    }
+}
+
+impl UdsStream {
+    /// Queues a handshake frame ahead of any messages
+    fn send_frame(&mut self, mut frame: Frame) -> () {
+        let len = frame.encoded_len() + FRAME_HEAD_LEN as usize;
+        let mut bytes = BytesMut::with_capacity(len);
+        if let Ok(()) = frame.encode_into(&mut bytes) {
+            self.outbound_queue
+                .push_back(SerialisedFrame::Bytes(bytes.freeze()));
+        } else {
+            panic!("Failed to encode bytes for Frame {:?}", frame.frame_type());
+        }
+    }
+}
+
+impl UdsPeer {
+    fn new() -> Self {
+        UdsPeer {
+            token: None,
+            queue: VecDeque::new(),
+            attempts: 0,
+            retry_at: None,
+        }
+    }
+}
+
+fn new_challenge(authenticator: &Option<Arc<dyn Authenticator>>) -> Vec<u8> {
+    match authenticator {
+        Some(authenticator) => authenticator.challenge(),
+        None => Vec::new(),
+    }
+}
+
+fn respond(authenticator: &Option<Arc<dyn Authenticator>>, challenge: &[u8]) -> Vec<u8> {
+    match authenticator {
+        Some(authenticator) => authenticator.respond(challenge),
+        None => Vec::new(),
+    }
+}
+
+fn verify(
+    authenticator: &Option<Arc<dyn Authenticator>>,
+    challenge: &[u8],
+    response: &[u8],
+) -> bool {
+    match authenticator {
+        Some(authenticator) => authenticator.verify(challenge, response),
+        None => true,
+    }
+}
+
+/// Binds a listener to the socket file at `path`,
+/// replacing the file if it was left behind by a system which is no longer running.
+pub(super) fn bind_uds(path: &Path) -> io::Result<UnixListener> {
+    match UnixListener::bind(path) {
+        Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                // Someone is still listening there
+                return Err(io::Error::from(io::ErrorKind::AddrInUse));
+            }
+            std::fs::remove_file(path)?;
+            UnixListener::bind(path)
+        }
+        result => result,
+    }
+}
+
+/// Drops the first `n` bytes of `frame`, which have been written already
+fn advance_frame(frame: &mut SerialisedFrame, n: usize) -> () {
+    match frame {
+        SerialisedFrame::Bytes(bytes) => bytes.advance(n),
+        SerialisedFrame::ChunkLease(chunk) => chunk.advance(n),
+        SerialisedFrame::ChunkRef(chunk) => chunk.advance(n),
+    }
+}
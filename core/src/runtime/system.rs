@@ -3,6 +3,7 @@ use super::*;
 #[cfg(all(nightly, feature = "type_erasure"))]
 use crate::utils::erased::CreateErased;
 use crate::{
+    dispatch::{network_status::NetworkStatusPort, reachability::ReachabilityPort},
     messaging::{
         DispatchEnvelope,
         MsgEnvelope,
@@ -18,6 +19,7 @@ use crate::{
 use hocon::{Hocon, HoconLoader};
 use oncemutex::{OnceMutex, OnceMutexGuard};
 use std::{any::TypeId, fmt, sync::Mutex};
+use uuid::Uuid;
 
 /// A Kompact system is a collection of components and services
 ///
@@ -950,6 +952,16 @@ impl KompactSystem {
         self.inner.system_path()
     }
 
+    /// Return the incarnation of this Kompact system
+    ///
+    /// The incarnation is a random id chosen when the system is built.
+    /// Unlike the [system path](KompactSystem::system_path), it differs between two systems
+    /// running on the same address one after the other, which allows remote systems to tell
+    /// that the system was restarted.
+    pub fn incarnation(&self) -> Uuid {
+        self.inner.incarnation
+    }
+
     /// Generate an unique path for the given component
     ///
     /// Produces a unique id [ActorPath](prelude::ActorPath) for `component`
@@ -1001,6 +1013,84 @@ impl KompactSystem {
         self.inner.supervision_port()
     }
 
+    /// Connect `port` to the [ReachabilityPort](crate::prelude::ReachabilityPort) of this system's dispatcher
+    ///
+    /// The connected component is informed whenever a remote system becomes reachable or unreachable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dispatcher does not provide a `ReachabilityPort`,
+    /// as is the case for the default [LocalDispatcher](crate::prelude::LocalDispatcher).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kompact::prelude::*;
+    ///
+    /// #[derive(ComponentDefinition, Actor)]
+    /// struct Watcher {
+    ///     ctx: ComponentContext<Self>,
+    ///     reachability: RequiredPort<ReachabilityPort>,
+    /// }
+    /// impl Watcher {
+    ///     fn new() -> Self {
+    ///         Watcher {
+    ///             ctx: ComponentContext::uninitialised(),
+    ///             reachability: RequiredPort::uninitialised(),
+    ///         }
+    ///     }
+    /// }
+    /// ignore_lifecycle!(Watcher);
+    /// impl Require<ReachabilityPort> for Watcher {
+    ///     fn handle(&mut self, event: ReachabilityIndication) -> Handled {
+    ///         info!(self.log(), "Reachability changed: {:?}", event);
+    ///         Handled::Ok
+    ///     }
+    /// }
+    ///
+    /// let mut conf = KompactConfig::default();
+    /// conf.system_components(DeadletterBox::new, NetworkConfig::default().build());
+    /// let system = conf.build().expect("system");
+    /// let watcher = system.create(Watcher::new);
+    /// watcher.on_definition(|w| system.connect_reachability_port(&mut w.reachability));
+    /// # system.shutdown().expect("shutdown");
+    /// ```
+    pub fn connect_reachability_port(&self, port: &mut RequiredPort<ReachabilityPort>) -> () {
+        self.inner.assert_active();
+        let connected = self
+            .inner
+            .get_internal_components()
+            .get_system_components()
+            .connect_reachability_port(port);
+        assert!(
+            connected,
+            "The dispatcher of this system does not provide a ReachabilityPort"
+        );
+    }
+
+    /// Connect `port` to the [NetworkStatusPort](crate::prelude::NetworkStatusPort) of this system's dispatcher
+    ///
+    /// The connected component is informed whenever a connection to a remote system is established or lost,
+    /// and may ask the dispatcher to connect to, disconnect from, or block remote systems.
+    /// See [connect_reachability_port](KompactSystem::connect_reachability_port) for an example of connecting a port this way.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dispatcher does not provide a `NetworkStatusPort`,
+    /// as is the case for the default [LocalDispatcher](crate::prelude::LocalDispatcher).
+    pub fn connect_network_status_port(&self, port: &mut RequiredPort<NetworkStatusPort>) -> () {
+        self.inner.assert_active();
+        let connected = self
+            .inner
+            .get_internal_components()
+            .get_system_components()
+            .connect_network_status_port(port);
+        assert!(
+            connected,
+            "The dispatcher of this system does not provide a NetworkStatusPort"
+        );
+    }
+
     /// The remote path for the deadletter box
     pub fn deadletter_path(&self) -> ActorPath {
         ActorPath::Named(NamedPath::with_system(self.system_path(), Vec::new()))
@@ -1494,6 +1584,11 @@ pub trait SystemHandle: Dispatching + CanCancelTimers {
     /// The system path forms a prefix for every [ActorPath](prelude::ActorPath).
     fn system_path(&self) -> SystemPath;
 
+    /// Return the incarnation of this Kompact system
+    ///
+    /// See [KompactSystem::incarnation](KompactSystem::incarnation).
+    fn incarnation(&self) -> Uuid;
+
     /// Returns a reference to the system's deadletter box
     fn deadletter_ref(&self) -> ActorRef<Never>;
 
@@ -1514,6 +1609,18 @@ pub trait SystemComponents: Send + Sync + 'static {
     fn dispatcher_ref(&self) -> DispatcherRef;
     /// Return a system path for this dispatcher
     fn system_path(&self) -> SystemPath;
+    /// Connect `port` to the dispatcher's [ReachabilityPort](crate::prelude::ReachabilityPort)
+    ///
+    /// Returns `false` if the dispatcher does not provide one, which is the default.
+    fn connect_reachability_port(&self, _port: &mut RequiredPort<ReachabilityPort>) -> bool {
+        false
+    }
+    /// Connect `port` to the dispatcher's [NetworkStatusPort](crate::prelude::NetworkStatusPort)
+    ///
+    /// Returns `false` if the dispatcher does not provide one, which is the default.
+    fn connect_network_status_port(&self, _port: &mut RequiredPort<NetworkStatusPort>) -> bool {
+        false
+    }
     /// Start all the system components
     fn start(&self, _system: &KompactSystem) -> ();
     /// Stop all the system components
@@ -1607,6 +1714,7 @@ impl InternalComponents {
 
 struct KompactRuntime {
     label: String,
+    incarnation: Uuid,
     throughput: usize,
     max_messages: usize,
     timer: Box<dyn TimerComponent>,
@@ -1624,6 +1732,7 @@ impl KompactRuntime {
         };
         KompactRuntime {
             label: conf.label,
+            incarnation: Uuid::new_v4(),
             throughput: conf.throughput,
             max_messages: mm,
             timer: (conf.timer_builder)(),
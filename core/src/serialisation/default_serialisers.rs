@@ -36,6 +36,33 @@ pub mod serialisation_ids {
     /// Id for a `()` (unit type) serialiser.
     pub const UNIT: SerId = 8;
 
+    /// Id for the messages dispatchers exchange about watched actors.
+    pub const WATCH: SerId = 9;
+
+    /// Id for a [Terminated](crate::prelude::Terminated) notification.
+    pub const TERMINATED: SerId = 10;
+
+    /// Id for the membership gossip of a [ClusterMembership](crate::cluster::ClusterMembership) component.
+    pub const CLUSTER_GOSSIP: SerId = 11;
+
+    /// Id for the topic registry a [PubSubMediator](crate::cluster::pubsub::PubSubMediator) replicates.
+    pub const PUBSUB_REGISTRY: SerId = 12;
+
+    /// Id for a [Publish](crate::cluster::pubsub::Publish) request.
+    pub const PUBSUB_PUBLISH: SerId = 13;
+
+    /// Id for a message sent via [tell_reliably](crate::prelude::ActorPath::tell_reliably).
+    pub const RELIABLE_MESSAGE: SerId = 14;
+
+    /// Id for the acknowledgement of a message sent via [tell_reliably](crate::prelude::ActorPath::tell_reliably).
+    pub const RELIABLE_ACK: SerId = 15;
+
+    /// Id for the release of a fully acknowledged channel of [tell_reliably](crate::prelude::ActorPath::tell_reliably).
+    pub const RELIABLE_RELEASE: SerId = 16;
+
+    /// Id for an [Undeliverable](crate::prelude::Undeliverable) message returned to its sender.
+    pub const UNDELIVERABLE: SerId = 17;
+
     /// Id for the Serde serialiser
     pub const SERDE: SerId = 19;
 
@@ -137,6 +137,50 @@ where
     })
 }
 
+/// Returns the length of the frame [serialise_msg](serialise_msg) would produce, according to the size hints
+///
+/// Returns `None` if the message does not provide a size hint.
+pub fn framed_size_hint<B>(src: &ActorPath, dst: &ActorPath, msg: &B) -> Option<usize>
+where
+    B: Serialisable + ?Sized,
+{
+    let hint = msg.size_hint()?;
+    Some(
+        FRAME_HEAD_LEN as usize
+            + src.size_hint().unwrap_or(0)
+            + dst.size_hint().unwrap_or(0)
+            + std::mem::size_of::<SerId>()
+            + hint,
+    )
+}
+
+/// Serialises the provided actor paths and message into a new [Bytes](bytes::Bytes) buffer
+///
+/// Produces the same format as [serialise_msg](serialise_msg), but allocates the buffer
+/// on the heap according to the message's size hint, instead of taking it from a buffer pool.
+/// Used for messages too large to fit into the pool.
+pub fn serialise_msg_to_bytes<B>(
+    src: &ActorPath,
+    dst: &ActorPath,
+    msg: &B,
+) -> Result<Bytes, SerError>
+where
+    B: Serialisable + ?Sized,
+{
+    let capacity = framed_size_hint(src, dst, msg).unwrap_or(FRAME_HEAD_LEN as usize);
+    let mut buf = BytesMut::with_capacity(capacity);
+    // Make space for the header:
+    buf.put_slice(&[0; FRAME_HEAD_LEN as usize]);
+
+    src.serialise(&mut buf)?; // src
+    dst.serialise(&mut buf)?; // dst
+    buf.put_ser_id(msg.ser_id()); // ser_id
+    Serialisable::serialise(msg, &mut buf)?; // data
+    let len = buf.len() - FRAME_HEAD_LEN as usize;
+    FrameHead::new(FrameType::Data, len).encode_into(&mut &mut buf[..FRAME_HEAD_LEN as usize]);
+    Ok(buf.freeze())
+}
+
 /// Serialises the msg into `buf` and returns a [ChunkRef](net::buffers::ChunkRef) of the serialised
 /// data to be used in [tell_preserialised()](actors::ActorPath#method.tell_preserialised)
 pub fn preserialise_msg<B>(msg: &B, buf: &mut BufferEncoder) -> Result<ChunkRef, SerError>
@@ -180,7 +224,7 @@ pub fn embed_msg(msg: NetMessage, buf: &mut BufferEncoder) -> Result<ChunkRef, S
 
     msg.sender.serialise(buf)?; // src
     msg.receiver.serialise(buf)?; // dst
-    let NetData { ser_id, data } = msg.data;
+    let NetData { ser_id, data, .. } = msg.data;
     buf.put_ser_id(ser_id); // ser_id
     match data {
         HeapOrSer::Boxed(b) => {
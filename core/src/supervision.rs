@@ -1,11 +1,12 @@
 use super::prelude::*;
 use crate::{
-    component::ContextSystemHandle,
+    component::{AbstractComponent, ContextSystemHandle},
     utils::{Fulfillable, KPromise},
     ControlEvent,
     KompactLogger,
 };
 
+use futures::{channel::mpsc::UnboundedSender, future::AbortHandle};
 use std::{
     collections::HashMap,
     fmt,
@@ -37,6 +38,71 @@ impl ListenEvent {
     }
 }
 
+/// A change in the lifecycle of a [watched](ComponentDefinition::watch_lifecycle) component
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// The component with the given id was started
+    Started(Uuid),
+    /// The component with the given id was stopped
+    Stopped(Uuid),
+    /// The component with the given id panicked and was marked as faulty
+    ///
+    /// This is the last event for the component.
+    Faulted(Uuid),
+    /// The component with the given id was killed
+    ///
+    /// This is the last event for the component.
+    ///
+    /// The id is [nil](Uuid::nil) if the watched reference had already been deallocated,
+    /// as the component's id can't be recovered from it anymore.
+    Destroyed(Uuid),
+}
+
+impl LifecycleEvent {
+    /// Returns the id of the component the event is about
+    pub fn id(&self) -> Uuid {
+        match self {
+            LifecycleEvent::Started(id) => *id,
+            LifecycleEvent::Stopped(id) => *id,
+            LifecycleEvent::Faulted(id) => *id,
+            LifecycleEvent::Destroyed(id) => *id,
+        }
+    }
+}
+
+/// Opaque reference to a [lifecycle watch](ComponentDefinition::watch_lifecycle)
+///
+/// Use this to stop watching with [unwatch_lifecycle](ComponentDefinition::unwatch_lifecycle).
+#[derive(Debug)]
+pub struct LifecycleWatch(AbortHandle);
+
+impl LifecycleWatch {
+    pub(crate) fn new(handle: AbortHandle) -> Self {
+        LifecycleWatch(handle)
+    }
+
+    pub(crate) fn cancel(self) -> () {
+        self.0.abort();
+    }
+}
+
+/// A reference to a component whose lifecycle can be [watched](ComponentDefinition::watch_lifecycle)
+///
+/// This is implemented for components themselves as well as all kinds of actor references.
+pub trait LifecycleWatchable {
+    /// Returns the referenced component, or `None` if it has already been deallocated
+    fn watched_component(&self) -> Option<Arc<dyn CoreContainer>>;
+}
+
+impl<C> LifecycleWatchable for Arc<C>
+where
+    C: AbstractComponent + ?Sized,
+{
+    fn watched_component(&self) -> Option<Arc<dyn CoreContainer>> {
+        self.clone().downgrade_dyn().upgrade()
+    }
+}
+
 /// Information about the fault that occurred
 pub struct FaultContext {
     /// The id of the component that faulted
@@ -165,6 +231,7 @@ pub(crate) enum SupervisorMsg {
     Killed(Uuid),
     Faulty(RecoveryHandler),
     Listen(Arc<Mutex<KPromise<()>>>, ListenEvent),
+    Subscribe(Arc<dyn CoreContainer>, UnboundedSender<LifecycleEvent>),
     Shutdown(Arc<Mutex<KPromise<()>>>),
 }
 
@@ -174,6 +241,7 @@ pub(crate) struct ComponentSupervisor {
     pub(crate) supervision: ProvidedPort<SupervisionPort>,
     children: HashMap<Uuid, Arc<dyn CoreContainer>>,
     listeners: HashMap<Uuid, Vec<(ListenEvent, KPromise<()>)>>,
+    subscribers: HashMap<Uuid, Vec<UnboundedSender<LifecycleEvent>>>,
     shutdown: Option<KPromise<()>>,
 }
 
@@ -184,6 +252,7 @@ impl ComponentSupervisor {
             supervision: ProvidedPort::uninitialised(),
             children: HashMap::new(),
             listeners: HashMap::new(),
+            subscribers: HashMap::new(),
             shutdown: None,
         }
     }
@@ -211,6 +280,43 @@ impl ComponentSupervisor {
         self.listeners.remove(id);
     }
 
+    /// Sends `event` to everyone watching its component, forgetting those who stopped watching
+    fn notify_subscribers(&mut self, event: LifecycleEvent) {
+        if let Some(subscribers) = self.subscribers.get_mut(&event.id()) {
+            subscribers.retain(|subscriber| subscriber.unbounded_send(event).is_ok());
+            if subscribers.is_empty() {
+                self.subscribers.remove(&event.id());
+            }
+        }
+    }
+
+    fn subscribe(
+        &mut self,
+        component: Arc<dyn CoreContainer>,
+        subscriber: UnboundedSender<LifecycleEvent>,
+    ) {
+        let id = component.id();
+        let core = component.core();
+        // The component sets its state before telling us, so we can't miss its final event
+        let last_event = if core.is_destroyed() {
+            Some(LifecycleEvent::Destroyed(id))
+        } else if core.is_faulty() {
+            Some(LifecycleEvent::Faulted(id))
+        } else {
+            None
+        };
+        match last_event {
+            Some(event) => {
+                trace!(self.ctx.log(), "Component({}) is already gone.", id);
+                let _ = subscriber.unbounded_send(event);
+            }
+            None => {
+                trace!(self.ctx.log(), "Subscribing to lifecycle of {}.", id);
+                self.subscribers.entry(id).or_default().push(subscriber);
+            }
+        }
+    }
+
     fn shutdown_if_no_more_children(&mut self) {
         if self.shutdown.is_some() {
             if self.children.is_empty() {
@@ -223,6 +329,7 @@ impl ComponentSupervisor {
                     .fulfil(())
                     .expect("Could not fulfill shutdown promise!");
                 self.listeners.clear(); // we won't be fulfilling these anyway
+                self.subscribers.clear();
             } else {
                 trace!(
                     self.ctx.log(),
@@ -262,6 +369,7 @@ impl Provide<SupervisionPort> for ComponentSupervisor {
                 self.children.insert(id, c.clone());
                 debug!(self.ctx.log(), "Component({}) was started.", id);
                 self.notify_listeners(&id, |l| matches!(l, ListenEvent::Started(_)));
+                self.notify_subscribers(LifecycleEvent::Started(id));
                 if self.shutdown.is_some() {
                     warn!(
                         self.ctx.log(),
@@ -273,6 +381,7 @@ impl Provide<SupervisionPort> for ComponentSupervisor {
             SupervisorMsg::Stopped(id) => {
                 debug!(self.ctx.log(), "Component({}) was stopped.", id);
                 self.notify_listeners(&id, |l| matches!(l, ListenEvent::Stopped(_)));
+                self.notify_subscribers(LifecycleEvent::Stopped(id));
             }
             SupervisorMsg::Killed(id) => {
                 match self.children.remove(&id) {
@@ -293,6 +402,8 @@ impl Provide<SupervisionPort> for ComponentSupervisor {
                     }
                     None => warn!(self.ctx.log(), "An untracked Component({}) was killed.", id),
                 }
+                self.notify_subscribers(LifecycleEvent::Destroyed(id));
+                self.subscribers.remove(&id);
                 self.shutdown_if_no_more_children()
             }
             SupervisorMsg::Faulty(recover_handler) => {
@@ -302,6 +413,8 @@ impl Provide<SupervisionPort> for ComponentSupervisor {
                     "Component({}) has been marked as faulty.", id
                 );
                 self.drop_listeners(&id); // will never be fulfilled
+                self.notify_subscribers(LifecycleEvent::Faulted(id));
+                self.subscribers.remove(&id);
                 match self.children.remove(&id) {
                     Some(carc) => drop(carc),
                     None => warn!(self.ctx.log(), "Component({}) faulted during start!.", id),
@@ -331,6 +444,9 @@ impl Provide<SupervisionPort> for ComponentSupervisor {
                     event.id()
                 ),
             },
+            SupervisorMsg::Subscribe(component, subscriber) => {
+                self.subscribe(component, subscriber)
+            }
             SupervisorMsg::Shutdown(amp) => match Arc::try_unwrap(amp) {
                 Ok(mp) => {
                     let promise = mp
@@ -343,6 +459,7 @@ impl Provide<SupervisionPort> for ComponentSupervisor {
                             .fulfil(())
                             .expect("Could not fulfill shutdown promise!");
                         self.listeners.clear(); // we won't be fulfilling these anyway
+                        self.subscribers.clear();
                     } else {
                         trace!(self.ctx.log(), "Killing {} children.", self.children.len());
                         self.shutdown = Some(promise);
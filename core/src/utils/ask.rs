@@ -106,6 +106,34 @@ where
     }
 }
 
+/// The reasons why [asking an actor path](ActorPath::ask_serialised) may fail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AskError {
+    /// No reply arrived before the timeout expired
+    Timeout,
+    /// The request could not be routed to its destination
+    SendFailed(String),
+    /// The system's dispatcher does not support asking actor paths
+    Unsupported,
+}
+impl error::Error for AskError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+impl fmt::Display for AskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AskError::Timeout => write!(f, "No reply arrived before the timeout expired."),
+            AskError::SendFailed(reason) => write!(f, "The request could not be sent: {}", reason),
+            AskError::Unsupported => write!(
+                f,
+                "The system's dispatcher does not support asking actor paths."
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,9 +2,11 @@ use std::sync::Arc;
 
 use futures::channel::oneshot;
 use std::{
+    collections::hash_map::RandomState,
     error,
     fmt,
     future::Future,
+    hash::{BuildHasher, Hasher},
     iter::FromIterator,
     pin::Pin,
     sync::TryLockError,
@@ -249,6 +251,15 @@ impl<T: Send + Sized + fmt::Debug, E: Send + Sized + fmt::Debug> KFuture<Result<
 
 pub use futures::executor::block_on;
 
+/// Returns a pseudo-random number in `[0, 1)`
+///
+/// Every `RandomState` is seeded differently, which is plenty of randomness for jitter
+/// and picking gossip partners.
+pub(crate) fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
 /// Blocks the current waiting for `f` to be completed or for the `timeout` to expire
 ///
 /// If the timeout expires first, then `f` is returned so it can be retried if desired.
@@ -0,0 +1,449 @@
+use kompact::{
+    cluster::{pubsub::*, *},
+    prelude::*,
+    prelude_test::net_test_helpers::*,
+};
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+const TIMEOUT: Duration = Duration::from_millis(5000);
+
+fn system_from_config_str(config: &str) -> KompactSystem {
+    let mut cfg = KompactConfig::new();
+    cfg.load_config_str(config);
+    cfg.system_components(DeadletterBox::new, NetworkConfig::default().build());
+    cfg.build().expect("KompactSystem")
+}
+
+/// Waits up to `timeout` for `condition` to hold
+fn wait_until<F>(timeout: Duration, condition: F) -> bool
+where
+    F: Fn() -> bool,
+{
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+struct ClusterMember {
+    system: KompactSystem,
+    membership: Arc<Component<ClusterMembership>>,
+    watcher: Arc<Component<ClusterWatcher>>,
+}
+
+impl ClusterMember {
+    fn start(system: KompactSystem, config: ClusterConfig) -> Self {
+        let membership = system.create(move || ClusterMembership::new(config));
+        let watcher = system.create(ClusterWatcher::new);
+        biconnect_components::<ClusterPort, _, _>(&membership, &watcher).expect("connection");
+        system
+            .start_notify(&watcher)
+            .wait_timeout(Duration::from_millis(1000))
+            .expect("Watcher never started!");
+        system
+            .start_notify(&membership)
+            .wait_timeout(Duration::from_millis(1000))
+            .expect("Membership never started!");
+        ClusterMember {
+            system,
+            membership,
+            watcher,
+        }
+    }
+
+    fn state_of(&self, system: &SystemPath) -> Option<MemberState> {
+        self.membership.on_definition(|m| {
+            m.members()
+                .into_iter()
+                .find(|member| member.system == *system)
+                .map(|member| member.state)
+        })
+    }
+
+    fn wait_for_state(&self, system: &SystemPath, state: MemberState) -> bool {
+        wait_until(TIMEOUT, || self.state_of(system) == Some(state))
+    }
+
+    fn wait_for_event<F>(&self, predicate: F) -> bool
+    where
+        F: Fn(&ClusterEvent) -> bool,
+    {
+        wait_until(TIMEOUT, || self.saw_event(&predicate))
+    }
+
+    /// Waits for `system` to be downed and then removed from the cluster
+    fn wait_for_removal(&self, system: &SystemPath) -> bool {
+        wait_until(TIMEOUT, || {
+            self.state_of(system).is_none()
+                && self
+                    .saw_event(|event| matches!(event, ClusterEvent::MemberDown(p) if p == system))
+                && self.saw_event(
+                    |event| matches!(event, ClusterEvent::MemberRemoved(p) if p == system),
+                )
+        })
+    }
+
+    fn saw_event<F>(&self, predicate: F) -> bool
+    where
+        F: Fn(&ClusterEvent) -> bool,
+    {
+        self.watcher
+            .on_definition(|w| w.received.iter().any(&predicate))
+    }
+}
+
+#[test]
+// Sets up three KompactSystems forming a cluster. The first one is the seed node, the second one joins
+// via its NetworkConfig, the third one via its HOCON config. All of them are expected to see each other up.
+// Then the third one leaves gracefully and the second one shuts down, which everyone left is expected to notice
+// by first marking them down and then removing them.
+fn cluster_membership() {
+    let gossip_interval = Duration::from_millis(100);
+    let mut seed_config = ClusterConfig::new();
+    seed_config.set_gossip_interval(gossip_interval);
+    let seed = ClusterMember::start(system_from_config_str(""), seed_config);
+    let seed_path = seed.system.system_path();
+
+    let mut config = ClusterConfig::new();
+    config.set_seed_nodes(vec![seed_path.clone()]);
+    config.set_gossip_interval(gossip_interval);
+    let second = ClusterMember::start(system_from_config_str(""), config);
+    let second_path = second.system.system_path();
+
+    let third = ClusterMember::start(
+        system_from_config_str(&format!(
+            r#"cluster {{ seed_nodes = ["{}"], gossip_interval = 100 ms }}"#,
+            seed_path
+        )),
+        ClusterConfig::new(),
+    );
+    let third_path = third.system.system_path();
+
+    let members = [&seed, &second, &third];
+    let paths = [&seed_path, &second_path, &third_path];
+    for member in members.iter() {
+        for path in paths.iter() {
+            assert!(
+                member.wait_for_state(path, MemberState::Up),
+                "{} never saw {} up",
+                member.system.system_path(),
+                path
+            );
+            assert!(member
+                .saw_event(|event| matches!(event, ClusterEvent::MemberUp(up) if up == *path)));
+        }
+    }
+
+    third
+        .watcher
+        .on_definition(|w| w.port.trigger(ClusterRequest::Leave));
+    for member in [&seed, &second].iter() {
+        assert!(
+            member.wait_for_removal(&third_path),
+            "{} never saw the third system leave",
+            member.system.system_path()
+        );
+    }
+    assert!(
+        third.wait_for_state(&third_path, MemberState::Down),
+        "The third system never saw itself leave"
+    );
+    assert!(third.saw_event(
+        |event| matches!(event, ClusterEvent::MemberLeaving(leaving) if *leaving == third_path)
+    ));
+
+    second
+        .system
+        .shutdown()
+        .expect("Kompact didn't shut down properly");
+    assert!(
+        seed.wait_for_removal(&second_path),
+        "The seed never noticed the second system shut down"
+    );
+    assert_eq!(seed.state_of(&seed_path), Some(MemberState::Up));
+
+    seed.watcher
+        .on_definition(|w| w.port.trigger(ClusterRequest::GetMembers));
+    assert!(wait_until(TIMEOUT, || {
+        seed.saw_event(
+            |event| matches!(event, ClusterEvent::Members(members) if members.len() == 1),
+        )
+    }));
+
+    seed.system
+        .shutdown()
+        .expect("Kompact didn't shut down properly");
+    third
+        .system
+        .shutdown()
+        .expect("Kompact didn't shut down properly");
+}
+
+#[test]
+// Sets up two KompactSystems forming a cluster, and then has the seed block the other one for a while.
+// The blocked system is expected to be reported unreachable and then reachable again, without ever going down.
+fn cluster_unreachable_member() {
+    let gossip_interval = Duration::from_millis(100);
+    let mut seed_config = ClusterConfig::new();
+    seed_config.set_gossip_interval(gossip_interval);
+    let seed = ClusterMember::start(system_from_config_str(""), seed_config);
+    let seed_path = seed.system.system_path();
+    let mut config = ClusterConfig::new();
+    config.set_seed_nodes(vec![seed_path]);
+    config.set_gossip_interval(gossip_interval);
+    let other = ClusterMember::start(system_from_config_str(""), config);
+    let other_path = other.system.system_path();
+    assert!(
+        seed.wait_for_state(&other_path, MemberState::Up),
+        "The other system never joined"
+    );
+
+    let status = seed.system.create(NetworkStatusWatcher::new);
+    status.on_definition(|w| seed.system.connect_network_status_port(&mut w.port));
+    seed.system
+        .start_notify(&status)
+        .wait_timeout(Duration::from_millis(1000))
+        .expect("Watcher never started!");
+    let request = |request: NetworkStatusRequest| {
+        status.on_definition(|w| w.port.trigger(request));
+    };
+    request(NetworkStatusRequest::BlockSystem(other_path.clone()));
+    assert!(
+        seed.wait_for_event(
+            |event| matches!(event, ClusterEvent::MemberUnreachable(p) if *p == other_path)
+        ),
+        "The blocked system was never reported unreachable"
+    );
+    let unreachable = seed.membership.on_definition(|m| m.unreachable_members());
+    assert_eq!(unreachable, vec![other_path.clone()]);
+
+    request(NetworkStatusRequest::UnblockSystem(other_path.clone()));
+    assert!(
+        seed.wait_for_event(
+            |event| matches!(event, ClusterEvent::MemberReachable(p) if *p == other_path)
+        ),
+        "The unblocked system was never reported reachable again"
+    );
+    assert_eq!(seed.state_of(&other_path), Some(MemberState::Up));
+    assert!(!seed.saw_event(|event| matches!(event, ClusterEvent::MemberDown(_))));
+
+    for member in [seed, other].iter() {
+        member
+            .system
+            .clone()
+            .shutdown()
+            .expect("Kompact didn't shut down properly");
+    }
+}
+
+#[test]
+// Sets up two KompactSystems forming a cluster, shuts down the second one and starts it again at the same address.
+// The restarted system is expected to join as a new member, replacing the one that went down.
+fn cluster_rejoin_same_address() {
+    let gossip_interval = Duration::from_millis(100);
+    let mut seed_config = ClusterConfig::new();
+    seed_config.set_gossip_interval(gossip_interval);
+    let seed = ClusterMember::start(system_from_config_str(""), seed_config);
+    let seed_path = seed.system.system_path();
+    let mut config = ClusterConfig::new();
+    config.set_seed_nodes(vec![seed_path]);
+    config.set_gossip_interval(gossip_interval);
+
+    let addr = free_addr();
+    let start_other = || {
+        let mut cfg = KompactConfig::new();
+        cfg.system_components(DeadletterBox::new, NetworkConfig::new(addr).build());
+        let system = cfg.build().expect("KompactSystem");
+        ClusterMember::start(system, config.clone())
+    };
+    let first = start_other();
+    let other_path = first.system.system_path();
+    let incarnation = |member: &ClusterMember| {
+        member.membership.on_definition(|m| {
+            m.members()
+                .into_iter()
+                .find(|member| member.system == other_path)
+                .map(|member| member.incarnation)
+        })
+    };
+    assert!(
+        seed.wait_for_state(&other_path, MemberState::Up),
+        "The first incarnation never joined"
+    );
+    let first_incarnation = incarnation(&first);
+    first
+        .system
+        .clone()
+        .shutdown()
+        .expect("Kompact didn't shut down properly");
+    assert!(
+        seed.wait_for_removal(&other_path),
+        "The first incarnation was never removed"
+    );
+
+    let second = start_other();
+    assert_eq!(second.system.system_path(), other_path);
+    assert!(
+        wait_until(TIMEOUT, || seed.state_of(&other_path)
+            == Some(MemberState::Up)
+            && incarnation(&seed) == incarnation(&second)),
+        "The second incarnation never joined"
+    );
+    assert_ne!(incarnation(&second), first_incarnation);
+
+    for member in [seed, second].iter() {
+        member
+            .system
+            .clone()
+            .shutdown()
+            .expect("Kompact didn't shut down properly");
+    }
+}
+
+/// Returns a local address nobody is listening on right now
+fn free_addr() -> std::net::SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("free address")
+}
+
+fn start_mediator(member: &ClusterMember) -> Arc<Component<PubSubMediator>> {
+    let mediator = member
+        .system
+        .create(|| PubSubMediator::with_gossip_interval(Duration::from_millis(100)));
+    biconnect_components::<ClusterPort, _, _>(&member.membership, &mediator).expect("connection");
+    member
+        .system
+        .start_notify(&mediator)
+        .wait_timeout(Duration::from_millis(1000))
+        .expect("Mediator never started!");
+    mediator
+}
+
+fn start_subscriber(
+    member: &ClusterMember,
+    mediator: &Arc<Component<PubSubMediator>>,
+    topic: &str,
+) -> Arc<Component<TopicSubscriber>> {
+    let (subscriber, registration) = member.system.create_and_register(TopicSubscriber::new);
+    let path = registration.wait_expect(
+        Duration::from_millis(1000),
+        "Subscriber failed to register!",
+    );
+    member
+        .system
+        .start_notify(&subscriber)
+        .wait_timeout(Duration::from_millis(1000))
+        .expect("Subscriber never started!");
+    mediator
+        .actor_ref()
+        .tell(PubSubMessage::subscribe(topic, path));
+    subscriber
+}
+
+fn received(subscriber: &Arc<Component<TopicSubscriber>>) -> Vec<String> {
+    subscriber.on_definition(|s| {
+        s.received
+            .iter()
+            .map(|(_, content)| content.clone())
+            .collect()
+    })
+}
+
+#[test]
+// Sets up a cluster of three KompactSystems with a pub/sub mediator each.
+// Messages published on one system are expected to reach the subscribers of their topic on all systems,
+// and subscribers that unsubscribe or die are expected to stop being advertised to the other systems.
+fn distributed_pubsub() {
+    let gossip_interval = Duration::from_millis(100);
+    let mut seed_config = ClusterConfig::new();
+    seed_config.set_gossip_interval(gossip_interval);
+    let seed = ClusterMember::start(system_from_config_str(""), seed_config);
+    let seed_path = seed.system.system_path();
+    let mut config = ClusterConfig::new();
+    config.set_seed_nodes(vec![seed_path.clone()]);
+    config.set_gossip_interval(gossip_interval);
+    let second = ClusterMember::start(system_from_config_str(""), config.clone());
+    let second_path = second.system.system_path();
+    let third = ClusterMember::start(system_from_config_str(""), config);
+
+    let seed_mediator = start_mediator(&seed);
+    let second_mediator = start_mediator(&second);
+    let third_mediator = start_mediator(&third);
+
+    let seed_news = start_subscriber(&seed, &seed_mediator, "news");
+    let seed_sports = start_subscriber(&seed, &seed_mediator, "sports");
+    let second_news = start_subscriber(&second, &second_mediator, "news");
+
+    let topics = |mediator: &Arc<Component<PubSubMediator>>, system: &SystemPath| {
+        mediator.on_definition(|m| m.remote_topics(system))
+    };
+    let expected = vec!["news".to_string(), "sports".to_string()];
+    assert!(
+        wait_until(TIMEOUT, || topics(&third_mediator, &seed_path) == expected),
+        "The seed's topics never reached the third system"
+    );
+    assert!(
+        wait_until(TIMEOUT, || topics(&third_mediator, &second_path)
+            == vec!["news".to_string()]),
+        "The second system's topics never reached the third system"
+    );
+
+    let publisher = third.system.actor_path_for(&third_mediator);
+    let publish = Publish::new("news", &"hello".to_string(), publisher.clone()).expect("publish");
+    third_mediator.actor_ref().tell(publish);
+    for subscriber in [&seed_news, &second_news].iter() {
+        assert!(
+            wait_until(TIMEOUT, || received(subscriber)
+                == vec!["hello".to_string()]),
+            "A news subscriber never got the news"
+        );
+    }
+    seed_news.on_definition(|s| assert_eq!(s.received[0].0, publisher));
+
+    let local = Publish::new(
+        "sports",
+        &"goal".to_string(),
+        seed.system.actor_path_for(&seed_mediator),
+    )
+    .expect("publish");
+    seed_mediator.actor_ref().tell(local);
+    assert!(
+        wait_until(TIMEOUT, || received(&seed_sports)
+            == vec!["goal".to_string()]),
+        "The sports subscriber never got the local publication"
+    );
+    assert_eq!(received(&second_news), vec!["hello".to_string()]);
+
+    second.system.kill(second_news);
+    assert!(
+        wait_until(TIMEOUT, || topics(&seed_mediator, &second_path).is_empty()),
+        "The dead subscriber was never removed"
+    );
+
+    seed_mediator.actor_ref().tell(PubSubMessage::unsubscribe(
+        "news",
+        seed.system.actor_path_for(&seed_news),
+    ));
+    assert!(
+        wait_until(TIMEOUT, || topics(&third_mediator, &seed_path)
+            == vec!["sports".to_string()]),
+        "The unsubscription never reached the third system"
+    );
+
+    for member in [seed, second, third].iter() {
+        member
+            .system
+            .clone()
+            .shutdown()
+            .expect("Kompact didn't shut down properly");
+    }
+}